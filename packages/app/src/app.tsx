@@ -62,6 +62,8 @@ declare global {
       updaterEnabled?: boolean
       deepLinks?: string[]
       wsl?: boolean
+      /** The project server a project window is bound to. */
+      project?: { directory: string; url: string }
     }
   }
 }
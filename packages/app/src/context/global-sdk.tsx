@@ -20,6 +20,7 @@ export const { use: useGlobalSDK, provider: GlobalSDKProvider } = createSimpleCo
 
     const eventFetch = (() => {
       if (!platform.fetch || !server.current) return
+      if (platform.serverProxy) return platform.fetch
       try {
         const url = new URL(server.current.http.url)
         const loopback = url.hostname === "localhost" || url.hostname === "127.0.0.1" || url.hostname === "::1"
@@ -57,6 +57,12 @@ export type Platform = {
   /** Fetch override */
   fetch?: typeof fetch
 
+  /** Set when `fetch` reaches the connected server through the platform, which keeps its credentials (desktop only) */
+  serverProxy?: {
+    /** Where to open a websocket to the server at `url` */
+    socketUrl(url: URL): Promise<URL>
+  }
+
   /** Get the configured default server URL (platform-specific) */
   getDefaultServerUrl?(): Promise<string | null>
 
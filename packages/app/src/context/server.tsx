@@ -39,7 +39,6 @@ export namespace ServerConnection {
   export type HttpBase = {
     url: string
     username?: string
-    password?: string
   }
 
   // Regular web connections
@@ -7,16 +7,8 @@ export function createSdkForServer({
 }: Omit<NonNullable<Parameters<typeof createOpencodeClient>[0]>, "baseUrl"> & {
   server: ServerConnection.HttpBase
 }) {
-  const auth = (() => {
-    if (!server.password) return
-    return {
-      Authorization: `Basic ${btoa(`${server.username ?? "opencode"}:${server.password}`)}`,
-    }
-  })()
-
   return createOpencodeClient({
     ...config,
-    headers: { ...config.headers, ...auth },
     baseUrl: server.url,
   })
 }
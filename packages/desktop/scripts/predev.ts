@@ -1,6 +1,12 @@
 import { $ } from "bun"
 
-import { copyBinaryToSidecarFolder, getCurrentSidecar, windowsify } from "./utils"
+import {
+  WSL_SIDECAR_BINARY,
+  copyBinaryToSidecarFolder,
+  copyWslBinaryToSidecarFolder,
+  getCurrentSidecar,
+  windowsify,
+} from "./utils"
 
 const RUST_TARGET = Bun.env.TAURI_ENV_TARGET_TRIPLE
 
@@ -13,3 +19,7 @@ await (sidecarConfig.ocBinary.includes("-baseline")
   : $`cd ../opencode && bun run build --single`)
 
 await copyBinaryToSidecarFolder(binaryPath, RUST_TARGET)
+// A single-target build has no Linux binary, unless one was built before.
+const wslBinaryPath = `../opencode/dist/${WSL_SIDECAR_BINARY}/bin/opencode`
+if (RUST_TARGET?.includes("windows") && (await Bun.file(wslBinaryPath).exists()))
+  await copyWslBinaryToSidecarFolder(wslBinaryPath)
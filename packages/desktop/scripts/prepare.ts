@@ -2,7 +2,14 @@
 import { $ } from "bun"
 
 import { Script } from "@opencode-ai/script"
-import { copyBinaryToSidecarFolder, getCurrentSidecar, windowsify } from "./utils"
+import {
+  RUST_TARGET,
+  WSL_SIDECAR_BINARY,
+  copyBinaryToSidecarFolder,
+  copyWslBinaryToSidecarFolder,
+  getCurrentSidecar,
+  windowsify,
+} from "./utils"
 
 const pkg = await Bun.file("./package.json").json()
 pkg.version = Script.version
@@ -17,3 +24,4 @@ await $`mkdir -p ${dir}`
 await $`gh run download ${Bun.env.GITHUB_RUN_ID} -n opencode-cli`.cwd(dir)
 
 await copyBinaryToSidecarFolder(windowsify(`${dir}/${sidecarConfig.ocBinary}/bin/opencode`))
+if (RUST_TARGET?.includes("windows")) await copyWslBinaryToSidecarFolder(`${dir}/${WSL_SIDECAR_BINARY}/bin/opencode`)
@@ -28,6 +28,9 @@ export const SIDECAR_BINARIES: Array<{ rustTarget: string; ocBinary: string; ass
   },
 ]
 
+// Linux CLI bundled with the Windows app, so WSL can be set up without downloading it.
+export const WSL_SIDECAR_BINARY = "opencode-linux-x64-baseline"
+
 export const RUST_TARGET = Bun.env.RUST_TARGET
 
 export function getCurrentSidecar(target = RUST_TARGET) {
@@ -45,6 +48,26 @@ export async function copyBinaryToSidecarFolder(source: string, target = RUST_TA
   await $`cp ${source} ${dest}`
 
   console.log(`Copied ${source} to ${dest}`)
+
+  // Signing rewrites the binary when the app is bundled, so only unsigned builds check this.
+  const checksum = new Bun.CryptoHasher("sha256").update(await Bun.file(dest).arrayBuffer()).digest("hex")
+  await Bun.write(`src-tauri/sidecars/opencode-cli-${target}.sha256`, `${checksum}  opencode-cli-${target}\n`)
+
+  console.log(`Wrote checksum manifest for ${dest}`)
+}
+
+// Only builds passing `--config src-tauri/tauri.wsl.conf.json` bundle it.
+export async function copyWslBinaryToSidecarFolder(source: string) {
+  await $`mkdir -p src-tauri/sidecars`
+  const dest = "src-tauri/sidecars/opencode-cli-wsl"
+
+  await $`cp ${source} ${dest}`
+  console.log(`Copied ${source} to ${dest}`)
+
+  const checksum = new Bun.CryptoHasher("sha256").update(await Bun.file(dest).arrayBuffer()).digest("hex")
+  await Bun.write(`${dest}.sha256`, `${checksum}  opencode-cli-wsl\n`)
+
+  console.log(`Wrote checksum manifest for ${dest}`)
 }
 
 export function windowsify(path: string) {
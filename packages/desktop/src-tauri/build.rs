@@ -1,3 +1,19 @@
 fn main() {
+    embed_sidecar_checksum();
     tauri_build::build()
 }
+
+// `scripts/utils.ts` writes a checksum manifest next to the sidecar it copies in; embed it so
+// `sync_cli` can verify the bundled binary before installing it. Signing changes the binary
+// afterwards, so signed apps check the sidecar's signature instead.
+fn embed_sidecar_checksum() {
+    let target = std::env::var("TARGET").unwrap();
+    let manifest = format!("sidecars/opencode-cli-{target}.sha256");
+    println!("cargo:rerun-if-changed={manifest}");
+
+    if let Ok(content) = std::fs::read_to_string(&manifest)
+        && let Some(checksum) = content.split_whitespace().next()
+    {
+        println!("cargo:rustc-env=OPENCODE_CLI_SHA256={checksum}");
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tauri_specta::Event;
+
+use crate::{deeplink, logging::LogLevel, sidecar_logs::SidecarLog};
+
+/// The same kind of problem is notified at most this often, since one fault tends to repeat.
+const QUIET_PERIOD: Duration = Duration::from_secs(60);
+
+#[derive(
+    Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    Crash,
+    PortInUse,
+    AuthFailure,
+}
+
+impl AlertKind {
+    fn title(self) -> &'static str {
+        match self {
+            AlertKind::Crash => "The opencode server crashed",
+            AlertKind::PortInUse => "The opencode server's port is in use",
+            AlertKind::AuthFailure => "The opencode server could not authenticate",
+        }
+    }
+}
+
+/// Sent along with the OS notification for a serious problem in the sidecar's output. Desktop
+/// notifications can't be clicked through, so `link` opens the log the line was written to.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct SidecarAlert {
+    pub kind: AlertKind,
+    pub line: String,
+    pub seq: u32,
+    pub link: String,
+}
+
+/// When each kind was last notified.
+#[derive(Default)]
+pub struct Alerts(Mutex<HashMap<AlertKind, Instant>>);
+
+/// The kind of problem a line of sidecar output reports, if it is one worth interrupting for.
+pub fn detect(line: &str) -> Option<AlertKind> {
+    let line = line.to_ascii_lowercase();
+    let any = |patterns: &[&str]| patterns.iter().any(|pattern| line.contains(pattern));
+
+    if any(&["eaddrinuse", "address already in use"]) {
+        Some(AlertKind::PortInUse)
+    } else if any(&[
+        "panic",
+        "segmentation fault",
+        "uncaught exception",
+        "fatal error",
+    ]) {
+        Some(AlertKind::Crash)
+    } else if any(&["unauthorized", "invalid api key", "authentication failed"]) {
+        Some(AlertKind::AuthFailure)
+    } else {
+        None
+    }
+}
+
+/// The problem `entry` reports, if it is a warning or error the server logged. Other output is
+/// not looked at, so a request or file that mentions a pattern doesn't raise an alert.
+fn alert_kind(entry: &SidecarLog) -> Option<AlertKind> {
+    matches!(entry.level, Some(LogLevel::Warn | LogLevel::Error))
+        .then(|| detect(&entry.line))
+        .flatten()
+}
+
+/// Raises a notification for `entry` if it reports a serious problem.
+pub fn check(app: &AppHandle, entry: &SidecarLog) {
+    let Some(kind) = alert_kind(entry) else {
+        return;
+    };
+    let Some(alerts) = app.try_state::<Alerts>() else {
+        return;
+    };
+
+    {
+        let mut last = alerts.0.lock().unwrap();
+        let now = Instant::now();
+        if last
+            .get(&kind)
+            .is_some_and(|at| now.duration_since(*at) < QUIET_PERIOD)
+        {
+            return;
+        }
+        last.insert(kind, now);
+    }
+
+    tracing::warn!(?kind, line = %entry.line, "Sidecar reported a problem");
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(kind.title())
+        .body(&entry.line)
+        .show()
+    {
+        tracing::warn!("Failed to show notification: {e}");
+    }
+
+    let _ = SidecarAlert {
+        kind,
+        line: entry.line.clone(),
+        seq: entry.seq,
+        link: deeplink::logs_link(entry.seq),
+    }
+    .emit(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_serious_problems() {
+        assert_eq!(
+            detect("error: Failed to start server. Is port 4096 in use? EADDRINUSE"),
+            Some(AlertKind::PortInUse)
+        );
+        assert_eq!(
+            detect("panic(main thread): Segmentation fault at address 0x0"),
+            Some(AlertKind::Crash)
+        );
+        assert_eq!(
+            detect("ERROR 2025-01-01T00:00:00 +1ms provider=anthropic 401 Unauthorized"),
+            Some(AlertKind::AuthFailure)
+        );
+        assert_eq!(
+            detect("INFO  2025-01-01T00:00:00 +1ms server started"),
+            None
+        );
+    }
+
+    #[test]
+    fn alerts_on_warnings_and_errors_only() {
+        let entry = |line: &str| {
+            let fields = crate::sidecar_logs::parse(line);
+            SidecarLog {
+                seq: 1,
+                stream: crate::sidecar_logs::LogStream::Stderr,
+                line: line.to_string(),
+                timestamp: String::new(),
+                level: fields.level,
+                message: fields.message,
+                request_id: None,
+                session_id: None,
+            }
+        };
+
+        assert_eq!(
+            alert_kind(&entry("ERROR 2025-01-01T00:00:00 +1ms listen EADDRINUSE")),
+            Some(AlertKind::PortInUse)
+        );
+        assert_eq!(
+            alert_kind(&entry("WARN  2025-01-01T00:00:00 +1ms 401 Unauthorized")),
+            Some(AlertKind::AuthFailure)
+        );
+        assert_eq!(
+            alert_kind(&entry("INFO  2025-01-01T00:00:00 +1ms read panic.md")),
+            None
+        );
+        assert_eq!(alert_kind(&entry("grep: unauthorized.txt")), None);
+    }
+}
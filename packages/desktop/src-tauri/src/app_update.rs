@@ -0,0 +1,327 @@
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tauri_specta::Event;
+
+use crate::{constants::UPDATER_ENABLED, settings, update_guard};
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/anomalyco/opencode/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/anomalyco/opencode-beta/releases/latest/download/latest.json";
+
+/// Which releases the app updates itself from.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    /// The channel a build was released on, going by its bundle identifier, so a beta build keeps
+    /// updating from beta releases until the user picks a channel.
+    fn for_identifier(identifier: &str) -> Self {
+        if identifier.ends_with(".beta") {
+            Self::Beta
+        } else {
+            Self::Stable
+        }
+    }
+
+    fn endpoint(self) -> &'static str {
+        match self {
+            Self::Stable => STABLE_ENDPOINT,
+            Self::Beta => BETA_ENDPOINT,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct AppUpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    /// Whether the update was downloaded and can be installed.
+    pub downloaded: bool,
+}
+
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppUpdateProgress {
+    /// `percent` is missing while the size of the download is unknown.
+    Downloading {
+        percent: Option<u32>,
+    },
+    Downloaded {
+        version: String,
+    },
+    Installing,
+    Failed {
+        message: String,
+    },
+}
+
+struct Pending {
+    update: Update,
+    bytes: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+pub struct AppUpdater(Mutex<Option<Pending>>);
+
+fn info(pending: &Pending) -> AppUpdateInfo {
+    AppUpdateInfo {
+        version: pending.update.version.clone(),
+        current_version: pending.update.current_version.clone(),
+        notes: pending.update.body.clone(),
+        date: pending.update.date.map(|date| date.to_string()),
+        downloaded: pending.bytes.is_some(),
+    }
+}
+
+/// Whether this install is part of a release rolled out to `rollout` percent of installs. The
+/// bucket depends on the version too, so the same installs aren't always first.
+pub fn in_rollout(install_id: &str, version: &str, rollout: Option<u64>) -> bool {
+    let Some(rollout) = rollout else {
+        return true;
+    };
+    let digest = Sha256::digest(format!("{install_id}:{version}").as_bytes());
+    let bucket = u16::from_be_bytes([digest[0], digest[1]]) % 100;
+    (bucket as u64) < rollout
+}
+
+/// A random id for this install, created on first use, that places it in staged rollouts.
+fn install_id(app: &AppHandle) -> Result<String, String> {
+    if let Some(id) = settings::load(app)?.install_id {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    settings::update(app, |s| {
+        s.install_id.get_or_insert_with(|| id.clone());
+        Ok(())
+    })?
+    .install_id
+    .ok_or_else(|| "Failed to store the install id".to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_update_channel(app: AppHandle) -> Result<UpdateChannel, String> {
+    let channel = settings::load(&app)?.update_channel;
+    Ok(channel.unwrap_or_else(|| UpdateChannel::for_identifier(&app.config().identifier)))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_update_channel(
+    app: AppHandle,
+    updater: State<'_, AppUpdater>,
+    channel: UpdateChannel,
+) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.update_channel = Some(channel);
+        Ok(())
+    })?;
+    // An update found on the previous channel no longer applies.
+    updater.0.lock().unwrap().take();
+
+    tracing::info!(?channel, "Switched update channel");
+    Ok(())
+}
+
+/// Looks for a newer release on the configured channel that this install is part of the rollout
+/// of.
+#[tauri::command]
+#[specta::specta]
+pub async fn check_for_update(app: AppHandle) -> Result<Option<AppUpdateInfo>, String> {
+    if !UPDATER_ENABLED {
+        return Err("Updates are disabled in this build".to_string());
+    }
+
+    let channel = get_update_channel(app.clone())?;
+    let endpoint = channel
+        .endpoint()
+        .parse()
+        .map_err(|e| format!("Invalid update endpoint: {}", e))?;
+    let update = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up the updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let updater = app.state::<AppUpdater>();
+    let Some(update) = update else {
+        updater.0.lock().unwrap().take();
+        return Ok(None);
+    };
+
+    let rollout = update.raw_json.get("rollout").and_then(|r| r.as_u64());
+    if !in_rollout(&install_id(&app)?, &update.version, rollout) {
+        tracing::info!(version = %update.version, ?rollout, "Update is not rolled out to this install yet");
+        updater.0.lock().unwrap().take();
+        return Ok(None);
+    }
+
+    tracing::info!(version = %update.version, ?channel, "Update available");
+    let mut pending = updater.0.lock().unwrap();
+    if let Some(current) = pending.as_ref()
+        && current.update.version == update.version
+    {
+        return Ok(Some(info(current)));
+    }
+    let found = Pending {
+        update,
+        bytes: None,
+    };
+    let result = info(&found);
+    *pending = Some(found);
+    Ok(Some(result))
+}
+
+/// Downloads the update found by `check_for_update`, sending `AppUpdateProgress` as it goes.
+#[tauri::command]
+#[specta::specta]
+pub async fn download_update(app: AppHandle) -> Result<AppUpdateInfo, String> {
+    let update = app
+        .state::<AppUpdater>()
+        .0
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|pending| pending.update.clone())
+        .ok_or("No update to download, check for updates first")?;
+
+    let mut downloaded = 0u64;
+    let mut last = None;
+    let bytes = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let percent = total
+                    .filter(|total| *total > 0)
+                    .map(|total| (downloaded * 100 / total).min(100) as u32);
+                if percent.is_none() || percent != last {
+                    last = percent;
+                    let _ = AppUpdateProgress::Downloading { percent }.emit(&app);
+                }
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| {
+            let message = format!("Failed to download update: {}", e);
+            let _ = AppUpdateProgress::Failed {
+                message: message.clone(),
+            }
+            .emit(&app);
+            message
+        })?;
+
+    let updater = app.state::<AppUpdater>();
+    let mut pending = updater.0.lock().unwrap();
+    let Some(pending) = pending
+        .as_mut()
+        .filter(|pending| pending.update.version == update.version)
+    else {
+        return Err("The update changed while it was downloading".to_string());
+    };
+    pending.bytes = Some(bytes);
+
+    tracing::info!(version = %update.version, "Downloaded update");
+    let _ = AppUpdateProgress::Downloaded {
+        version: update.version.clone(),
+    }
+    .emit(&app);
+    Ok(info(pending))
+}
+
+/// Installs the downloaded update and restarts the app, stopping the servers the app started.
+/// Unless `force` is set, returns the sessions those servers are still working on instead, so
+/// the user can be asked first.
+#[tauri::command]
+#[specta::specta]
+pub async fn install_update(app: AppHandle, force: bool) -> Result<Vec<String>, String> {
+    let (update, bytes) = {
+        let updater = app.state::<AppUpdater>();
+        let pending = updater.0.lock().unwrap();
+        match pending.as_ref() {
+            Some(Pending {
+                update,
+                bytes: Some(bytes),
+            }) => (update.clone(), bytes.clone()),
+            _ => return Err("No downloaded update to install".to_string()),
+        }
+    };
+
+    if !force {
+        let sessions = update_guard::busy_sessions(&app).await;
+        if !sessions.is_empty() {
+            update_guard::defer_app(&app, &update.version, sessions.clone());
+            return Ok(sessions);
+        }
+    }
+    update_guard::clear_app(&app);
+
+    let _ = AppUpdateProgress::Installing.emit(&app);
+    tracing::info!(version = %update.version, "Stopping servers to install update");
+    futures::join!(
+        crate::stop_server(app.clone()),
+        crate::instances::stop_all(&app)
+    );
+
+    if let Err(e) = update.install(bytes) {
+        let message = format!("Failed to install update: {}", e);
+        let _ = AppUpdateProgress::Failed {
+            message: message.clone(),
+        }
+        .emit(&app);
+        return Err(message);
+    }
+
+    tracing::info!(version = %update.version, "Installed update, restarting");
+    app.restart();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beta_builds_default_to_the_beta_channel() {
+        assert_eq!(
+            UpdateChannel::for_identifier("ai.opencode.desktop.beta"),
+            UpdateChannel::Beta
+        );
+        assert_eq!(
+            UpdateChannel::for_identifier("ai.opencode.desktop"),
+            UpdateChannel::Stable
+        );
+        assert_eq!(
+            UpdateChannel::for_identifier("ai.opencode.desktop.dev"),
+            UpdateChannel::Stable
+        );
+    }
+
+    #[test]
+    fn rollout_admits_a_stable_share_of_installs() {
+        assert!(in_rollout("install", "1.2.0", None));
+        assert!(!in_rollout("install", "1.2.0", Some(0)));
+        assert!(in_rollout("install", "1.2.0", Some(100)));
+        assert_eq!(
+            in_rollout("install", "1.2.0", Some(50)),
+            in_rollout("install", "1.2.0", Some(50))
+        );
+
+        let admitted = (0..1000)
+            .filter(|i| in_rollout(&format!("install-{i}"), "1.2.0", Some(10)))
+            .count();
+        assert!((50..150).contains(&admitted), "{admitted} of 1000 admitted");
+    }
+}
@@ -0,0 +1,216 @@
+use std::path::PathBuf;
+
+use tauri::AppHandle;
+
+use crate::settings;
+
+/// Passed by the login item so a background-mode launch doesn't open a window.
+pub const BACKGROUND_ARG: &str = "--background";
+
+fn executable() -> Result<PathBuf, String> {
+    // An AppImage runs from a temporary mount, so the login item must point at the image itself.
+    #[cfg(target_os = "linux")]
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+
+    std::env::current_exe().map_err(|e| format!("Failed to resolve the app executable: {}", e))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn product_name(app: &AppHandle) -> String {
+    app.config()
+        .product_name
+        .clone()
+        .unwrap_or_else(|| "OpenCode".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn entry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = dirs::config_dir().ok_or("Failed to resolve the config directory")?;
+    Ok(dir
+        .join("autostart")
+        .join(format!("{}.desktop", app.config().identifier)))
+}
+
+#[cfg(target_os = "macos")]
+fn entry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to resolve the home directory")?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", app.config().identifier)))
+}
+
+#[cfg(target_os = "linux")]
+fn entry_contents(app: &AppHandle, exe: &std::path::Path) -> String {
+    // Quoting as described by the desktop entry spec for `Exec`.
+    let exe = exe.display().to_string();
+    let escaped = exe
+        .chars()
+        .flat_map(|c| match c {
+            '"' | '`' | '$' | '\\' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect::<String>();
+
+    format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec=\"{escaped}\" {BACKGROUND_ARG}\nX-GNOME-Autostart-enabled=true\n",
+        product_name(app)
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn entry_contents(app: &AppHandle, exe: &std::path::Path) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{BACKGROUND_ARG}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        escape(&app.config().identifier),
+        escape(&exe.display().to_string())
+    )
+}
+
+#[cfg(not(windows))]
+fn is_enabled(app: &AppHandle) -> Result<bool, String> {
+    Ok(entry_path(app)?.is_file())
+}
+
+#[cfg(not(windows))]
+fn enable(app: &AppHandle) -> Result<(), String> {
+    let path = entry_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, entry_contents(app, &executable()?))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(not(windows))]
+fn disable(app: &AppHandle) -> Result<(), String> {
+    let path = entry_path(app)?;
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(windows)]
+pub const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(windows)]
+pub fn reg(args: &[&str]) -> Result<std::process::Output, String> {
+    use std::os::windows::process::CommandExt;
+    use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+    std::process::Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .output()
+        .map_err(|e| format!("Failed to run reg: {}", e))
+}
+
+#[cfg(windows)]
+fn is_enabled(app: &AppHandle) -> Result<bool, String> {
+    let output = reg(&["query", RUN_KEY, "/v", &product_name(app)])?;
+    Ok(output.status.success())
+}
+
+#[cfg(windows)]
+fn enable(app: &AppHandle) -> Result<(), String> {
+    let command = format!("\"{}\" {BACKGROUND_ARG}", executable()?.display());
+    let output = reg(&[
+        "add",
+        RUN_KEY,
+        "/v",
+        &product_name(app),
+        "/t",
+        "REG_SZ",
+        "/d",
+        &command,
+        "/f",
+    ])?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn disable(app: &AppHandle) -> Result<(), String> {
+    if !is_enabled(app)? {
+        return Ok(());
+    }
+
+    let output = reg(&["delete", RUN_KEY, "/v", &product_name(app), "/f"])?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+/// Whether this launch came from the login item while background mode is on, in which case no
+/// window is opened until the user asks for one.
+pub fn launched_headless(app: &AppHandle) -> bool {
+    std::env::args().any(|arg| arg == BACKGROUND_ARG) && get_background_mode(app.clone())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_autostart(app: AppHandle) -> Result<bool, String> {
+    is_enabled(&app)
+}
+
+/// Starts the app when the user logs in. With background mode on it starts without a window.
+#[tauri::command]
+#[specta::specta]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), String> {
+    if enabled {
+        enable(&app)?;
+    } else {
+        disable(&app)?;
+    }
+
+    tracing::info!(enabled, "Updated start at login");
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_background_mode(app: AppHandle) -> bool {
+    settings::load(&app).is_ok_and(|s| s.background_mode)
+}
+
+/// Keeps the app and its server running under the tray icon after the last window is closed.
+#[tauri::command]
+#[specta::specta]
+pub fn set_background_mode(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.background_mode = enabled;
+        Ok(())
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,572 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tauri::{AppHandle, Manager, State};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    cli, instances,
+    operations::{self, OperationKind},
+    settings, supervisor, tray,
+    wsl_path::{self, WslPathMode},
+};
+
+const PREFIX: &str = "opencode-state_";
+const AUTO_PREFIX: &str = "opencode-state_auto_";
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// How often the state is backed up automatically, and how many of those backups are kept.
+/// Backups made by hand are never pruned.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct BackupSchedule {
+    pub interval_hours: u32,
+    pub keep: u32,
+}
+
+impl BackupSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interval_hours == 0 {
+            return Err("Backup interval must be at least one hour".to_string());
+        }
+        if self.keep == 0 {
+            return Err("At least one automatic backup must be kept".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A snapshot of the server's state and data directories.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct BackupInfo {
+    pub name: String,
+    pub path: String,
+    pub created_at: String,
+    pub size_kb: u32,
+    /// Made by the backup schedule rather than by hand.
+    pub automatic: bool,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct BackupStatus {
+    pub schedule: Option<BackupSchedule>,
+    pub last: Option<BackupInfo>,
+    /// When the next automatic backup is due, if scheduled.
+    pub next_at: Option<String>,
+    /// Why the last automatic backup failed, until one succeeds.
+    pub last_error: Option<String>,
+}
+
+/// The error of the last automatic backup, if it failed.
+#[derive(Default)]
+pub struct BackupScheduler(Mutex<Option<String>>);
+
+/// Where the sidecar keeps prompt history and other state: `opencode` under the `XDG_STATE_HOME`
+/// it is spawned with. A WSL sidecar that doesn't share its state is reached through the
+/// `\\wsl.localhost` share.
+pub fn state_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(windows) && cli::is_wsl_enabled(app) && !cli::shared_wsl_state(app) {
+        return wsl_path::translate(app, "~/.local/state/opencode", WslPathMode::Windows)
+            .map(PathBuf::from)
+            .map_err(|e| format!("Failed to resolve the WSL state directory: {}", e));
+    }
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join("opencode"))
+        .map_err(|e| format!("Failed to resolve state directory: {}", e))
+}
+
+/// Where the sidecar keeps sessions and storage: `opencode` under `XDG_DATA_HOME`, which the
+/// sidecar inherits. A WSL sidecar keeps it in its home even when it shares its state.
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(windows) && cli::is_wsl_enabled(app) {
+        return wsl_path::translate(app, "~/.local/share/opencode", WslPathMode::Windows)
+            .map(PathBuf::from)
+            .map_err(|e| format!("Failed to resolve the WSL data directory: {}", e));
+    }
+    cli::data_dir().ok_or_else(|| "Failed to resolve data directory".to_string())
+}
+
+/// The directories a backup holds, by the folder they are stored under in the archive.
+fn backed_up_dirs(app: &AppHandle) -> Result<Vec<(&'static str, PathBuf)>, String> {
+    Ok(vec![("state", state_dir(app)?), ("data", data_dir(app)?)])
+}
+
+pub fn backups_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join("backups"))
+        .map_err(|e| format!("Failed to resolve backups directory: {}", e))
+}
+
+fn info(path: &Path) -> Option<BackupInfo> {
+    let name = path.file_name()?.to_str()?;
+    if !name.starts_with(PREFIX) || !name.ends_with(".zip") {
+        return None;
+    }
+    let metadata = path.metadata().ok()?;
+    let created_at: chrono::DateTime<chrono::Utc> = metadata.modified().ok()?.into();
+
+    Some(BackupInfo {
+        name: name.to_string(),
+        path: path.to_string_lossy().to_string(),
+        created_at: created_at.to_rfc3339(),
+        size_kb: (metadata.len() / 1024).try_into().unwrap_or(u32::MAX),
+        automatic: name.starts_with(AUTO_PREFIX),
+    })
+}
+
+fn add_dir(
+    zip: &mut ZipWriter<File>,
+    prefix: &str,
+    root: &Path,
+    dir: &Path,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Backup cancelled".to_string());
+        }
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = std::iter::once(prefix.into())
+            .chain(
+                path.strip_prefix(root)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy()),
+            )
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if file_type.is_dir() {
+            zip.add_directory(&name, SimpleFileOptions::default())
+                .map_err(|e| format!("Failed to add {name} to backup: {}", e))?;
+            add_dir(zip, prefix, root, &path, cancel)?;
+        } else if file_type.is_file() {
+            let mut file = File::open(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            zip.start_file(&name, SimpleFileOptions::default().large_file(true))
+                .map_err(|e| format!("Failed to add {name} to backup: {}", e))?;
+            std::io::copy(&mut file, zip)
+                .map_err(|e| format!("Failed to write {name} to backup: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Compresses everything under each of `dirs` into a zip at `archive`, each under a folder of
+/// its name, until `cancel` is set.
+fn pack(dirs: &[(&str, PathBuf)], archive: &Path, cancel: &AtomicBool) -> Result<(), String> {
+    let file = File::create(archive).map_err(|e| format!("Failed to create backup: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    for (name, dir) in dirs {
+        if dir.is_dir() {
+            zip.add_directory(*name, SimpleFileOptions::default())
+                .map_err(|e| format!("Failed to add {name} to backup: {}", e))?;
+            add_dir(&mut zip, name, dir, dir, cancel)?;
+        }
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to write backup: {}", e))?
+        .flush()
+        .map_err(|e| format!("Failed to write backup: {}", e))
+}
+
+/// Extracts each folder of `archive` into the directory of its name in `dirs`, refusing entries
+/// that would land outside of it. Older backups only held the state, which goes to the first.
+fn unpack(archive: &Path, dirs: &[(&str, PathBuf)]) -> Result<(), String> {
+    let file = File::open(archive).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Failed to read backup: {}", e))?;
+    let target = |name: &str| {
+        let (folder, rest) = name.split_once('/').unwrap_or((name, ""));
+        dirs.iter()
+            .find(|(dir, _)| *dir == folder)
+            .map(|(_, dir)| (dir.clone(), rest.to_string()))
+    };
+    let foldered = zip.file_names().all(|name| target(name).is_some());
+
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| format!("Failed to read backup: {}", e))?;
+        if entry.enclosed_name().is_none() {
+            return Err(format!("Backup contains an unsafe path: {}", entry.name()));
+        }
+        let (dir, name) = match foldered.then(|| target(entry.name())).flatten() {
+            Some(target) => target,
+            None => (dirs[0].1.clone(), entry.name().to_string()),
+        };
+        let path = dir.join(name);
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut out = File::create(&path)
+            .map_err(|e| format!("Failed to restore {}: {}", path.display(), e))?;
+        std::io::copy(&mut entry, &mut out)
+            .map_err(|e| format!("Failed to restore {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Puts the extracted `restored` directory in place of `state`, keeping the old state until the
+/// swap succeeded.
+fn swap(state: &Path, restored: &Path) -> Result<(), String> {
+    let previous = state.with_extension("before-restore");
+    let _ = std::fs::remove_dir_all(&previous);
+
+    let had_state = state.exists();
+    if had_state {
+        std::fs::rename(state, &previous)
+            .map_err(|e| format!("Failed to move the current state aside: {}", e))?;
+    }
+    if let Err(e) = std::fs::rename(restored, state) {
+        if had_state {
+            let _ = std::fs::rename(&previous, state);
+        }
+        return Err(format!("Failed to restore state: {}", e));
+    }
+
+    let _ = std::fs::remove_dir_all(&previous);
+    Ok(())
+}
+
+pub fn backup_due(last: Option<DateTime<Utc>>, interval_hours: u32, now: DateTime<Utc>) -> bool {
+    last.is_none_or(|last| now - last >= chrono::Duration::hours(interval_hours as i64))
+}
+
+/// Automatic backups beyond the `keep` most recent ones.
+fn expired(backups: &[BackupInfo], keep: u32) -> Vec<&BackupInfo> {
+    let mut automatic = backups
+        .iter()
+        .filter(|backup| backup.automatic)
+        .collect::<Vec<_>>();
+    automatic.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    automatic.into_iter().skip(keep as usize).collect()
+}
+
+fn created_at(backup: &BackupInfo) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&backup.created_at)
+        .ok()
+        .map(|at| at.to_utc())
+}
+
+async fn backup(
+    app: &AppHandle,
+    automatic: bool,
+    cancel: Arc<AtomicBool>,
+) -> Result<BackupInfo, String> {
+    let dirs = backed_up_dirs(app)?;
+    let dir = backups_dir(app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create backups directory: {}", e))?;
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let prefix = if automatic { AUTO_PREFIX } else { PREFIX };
+    let path = dir.join(format!("{prefix}{timestamp}.zip"));
+    let archive = path.clone();
+    let pack = async move {
+        tokio::task::spawn_blocking(move || {
+            pack(&dirs, &archive, &cancel).inspect_err(|_| {
+                let _ = std::fs::remove_file(&archive);
+            })
+        })
+        .await
+        .map_err(|e| format!("Backup task failed: {}", e))?
+    };
+    // The sidecar is stopped so its database is consistent on disk. This runs in its own task,
+    // which still starts the server again and cleans up if this future is dropped on cancel.
+    let task_app = app.clone();
+    tokio::spawn(async move {
+        if supervisor::current_spec(&task_app).is_some() {
+            supervisor::restart_with(&task_app, pack).await
+        } else {
+            pack.await
+        }
+    })
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))??;
+
+    tracing::info!(path = %path.display(), automatic, "Created state backup");
+    let info = info(&path).ok_or_else(|| "Failed to read the created backup".to_string())?;
+    tray::refresh(app);
+    Ok(info)
+}
+
+/// Makes an automatic backup once the schedule calls for one, and prunes the oldest.
+async fn backup_if_due(app: &AppHandle) -> Result<(), String> {
+    let Some(schedule) = settings::load(app)?.backup_schedule else {
+        return Ok(());
+    };
+    let backups = list_backups(app.clone())?;
+    let last = backups
+        .iter()
+        .filter(|backup| backup.automatic)
+        .find_map(created_at);
+    if !backup_due(last, schedule.interval_hours, Utc::now()) {
+        return Ok(());
+    }
+
+    backup(app, true, Arc::default()).await?;
+
+    for backup in expired(&list_backups(app.clone())?, schedule.keep) {
+        tracing::info!(name = %backup.name, "Pruning old state backup");
+        if let Err(e) = std::fs::remove_file(&backup.path) {
+            tracing::warn!(name = %backup.name, "Failed to prune state backup: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Backs up the state on the configured schedule until the app exits.
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(SCHEDULE_CHECK_INTERVAL).await;
+            let res = backup_if_due(&app).await;
+            if let Err(e) = &res {
+                tracing::warn!("Failed to back up state: {e}");
+            }
+            *app.state::<BackupScheduler>().0.lock().unwrap() = res.err();
+        }
+    });
+}
+
+/// Compresses the server's sessions, history and storage into a new backup, with the main server
+/// stopped meanwhile. It can be cancelled as `operation_id` until it is written.
+#[tauri::command]
+#[specta::specta]
+pub async fn create_backup(
+    app: AppHandle,
+    operation_id: Option<String>,
+) -> Result<BackupInfo, String> {
+    let operation = operations::begin(&app, OperationKind::Backup, operation_id)?;
+    operation
+        .run(backup(&app, false, operation.cancel_flag()))
+        .await
+}
+
+/// Backups of the server's state, newest first.
+#[tauri::command]
+#[specta::specta]
+pub fn list_backups(app: AppHandle) -> Result<Vec<BackupInfo>, String> {
+    let Ok(entries) = std::fs::read_dir(backups_dir(&app)?) else {
+        return Ok(vec![]);
+    };
+    let mut backups = entries
+        .flatten()
+        .filter_map(|entry| info(&entry.path()))
+        .collect::<Vec<_>>();
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(backups)
+}
+
+/// Replaces the server's state with the backup called `name`. The servers the app started are
+/// stopped while the state is swapped, and the main one is started again afterwards.
+#[tauri::command]
+#[specta::specta]
+pub async fn restore_backup(app: AppHandle, name: String) -> Result<(), String> {
+    let Some(backup) = list_backups(app.clone())?
+        .into_iter()
+        .find(|backup| backup.name == name)
+    else {
+        return Err(format!("No backup named {name}"));
+    };
+
+    // Extract before stopping anything, so a broken backup doesn't take the server down.
+    let dirs = backed_up_dirs(&app)?;
+    let restored = dirs
+        .iter()
+        .map(|(name, dir)| (*name, dir.with_extension("restoring")))
+        .collect::<Vec<_>>();
+    let clean_up = |restored: &[(&str, PathBuf)]| {
+        for (_, dir) in restored {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    };
+    clean_up(&restored);
+    let (archive, targets) = (PathBuf::from(&backup.path), restored.clone());
+    tokio::task::spawn_blocking(move || unpack(&archive, &targets))
+        .await
+        .map_err(|e| format!("Restore task failed: {}", e))?
+        .inspect_err(|_| clean_up(&restored))?;
+
+    tracing::info!(name, "Restoring state backup");
+    instances::stop_all(&app).await;
+
+    // Directories the backup didn't hold are left as they are.
+    let swap_all = || {
+        dirs.iter()
+            .zip(&restored)
+            .filter(|(_, (_, restored))| restored.exists())
+            .try_for_each(|((_, dir), (_, restored))| swap(dir, restored))
+    };
+    let res = if supervisor::current_spec(&app).is_some() {
+        supervisor::restart_with(&app, async { swap_all() }).await
+    } else {
+        crate::stop_server(app.clone()).await;
+        swap_all()
+    };
+    clean_up(&restored);
+    res
+}
+
+/// The most recent backup and when the next automatic one is due.
+#[tauri::command]
+#[specta::specta]
+pub fn get_backup_status(
+    app: AppHandle,
+    scheduler: State<'_, BackupScheduler>,
+) -> Result<BackupStatus, String> {
+    let schedule = settings::load(&app)?.backup_schedule;
+    let backups = list_backups(app)?;
+    let next_at = schedule.map(|schedule| {
+        backups
+            .iter()
+            .filter(|backup| backup.automatic)
+            .find_map(created_at)
+            .map_or_else(Utc::now, |last| {
+                last + chrono::Duration::hours(schedule.interval_hours as i64)
+            })
+            .to_rfc3339()
+    });
+
+    Ok(BackupStatus {
+        schedule,
+        last: backups.into_iter().next(),
+        next_at,
+        last_error: scheduler.0.lock().unwrap().clone(),
+    })
+}
+
+/// Backs up the state automatically on `schedule`, or stops doing so when `None`.
+#[tauri::command]
+#[specta::specta]
+pub fn set_backup_schedule(app: AppHandle, schedule: Option<BackupSchedule>) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.backup_schedule = schedule;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup(name: &str, created_at: &str) -> BackupInfo {
+        BackupInfo {
+            name: name.to_string(),
+            path: name.to_string(),
+            created_at: created_at.to_string(),
+            size_kb: 1,
+            automatic: name.starts_with(AUTO_PREFIX),
+        }
+    }
+
+    #[test]
+    fn prunes_only_old_automatic_backups() {
+        let backups = [
+            backup("opencode-state_auto_3.zip", "2025-01-03T00:00:00+00:00"),
+            backup("opencode-state_manual.zip", "2025-01-02T12:00:00+00:00"),
+            backup("opencode-state_auto_1.zip", "2025-01-01T00:00:00+00:00"),
+            backup("opencode-state_auto_2.zip", "2025-01-02T00:00:00+00:00"),
+        ];
+
+        let expired = expired(&backups, 2);
+        assert_eq!(
+            expired.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(),
+            ["opencode-state_auto_1.zip"]
+        );
+
+        let now = DateTime::parse_from_rfc3339("2025-01-03T05:00:00Z")
+            .unwrap()
+            .to_utc();
+        let last = created_at(&backups[0]);
+        assert!(!backup_due(last, 6, now));
+        assert!(backup_due(last, 5, now));
+        assert!(backup_due(None, 24, now));
+    }
+
+    #[test]
+    fn restores_what_was_backed_up() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let state = root.join("state/opencode");
+        let data = root.join("data/opencode");
+        std::fs::create_dir_all(data.join("storage/session")).unwrap();
+        std::fs::write(data.join("storage/session/ses_1.json"), "{}").unwrap();
+        std::fs::create_dir_all(&state).unwrap();
+        std::fs::write(state.join("history"), "hello").unwrap();
+
+        let archive = root.join("backup.zip");
+        let dirs = [("state", state.clone()), ("data", data.clone())];
+        pack(&dirs, &archive, &AtomicBool::new(false)).unwrap();
+
+        std::fs::write(state.join("history"), "changed").unwrap();
+        std::fs::write(data.join("new"), "").unwrap();
+
+        let restored = dirs
+            .clone()
+            .map(|(name, dir)| (name, dir.with_extension("restoring")));
+        unpack(&archive, &restored).unwrap();
+        swap(&state, &restored[0].1).unwrap();
+        swap(&data, &restored[1].1).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(state.join("history")).unwrap(),
+            "hello"
+        );
+        assert!(data.join("storage/session/ses_1.json").is_file());
+        assert!(!data.join("new").exists());
+        assert!(!restored[0].1.exists());
+        assert!(!root.join("state/opencode.before-restore").exists());
+    }
+
+    #[test]
+    fn restores_state_only_backups_into_the_state() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let archive = root.join("backup.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("history", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"hello").unwrap();
+        zip.finish().unwrap();
+
+        let state = root.join("state.restoring");
+        let data = root.join("data.restoring");
+        unpack(
+            &archive,
+            &[("state", state.clone()), ("data", data.clone())],
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(state.join("history")).unwrap(),
+            "hello"
+        );
+        assert!(!data.exists());
+    }
+}
@@ -0,0 +1,129 @@
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{server, settings};
+
+const MIN_PASSWORD_LENGTH: u32 = 16;
+const MIN_DISTINCT_CHARS: usize = 8;
+
+/// Why the sidecar was not started on a non-loopback hostname.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerBindRejected {
+    /// Listening beyond this machine has not been allowed in the settings.
+    LanNotAllowed { hostname: String },
+    /// The server password is too easy to guess for a server other machines can reach.
+    WeakPassword { hostname: String, min_length: u32 },
+}
+
+impl std::fmt::Display for ServerBindRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LanNotAllowed { hostname } => write!(
+                f,
+                "Refusing to listen on {hostname}: allow LAN access to serve other machines"
+            ),
+            Self::WeakPassword {
+                hostname,
+                min_length,
+            } => write!(
+                f,
+                "Refusing to listen on {hostname}: the server password needs at least {min_length} characters and must not be easy to guess"
+            ),
+        }
+    }
+}
+
+fn is_strong(password: &str) -> bool {
+    let mut chars = password.chars().collect::<Vec<_>>();
+    if chars.len() < MIN_PASSWORD_LENGTH as usize {
+        return false;
+    }
+    chars.sort_unstable();
+    chars.dedup();
+    chars.len() >= MIN_DISTINCT_CHARS
+}
+
+/// Loopback hostnames are always fine. Anything else needs `allow_lan` and a strong password.
+pub fn check(hostname: &str, password: &str, allow_lan: bool) -> Result<(), ServerBindRejected> {
+    if server::is_loopback_hostname(hostname) {
+        return Ok(());
+    }
+    if !allow_lan {
+        return Err(ServerBindRejected::LanNotAllowed {
+            hostname: hostname.to_string(),
+        });
+    }
+    if !is_strong(password) {
+        return Err(ServerBindRejected::WeakPassword {
+            hostname: hostname.to_string(),
+            min_length: MIN_PASSWORD_LENGTH,
+        });
+    }
+    Ok(())
+}
+
+fn allow_lan(app: &AppHandle) -> bool {
+    settings::load(app).map(|s| s.allow_lan).unwrap_or(false)
+}
+
+/// Whether a sidecar listening on `hostname` with `password` would be started at all.
+pub fn is_allowed(app: &AppHandle, hostname: &str, password: &str) -> bool {
+    check(hostname, password, allow_lan(app)).is_ok()
+}
+
+/// Checks the hostname the sidecar is about to listen on, telling the frontend why it was
+/// rejected.
+pub fn ensure_allowed(app: &AppHandle, hostname: &str, password: &str) -> Result<(), String> {
+    check(hostname, password, allow_lan(app)).map_err(|rejected| {
+        tracing::error!(%hostname, "{rejected}");
+        let message = rejected.to_string();
+        let _ = rejected.emit(app);
+        message
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_allow_lan(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.allow_lan)
+}
+
+/// Whether the server may listen on addresses other machines can reach. Takes effect when the
+/// server is next started.
+#[tauri::command]
+#[specta::specta]
+pub fn set_allow_lan(app: AppHandle, allowed: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.allow_lan = allowed;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_loopback_is_allowed_by_default() {
+        let password = uuid::Uuid::new_v4().to_string();
+
+        assert!(check("127.0.0.1", "", false).is_ok());
+        assert!(check("localhost", "", false).is_ok());
+        assert!(matches!(
+            check("0.0.0.0", &password, false),
+            Err(ServerBindRejected::LanNotAllowed { .. })
+        ));
+        assert!(check("0.0.0.0", &password, true).is_ok());
+        assert!(matches!(
+            check("0.0.0.0", "aaaaaaaaaaaaaaaaaaaa", true),
+            Err(ServerBindRejected::WeakPassword { .. })
+        ));
+        assert!(matches!(
+            check("192.168.1.5", "hunter2", true),
+            Err(ServerBindRejected::WeakPassword { .. })
+        ));
+    }
+}
@@ -1,11 +1,16 @@
-use futures::{FutureExt, Stream, StreamExt, future};
-use process_wrap::tokio::CommandWrap;
+use futures::{
+    FutureExt, Stream, StreamExt,
+    future::{self, Shared},
+};
 #[cfg(unix)]
 use process_wrap::tokio::ProcessGroup;
+use process_wrap::tokio::{ChildWrapper, CommandWrap};
 #[cfg(windows)]
 use process_wrap::tokio::{CommandWrapper, JobObject, KillOnDrop};
+use std::collections::VecDeque;
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::{process::Stdio, time::Duration};
 use tauri::{AppHandle, Manager, path::BaseDirectory};
@@ -19,9 +24,27 @@ use tokio::{
 use tokio_stream::wrappers::ReceiverStream;
 use tracing::Instrument;
 #[cfg(windows)]
-use windows::Win32::System::Threading::{CREATE_NO_WINDOW, CREATE_SUSPENDED};
-
-use crate::server::get_wsl_config;
+pub use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+#[cfg(windows)]
+use windows::Win32::System::Threading::CREATE_SUSPENDED;
+
+use crate::bind_guard;
+use crate::cli_config;
+use crate::cli_version;
+use crate::credentials;
+use crate::dotenv;
+use crate::errors::{DesktopError, ErrorCode};
+use crate::logging;
+use crate::operations::{self, OperationKind};
+use crate::orphans;
+use crate::proxy;
+use crate::server::{get_wsl_config, wsl_distro_args};
+use crate::server_socket;
+use crate::settings;
+use crate::shell_env;
+use crate::sidecar_logs::{self, LogStream};
+use crate::supervisor::SidecarSpec;
+use crate::wsl_path;
 
 #[cfg(windows)]
 #[derive(Clone, Copy, Debug)]
@@ -37,8 +60,18 @@ impl CommandWrapper for WinCreationFlags {
     }
 }
 
-const CLI_INSTALL_DIR: &str = ".opencode/bin";
-const CLI_BINARY_NAME: &str = "opencode";
+pub const CLI_INSTALL_DIR: &str = ".opencode/bin";
+pub const CLI_BINARY_NAME: &str = "opencode";
+/// Linux build of the CLI bundled with Windows builds made with `tauri.wsl.conf.json`.
+const WSL_RESOURCE: &str = "opencode-cli-wsl";
+/// Checksum manifest bundled next to [`WSL_RESOURCE`].
+const WSL_RESOURCE_SHA256: &str = "opencode-cli-wsl.sha256";
+/// Release targets the bundled Linux CLI (`opencode-linux-x64-baseline`) runs on.
+const WSL_RESOURCE_TARGETS: [&str; 2] = ["linux-x64", "linux-x64-baseline"];
+/// Where the WSL sidecar keeps its state unless it is shared with Windows.
+const WSL_STATE_HOME: &str = "$HOME/.local/state";
+/// Variables holding Windows paths the WSL sidecar needs translated.
+const WSL_PATH_ENVS: [&str; 2] = ["OPENCODE_CONFIG", "XDG_STATE_HOME"];
 
 #[derive(serde::Deserialize, Debug)]
 pub struct ServerConfig {
@@ -65,39 +98,183 @@ pub struct TerminatedPayload {
     pub signal: Option<i32>,
 }
 
+/// How a sidecar started by `serve` exited.
+#[derive(Clone, Debug)]
+pub struct SidecarTerminated {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    /// Its last lines of stderr, oldest first, for explaining a crash.
+    pub stderr: Vec<String>,
+}
+
+/// Resolves once the sidecar process exits. Shared so both the startup health check and the
+/// supervisor can wait on it.
+pub type SidecarExit = Shared<oneshot::Receiver<SidecarTerminated>>;
+
+const STDERR_TAIL: usize = 20;
+
+/// How long the sidecar gets to exit on its own before it is force-killed.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+const CONFIG_ATTEMPTS: u32 = 3;
+const CONFIG_TIMEOUT: Duration = Duration::from_secs(10);
+const CONFIG_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[cfg(unix)]
+const SIGTERM: i32 = 15;
+
+#[derive(Clone, Copy, Debug)]
+enum Stop {
+    Graceful,
+    Force,
+}
+
 #[derive(Clone, Debug)]
 pub struct CommandChild {
-    kill: mpsc::Sender<()>,
+    stop: mpsc::Sender<Stop>,
+    exit: Option<SidecarExit>,
+    pid: Option<u32>,
 }
 
 impl CommandChild {
+    /// The spawned process, which may be the user's shell running the CLI.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Whether the process has already terminated.
+    pub fn has_exited(&self) -> bool {
+        self.exit.as_ref().is_some_and(|exit| exit.peek().is_some())
+    }
+
     pub fn kill(&self) -> std::io::Result<()> {
-        self.kill
-            .try_send(())
+        self.stop
+            .try_send(Stop::Force)
             .map_err(|e| std::io::Error::other(e.to_string()))
     }
+
+    /// Asks the process to exit (SIGTERM on unix, `taskkill` on Windows) and waits up to `grace`
+    /// for it to do so before force-killing it.
+    pub async fn shutdown(self, grace: Duration) {
+        let Some(exit) = self.exit.clone() else {
+            let _ = self.kill();
+            return;
+        };
+
+        // The process already exited if nobody is listening for stop requests anymore.
+        if self.stop.send(Stop::Graceful).await.is_err() {
+            return;
+        }
+
+        if tokio::time::timeout(grace, exit.clone()).await.is_ok() {
+            return;
+        }
+
+        tracing::warn!(?grace, "Sidecar did not exit in time, killing it");
+        let _ = self.kill();
+        let _ = tokio::time::timeout(grace, exit).await;
+    }
 }
 
+/// The config as the CLI resolves it. Running the CLI is retried when it fails or hangs, while a
+/// config that doesn't parse is reported as `ConfigInvalid` straight away. A missing config is
+/// not an error, it just leaves everything at its default.
 pub async fn get_config(app: &AppHandle) -> Option<Config> {
-    let (events, _) = spawn_command(app, "debug config", &[]).ok()?;
+    for attempt in 1..=CONFIG_ATTEMPTS {
+        let raw = match tokio::time::timeout(CONFIG_TIMEOUT, get_raw_config(app)).await {
+            Ok(Some(raw)) => raw,
+            Ok(None) => {
+                tracing::warn!(attempt, "Failed to run the CLI to read its config");
+                tokio::time::sleep(CONFIG_RETRY_DELAY * attempt).await;
+                continue;
+            }
+            Err(_) => {
+                tracing::warn!(attempt, "Timed out reading the CLI config");
+                continue;
+            }
+        };
 
-    events
-        .fold(String::new(), async |mut config_str, event| {
+        return match serde_json::from_str::<Config>(&raw) {
+            Ok(config) => {
+                cli_config::config_valid();
+                Some(config)
+            }
+            Err(e) => {
+                cli_config::config_invalid(app, &raw, e);
+                None
+            }
+        };
+    }
+
+    tracing::error!("Giving up on reading the CLI config, using defaults");
+    None
+}
+
+/// Files the CLI merges its global config from, in load order.
+pub const GLOBAL_CONFIG_FILES: [&str; 3] = ["config.json", "opencode.json", "opencode.jsonc"];
+
+/// Where the CLI keeps its global config, following the XDG base directory spec on every
+/// platform like the CLI does.
+pub fn global_config_dir() -> Option<std::path::PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join("opencode"))
+}
+
+/// Where the CLI keeps its data, like its database and caches from the sidecar's installs.
+pub fn data_dir() -> Option<std::path::PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))
+        .map(|dir| dir.join("opencode"))
+}
+
+/// Where the CLI caches installed packages and models.dev data.
+pub fn cache_dir() -> Option<std::path::PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cache")))
+        .map(|dir| dir.join("opencode"))
+}
+
+/// Output of `opencode debug config`, unparsed.
+pub async fn get_raw_config(app: &AppHandle) -> Option<String> {
+    capture_output(app, "debug config").await
+}
+
+pub async fn get_sidecar_version(app: &AppHandle) -> Option<String> {
+    let version = capture_output(app, "--version").await?;
+    Some(version.trim().to_string())
+}
+
+pub async fn get_models(app: &AppHandle) -> Option<String> {
+    capture_output(app, "models").await
+}
+
+async fn capture_output(app: &AppHandle, args: &str) -> Option<String> {
+    let (events, _) = spawn_command(app, args, &[], None).await.ok()?;
+
+    let output = events
+        .fold(String::new(), async |mut output, event| {
             if let CommandEvent::Stdout(s) = &event {
-                config_str += s.as_str()
+                output += s.as_str()
             }
             if let CommandEvent::Stderr(s) = &event {
-                config_str += s.as_str()
+                output += s.as_str()
             }
 
-            config_str
+            output
         })
-        .map(|v| serde_json::from_str::<Config>(&v))
-        .await
-        .ok()
+        .await;
+
+    Some(output)
 }
 
-fn get_cli_install_path() -> Option<std::path::PathBuf> {
+#[cfg(not(windows))]
+pub fn get_cli_install_path() -> Option<std::path::PathBuf> {
     std::env::var("HOME").ok().map(|home| {
         std::path::PathBuf::from(home)
             .join(CLI_INSTALL_DIR)
@@ -105,6 +282,15 @@ fn get_cli_install_path() -> Option<std::path::PathBuf> {
     })
 }
 
+#[cfg(windows)]
+pub fn get_cli_install_path() -> Option<std::path::PathBuf> {
+    dirs::data_local_dir().map(|dir| {
+        dir.join("opencode")
+            .join("bin")
+            .join(format!("{CLI_BINARY_NAME}.exe"))
+    })
+}
+
 pub fn get_sidecar_path(app: &tauri::AppHandle) -> std::path::PathBuf {
     // Get binary with symlinks support
     tauri::process::current_binary(&app.env())
@@ -114,7 +300,7 @@ pub fn get_sidecar_path(app: &tauri::AppHandle) -> std::path::PathBuf {
         .join("opencode-cli")
 }
 
-fn is_cli_installed() -> bool {
+pub fn is_cli_installed() -> bool {
     get_cli_install_path()
         .map(|path| path.exists())
         .unwrap_or(false)
@@ -122,23 +308,152 @@ fn is_cli_installed() -> bool {
 
 const INSTALL_SCRIPT: &str = include_str!("../../../../install");
 
+/// Progress of a CLI install, so the frontend can show what is happening.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CliInstallProgress {
+    /// `percent` is missing while the size of the download is unknown.
+    Downloading {
+        percent: Option<u32>,
+    },
+    Verifying,
+    Extracting,
+    Installing,
+    UpdatingPath,
+    Done {
+        path: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl CliInstallProgress {
+    /// Emits the progress, also as that of the CLI install operation running.
+    pub fn send(self, app: &AppHandle) {
+        let (percent, step) = match &self {
+            Self::Downloading { percent } => (*percent, "downloading"),
+            Self::Verifying => (None, "verifying"),
+            Self::Extracting => (None, "extracting"),
+            Self::Installing => (None, "installing"),
+            Self::UpdatingPath => (None, "updating_path"),
+            Self::Done { .. } | Self::Failed { .. } => (None, "done"),
+        };
+        operations::report(app, OperationKind::CliInstall, percent, step);
+        let _ = self.emit(app);
+    }
+}
+
+/// Emits the outcome of an install the user asked for and passes it on.
+pub fn report_install(
+    app: &AppHandle,
+    result: Result<String, impl Into<DesktopError>>,
+) -> Result<String, DesktopError> {
+    let result = result.map_err(Into::into);
+    let progress = match &result {
+        Ok(path) => CliInstallProgress::Done { path: path.clone() },
+        Err(error) => {
+            tracing::error!(message = %error, "Failed to install CLI");
+            CliInstallProgress::Failed {
+                message: error.message.clone(),
+            }
+        }
+    };
+    let _ = progress.emit(app);
+    result
+}
+
+/// Installs the CLI bundled with the app. It can be cancelled as `operation_id`, up to the point
+/// where the binary is put in place.
 #[tauri::command]
 #[specta::specta]
-pub fn install_cli(app: tauri::AppHandle) -> Result<String, String> {
-    if cfg!(not(unix)) {
-        return Err("CLI installation is only supported on macOS & Linux".to_string());
+pub async fn install_cli(
+    app: tauri::AppHandle,
+    operation_id: Option<String>,
+) -> Result<String, DesktopError> {
+    let operation = operations::begin(&app, OperationKind::CliInstall, operation_id)?;
+    operation
+        .run(async { report_install(&app, install_bundled(&app).await) })
+        .await
+}
+
+pub async fn install_bundled(app: &AppHandle) -> Result<String, DesktopError> {
+    #[cfg(windows)]
+    if is_wsl_enabled(app) {
+        let target = crate::wsl_bootstrap::target(app).await?;
+        if let Some(binary) = offline_wsl_binary(app, &target) {
+            return Ok(install_offline_wsl(app, &binary).await?);
+        }
+        return Ok(install_version_wsl(app, &app.package_info().version.to_string()).await?);
     }
 
-    let sidecar = get_sidecar_path(&app);
+    let sidecar = get_sidecar_path(app);
+    #[cfg(windows)]
+    let sidecar = sidecar.with_extension("exe");
     if !sidecar.exists() {
-        return Err("Sidecar binary not found".to_string());
+        return Err(DesktopError::new(
+            ErrorCode::SidecarNotFound,
+            "Sidecar binary not found",
+        ));
+    }
+
+    install_binary(app, sidecar, app.package_info().version.clone()).await
+}
+
+/// Adds `binary`, the CLI at `version`, to the version cache and makes it the user's CLI.
+/// Returns where it was installed. Once started the copy runs to the end even if the caller is
+/// cancelled, since stopping halfway could leave the user with no CLI at all.
+pub async fn install_binary(
+    app: &AppHandle,
+    binary: PathBuf,
+    version: semver::Version,
+) -> Result<String, DesktopError> {
+    CliInstallProgress::Installing.send(app);
+
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let cached = cli_version::cache_binary(&binary, &version)?;
+        install_binary_blocking(&app, &cached)
+    })
+    .await
+    .map_err(|e| format!("Failed to install CLI: {}", e))?
+}
+
+/// Makes `binary`, already in the version cache, the user's CLI. Like `install_binary`, it
+/// isn't interrupted by cancelling.
+pub async fn activate_binary(app: &AppHandle, binary: PathBuf) -> Result<String, DesktopError> {
+    CliInstallProgress::Installing.send(app);
+
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || install_binary_blocking(&app, &binary))
+        .await
+        .map_err(|e| format!("Failed to install CLI: {}", e))?
+}
+
+fn install_path() -> Result<PathBuf, DesktopError> {
+    get_cli_install_path().ok_or_else(|| {
+        DesktopError::new(
+            ErrorCode::CliInstallPathUnknown,
+            "Could not determine install path",
+        )
+    })
+}
+
+/// The install script copies `binary` and sets up `PATH`; the copy is then swapped for a link into
+/// the version cache, so switching versions never overwrites one.
+#[cfg(not(windows))]
+fn install_binary_blocking(_app: &AppHandle, binary: &Path) -> Result<String, DesktopError> {
+    let install_path = install_path()?;
+    // The script's `cp` would write through a link to the previously active version.
+    if std::fs::symlink_metadata(&install_path).is_ok_and(|m| m.file_type().is_symlink()) {
+        std::fs::remove_file(&install_path)
+            .map_err(|e| format!("Failed to replace existing CLI: {}", e))?;
     }
 
     let temp_script = std::env::temp_dir().join("opencode-install.sh");
     std::fs::write(&temp_script, INSTALL_SCRIPT)
         .map_err(|e| format!("Failed to write install script: {}", e))?;
 
-    #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
         std::fs::set_permissions(&temp_script, std::fs::Permissions::from_mode(0o755))
@@ -147,7 +462,7 @@ pub fn install_cli(app: tauri::AppHandle) -> Result<String, String> {
 
     let output = std::process::Command::new(&temp_script)
         .arg("--binary")
-        .arg(&sidecar)
+        .arg(binary)
         .output()
         .map_err(|e| format!("Failed to run install script: {}", e))?;
 
@@ -155,16 +470,402 @@ pub fn install_cli(app: tauri::AppHandle) -> Result<String, String> {
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Install script failed: {}", stderr));
+        return Err(DesktopError::new(
+            ErrorCode::CliInstallScriptFailed,
+            format!("Install script failed: {}", stderr),
+        )
+        .with("output", stderr.trim()));
+    }
+
+    let link = install_path.with_extension("link");
+    let _ = std::fs::remove_file(&link);
+    std::os::unix::fs::symlink(binary, &link)
+        .and_then(|()| std::fs::rename(&link, &install_path))
+        .map_err(|e| format!("Failed to link CLI version: {}", e))?;
+
+    Ok(install_path.to_string_lossy().to_string())
+}
+
+#[cfg(windows)]
+fn install_binary_blocking(app: &AppHandle, binary: &Path) -> Result<String, DesktopError> {
+    let install_path = install_path()?;
+    let install_dir = install_path
+        .parent()
+        .ok_or_else(|| "Could not determine install directory".to_string())?;
+
+    std::fs::create_dir_all(install_dir)
+        .map_err(|e| format!("Failed to create install directory: {}", e))?;
+
+    // A running executable can't be overwritten on Windows, but it can be renamed out of the way.
+    let stale = install_path.with_extension("exe.old");
+    let _ = std::fs::remove_file(&stale);
+    if install_path.exists() {
+        std::fs::rename(&install_path, &stale)
+            .map_err(|e| format!("Failed to replace existing CLI: {}", e))?;
     }
 
-    let install_path =
-        get_cli_install_path().ok_or_else(|| "Could not determine install path".to_string())?;
+    if let Err(e) = std::fs::copy(binary, &install_path) {
+        // Put the previous binary back so a failed update doesn't leave no CLI at all.
+        let _ = std::fs::remove_file(&install_path);
+        if stale.exists() {
+            let _ = std::fs::rename(&stale, &install_path);
+        }
+        return Err(format!("Failed to copy CLI binary: {}", e).into());
+    }
+
+    CliInstallProgress::UpdatingPath.send(app);
+    if let Err(e) = add_to_user_path(install_dir) {
+        tracing::warn!("Failed to add CLI to PATH: {e}");
+    }
 
     Ok(install_path.to_string_lossy().to_string())
 }
 
-pub fn sync_cli(app: tauri::AppHandle) -> Result<(), String> {
+#[cfg(windows)]
+fn add_to_user_path(dir: &std::path::Path) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+
+    // [Environment]::SetEnvironmentVariable persists to HKCU\Environment and broadcasts the
+    // change, so new terminals pick it up without logging out.
+    const SCRIPT: &str = r#"
+        $dir = $env:OPENCODE_CLI_DIR
+        $path = [Environment]::GetEnvironmentVariable('Path', 'User')
+        $entries = @($path -split ';' | Where-Object { $_ })
+        if ($entries -notcontains $dir) {
+            [Environment]::SetEnvironmentVariable('Path', (($entries + $dir) -join ';'), 'User')
+        }
+    "#;
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("OPENCODE_CLI_DIR", dir)
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(())
+}
+
+/// The last percentage in curl's `--progress-bar` output, which redraws itself with `\r`.
+#[cfg(any(windows, test))]
+fn curl_percent(output: &str) -> Option<u32> {
+    let end = output.rfind('%')?;
+    let start = output[..end]
+        .rfind(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map_or(0, |i| i + 1);
+    let percent = output[start..end].parse::<f32>().ok()?;
+    Some(percent.clamp(0.0, 100.0) as u32)
+}
+
+/// Installs `version` of the CLI inside WSL. The install script downloads it from the same
+/// releases the app would, and its curl progress bar is forwarded as download progress.
+#[cfg(windows)]
+pub async fn install_version_wsl(app: &tauri::AppHandle, version: &str) -> Result<String, String> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut child = Command::new("wsl")
+        .args(wsl_distro_args(app))
+        .args(["-e", "bash", "-s", "--", "--version", version])
+        .stdin(Stdio::piped())
+        // Only stderr carries progress and errors, and an unread stdout pipe would fill up and
+        // stall the script.
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .spawn()
+        .map_err(|e| format!("Failed to run install script in WSL: {}", e))?;
+
+    // The script is fed over stdin, so normalise line endings from Windows checkouts.
+    let script = INSTALL_SCRIPT.replace("\r\n", "\n");
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "Failed to open WSL stdin".to_string())?;
+    let write = async move {
+        stdin
+            .write_all(script.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write install script: {}", e))
+    };
+
+    let mut stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "Failed to open WSL stderr".to_string())?;
+    let read = async {
+        let mut output = String::new();
+        let mut buf = [0; 1024];
+        let mut last = None;
+        CliInstallProgress::Downloading { percent: None }.send(app);
+        while let Ok(n @ 1..) = stderr.read(&mut buf).await {
+            let chunk = String::from_utf8_lossy(&buf[..n]);
+            output.push_str(&chunk);
+
+            let percent = curl_percent(&chunk);
+            if percent.is_some() && percent != last {
+                last = percent;
+                CliInstallProgress::Downloading { percent }.send(app);
+            }
+        }
+        output
+    };
+
+    let (written, stderr) = futures::join!(write, read);
+    written?;
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| format!("Failed to run install script in WSL: {}", e))?;
+
+    if !status.success() {
+        // Drop the progress bar redraws so only the error is left.
+        let message = stderr
+            .split(['\r', '\n'])
+            .filter(|line| !line.trim().is_empty() && curl_percent(line).is_none())
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(format!("Install script failed: {}", message));
+    }
+
+    Ok(format!("~/{CLI_INSTALL_DIR}/{CLI_BINARY_NAME}"))
+}
+
+/// Copies the bundled Linux CLI into WSL, for machines that can't download it.
+#[cfg(windows)]
+async fn install_offline_wsl(app: &tauri::AppHandle, binary: &WslBinary) -> Result<String, String> {
+    CliInstallProgress::Installing.send(app);
+
+    let script = format!(
+        "BIN=\"$HOME/{CLI_INSTALL_DIR}/{CLI_BINARY_NAME}\"\n{}",
+        wsl_copy_command(binary)
+    );
+    let output = Command::new("wsl")
+        .args(wsl_distro_args(app))
+        .args(["-e", "bash", "-c", &script])
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to copy CLI into WSL: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to copy CLI into WSL: {}", stderr.trim()));
+    }
+
+    Ok(format!("~/{CLI_INSTALL_DIR}/{CLI_BINARY_NAME}"))
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug, Default)]
+pub struct UninstallReport {
+    pub removed_binary: Option<String>,
+    pub cleaned_files: Vec<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn uninstall_cli(app: tauri::AppHandle) -> Result<UninstallReport, DesktopError> {
+    #[cfg(windows)]
+    {
+        if is_wsl_enabled(&app) {
+            uninstall_cli_wsl(&app).map_err(DesktopError::from)
+        } else {
+            uninstall_cli_windows()
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = app;
+        uninstall_cli_unix()
+    }
+}
+
+#[cfg(not(windows))]
+fn uninstall_cli_unix() -> Result<UninstallReport, DesktopError> {
+    let mut report = UninstallReport::default();
+
+    let install_path = install_path()?;
+    if install_path.exists() {
+        std::fs::remove_file(&install_path)
+            .map_err(|e| format!("Failed to remove CLI binary: {}", e))?;
+        report.removed_binary = Some(install_path.to_string_lossy().to_string());
+    }
+
+    let Some(install_dir) = install_path.parent() else {
+        return Ok(report);
+    };
+    let _ = std::fs::remove_dir(install_dir);
+
+    for file in shell_config_files() {
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let Some(cleaned) = strip_path_entries(&content, &install_dir.to_string_lossy()) else {
+            continue;
+        };
+
+        match std::fs::write(&file, cleaned) {
+            Ok(()) => report
+                .cleaned_files
+                .push(file.to_string_lossy().to_string()),
+            Err(e) => tracing::warn!(file = %file.display(), "Failed to clean shell config: {e}"),
+        }
+    }
+
+    tracing::info!(?report, "Uninstalled CLI");
+
+    Ok(report)
+}
+
+/// Every user-level shell config the install script may have appended a PATH entry to.
+#[cfg(not(windows))]
+fn shell_config_files() -> Vec<std::path::PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return vec![];
+    };
+    let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| home.join(".config"));
+    let zdotdir = std::env::var_os("ZDOTDIR")
+        .filter(|v| !v.is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| home.clone());
+
+    vec![
+        home.join(".config/fish/config.fish"),
+        zdotdir.join(".zshrc"),
+        zdotdir.join(".zshenv"),
+        xdg_config.join("zsh/.zshrc"),
+        xdg_config.join("zsh/.zshenv"),
+        home.join(".bashrc"),
+        home.join(".bash_profile"),
+        home.join(".profile"),
+        home.join(".ashrc"),
+        xdg_config.join("bash/.bashrc"),
+        xdg_config.join("bash/.bash_profile"),
+    ]
+}
+
+/// Removes the `# opencode` marker and PATH line the install script appends, returning `None`
+/// when the content has nothing to remove.
+#[cfg(not(windows))]
+fn strip_path_entries(content: &str, install_dir: &str) -> Option<String> {
+    let entries = [
+        format!("export PATH={install_dir}:$PATH"),
+        format!("fish_add_path {install_dir}"),
+    ];
+
+    let lines = content.lines().collect::<Vec<_>>();
+    let mut kept: Vec<&str> = Vec::with_capacity(lines.len());
+    let mut changed = false;
+
+    for line in lines {
+        if !entries.iter().any(|entry| entry == line) {
+            kept.push(line);
+            continue;
+        }
+
+        changed = true;
+        if kept.last() == Some(&"# opencode") {
+            kept.pop();
+            // the script separates its block with a blank line
+            if kept.last() == Some(&"") {
+                kept.pop();
+            }
+        }
+    }
+
+    if !changed {
+        return None;
+    }
+
+    let mut cleaned = kept.join("\n");
+    if content.ends_with('\n') {
+        cleaned.push('\n');
+    }
+    Some(cleaned)
+}
+
+#[cfg(windows)]
+fn uninstall_cli_windows() -> Result<UninstallReport, DesktopError> {
+    use std::os::windows::process::CommandExt;
+
+    let mut report = UninstallReport::default();
+
+    let install_path = install_path()?;
+    let _ = std::fs::remove_file(install_path.with_extension("exe.old"));
+    if install_path.exists() {
+        std::fs::remove_file(&install_path)
+            .map_err(|e| format!("Failed to remove CLI binary: {}", e))?;
+        report.removed_binary = Some(install_path.to_string_lossy().to_string());
+    }
+
+    let Some(install_dir) = install_path.parent() else {
+        return Ok(report);
+    };
+    let _ = std::fs::remove_dir(install_dir);
+
+    const SCRIPT: &str = r#"
+        $dir = $env:OPENCODE_CLI_DIR
+        $path = [Environment]::GetEnvironmentVariable('Path', 'User')
+        $entries = @($path -split ';' | Where-Object { $_ })
+        if ($entries -contains $dir) {
+            [Environment]::SetEnvironmentVariable('Path', (($entries | Where-Object { $_ -ne $dir }) -join ';'), 'User')
+            Write-Output 'removed'
+        }
+    "#;
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("OPENCODE_CLI_DIR", install_dir)
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+
+    if String::from_utf8_lossy(&output.stdout).trim() == "removed" {
+        report
+            .cleaned_files
+            .push("HKCU\\Environment\\Path".to_string());
+    }
+
+    tracing::info!(?report, "Uninstalled CLI");
+
+    Ok(report)
+}
+
+#[cfg(windows)]
+fn uninstall_cli_wsl(app: &tauri::AppHandle) -> Result<UninstallReport, String> {
+    use std::os::windows::process::CommandExt;
+
+    let path = format!("$HOME/{CLI_INSTALL_DIR}/{CLI_BINARY_NAME}");
+    let script = format!("if [ -e \"{path}\" ]; then rm -f \"{path}\" && echo \"{path}\"; fi");
+
+    let output = std::process::Command::new("wsl")
+        .args(wsl_distro_args(app))
+        .args(["-e", "sh", "-c", &script])
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .output()
+        .map_err(|e| format!("Failed to run uninstall in WSL: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to remove CLI from WSL: {}", stderr));
+    }
+
+    let removed = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    Ok(UninstallReport {
+        removed_binary: (!removed.is_empty()).then_some(removed),
+        cleaned_files: vec![],
+    })
+}
+
+pub async fn sync_cli(app: tauri::AppHandle) -> Result<(), String> {
     if cfg!(debug_assertions) {
         tracing::debug!("Skipping CLI sync for debug build");
         return Ok(());
@@ -175,6 +876,35 @@ pub fn sync_cli(app: tauri::AppHandle) -> Result<(), String> {
         return Ok(());
     }
 
+    let cli_version = installed_cli_version()?;
+    let app_version = app.package_info().version.clone();
+
+    if cli_version >= app_version {
+        tracing::info!(
+            %cli_version, %app_version,
+            "CLI is up to date, skipping sync"
+        );
+        return Ok(());
+    }
+
+    tracing::info!(
+        %cli_version, %app_version,
+        "CLI is older than app version, syncing"
+    );
+
+    if crate::update_guard::defer_cli(&app, &app_version.to_string()).await {
+        return Ok(());
+    }
+
+    verify_sidecar(&app)?;
+    install_cli(app, None).await?;
+
+    tracing::info!("Synced installed CLI");
+
+    Ok(())
+}
+
+pub fn installed_cli_version() -> Result<semver::Version, String> {
     let cli_path =
         get_cli_install_path().ok_or_else(|| "Could not determine CLI install path".to_string())?;
 
@@ -188,40 +918,201 @@ pub fn sync_cli(app: tauri::AppHandle) -> Result<(), String> {
     }
 
     let cli_version_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let cli_version = semver::Version::parse(&cli_version_str)
-        .map_err(|e| format!("Failed to parse CLI version '{}': {}", cli_version_str, e))?;
+    semver::Version::parse(&cli_version_str)
+        .map_err(|e| format!("Failed to parse CLI version '{}': {}", cli_version_str, e))
+}
 
-    let app_version = app.package_info().version.clone();
+/// Checks the bundled sidecar before it is copied over the user's CLI, so a tampered or
+/// corrupted binary is never installed. Bundling signs the sidecar after its checksum was taken,
+/// so in a signed app it has to carry a valid signature from the app's own signer instead.
+fn verify_sidecar(app: &tauri::AppHandle) -> Result<(), String> {
+    let sidecar = get_sidecar_path(app);
+    #[cfg(windows)]
+    let sidecar = sidecar.with_extension("exe");
 
-    if cli_version >= app_version {
-        tracing::info!(
-            %cli_version, %app_version,
-            "CLI is up to date, skipping sync"
-        );
+    let app_signer = std::env::current_exe()
+        .ok()
+        .and_then(|exe| code_signer(&exe));
+    if let Some(app_signer) = app_signer {
+        if code_signer(&sidecar).as_deref() != Some(app_signer.as_str()) {
+            return Err(format!(
+                "Sidecar {} is not signed by {}, refusing to sync",
+                sidecar.display(),
+                app_signer
+            ));
+        }
+
+        tracing::debug!(signer = %app_signer, "Verified sidecar signature");
         return Ok(());
     }
 
-    tracing::info!(
-        %cli_version, %app_version,
-        "CLI is older than app version, syncing"
-    );
-
-    install_cli(app)?;
+    let Some(expected) = option_env!("OPENCODE_CLI_SHA256") else {
+        return Err(
+            "No checksum manifest was bundled for the sidecar, refusing to sync".to_string(),
+        );
+    };
 
-    tracing::info!("Synced installed CLI");
+    let actual = sha256_file(&sidecar)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(format!(
+            "Sidecar checksum mismatch for {}: expected {}, got {}",
+            sidecar.display(),
+            expected,
+            actual
+        ));
+    }
 
+    tracing::debug!(checksum = %actual, "Verified sidecar checksum");
     Ok(())
 }
 
-fn get_user_shell() -> String {
-    std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+/// The team that signed the binary at `path`, if its signature is intact.
+#[cfg(target_os = "macos")]
+fn code_signer(path: &Path) -> Option<String> {
+    let verified = std::process::Command::new("codesign")
+        .args(["--verify", "--strict"])
+        .arg(path)
+        .output()
+        .ok()?;
+    if !verified.status.success() {
+        return None;
+    }
+
+    // `codesign -dv` describes the signature on stderr.
+    let details = std::process::Command::new("codesign")
+        .arg("-dv")
+        .arg(path)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&details.stderr)
+        .lines()
+        .find_map(|line| line.strip_prefix("TeamIdentifier="))
+        .filter(|team| *team != "not set")
+        .map(str::to_string)
+}
+
+/// The thumbprint of the certificate that signed the binary at `path`, if its signature is valid.
+#[cfg(windows)]
+fn code_signer(path: &Path) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+
+    const SCRIPT: &str = r#"
+        $signature = Get-AuthenticodeSignature -LiteralPath $env:OPENCODE_SIGNED_FILE
+        if ($signature.Status -eq 'Valid') { $signature.SignerCertificate.Thumbprint }
+    "#;
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("OPENCODE_SIGNED_FILE", path)
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .output()
+        .ok()?;
+    let thumbprint = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !thumbprint.is_empty()).then_some(thumbprint)
+}
+
+/// Linux bundles aren't signed, so the sidecar keeps the checksum it was built with.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn code_signer(_path: &Path) -> Option<String> {
+    None
 }
 
-fn is_wsl_enabled(_app: &tauri::AppHandle) -> bool {
+fn sha256_file(path: &std::path::Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to open sidecar binary: {}", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read sidecar binary: {}", e))?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn is_wsl_enabled(_app: &tauri::AppHandle) -> bool {
     get_wsl_config(_app.clone()).is_ok_and(|v| v.enabled)
 }
 
-fn shell_escape(input: &str) -> String {
+/// The Linux CLI bundled for WSL and the checksum it was built with.
+#[derive(Clone, Debug)]
+pub struct WslBinary {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// The Linux CLI bundled for WSL, if this build has one along with its checksum manifest.
+fn bundled_wsl_binary(app: &AppHandle) -> Option<WslBinary> {
+    let path = app
+        .path()
+        .resolve(WSL_RESOURCE, BaseDirectory::Resource)
+        .ok()
+        .filter(|path| path.is_file())?;
+    let manifest = app
+        .path()
+        .resolve(WSL_RESOURCE_SHA256, BaseDirectory::Resource)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())?;
+    let sha256 = manifest
+        .split_whitespace()
+        .next()
+        .filter(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))?;
+
+    Some(WslBinary {
+        path,
+        sha256: sha256.to_ascii_lowercase(),
+    })
+}
+
+/// The binary to copy into WSL when offline install is enabled and it runs on the distro's
+/// release `target`. Falls back to downloading otherwise.
+pub fn offline_wsl_binary(app: &AppHandle, target: &str) -> Option<WslBinary> {
+    if !settings::load(app).is_ok_and(|s| s.wsl_offline_install) {
+        return None;
+    }
+    let Some(binary) = bundled_wsl_binary(app) else {
+        tracing::warn!("No Linux CLI bundled with this build, downloading it in WSL instead");
+        return None;
+    };
+    if !WSL_RESOURCE_TARGETS.contains(&target) {
+        tracing::warn!(%target, "The bundled Linux CLI does not run on this distro, downloading it in WSL instead");
+        return None;
+    }
+    Some(binary)
+}
+
+/// Shell command copying `binary`, a Windows path, to `$BIN` inside WSL and checking it against
+/// its bundled checksum before making it executable.
+pub fn wsl_copy_command(binary: &WslBinary) -> String {
+    format!(
+        "mkdir -p \"$(dirname \"$BIN\")\" && cp \"$(wslpath -u {})\" \"$BIN\" && \
+         {{ echo \"{}  $BIN\" | sha256sum -c --status || {{ rm -f \"$BIN\"; echo 'The bundled CLI does not match its checksum' >&2; exit 1; }}; }} && \
+         chmod 755 \"$BIN\"",
+        shell_escape(&binary.path.to_string_lossy()),
+        binary.sha256
+    )
+}
+
+/// Whether the WSL sidecar keeps its state in the app's data directory instead of inside WSL.
+pub fn shared_wsl_state(app: &AppHandle) -> bool {
+    settings::load(app)
+        .map(|s| s.wsl_shared_state)
+        .unwrap_or(false)
+}
+
+/// A `KEY=value` assignment for the WSL launch script. Windows paths to the config file and state
+/// directory are translated, since the CLI inside WSL can't open them as they are, leaving the
+/// ones only `wslpath` knows to the script.
+fn wsl_env(key: &str, value: &str) -> String {
+    if !WSL_PATH_ENVS.contains(&key) {
+        return format!("{key}={}", shell_escape(value));
+    }
+    match wsl_path::to_linux(value) {
+        Some(path) => format!("{key}={}", shell_escape(&path)),
+        None => format!("{key}=\"$(wslpath -u {})\"", shell_escape(value)),
+    }
+}
+
+pub fn shell_escape(input: &str) -> String {
     if input.is_empty() {
         return "''".to_string();
     }
@@ -232,11 +1123,17 @@ fn shell_escape(input: &str) -> String {
     escaped
 }
 
-pub fn spawn_command(
-    app: &tauri::AppHandle,
-    args: &str,
-    extra_env: &[(&str, String)],
-) -> Result<(impl Stream<Item = CommandEvent> + 'static, CommandChild), std::io::Error> {
+/// The CLI version pinned by the project in `cwd`. WSL installs its own CLI, so pins only apply
+/// to native sidecars.
+pub fn pinned_binary(app: &AppHandle, cwd: Option<&Path>) -> Option<PathBuf> {
+    cwd.and_then(|cwd| cli_version::binary_for(app, cwd))
+}
+
+/// The environment the CLI runs with in `cwd`: the desktop defaults and custom config file, then the
+/// proxy, the project's `.env` file and the user's own variables, each able to override the ones
+/// before.
+pub fn sidecar_envs(app: &tauri::AppHandle, cwd: Option<&Path>) -> Vec<(String, String)> {
+    let settings = settings::load(app).unwrap_or_default();
     let state_dir = app
         .path()
         .resolve("", BaseDirectory::AppLocalData)
@@ -257,6 +1154,25 @@ pub fn spawn_command(
             state_dir.to_string_lossy().to_string(),
         ),
     ];
+    envs.extend(
+        settings
+            .config_path
+            .map(|path| ("OPENCODE_CONFIG".to_string(), path)),
+    );
+    envs.extend(proxy::sidecar_envs(app));
+    envs.extend(dotenv::load(app, cwd));
+    envs.extend(settings.extra_env);
+    envs
+}
+
+pub async fn spawn_command(
+    app: &tauri::AppHandle,
+    args: &str,
+    extra_env: &[(&str, String)],
+    cwd: Option<&Path>,
+) -> Result<(impl Stream<Item = CommandEvent> + 'static, CommandChild), std::io::Error> {
+    let mut envs = sidecar_envs(app, cwd);
+    // User variables may override the defaults, but not what the caller needs to set.
     envs.extend(
         extra_env
             .iter()
@@ -266,43 +1182,53 @@ pub fn spawn_command(
     let mut cmd = if cfg!(windows) {
         if is_wsl_enabled(app) {
             tracing::info!("WSL is enabled, spawning CLI server in WSL");
-            let version = app.package_info().version.to_string();
+            // `wsl_bootstrap::ensure` installs the CLI before anything is spawned.
             let mut script = vec![
                 "set -e".to_string(),
-                "BIN=\"$HOME/.opencode/bin/opencode\"".to_string(),
+                format!("BIN=\"$HOME/{CLI_INSTALL_DIR}/{CLI_BINARY_NAME}\""),
                 "if [ ! -x \"$BIN\" ]; then".to_string(),
-                format!(
-                    "  curl -fsSL https://opencode.ai/install | bash -s -- --version {} --no-modify-path",
-                    shell_escape(&version)
-                ),
+                "  echo \"The CLI is not installed in WSL at $BIN\" >&2".to_string(),
+                "  exit 127".to_string(),
                 "fi".to_string(),
             ];
 
+            let shared_state = shared_wsl_state(app);
             let mut env_prefix = vec![
                 "OPENCODE_EXPERIMENTAL_ICON_DISCOVERY=true".to_string(),
                 "OPENCODE_EXPERIMENTAL_FILEWATCHER=true".to_string(),
                 "OPENCODE_CLIENT=desktop".to_string(),
-                "XDG_STATE_HOME=\"$HOME/.local/state\"".to_string(),
             ];
+            if !shared_state {
+                env_prefix.push(format!("XDG_STATE_HOME=\"{WSL_STATE_HOME}\""));
+            }
             env_prefix.extend(
                 envs.iter()
                     .filter(|(key, _)| key != "OPENCODE_EXPERIMENTAL_ICON_DISCOVERY")
                     .filter(|(key, _)| key != "OPENCODE_EXPERIMENTAL_FILEWATCHER")
                     .filter(|(key, _)| key != "OPENCODE_CLIENT")
-                    .filter(|(key, _)| key != "XDG_STATE_HOME")
-                    .map(|(key, value)| format!("{}={}", key, shell_escape(value))),
+                    .filter(|(key, _)| shared_state || key != "XDG_STATE_HOME")
+                    .map(|(key, value)| wsl_env(key, value)),
             );
 
             script.push(format!("{} exec \"$BIN\" {}", env_prefix.join(" "), args));
 
             let mut cmd = Command::new("wsl");
+            cmd.args(wsl_distro_args(app));
+            if let Some(cwd) = cwd {
+                cmd.arg("--cd").arg(cwd);
+            }
             cmd.args(["-e", "bash", "-lc", &script.join("\n")]);
             cmd
         } else {
-            let sidecar = get_sidecar_path(app);
+            let sidecar = pinned_binary(app, cwd).unwrap_or_else(|| get_sidecar_path(app));
             let mut cmd = Command::new(sidecar);
             cmd.args(args.split_whitespace());
 
+            if shell_env::enabled(app)
+                && let Some(shell_env) = shell_env::get(&shell_env::resolve(app)).await
+            {
+                cmd.envs(shell_env.iter().map(|(key, value)| (key, value)));
+            }
             for (key, value) in envs {
                 cmd.env(key, value);
             }
@@ -310,18 +1236,22 @@ pub fn spawn_command(
             cmd
         }
     } else {
-        let sidecar = get_sidecar_path(app);
-        let shell = get_user_shell();
-
-        let line = if shell.ends_with("/nu") {
-            format!("^\"{}\" {}", sidecar.display(), args)
-        } else {
-            format!("\"{}\" {}", sidecar.display(), args)
+        let sidecar = pinned_binary(app, cwd).unwrap_or_else(|| get_sidecar_path(app));
+        let shell = shell_env::resolve(app);
+
+        let mut cmd = match shell_env::get(&shell).await {
+            Some(shell_env) => {
+                let mut cmd = Command::new(sidecar);
+                cmd.args(args.split_whitespace());
+                cmd.envs(shell_env.iter().map(|(key, value)| (key, value)));
+                cmd
+            }
+            None => {
+                let line = shell_env::command_line(&shell, &sidecar.display().to_string(), args);
+                Command::from(shell.command(&line))
+            }
         };
 
-        let mut cmd = Command::new(shell);
-        cmd.args(["-il", "-c", &line]);
-
         for (key, value) in envs {
             cmd.env(key, value);
         }
@@ -329,6 +1259,13 @@ pub fn spawn_command(
         cmd
     };
 
+    // wsl applies the directory itself through `--cd`.
+    if let Some(cwd) = cwd
+        && !(cfg!(windows) && is_wsl_enabled(app))
+    {
+        cmd.current_dir(cwd);
+    }
+
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
     cmd.stdin(Stdio::null());
@@ -346,9 +1283,10 @@ pub fn spawn_command(
     }
 
     let mut child = wrap.spawn()?;
+    let pid = child.id();
     let guard = Arc::new(tokio::sync::RwLock::new(()));
     let (tx, rx) = mpsc::channel(256);
-    let (kill_tx, mut kill_rx) = mpsc::channel(1);
+    let (stop_tx, mut stop_rx) = mpsc::channel(1);
 
     let stdout = spawn_pipe_reader(
         tx.clone(),
@@ -364,7 +1302,7 @@ pub fn spawn_command(
     );
 
     tokio::task::spawn(async move {
-        let mut kill_open = true;
+        let mut stop_open = true;
         let status = loop {
             match child.try_wait() {
                 Ok(Some(status)) => break Ok(status),
@@ -373,12 +1311,20 @@ pub fn spawn_command(
             }
 
             tokio::select! {
-                msg = kill_rx.recv(), if kill_open => {
-                    if msg.is_some() {
+                msg = stop_rx.recv(), if stop_open => match msg {
+                    Some(Stop::Graceful) => {
+                        if let Err(err) = terminate(child.as_ref()) {
+                            tracing::warn!(%err, "Failed to stop process gracefully, killing it");
+                            let _ = child.start_kill();
+                        }
+                    }
+                    Some(Stop::Force) => {
+                        #[cfg(windows)]
+                        let _ = kill_tree(child.as_ref());
                         let _ = child.start_kill();
                     }
-                    kill_open = false;
-                }
+                    None => stop_open = false,
+                },
                 _ = tokio::time::sleep(Duration::from_millis(100)) => {}
             }
         };
@@ -403,7 +1349,52 @@ pub fn spawn_command(
     let event_stream = ReceiverStream::new(rx);
     let event_stream = sqlite_migration::logs_middleware(app.clone(), event_stream);
 
-    Ok((event_stream, CommandChild { kill: kill_tx }))
+    Ok((
+        event_stream,
+        CommandChild {
+            stop: stop_tx,
+            exit: None,
+            pid,
+        },
+    ))
+}
+
+#[cfg(unix)]
+fn terminate(child: &dyn ChildWrapper) -> std::io::Result<()> {
+    child.signal(SIGTERM)
+}
+
+#[cfg(windows)]
+fn terminate(child: &dyn ChildWrapper) -> std::io::Result<()> {
+    // Without `/F` taskkill asks the process tree to close instead of terminating it.
+    taskkill(child, &[])
+}
+
+/// Terminates the process tree outright, once it had its chance to exit.
+#[cfg(windows)]
+fn kill_tree(child: &dyn ChildWrapper) -> std::io::Result<()> {
+    taskkill(child, &["/F"])
+}
+
+#[cfg(windows)]
+fn taskkill(child: &dyn ChildWrapper, flags: &[&str]) -> std::io::Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    let pid = child
+        .id()
+        .ok_or_else(|| std::io::Error::other("Process has no pid"))?;
+
+    let status = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T"])
+        .args(flags)
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .status()?;
+
+    if !status.success() {
+        return Err(std::io::Error::other("taskkill failed"));
+    }
+
+    Ok(())
 }
 
 fn signal_from_status(status: std::process::ExitStatus) -> Option<i32> {
@@ -417,38 +1408,86 @@ fn signal_from_status(status: std::process::ExitStatus) -> Option<i32> {
     }
 }
 
-pub fn serve(
+/// Starts the sidecar described by `spec`. Fails without spawning anything when the hostname
+/// would expose the server to other machines without that being allowed.
+pub async fn serve(
     app: &AppHandle,
-    hostname: &str,
-    port: u32,
-    password: &str,
-) -> (CommandChild, oneshot::Receiver<TerminatedPayload>) {
-    let (exit_tx, exit_rx) = oneshot::channel::<TerminatedPayload>();
-
-    tracing::info!(port, "Spawning sidecar");
+    spec: &SidecarSpec,
+) -> Result<(CommandChild, SidecarExit), String> {
+    let (exit_tx, exit_rx) = oneshot::channel::<SidecarTerminated>();
+    let SidecarSpec {
+        hostname,
+        port,
+        password,
+        tls,
+        directory,
+        wsl_address,
+        socket,
+        bridge_port: _,
+    } = spec;
+    let listen = wsl_address.as_deref().unwrap_or(hostname);
+    bind_guard::ensure_allowed(app, listen, password)?;
+
+    tracing::info!(port, tls = tls.is_some(), ?directory, "Spawning sidecar");
 
-    let envs = [
+    let mut envs = vec![
         ("OPENCODE_SERVER_USERNAME", "opencode".to_string()),
         ("OPENCODE_SERVER_PASSWORD", password.to_string()),
     ];
+    match credentials::tokens_file(app) {
+        Ok(path) => envs.push(("OPENCODE_SERVER_TOKENS_FILE", path.display().to_string())),
+        Err(e) => tracing::warn!("{e}, server tokens will not be accepted"),
+    }
+    if let Some(tls) = tls {
+        envs.push(("OPENCODE_TLS_CERT", tls.cert.display().to_string()));
+        envs.push(("OPENCODE_TLS_KEY", tls.key.display().to_string()));
+    }
+    // Lets the sidecar keep logging if it outlives this session; WSL can't reach the directory.
+    if !(cfg!(windows) && is_wsl_enabled(app)) {
+        match orphans::detached_log_dir(app) {
+            Ok(dir) => envs.push(("OPENCODE_DETACHED_LOG_DIR", dir.display().to_string())),
+            Err(e) => tracing::warn!("{e}, an adopted sidecar will not have logs"),
+        }
+    }
+    // The server ignores `--hostname` and `--port` when it has a socket to listen on.
+    if let Some(socket) = socket {
+        server_socket::remove_stale(socket);
+        envs.push(("OPENCODE_SERVER_SOCKET", socket.display().to_string()));
+    }
 
-    let (events, child) = spawn_command(
+    let (events, mut child) = spawn_command(
         app,
-        format!("--print-logs --log-level WARN serve --hostname {hostname} --port {port}").as_str(),
+        format!(
+            "--print-logs --log-level {} serve --hostname {listen} --port {port}",
+            logging::sidecar_log_level(app)
+        )
+        .as_str(),
         &envs,
+        directory.as_deref(),
     )
-    .expect("Failed to spawn opencode");
+    .await
+    .map_err(|e| format!("Failed to spawn opencode: {}", e))?;
+    let pid = child.pid();
+    if let Some(pid) = pid {
+        orphans::record(app, pid, spec);
+    }
 
     let mut exit_tx = Some(exit_tx);
+    let mut stderr = VecDeque::with_capacity(STDERR_TAIL);
+    let app = app.clone();
     tokio::spawn(
         events
             .for_each(move |event| {
                 match event {
                     CommandEvent::Stdout(line) => {
-                        tracing::info!("{line}");
+                        sidecar_logs::record(&app, LogStream::Stdout, line);
                     }
                     CommandEvent::Stderr(line) => {
-                        tracing::info!("{line}");
+                        if stderr.len() == STDERR_TAIL {
+                            stderr.pop_front();
+                        }
+                        stderr.push_back(line.clone());
+                        sidecar_logs::record(&app, LogStream::Stderr, line);
                     }
                     CommandEvent::Error(err) => {
                         tracing::error!("{err}");
@@ -459,9 +1498,15 @@ pub fn serve(
                             signal = ?payload.signal,
                             "Sidecar terminated"
                         );
-
+                        if let Some(pid) = pid {
+                            orphans::forget(&app, pid);
+                        }
                         if let Some(tx) = exit_tx.take() {
-                            let _ = tx.send(payload);
+                            let _ = tx.send(SidecarTerminated {
+                                code: payload.code,
+                                signal: payload.signal,
+                                stderr: stderr.drain(..).collect(),
+                            });
                         }
                     }
                 }
@@ -471,7 +1516,68 @@ pub fn serve(
             .instrument(tracing::info_span!("sidecar")),
     );
 
-    (child, exit_rx)
+    let exit = exit_rx.shared();
+    child.exit = Some(exit.clone());
+
+    Ok((child, exit))
+}
+
+/// Takes charge of a sidecar started by a previous session. Its output pipes closed with that
+/// session, so its logs are followed in the file it moved them to; stop requests signal its
+/// process tree.
+pub fn adopt(app: &AppHandle, pid: u32) -> (CommandChild, SidecarExit) {
+    let (exit_tx, exit_rx) = oneshot::channel();
+    let (stop_tx, mut stop_rx) = mpsc::channel(1);
+    let app = app.clone();
+
+    tokio::spawn(async move {
+        let mut system = sysinfo::System::new();
+        let mut stop_open = true;
+        let mut logs = orphans::LogTail::new(&app, pid);
+        loop {
+            for line in logs.iter_mut().flat_map(orphans::LogTail::read_lines) {
+                sidecar_logs::record(&app, LogStream::Stderr, line);
+            }
+
+            system.refresh_processes_specifics(
+                sysinfo::ProcessesToUpdate::Some(&[sysinfo::Pid::from_u32(pid)]),
+                true,
+                sysinfo::ProcessRefreshKind::nothing(),
+            );
+            if system.process(sysinfo::Pid::from_u32(pid)).is_none() {
+                break;
+            }
+
+            tokio::select! {
+                msg = stop_rx.recv(), if stop_open => match msg {
+                    Some(Stop::Graceful) => {
+                        orphans::signal_tree(pid, false);
+                    }
+                    Some(Stop::Force) => {
+                        orphans::signal_tree(pid, true);
+                    }
+                    None => stop_open = false,
+                },
+                _ = tokio::time::sleep(Duration::from_millis(500)) => {}
+            }
+        }
+
+        tracing::info!(pid, "Adopted sidecar terminated");
+        orphans::forget(&app, pid);
+        let _ = exit_tx.send(SidecarTerminated {
+            code: None,
+            signal: None,
+            stderr: Vec::new(),
+        });
+    });
+
+    let exit = exit_rx.shared();
+    let child = CommandChild {
+        stop: stop_tx,
+        exit: Some(exit.clone()),
+        pid: Some(pid),
+    };
+    (child, exit)
 }
 
 pub mod sqlite_migration {
@@ -556,3 +1662,73 @@ async fn read_line<F: Fn(String) -> CommandEvent + Send + Copy + 'static>(
         }
     }
 }
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_install_script_path_block() {
+        let content = "alias ll='ls -l'\n\n# opencode\nexport PATH=/home/me/.opencode/bin:$PATH\n";
+        let cleaned = strip_path_entries(content, "/home/me/.opencode/bin").unwrap();
+        assert_eq!(cleaned, "alias ll='ls -l'\n");
+    }
+
+    #[test]
+    fn strips_fish_path_entry() {
+        let content = "set -x EDITOR vim\n\n# opencode\nfish_add_path /home/me/.opencode/bin\n";
+        let cleaned = strip_path_entries(content, "/home/me/.opencode/bin").unwrap();
+        assert_eq!(cleaned, "set -x EDITOR vim\n");
+    }
+
+    #[test]
+    fn leaves_unrelated_config_untouched() {
+        let content = "export PATH=/opt/bin:$PATH\n";
+        assert_eq!(strip_path_entries(content, "/home/me/.opencode/bin"), None);
+    }
+
+    #[test]
+    fn reads_curl_progress() {
+        assert_eq!(curl_percent("\r####            21.4%"), Some(21));
+        assert_eq!(curl_percent("\r###### 50.0%\r######## 100.0%"), Some(100));
+        assert_eq!(curl_percent("Installing opencode"), None);
+    }
+
+    #[test]
+    fn quotes_bundled_wsl_binary_path() {
+        let command = wsl_copy_command(&WslBinary {
+            path: PathBuf::from("C:\\Program Files\\O'Code\\opencode-cli-wsl"),
+            sha256: "ab".repeat(32),
+        });
+        assert!(command.contains(r#"wslpath -u 'C:\Program Files\O'"'"'Code\opencode-cli-wsl'"#));
+        assert!(command.contains(&format!(
+            r#"echo "{}  $BIN" | sha256sum -c"#,
+            "ab".repeat(32)
+        )));
+        assert!(command.ends_with(r#"chmod 755 "$BIN""#));
+    }
+
+    #[test]
+    fn translates_windows_config_paths_for_wsl() {
+        assert_eq!(
+            wsl_env("OPENCODE_CONFIG", r"C:\Users\me\opencode.json"),
+            "OPENCODE_CONFIG='/mnt/c/Users/me/opencode.json'"
+        );
+        assert_eq!(
+            wsl_env("OPENCODE_CONFIG", r"\\server\share\opencode.json"),
+            r#"OPENCODE_CONFIG="$(wslpath -u '\\server\share\opencode.json')""#
+        );
+        assert_eq!(
+            wsl_env("OPENCODE_CONFIG", "/home/me/opencode.json"),
+            "OPENCODE_CONFIG='/home/me/opencode.json'"
+        );
+        assert_eq!(
+            wsl_env(
+                "XDG_STATE_HOME",
+                r"C:\Users\me\AppData\Local\ai.opencode.desktop"
+            ),
+            "XDG_STATE_HOME='/mnt/c/Users/me/AppData/Local/ai.opencode.desktop'"
+        );
+        assert_eq!(wsl_env("NODE_ENV", r"C:\x"), r"NODE_ENV='C:\x'");
+    }
+}
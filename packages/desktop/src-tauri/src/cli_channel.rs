@@ -0,0 +1,397 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::{
+    cli::{self, CliInstallProgress},
+    errors::{DesktopError, ErrorCode},
+    operations::{self, OperationKind},
+    proxy, settings, update_guard,
+};
+
+const RELEASES_API: &str = "https://api.github.com/repos/anomalyco/opencode/releases";
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Where the installed CLI comes from.
+#[derive(
+    Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default, PartialEq, Eq,
+)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CliChannel {
+    /// The CLI bundled with the app, kept at the app's version.
+    #[default]
+    Bundled,
+    /// The latest release.
+    Stable,
+    /// The newest release, prereleases included.
+    Nightly,
+    /// A specific release, never updated.
+    Pinned { version: String },
+}
+
+impl CliChannel {
+    pub fn validate(&self) -> Result<(), DesktopError> {
+        if let Self::Pinned { version } = self {
+            parse_version(version)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct Release {
+    tag_name: String,
+    draft: bool,
+    assets: Vec<Asset>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+    /// `sha256:<hex>`, computed by GitHub on upload.
+    digest: Option<String>,
+}
+
+pub fn parse_version(version: &str) -> Result<semver::Version, DesktopError> {
+    let version = version.trim();
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version)).map_err(|e| {
+        DesktopError::new(
+            ErrorCode::InvalidCliVersion,
+            format!("Invalid CLI version '{}': {}", version, e),
+        )
+        .with("version", version)
+    })
+}
+
+fn sha256_digest(digest: &str) -> Option<&str> {
+    digest
+        .strip_prefix("sha256:")
+        .filter(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The release archive for this machine, named like the install script does.
+fn asset_name() -> Result<String, String> {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        os @ ("linux" | "windows") => os,
+        os => return Err(format!("No CLI releases for {os}")),
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" if os != "windows" => "arm64",
+        arch => return Err(format!("No CLI releases for {os} on {arch}")),
+    };
+
+    let mut target = format!("{os}-{arch}");
+    #[cfg(target_arch = "x86_64")]
+    if !std::arch::is_x86_feature_detected!("avx2") {
+        target.push_str("-baseline");
+    }
+    if os == "linux" && Path::new("/etc/alpine-release").exists() {
+        target.push_str("-musl");
+    }
+
+    let ext = if os == "linux" { "tar.gz" } else { "zip" };
+    Ok(format!("opencode-{target}.{ext}"))
+}
+
+async fn fetch<T: serde::de::DeserializeOwned>(app: &AppHandle, url: &str) -> Result<T, String> {
+    let response = proxy::http_client(app, Duration::from_secs(30))?
+        .get(url)
+        .header(reqwest::header::USER_AGENT, "opencode-desktop")
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch release information: {}", e))?;
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read release information: {}", e))?;
+    serde_json::from_str(&body).map_err(|e| format!("Failed to parse release information: {}", e))
+}
+
+async fn resolve(app: &AppHandle, channel: &CliChannel) -> Result<Release, String> {
+    match channel {
+        CliChannel::Bundled => Err("The bundled CLI has no release to download".to_string()),
+        CliChannel::Stable => fetch(app, &format!("{RELEASES_API}/latest")).await,
+        CliChannel::Nightly => fetch::<Vec<Release>>(app, &format!("{RELEASES_API}?per_page=20"))
+            .await?
+            .into_iter()
+            .find(|release| !release.draft)
+            .ok_or_else(|| "No releases found".to_string()),
+        CliChannel::Pinned { version } => {
+            let version = parse_version(version)?;
+            fetch(app, &format!("{RELEASES_API}/tags/v{version}"))
+                .await
+                .map_err(|e| format!("Release {version} not found: {e}"))
+        }
+    }
+}
+
+/// Asset `name` of `release` with the sha256 GitHub published for it.
+fn checked_asset<'a>(release: &'a Release, name: &str) -> Result<(&'a Asset, &'a str), String> {
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| format!("Release {} has no {name}", release.tag_name))?;
+    let digest = asset
+        .digest
+        .as_deref()
+        .and_then(sha256_digest)
+        .ok_or_else(|| {
+            format!(
+                "Release {} publishes no checksum for {name}",
+                release.tag_name
+            )
+        })?;
+    Ok((asset, digest))
+}
+
+/// The download url and sha256 of asset `name` in release `version`, for installs that download
+/// it themselves.
+pub async fn release_asset(
+    app: &AppHandle,
+    version: &semver::Version,
+    name: &str,
+) -> Result<(String, String), String> {
+    let channel = CliChannel::Pinned {
+        version: version.to_string(),
+    };
+    let release = resolve(app, &channel).await?;
+    let (asset, digest) = checked_asset(&release, name)?;
+    Ok((asset.browser_download_url.clone(), digest.to_string()))
+}
+
+fn extract(archive: &Path, dir: &Path) -> Result<PathBuf, String> {
+    let binary = dir.join(if cfg!(windows) {
+        "opencode.exe"
+    } else {
+        "opencode"
+    });
+
+    if archive.extension().is_some_and(|ext| ext == "zip") {
+        let file = std::fs::File::open(archive)
+            .map_err(|e| format!("Failed to open CLI archive: {}", e))?;
+        let mut zip =
+            zip::ZipArchive::new(file).map_err(|e| format!("Failed to read CLI archive: {}", e))?;
+        let name = binary.file_name().unwrap_or_default().to_string_lossy();
+        let mut entry = zip
+            .by_name(&name)
+            .map_err(|e| format!("CLI archive has no {name}: {}", e))?;
+        let mut out =
+            std::fs::File::create(&binary).map_err(|e| format!("Failed to extract CLI: {}", e))?;
+        std::io::copy(&mut entry, &mut out).map_err(|e| format!("Failed to extract CLI: {}", e))?;
+    } else {
+        let status = std::process::Command::new("tar")
+            .arg("-xzf")
+            .arg(archive)
+            .arg("-C")
+            .arg(dir)
+            .status()
+            .map_err(|e| format!("Failed to run tar: {}", e))?;
+        if !status.success() {
+            return Err("Failed to extract CLI archive".to_string());
+        }
+    }
+
+    if !binary.is_file() {
+        return Err("CLI archive did not contain the opencode binary".to_string());
+    }
+    Ok(binary)
+}
+
+/// Downloads the release archive into `dir`, checks it against the digest GitHub published for
+/// it and extracts the binary.
+async fn download(app: &AppHandle, release: &Release, dir: &Path) -> Result<PathBuf, String> {
+    let name = asset_name()?;
+    let (asset, expected) = checked_asset(release, &name)?;
+
+    tracing::info!(url = %asset.browser_download_url, "Downloading CLI");
+    CliInstallProgress::Downloading { percent: None }.send(app);
+    let mut response = proxy::http_client(app, DOWNLOAD_TIMEOUT)?
+        .get(&asset.browser_download_url)
+        .header(reqwest::header::USER_AGENT, "opencode-desktop")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download CLI: {}", e))?;
+
+    let total = response.content_length().filter(|len| *len > 0);
+    let mut bytes = Vec::new();
+    let mut last = None;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to download CLI: {}", e))?
+    {
+        bytes.extend_from_slice(&chunk);
+        let percent = total.map(|total| (bytes.len() as u64 * 100 / total).min(100) as u32);
+        if percent.is_some() && percent != last {
+            last = percent;
+            CliInstallProgress::Downloading { percent }.send(app);
+        }
+    }
+
+    CliInstallProgress::Verifying.send(app);
+    let actual = format!("{:x}", Sha256::digest(&bytes));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(format!(
+            "Checksum mismatch for {name}: expected {expected}, got {actual}"
+        ));
+    }
+
+    let archive = dir.join(&name);
+    std::fs::write(&archive, &bytes).map_err(|e| format!("Failed to save CLI archive: {}", e))?;
+    CliInstallProgress::Extracting.send(app);
+    extract(&archive, dir)
+}
+
+/// Downloads release `version` into `dir`, returning the extracted binary.
+pub async fn download_version(
+    app: &AppHandle,
+    version: &semver::Version,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    let channel = CliChannel::Pinned {
+        version: version.to_string(),
+    };
+    let release = resolve(app, &channel).await?;
+    download(app, &release, dir).await
+}
+
+/// Installs the CLI from `release`, returning where it was installed.
+async fn install_release(app: &AppHandle, release: &Release) -> Result<String, String> {
+    let version = parse_version(&release.tag_name)?;
+
+    #[cfg(windows)]
+    if cli::is_wsl_enabled(app) {
+        return cli::install_version_wsl(app, &version.to_string()).await;
+    }
+
+    let dir = std::env::temp_dir().join(format!("opencode-cli-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create download directory: {}", e))?;
+    let installed = match download(app, release, &dir).await {
+        Ok(binary) => cli::install_binary(app, binary, version.clone())
+            .await
+            .map_err(String::from),
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let path = installed?;
+    tracing::info!(%version, "Installed CLI release");
+    Ok(path)
+}
+
+async fn install_channel(app: &AppHandle, channel: &CliChannel) -> Result<String, String> {
+    let release = resolve(app, channel).await?;
+    install_release(app, &release).await
+}
+
+pub fn get(app: &AppHandle) -> CliChannel {
+    settings::load(app)
+        .map(|s| s.cli_channel)
+        .unwrap_or_default()
+}
+
+/// Brings an installed CLI in line with the chosen channel.
+pub async fn sync(app: AppHandle) -> Result<(), String> {
+    let channel = get(&app);
+    if channel == CliChannel::Bundled {
+        return cli::sync_cli(app).await;
+    }
+
+    if cfg!(debug_assertions) {
+        tracing::debug!("Skipping CLI sync for debug build");
+        return Ok(());
+    }
+    if !cli::is_cli_installed() {
+        tracing::info!("No CLI installation found, skipping sync");
+        return Ok(());
+    }
+
+    let release = resolve(&app, &channel).await?;
+    let release_version = parse_version(&release.tag_name)?;
+    let cli_version = cli::installed_cli_version()?;
+    let outdated = match channel {
+        CliChannel::Pinned { .. } => cli_version != release_version,
+        _ => cli_version < release_version,
+    };
+    if !outdated {
+        tracing::info!(%cli_version, ?channel, "CLI is up to date, skipping sync");
+        return Ok(());
+    }
+
+    if update_guard::defer_cli(&app, &release_version.to_string()).await {
+        return Ok(());
+    }
+
+    tracing::info!(%cli_version, %release_version, ?channel, "Syncing CLI");
+    install_release(&app, &release).await?;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_cli_channel(app: AppHandle) -> CliChannel {
+    get(&app)
+}
+
+/// Installs the CLI from `channel` and keeps it updated from there. The setting only changes once
+/// the install succeeds. Returns where the CLI was installed. It can be cancelled as
+/// `operation_id`.
+#[tauri::command]
+#[specta::specta]
+pub async fn switch_cli_channel(
+    app: AppHandle,
+    channel: CliChannel,
+    operation_id: Option<String>,
+) -> Result<String, DesktopError> {
+    channel.validate()?;
+
+    let operation = operations::begin(&app, OperationKind::CliInstall, operation_id)?;
+    let path = operation
+        .run(async {
+            match &channel {
+                CliChannel::Bundled => cli::report_install(&app, cli::install_bundled(&app).await),
+                channel => cli::report_install(&app, install_channel(&app, channel).await),
+            }
+        })
+        .await?;
+
+    settings::update(&app, |s| {
+        s.cli_channel = channel.clone();
+        Ok(())
+    })?;
+
+    tracing::info!(?channel, "Switched CLI channel");
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions_and_digests() {
+        assert_eq!(
+            parse_version("v1.2.3").unwrap(),
+            semver::Version::new(1, 2, 3)
+        );
+        assert!(parse_version("latest").is_err());
+
+        let hex = "ab".repeat(32);
+        assert_eq!(sha256_digest(&format!("sha256:{hex}")), Some(hex.as_str()));
+        assert_eq!(sha256_digest("sha512:abcd"), None);
+        assert_eq!(sha256_digest("sha256:xyz"), None);
+    }
+}
@@ -0,0 +1,598 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+use tauri_specta::Event;
+
+use crate::{
+    cli, config_watch,
+    errors::{DesktopError, ErrorCode},
+    instances, proxy, settings, trust,
+};
+
+const SCHEMA_URL: &str = "https://opencode.ai/config.json";
+const SCHEMA_CACHE: &str = "config-schema.json";
+/// How long a cached schema is used before it is refreshed in the background.
+const SCHEMA_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The config as the CLI resolves it, merged from every source.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_full_config(app: AppHandle) -> Result<Value, DesktopError> {
+    let raw = cli::get_raw_config(&app).await.ok_or_else(|| {
+        DesktopError::new(
+            ErrorCode::CliConfigUnreadable,
+            "Failed to read the CLI config",
+        )
+    })?;
+    serde_json::from_str(&raw).map_err(|e| {
+        DesktopError::new(
+            ErrorCode::CliConfigUnreadable,
+            format!("Failed to parse the CLI config: {}", e),
+        )
+    })
+}
+
+/// Where the config file edits go to was picked.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigPathSource {
+    /// The custom path in the desktop settings.
+    Setting,
+    /// `OPENCODE_CONFIG` in the environment the app was started with.
+    Environment,
+    /// The global config directory.
+    Global,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct ConfigPath {
+    pub path: String,
+    pub source: ConfigPathSource,
+    pub exists: bool,
+}
+
+/// The file `OPENCODE_CONFIG` points the CLI at, if anything does.
+pub fn custom_config_file(app: &AppHandle) -> Option<(PathBuf, ConfigPathSource)> {
+    let setting = settings::load(app).ok().and_then(|s| s.config_path);
+    match setting {
+        Some(path) => Some((PathBuf::from(path), ConfigPathSource::Setting)),
+        None => std::env::var_os("OPENCODE_CONFIG")
+            .filter(|v| !v.is_empty())
+            .map(|path| (PathBuf::from(path), ConfigPathSource::Environment)),
+    }
+}
+
+/// The config file edits go to: the custom one, which the CLI loads after the global ones, or else
+/// the global one it loads last, so its values win, or a new `opencode.json`.
+fn config_file(app: &AppHandle) -> Result<(PathBuf, ConfigPathSource), String> {
+    if let Some(custom) = custom_config_file(app) {
+        return Ok(custom);
+    }
+    let dir = cli::global_config_dir()
+        .ok_or_else(|| "Failed to resolve the CLI config directory".to_string())?;
+    let file = cli::GLOBAL_CONFIG_FILES
+        .iter()
+        .rev()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| dir.join("opencode.json"));
+    Ok((file, ConfigPathSource::Global))
+}
+
+/// The config file the CLI is pointed at and that edits from the app go to.
+#[tauri::command]
+#[specta::specta]
+pub fn resolve_config_path(app: AppHandle) -> Result<ConfigPath, String> {
+    let (file, source) = config_file(&app)?;
+    Ok(ConfigPath {
+        path: file.display().to_string(),
+        source,
+        exists: file.is_file(),
+    })
+}
+
+/// Shows the effective config file in the file manager, or the directory it would be created in.
+#[tauri::command]
+#[specta::specta]
+pub fn reveal_config_path(app: AppHandle) -> Result<(), String> {
+    let (file, _) = config_file(&app)?;
+    if file.is_file() {
+        return app
+            .opener()
+            .reveal_item_in_dir(&file)
+            .map_err(|e| format!("Failed to reveal {}: {}", file.display(), e));
+    }
+    let dir = file
+        .parent()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| format!("{} does not exist", file.display()))?;
+    app.opener()
+        .open_path(dir.display().to_string(), None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", dir.display(), e))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_config_path(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(settings::load(&app)?.config_path)
+}
+
+/// Points the CLI at `path` through `OPENCODE_CONFIG`, or back at the global config with `None`.
+/// The file may not exist yet, but its directory must. Takes effect once the server restarts.
+#[tauri::command]
+#[specta::specta]
+pub fn set_config_path(app: AppHandle, path: Option<String>) -> Result<ConfigPath, String> {
+    if let Some(path) = &path {
+        let file = Path::new(path);
+        if file.is_dir() {
+            return Err(format!("{path} is a directory"));
+        }
+        if !file.parent().is_some_and(Path::is_dir) {
+            return Err(format!("The directory for {path} does not exist"));
+        }
+    }
+
+    settings::update(&app, |s| {
+        s.config_path = path;
+        Ok(())
+    })?;
+
+    resolve_config_path(app)
+}
+
+fn read_config(path: &Path) -> Result<Value, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&strip_jsonc(&contents))
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok(serde_json::json!({ "$schema": SCHEMA_URL }))
+        }
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// The CLI config can't be used, either because a config file doesn't parse or because the CLI
+/// rejected it. `path`, `line` and `column` point at the problem when it is in a file.
+#[derive(
+    tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type, PartialEq,
+)]
+pub struct ConfigInvalid {
+    pub path: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+// The last problem found, for windows that were not listening when it was reported.
+static INVALID: Mutex<Option<ConfigInvalid>> = Mutex::new(None);
+
+/// Where `contents` of the config file at `path` stops parsing, if it does.
+fn locate(path: &Path, contents: &str) -> Option<ConfigInvalid> {
+    let e = serde_json::from_str::<Value>(&strip_jsonc(contents)).err()?;
+    Some(ConfigInvalid {
+        path: Some(path.display().to_string()),
+        line: Some(e.line() as u32),
+        column: Some(e.column() as u32),
+        message: without_position(&e),
+    })
+}
+
+fn without_position(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_string(),
+        None => message,
+    }
+}
+
+/// Reports that the CLI's `output` for its config didn't parse as one, pointing at the config
+/// file to blame when there is one.
+pub fn config_invalid(app: &AppHandle, output: &str, error: serde_json::Error) {
+    let in_file = config_watch::watched_files(app)
+        .into_iter()
+        .find_map(|path| {
+            // A missing file is fine, it is just not part of the config.
+            let contents = std::fs::read_to_string(&path).ok()?;
+            locate(&path, &contents)
+        });
+    let invalid = in_file.unwrap_or_else(|| ConfigInvalid {
+        path: None,
+        line: None,
+        column: None,
+        // Output that is JSON has a value of the wrong type, while anything else is the CLI
+        // explaining why it rejected the config.
+        message: match serde_json::from_str::<Value>(output) {
+            Ok(_) => without_position(&error),
+            Err(_) => output
+                .trim()
+                .lines()
+                .take(20)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+    });
+
+    tracing::error!(path = ?invalid.path, line = ?invalid.line, message = %invalid.message, "Invalid CLI config");
+    *INVALID.lock().unwrap() = Some(invalid.clone());
+    let _ = invalid.emit(app);
+}
+
+/// Forgets a previously reported problem once the config parses again.
+pub fn config_valid() {
+    INVALID.lock().unwrap().take();
+}
+
+/// The problem with the CLI config found when it was last read, if any.
+#[tauri::command]
+#[specta::specta]
+pub fn get_config_error() -> Option<ConfigInvalid> {
+    INVALID.lock().unwrap().clone()
+}
+
+/// `contents` with JSONC comments and trailing commas removed, so it parses as JSON.
+pub fn strip_jsonc(contents: &str) -> String {
+    // Calls `f` for each character outside of strings, which returns how many bytes after it to
+    // drop along with it, or `None` to keep it. Dropped newlines are kept, so line numbers in
+    // errors stay right.
+    fn outside_strings(input: &str, mut f: impl FnMut(char, &str) -> Option<usize>) -> String {
+        let mut out = String::with_capacity(input.len());
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut i = 0;
+        while let Some(c) = input[i..].chars().next() {
+            let len = c.len_utf8();
+            if in_string {
+                out.push(c);
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                i += len;
+                continue;
+            }
+            match f(c, &input[i + len..]) {
+                Some(skip) => {
+                    let dropped = &input[i..i + len + skip];
+                    out.extend(dropped.chars().filter(|&c| c == '\n'));
+                    i += len + skip;
+                }
+                None => {
+                    in_string = c == '"';
+                    out.push(c);
+                    i += len;
+                }
+            }
+        }
+        out
+    }
+
+    let without_comments = outside_strings(contents, |c, rest| match (c, rest.chars().next()) {
+        ('/', Some('/')) => Some(rest.find('\n').unwrap_or(rest.len())),
+        ('/', Some('*')) => Some(rest[1..].find("*/").map_or(rest.len(), |end| end + 3)),
+        _ => None,
+    });
+    outside_strings(&without_comments, |c, rest| {
+        let trailing = c == ',' && rest.trim_start().starts_with(['}', ']']);
+        trailing.then_some(0)
+    })
+}
+
+/// Sets the value at `path` in `config`, creating objects along the way. `null` removes it.
+fn set_path(config: &mut Value, path: &[String], value: Value) -> Result<(), String> {
+    let (key, parents) = path
+        .split_last()
+        .ok_or_else(|| "The config path is empty".to_string())?;
+
+    let mut target = config;
+    for (depth, segment) in parents.iter().enumerate() {
+        let object = target
+            .as_object_mut()
+            .ok_or_else(|| format!("'{}' is not an object", parents[..depth].join(".")))?;
+        target = object
+            .entry(segment.clone())
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+
+    let object = target
+        .as_object_mut()
+        .ok_or_else(|| format!("'{}' is not an object", parents.join(".")))?;
+    if value.is_null() {
+        object.remove(key);
+    } else {
+        object.insert(key.clone(), value);
+    }
+    Ok(())
+}
+
+pub fn schema_cache(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(SCHEMA_CACHE))
+        .map_err(|e| format!("Failed to resolve the app data directory: {}", e))
+}
+
+async fn fetch_schema(app: &AppHandle) -> Result<String, String> {
+    proxy::http_client(app, Duration::from_secs(10))?
+        .get(SCHEMA_URL)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch the config schema: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read the config schema: {}", e))
+}
+
+/// Fetches the schema from opencode.ai and caches it, returning it if it parses.
+async fn refresh_schema(app: &AppHandle, cache: &Path) -> Result<Value, String> {
+    let schema = fetch_schema(app).await?;
+    let parsed = serde_json::from_str(&schema)
+        .map_err(|e| format!("Failed to parse the config schema: {}", e))?;
+    if let Err(e) = std::fs::write(cache, &schema) {
+        tracing::warn!("Failed to cache the config schema: {e}");
+    }
+    Ok(parsed)
+}
+
+/// The CLI's config schema. The cached copy is used as long as there is one, and refreshed in the
+/// background once it is a day old, so only the first write waits for opencode.ai. `None` when
+/// there is no cached copy and opencode.ai can't be reached.
+async fn schema(app: &AppHandle) -> Option<Value> {
+    let cache = schema_cache(app).ok()?;
+    let cached = std::fs::read_to_string(&cache)
+        .ok()
+        .and_then(|cached| serde_json::from_str::<Value>(&cached).ok());
+    let Some(cached) = cached else {
+        return refresh_schema(app, &cache)
+            .await
+            .inspect_err(|e| tracing::warn!("{e}"))
+            .ok();
+    };
+
+    let stale = std::fs::metadata(&cache)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_none_or(|age| age > SCHEMA_MAX_AGE);
+    if stale {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = refresh_schema(&app, &cache).await {
+                tracing::warn!("{e}, keeping the cached schema");
+            }
+        });
+    }
+    Some(cached)
+}
+
+fn validate(schema: &Value, config: &Value) -> Result<(), DesktopError> {
+    let validator =
+        jsonschema::validator_for(schema).map_err(|e| format!("Invalid config schema: {}", e))?;
+    let errors = validator
+        .iter_errors(config)
+        .map(|e| match e.instance_path.to_string() {
+            path if path.is_empty() => e.to_string(),
+            path => format!("{path}: {e}"),
+        })
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DesktopError::new(
+            ErrorCode::InvalidCliConfig,
+            format!("Invalid config: {}", errors.join("; ")),
+        )
+        .with("errors", errors.join("; ")))
+    }
+}
+
+/// Checks `config` against the config schema, then writes it to `file`, keeping the previous file
+/// as `.bak` since comments are not preserved.
+async fn write_config(app: &AppHandle, file: &Path, config: &Value) -> Result<(), DesktopError> {
+    match schema(app).await {
+        Some(schema) => validate(&schema, config)?,
+        None => tracing::warn!(
+            "No config schema is cached and opencode.ai can't be reached, writing without validation"
+        ),
+    }
+
+    let contents = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize the config: {}", e))?;
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    if file.exists() {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        std::fs::copy(file, file.with_file_name(format!("{name}.bak")))
+            .map_err(|e| format!("Failed to back up {}: {}", file.display(), e))?;
+    }
+    std::fs::write(file, contents + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+    Ok(())
+}
+
+/// Sets the value at `path` in the CLI config file, or removes it when `value` is `null`. The
+/// result is checked against the config schema before it is written, and the previous file is
+/// kept as `.bak` since comments are not preserved.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_config_value(
+    app: AppHandle,
+    path: Vec<String>,
+    value: Value,
+) -> Result<(), DesktopError> {
+    let (file, _) = config_file(&app)?;
+    let mut config = read_config(&file)?;
+    set_path(&mut config, &path, value)?;
+    write_config(&app, &file, &config).await?;
+
+    tracing::info!(path = %path.join("."), file = %file.display(), "Updated CLI config");
+    Ok(())
+}
+
+/// Merges `patch` into `config` the way the server merges a config update: objects are merged key
+/// by key, anything else replaces what was there, and `null` removes it.
+fn merge_patch(config: &mut Value, patch: Value) -> Result<(), String> {
+    fn leaves(value: Value, path: &mut Vec<String>, out: &mut Vec<(Vec<String>, Value)>) {
+        match value {
+            Value::Object(object) => {
+                for (key, child) in object {
+                    path.push(key);
+                    leaves(child, path, out);
+                    path.pop();
+                }
+            }
+            value => out.push((path.clone(), value)),
+        }
+    }
+
+    if !patch.is_object() {
+        return Err("The config patch is not an object".to_string());
+    }
+    let mut changes = Vec::new();
+    leaves(patch, &mut Vec::new(), &mut changes);
+    for (path, value) in changes {
+        set_path(config, &path, value)?;
+    }
+    Ok(())
+}
+
+/// Project config files the CLI merges, in load order.
+const PROJECT_CONFIG_FILES: [&str; 2] = ["opencode.jsonc", "opencode.json"];
+
+/// The config file of the project in `directory` that wins: the last one the CLI loads, or a new
+/// `opencode.json`.
+fn project_config_file(directory: &Path) -> PathBuf {
+    PROJECT_CONFIG_FILES
+        .iter()
+        .rev()
+        .map(|name| directory.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| directory.join("opencode.json"))
+}
+
+/// Applies a partial config to the CLI config file, or to the config of the project in
+/// `directory`, which must be trusted. The whole result is checked against the config schema and
+/// written at once, so a rejected patch leaves the file as it was. The server picks it up once
+/// its instances are disposed.
+#[tauri::command]
+#[specta::specta]
+pub async fn patch_config(
+    app: AppHandle,
+    patch: Value,
+    directory: Option<String>,
+) -> Result<(), DesktopError> {
+    let file = match &directory {
+        Some(directory) => {
+            let directory = instances::canonical_directory(directory)?;
+            trust::ensure(&app, &directory).await?;
+            project_config_file(&directory)
+        }
+        None => config_file(&app)?.0,
+    };
+    let mut config = read_config(&file)?;
+    merge_patch(&mut config, patch)?;
+    write_config(&app, &file, &config).await?;
+
+    tracing::info!(file = %file.display(), "Patched config");
+    Ok(())
+}
+
+/// Opens the CLI config file in the default editor, creating it first if needed. Returns its path.
+#[tauri::command]
+#[specta::specta]
+pub fn open_config_in_editor(app: AppHandle) -> Result<String, DesktopError> {
+    let (file, _) = config_file(&app)?;
+    if !file.exists() {
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let contents = serde_json::to_string_pretty(&read_config(&file)?)
+            .map_err(|e| format!("Failed to serialize the config: {}", e))?;
+        std::fs::write(&file, contents + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+    }
+
+    let path = file.display().to_string();
+    app.opener()
+        .open_path(&path, None::<&str>)
+        .map_err(|e| format!("Failed to open {path}: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_jsonc_comments_and_trailing_commas() {
+        let jsonc = r#"{
+            // the model
+            "model": "a//b", /* inline */
+            "share": "auto,}",
+            "tools": ["x", "y",],
+        }"#;
+        let config: Value = serde_json::from_str(&strip_jsonc(jsonc)).unwrap();
+        assert_eq!(
+            config,
+            serde_json::json!({ "model": "a//b", "share": "auto,}", "tools": ["x", "y"] })
+        );
+    }
+
+    #[test]
+    fn locates_parse_errors_in_config_files() {
+        let path = Path::new("opencode.jsonc");
+        assert_eq!(locate(path, "{\n  // fine\n  \"model\": \"a\",\n}"), None);
+
+        let invalid = locate(path, "{\n  /* a\n  comment */\n  \"model\" \"a\"\n}").unwrap();
+        assert_eq!(invalid.path.as_deref(), Some("opencode.jsonc"));
+        assert_eq!((invalid.line, invalid.column), (Some(4), Some(11)));
+        assert_eq!(invalid.message, "expected `:`");
+    }
+
+    #[test]
+    fn sets_and_removes_nested_values() {
+        let mut config = serde_json::json!({ "model": "a" });
+        let path = ["server", "port"].map(String::from);
+
+        set_path(&mut config, &path, serde_json::json!(4096)).unwrap();
+        assert_eq!(config["server"]["port"], 4096);
+
+        set_path(&mut config, &path, Value::Null).unwrap();
+        assert_eq!(config, serde_json::json!({ "model": "a", "server": {} }));
+
+        let under_string = ["model", "name"].map(String::from);
+        assert!(set_path(&mut config, &under_string, Value::Null).is_err());
+    }
+
+    #[test]
+    fn merges_patches_key_by_key() {
+        let mut config = serde_json::json!({
+            "model": "a",
+            "share": "auto",
+            "provider": { "x": { "name": "X" } },
+        });
+        merge_patch(
+            &mut config,
+            serde_json::json!({ "share": null, "provider": { "x": { "models": ["m"] } } }),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            serde_json::json!({ "model": "a", "provider": { "x": { "name": "X", "models": ["m"] } } })
+        );
+
+        assert!(merge_patch(&mut config, serde_json::json!(["model"])).is_err());
+        assert!(merge_patch(&mut config, serde_json::json!({ "model": { "name": "b" } })).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use futures::StreamExt;
+use tauri::{AppHandle, ipc::Channel};
+
+use crate::{
+    cli::{self, CommandEvent},
+    errors::{DesktopError, ErrorCode},
+    sidecar_logs::LogStream,
+};
+
+/// CLI subcommands the UI may run. They only read state, so running them can't break the server.
+const ALLOWED: [&str; 8] = [
+    "--version",
+    "auth list",
+    "models",
+    "stats",
+    "mcp list",
+    "agent list",
+    "debug config",
+    "debug paths",
+];
+
+/// A line a CLI command printed.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct CliOutput {
+    pub stream: LogStream,
+    pub line: String,
+}
+
+/// Arguments are joined into a command line that may go through the user's shell, so only plain
+/// words are accepted.
+fn valid_arg(arg: &str) -> bool {
+    !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.:/=@+,".contains(c))
+}
+
+/// Runs an allow-listed `opencode` subcommand with `args`, streaming its output to `output`.
+/// Returns the exit code, which is missing if it was killed by a signal.
+#[tauri::command]
+#[specta::specta]
+pub async fn run_cli(
+    app: AppHandle,
+    subcommand: String,
+    args: Vec<String>,
+    output: Channel<CliOutput>,
+) -> Result<Option<i32>, DesktopError> {
+    if !ALLOWED.contains(&subcommand.as_str()) {
+        return Err(DesktopError::new(
+            ErrorCode::CliCommandNotAllowed,
+            format!("opencode {subcommand} can't be run from the app"),
+        )
+        .with("subcommand", subcommand));
+    }
+    if let Some(arg) = args.iter().find(|arg| !valid_arg(arg)) {
+        return Err(DesktopError::new(
+            ErrorCode::InvalidCliArgument,
+            format!("Invalid argument {arg:?}"),
+        )
+        .with("argument", arg));
+    }
+
+    let line = std::iter::once(subcommand.as_str())
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    tracing::info!(command = %line, "Running CLI command");
+    let (mut events, _child) = cli::spawn_command(&app, &line, &[], None)
+        .await
+        .map_err(|e| format!("Failed to run opencode {subcommand}: {}", e))?;
+
+    while let Some(event) = events.next().await {
+        let (stream, line) = match event {
+            CommandEvent::Stdout(line) => (LogStream::Stdout, line),
+            CommandEvent::Stderr(line) => (LogStream::Stderr, line),
+            CommandEvent::Error(e) => {
+                return Err(format!("opencode {subcommand} failed: {e}").into());
+            }
+            CommandEvent::Terminated(payload) => return Ok(payload.code),
+        };
+        let _ = output.send(CliOutput {
+            stream,
+            line: line.trim_end_matches(['\r', '\n']).to_string(),
+        });
+    }
+
+    Err(format!("opencode {subcommand} exited without a status").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_plain_arguments_are_accepted() {
+        assert!(valid_arg("anthropic"));
+        assert!(valid_arg("--format=json"));
+        assert!(valid_arg("openai/gpt-5"));
+
+        assert!(!valid_arg(""));
+        assert!(!valid_arg("a b"));
+        assert!(!valid_arg("$(rm)"));
+        assert!(!valid_arg("x;y"));
+        assert!(!valid_arg("'quoted'"));
+    }
+}
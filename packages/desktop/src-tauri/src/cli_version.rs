@@ -0,0 +1,306 @@
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+
+use crate::{
+    cli, cli_channel,
+    errors::{DesktopError, ErrorCode},
+    operations::{self, OperationKind},
+    settings,
+};
+
+/// Names the CLI version a project's server runs, independent of the app's version.
+const VERSION_FILE: &str = ".opencode-version";
+/// Next to the CLI's bin directory, holding each installed version in its own directory.
+const VERSIONS_DIR: &str = "versions";
+
+/// A CLI version in the cache.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct CliVersionInfo {
+    pub version: String,
+    pub path: String,
+    /// Whether this is the version on the user's `PATH`.
+    pub active: bool,
+}
+
+/// The version pinned by the project's `.opencode-version`, if it has one. Blank lines and `#`
+/// comments are ignored.
+pub fn project_version(directory: &Path) -> Result<Option<semver::Version>, String> {
+    let contents = match std::fs::read_to_string(directory.join(VERSION_FILE)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {VERSION_FILE}: {}", e)),
+    };
+
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(cli_channel::parse_version)
+        .transpose()
+        .map_err(String::from)
+}
+
+pub fn versions_dir() -> Result<PathBuf, String> {
+    cli::get_cli_install_path()
+        .and_then(|path| Some(path.parent()?.parent()?.join(VERSIONS_DIR)))
+        .ok_or_else(|| "Could not determine the CLI versions directory".to_string())
+}
+
+fn version_dir(version: &semver::Version) -> Result<PathBuf, String> {
+    Ok(versions_dir()?.join(version.to_string()))
+}
+
+fn binary_in(dir: &Path) -> PathBuf {
+    dir.join(if cfg!(windows) {
+        "opencode.exe"
+    } else {
+        "opencode"
+    })
+}
+
+/// The cached binary for `version`, if it is installed.
+pub fn cached_binary(version: &semver::Version) -> Option<PathBuf> {
+    let binary = binary_in(&version_dir(version).ok()?);
+    binary.is_file().then_some(binary)
+}
+
+/// Every cached version, oldest first.
+pub fn cached_versions() -> Vec<semver::Version> {
+    let Ok(entries) =
+        versions_dir().and_then(|dir| std::fs::read_dir(dir).map_err(|e| e.to_string()))
+    else {
+        return Vec::new();
+    };
+
+    let mut versions = entries
+        .flatten()
+        .filter(|entry| binary_in(&entry.path()).is_file())
+        .filter_map(|entry| semver::Version::parse(&entry.file_name().to_string_lossy()).ok())
+        .collect::<Vec<_>>();
+    versions.sort();
+    versions
+}
+
+/// The pinned version is the one bundled with the app, so no other binary is needed.
+fn is_bundled(app: &AppHandle, version: &semver::Version) -> bool {
+    app.package_info().version == *version
+}
+
+/// The cached binary to run in `directory`, or `None` to use the bundled one. A pinned version
+/// that isn't cached yet falls back to the bundled binary.
+pub fn binary_for(app: &AppHandle, directory: &Path) -> Option<PathBuf> {
+    let version = match project_version(directory) {
+        Ok(Some(version)) if !is_bundled(app, &version) => version,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::warn!(directory = %directory.display(), "Ignoring pinned CLI version: {e}");
+            return None;
+        }
+    };
+
+    let binary = cached_binary(&version);
+    if binary.is_none() {
+        tracing::warn!(
+            %version,
+            directory = %directory.display(),
+            "Pinned CLI version is not cached, using the bundled CLI"
+        );
+    }
+    binary
+}
+
+/// A scratch directory next to `dir`, so a failed install never leaves a partial version behind.
+fn partial_dir(dir: &Path) -> Result<PathBuf, String> {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    let partial = dir.with_file_name(format!(".{name}-{}.partial", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&partial)
+        .map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+    Ok(partial)
+}
+
+/// Moves `binary` out of `partial` into `dir`.
+fn store(partial: &Path, binary: &Path, dir: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(binary, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to set CLI permissions: {}", e))?;
+    }
+
+    let staged = partial.join("bin");
+    std::fs::create_dir_all(&staged)
+        .map_err(|e| format!("Failed to create {}: {}", staged.display(), e))?;
+    std::fs::rename(binary, binary_in(&staged))
+        .map_err(|e| format!("Failed to stage CLI: {}", e))?;
+
+    match std::fs::rename(&staged, dir) {
+        Ok(()) => Ok(()),
+        // Another install of the same version finished first.
+        Err(_) if binary_in(dir).is_file() => Ok(()),
+        Err(e) => Err(format!("Failed to cache CLI: {}", e)),
+    }
+}
+
+/// Copies `binary` into the cache as `version`, returning the cached copy. A version that is
+/// already cached is kept as is.
+pub fn cache_binary(binary: &Path, version: &semver::Version) -> Result<PathBuf, String> {
+    if let Some(cached) = cached_binary(version) {
+        return Ok(cached);
+    }
+
+    let dir = version_dir(version)?;
+    let partial = partial_dir(&dir)?;
+    let copy = partial.join("download");
+    let cached = std::fs::copy(binary, &copy)
+        .map_err(|e| format!("Failed to copy CLI: {}", e))
+        .and_then(|_| store(&partial, &copy, &dir));
+    let _ = std::fs::remove_dir_all(&partial);
+
+    cached.map(|()| binary_in(&dir))
+}
+
+/// Downloads release `version` into the cache unless it is there already, returning the binary.
+async fn download(app: &AppHandle, version: &semver::Version) -> Result<PathBuf, String> {
+    if let Some(cached) = cached_binary(version) {
+        return Ok(cached);
+    }
+
+    tracing::info!(%version, "Downloading CLI version");
+    let dir = version_dir(version)?;
+    let partial = partial_dir(&dir)?;
+    let cached = match cli_channel::download_version(app, version, &partial).await {
+        Ok(binary) => store(&partial, &binary, &dir),
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_dir_all(&partial);
+
+    cached.map(|()| binary_in(&dir))
+}
+
+/// Downloads the version pinned by `directory` into the cache unless it's there already.
+pub async fn ensure(app: &AppHandle, directory: &Path) -> Result<(), String> {
+    if cfg!(windows) && cli::is_wsl_enabled(app) {
+        return Ok(());
+    }
+    match project_version(directory)? {
+        Some(version) if !is_bundled(app, &version) => download(app, &version).await.map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_cli_versions() -> Vec<CliVersionInfo> {
+    let active = cli::installed_cli_version().ok();
+    cached_versions()
+        .into_iter()
+        .filter_map(|version| {
+            Some(CliVersionInfo {
+                path: cached_binary(&version)?.display().to_string(),
+                active: active.as_ref() == Some(&version),
+                version: version.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Downloads `version` into the cache without making it the active CLI. Returns the binary. It
+/// can be cancelled as `operation_id`.
+#[tauri::command]
+#[specta::specta]
+pub async fn install_cli_version(
+    app: AppHandle,
+    version: String,
+    operation_id: Option<String>,
+) -> Result<String, DesktopError> {
+    let version = cli_channel::parse_version(&version)?;
+    let operation = operations::begin(&app, OperationKind::CliInstall, operation_id)?;
+    operation
+        .run(async {
+            let installed = download(&app, &version)
+                .await
+                .map(|binary| binary.display().to_string());
+            cli::report_install(&app, installed)
+        })
+        .await
+}
+
+/// Makes the cached `version` the CLI on the user's `PATH`. The CLI channel may move it on again
+/// when it next syncs, unless it is pinned.
+#[tauri::command]
+#[specta::specta]
+pub async fn activate_cli_version(app: AppHandle, version: String) -> Result<String, DesktopError> {
+    if cfg!(windows) && cli::is_wsl_enabled(&app) {
+        return Err(DesktopError::new(
+            ErrorCode::CliVersionsUnavailableInWsl,
+            "CLI versions can't be switched while the server runs in WSL",
+        ));
+    }
+
+    let version = cli_channel::parse_version(&version)?;
+    let binary = cached_binary(&version).ok_or_else(|| {
+        DesktopError::new(
+            ErrorCode::CliVersionNotInstalled,
+            format!("CLI {version} is not installed"),
+        )
+        .with("version", version.to_string())
+    })?;
+    let path = cli::report_install(&app, cli::activate_binary(&app, binary).await)?;
+
+    tracing::info!(%version, "Activated CLI version");
+    Ok(path)
+}
+
+/// Removes cached versions other than the `keep` newest. The active version, the app's own and
+/// any pinned by a recent project are always kept. Returns the removed versions.
+#[tauri::command]
+#[specta::specta]
+pub fn prune_cli_versions(app: AppHandle, keep: u32) -> Result<Vec<String>, DesktopError> {
+    let mut kept = vec![app.package_info().version.clone()];
+    kept.extend(cli::installed_cli_version().ok());
+    kept.extend(
+        settings::load(&app)?
+            .recent_projects
+            .iter()
+            .filter_map(|project| project_version(Path::new(&project.path)).ok().flatten()),
+    );
+
+    let mut removed = Vec::new();
+    let mut versions = cached_versions();
+    versions.reverse();
+    for version in versions.into_iter().skip(keep as usize) {
+        if kept.contains(&version) {
+            continue;
+        }
+        let dir = version_dir(&version)?;
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to remove CLI {version}: {}", e))?;
+        removed.push(version.to_string());
+    }
+
+    tracing::info!(?removed, "Pruned CLI versions");
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_pinned_version_from_project() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        assert_eq!(project_version(dir).unwrap(), None);
+
+        std::fs::write(dir.join(VERSION_FILE), "# pinned for CI\n\nv1.0.12\n").unwrap();
+        let pinned = project_version(dir).unwrap();
+
+        std::fs::write(dir.join(VERSION_FILE), "latest\n").unwrap();
+        let invalid = project_version(dir);
+
+        assert_eq!(pinned, Some(semver::Version::new(1, 0, 12)));
+        assert!(invalid.is_err());
+    }
+}
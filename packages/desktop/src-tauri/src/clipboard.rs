@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::{
+    file_dialogs::{DialogGrants, GrantKind},
+    file_drop,
+};
+
+/// Larger images are most likely not screenshots, and would bloat the prompt.
+const MAX_PIXELS: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct ClipboardImage {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub size_kb: u32,
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .map_err(|e| format!("Failed to encode the image: {}", e))?;
+    Ok(bytes)
+}
+
+/// Names pasted images after their content, so pasting the same screenshot twice reuses the
+/// file.
+fn file_name(png: &[u8]) -> String {
+    let digest = Sha256::digest(png);
+    let hash = digest[..16]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("{hash}.png")
+}
+
+fn clipboard_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    file_drop::staging_dir(app)
+        .map(|dir| dir.join("clipboard"))
+        .ok_or_else(|| "Failed to resolve the attachment directory".to_string())
+}
+
+/// Saves the image on the clipboard as a PNG attachment. Returns nothing when the clipboard
+/// holds no image.
+#[tauri::command]
+#[specta::specta]
+pub async fn paste_clipboard_image(app: AppHandle) -> Result<Option<ClipboardImage>, String> {
+    let image = match app.clipboard().read_image() {
+        Ok(image) => image,
+        Err(e) => {
+            tracing::debug!("No image on the clipboard: {e}");
+            return Ok(None);
+        }
+    };
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 {
+        return Ok(None);
+    }
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return Err(format!(
+            "The image is too large to attach ({width}x{height})"
+        ));
+    }
+    let rgba = image.rgba().to_vec();
+
+    let app_handle = app.clone();
+    let (path, size) = tauri::async_runtime::spawn_blocking(move || {
+        let png = encode_png(&rgba, width, height)?;
+        let dir = clipboard_dir(&app_handle)?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create the attachment directory: {}", e))?;
+        let path = dir.join(file_name(&png));
+        if !path.exists() {
+            std::fs::write(&path, &png).map_err(|e| format!("Failed to save the image: {}", e))?;
+        }
+        Ok::<_, String>((path, png.len() as u64))
+    })
+    .await
+    .map_err(|e| format!("Failed to save the image: {}", e))??;
+
+    app.state::<DialogGrants>().record(&path, GrantKind::File);
+    tracing::info!(path = %path.display(), width, height, "Saved clipboard image");
+
+    Ok(Some(ClipboardImage {
+        path: file_drop::shown(&app, &path)?,
+        width,
+        height,
+        size_kb: size.div_ceil(1024).try_into().unwrap_or(u32::MAX),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_images_under_their_content_hash() {
+        let red = [255, 0, 0, 255].repeat(6);
+        let png = encode_png(&red, 3, 2).unwrap();
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut decoded).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(&decoded[..info.buffer_size()], red.as_slice());
+
+        let blue = [0, 0, 255, 255].repeat(6);
+        assert_eq!(file_name(&png), file_name(&encode_png(&red, 3, 2).unwrap()));
+        assert_ne!(
+            file_name(&png),
+            file_name(&encode_png(&blue, 3, 2).unwrap())
+        );
+        assert!(encode_png(&red, 4, 2).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::{path::PathBuf, time::Duration};
+
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{cli, cli_config, models};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Sent when the CLI config changes on disk.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ConfigChanged {
+    /// Top-level keys whose value changed, added or removed.
+    pub keys: Vec<String>,
+    /// The server's hostname or port changed, which only takes effect once the app reconnects.
+    pub restart_required: bool,
+}
+
+/// The files the CLI reads its global config from, then the custom one it is pointed at.
+pub fn watched_files(app: &AppHandle) -> Vec<PathBuf> {
+    let mut files = cli::global_config_dir()
+        .map(|dir| cli::GLOBAL_CONFIG_FILES.map(|file| dir.join(file)).to_vec())
+        .unwrap_or_default();
+    files.extend(cli_config::custom_config_file(app).map(|(path, _)| path));
+    files
+}
+
+/// Modification time and size of each file, so an edit is noticed without reading them.
+fn fingerprint(files: &[PathBuf]) -> Vec<Option<(std::time::SystemTime, u64)>> {
+    files
+        .iter()
+        .map(|file| {
+            let metadata = std::fs::metadata(file).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+/// Top-level keys that differ between two resolved configs, sorted.
+pub fn changed_keys(old: &Value, new: &Value) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+
+    let mut keys = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+fn server_changed(old: &Value, new: &Value) -> bool {
+    ["/server/hostname", "/server/port"]
+        .iter()
+        .any(|pointer| old.pointer(pointer) != new.pointer(pointer))
+}
+
+/// The config as the CLI resolves it, merged from every source.
+async fn resolved_config(app: &AppHandle) -> Option<Value> {
+    let raw = cli::get_raw_config(app).await?;
+    match serde_json::from_str(&raw) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            tracing::warn!("CLI config did not parse, ignoring the change: {e}");
+            None
+        }
+    }
+}
+
+/// Reloads the CLI config whenever one of its files changes, until the app exits.
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+
+    tokio::spawn(async move {
+        let mut seen = fingerprint(&watched_files(&app));
+        let mut config = resolved_config(&app).await;
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            // Listed again each time, so a new custom config path is picked up.
+            let current = fingerprint(&watched_files(&app));
+            if current == seen {
+                continue;
+            }
+            seen = current;
+
+            // A half-written file keeps the last good config until the edit is finished.
+            let Some(new) = resolved_config(&app).await else {
+                continue;
+            };
+            let Some(old) = config.replace(new.clone()) else {
+                continue;
+            };
+
+            let keys = changed_keys(&old, &new);
+            if keys.is_empty() {
+                continue;
+            }
+
+            models::invalidate(&app);
+            let restart_required = server_changed(&old, &new);
+            tracing::info!(?keys, restart_required, "CLI config changed");
+            let _ = ConfigChanged {
+                keys,
+                restart_required,
+            }
+            .emit(&app);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_top_level_keys() {
+        let old = serde_json::json!({ "model": "a", "server": { "port": 4096 }, "theme": "dark" });
+        let new = serde_json::json!({ "model": "b", "server": { "port": 4096 }, "share": "auto" });
+
+        assert_eq!(changed_keys(&old, &new), ["model", "share", "theme"]);
+        assert!(!server_changed(&old, &new));
+        assert!(server_changed(
+            &old,
+            &serde_json::json!({ "server": { "port": 5000 } })
+        ));
+    }
+}
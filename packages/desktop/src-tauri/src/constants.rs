@@ -1,9 +1,12 @@
 use tauri_plugin_window_state::StateFlags;
 
 pub const SETTINGS_STORE: &str = "opencode.settings.dat";
-pub const DEFAULT_SERVER_URL_KEY: &str = "defaultServerUrl";
-pub const WSL_ENABLED_KEY: &str = "wslEnabled";
+pub const MODELS_STORE: &str = "opencode.models.dat";
 pub const UPDATER_ENABLED: bool = option_env!("TAURI_SIGNING_PRIVATE_KEY").is_some();
+/// Where submitted crash reports go. Builds without it can only keep reports locally.
+pub const CRASH_REPORT_URL: Option<&str> = option_env!("OPENCODE_CRASH_REPORT_URL");
+/// Where opted-in usage metrics are uploaded. Builds without it never upload them.
+pub const USAGE_METRICS_URL: Option<&str> = option_env!("OPENCODE_USAGE_METRICS_URL");
 
 pub fn window_state_flags() -> StateFlags {
     StateFlags::all() - StateFlags::DECORATIONS - StateFlags::VISIBLE
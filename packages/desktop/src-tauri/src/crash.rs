@@ -0,0 +1,172 @@
+use std::{path::Path, time::Duration};
+
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::cli::SidecarTerminated;
+
+/// Exits this soon after a start count as the sidecar failing to start at all.
+const IMMEDIATE_EXIT: Duration = Duration::from_secs(5);
+/// Immediate exits in a row after which restarting is pointless.
+const CRASH_LOOP_EXITS: u32 = 3;
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashKind {
+    PortInUse,
+    MissingBinary,
+    BadConfig,
+    Unknown,
+}
+
+/// Sent when a sidecar keeps exiting right after it starts and is no longer restarted.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ServerCrash {
+    /// The project directory of a per-project server, `None` for the app's own server.
+    pub directory: Option<String>,
+    pub kind: CrashKind,
+    pub suggestion: String,
+    pub exits: u32,
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    /// The last lines the sidecar wrote to stderr, oldest first.
+    pub stderr: Vec<String>,
+}
+
+/// Counts consecutive exits that happened right after a start.
+#[derive(Default)]
+pub struct CrashLoop {
+    immediate_exits: u32,
+}
+
+impl CrashLoop {
+    /// Records an exit after `uptime`, returning whether the sidecar is crash looping.
+    pub fn exited(&mut self, uptime: Duration) -> bool {
+        if uptime < IMMEDIATE_EXIT {
+            self.immediate_exits += 1;
+        } else {
+            self.immediate_exits = 0;
+        }
+        self.immediate_exits >= CRASH_LOOP_EXITS
+    }
+}
+
+fn classify(code: Option<i32>, stderr: &[String]) -> CrashKind {
+    let output = stderr.join("\n").to_lowercase();
+    let mentions = |needles: &[&str]| needles.iter().any(|needle| output.contains(needle));
+
+    if mentions(&[
+        "eaddrinuse",
+        "address already in use",
+        "failed to start server on port",
+    ]) {
+        CrashKind::PortInUse
+    } else if mentions(&[
+        "configjsonerror",
+        "configinvaliderror",
+        "configdirectorytypoerror",
+        "configuration is invalid",
+        "is not valid json",
+    ]) {
+        CrashKind::BadConfig
+    } else if code == Some(127)
+        || mentions(&["command not found", "is not recognized as", "enoent"])
+    {
+        CrashKind::MissingBinary
+    } else {
+        CrashKind::Unknown
+    }
+}
+
+fn suggestion(kind: CrashKind) -> &'static str {
+    match kind {
+        CrashKind::PortInUse => {
+            "Another program is using the server port. Close it, or choose a different port range in settings."
+        }
+        CrashKind::MissingBinary => {
+            "The opencode binary could not be started. Reinstall the app, or check the shell settings if it runs through a custom shell."
+        }
+        CrashKind::BadConfig => {
+            "The opencode config is invalid. Fix the file named in the log, then restart the server."
+        }
+        CrashKind::Unknown => "Check the server logs for details, then restart the server.",
+    }
+}
+
+/// Logs the crash loop and tells the frontend what went wrong.
+pub fn report(app: &AppHandle, directory: Option<&Path>, terminated: Option<SidecarTerminated>) {
+    let SidecarTerminated {
+        code,
+        signal,
+        stderr,
+    } = terminated.unwrap_or(SidecarTerminated {
+        code: None,
+        signal: None,
+        stderr: vec![],
+    });
+    let kind = classify(code, &stderr);
+
+    tracing::error!(
+        ?directory,
+        ?kind,
+        ?code,
+        ?signal,
+        "Sidecar exits right after starting, not restarting it"
+    );
+
+    let _ = ServerCrash {
+        directory: directory.map(|d| d.display().to_string()),
+        kind,
+        suggestion: suggestion(kind).to_string(),
+        exits: CRASH_LOOP_EXITS,
+        code,
+        signal,
+        stderr,
+    }
+    .emit(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(output: &str) -> Vec<String> {
+        output.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn classifies_common_failures() {
+        assert_eq!(
+            classify(
+                Some(1),
+                &lines("Error: Failed to start server on port 4096")
+            ),
+            CrashKind::PortInUse
+        );
+        assert_eq!(
+            classify(
+                Some(1),
+                &lines(
+                    "Error: Config file at /home/me/.config/opencode/opencode.json is not valid JSON(C)"
+                )
+            ),
+            CrashKind::BadConfig
+        );
+        assert_eq!(
+            classify(Some(127), &lines("zsh:1: no such file")),
+            CrashKind::MissingBinary
+        );
+        assert_eq!(classify(Some(1), &[]), CrashKind::Unknown);
+    }
+
+    #[test]
+    fn crash_loop_needs_consecutive_immediate_exits() {
+        let mut crash_loop = CrashLoop::default();
+        assert!(!crash_loop.exited(Duration::from_secs(1)));
+        assert!(!crash_loop.exited(Duration::from_secs(1)));
+        assert!(!crash_loop.exited(Duration::from_secs(30)));
+        assert!(!crash_loop.exited(Duration::from_secs(1)));
+        assert!(!crash_loop.exited(Duration::from_secs(1)));
+        assert!(crash_loop.exited(Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{cli::SidecarTerminated, constants, proxy, settings};
+
+/// Older reports are deleted once there are more than this many.
+const MAX_REPORTS: usize = 20;
+const MAX_BACKTRACE_FRAMES: usize = 100;
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Mirrors the consent setting, since the panic hook can't read the settings store.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REPORTER: OnceLock<Reporter> = OnceLock::new();
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashReportKind {
+    /// The app itself panicked.
+    Panic,
+    /// A server the app started exited on its own with an error.
+    SidecarExit,
+}
+
+/// What is stored about a crash, and exactly what is sent when the user submits it. Paths in
+/// the home directory are shortened to `~`.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct CrashReport {
+    pub id: String,
+    pub kind: CrashReportKind,
+    pub created_at: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub message: String,
+    /// Where in the source the app panicked.
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: Vec<String>,
+    /// The project of a per-project server that exited.
+    pub directory: Option<String>,
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub uptime_secs: Option<u32>,
+    /// The last lines the server wrote to stderr.
+    pub stderr: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<String>,
+}
+
+struct Reporter {
+    dir: PathBuf,
+    app_version: String,
+    home: Option<String>,
+}
+
+impl Reporter {
+    fn redact(&self, text: &str) -> String {
+        match &self.home {
+            Some(home) if !home.is_empty() => text.replace(home.as_str(), "~"),
+            _ => text.to_string(),
+        }
+    }
+
+    fn report(&self, kind: CrashReportKind, message: &str) -> CrashReport {
+        let now = chrono::Utc::now();
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        CrashReport {
+            id: format!("{}-{}", now.format("%Y%m%d-%H%M%S"), &suffix[..8]),
+            kind,
+            created_at: now.to_rfc3339(),
+            app_version: self.app_version.clone(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            message: self.redact(message),
+            location: None,
+            thread: None,
+            backtrace: Vec::new(),
+            directory: None,
+            code: None,
+            signal: None,
+            uptime_secs: None,
+            stderr: Vec::new(),
+            submitted_at: None,
+        }
+    }
+
+    fn panic_report(&self, info: &std::panic::PanicHookInfo) -> CrashReport {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        let mut report = self.report(CrashReportKind::Panic, &message);
+        report.location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+        report.thread = std::thread::current().name().map(str::to_string);
+        report.backtrace = std::backtrace::Backtrace::force_capture()
+            .to_string()
+            .lines()
+            .take(MAX_BACKTRACE_FRAMES)
+            .map(|line| self.redact(line.trim_end()))
+            .collect();
+        report
+    }
+
+    fn exit_report(
+        &self,
+        directory: Option<&Path>,
+        terminated: &SidecarTerminated,
+        uptime: Duration,
+    ) -> CrashReport {
+        let message = match (terminated.code, terminated.signal) {
+            (_, Some(signal)) => format!("The server was killed by signal {signal}"),
+            (Some(code), None) => format!("The server exited with code {code}"),
+            (None, None) => "The server exited".to_string(),
+        };
+        let mut report = self.report(CrashReportKind::SidecarExit, &message);
+        report.directory = directory.map(|d| self.redact(&d.to_string_lossy()));
+        report.code = terminated.code;
+        report.signal = terminated.signal;
+        report.uptime_secs = Some(uptime.as_secs().min(u32::MAX as u64) as u32);
+        report.stderr = terminated
+            .stderr
+            .iter()
+            .map(|line| self.redact(line))
+            .collect();
+        report
+    }
+}
+
+fn save(dir: &Path, report: &CrashReport) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let contents = serde_json::to_string_pretty(report)
+        .map_err(|e| format!("Failed to serialize the crash report: {}", e))?;
+    let path = dir.join(format!("{}.json", report.id));
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The reports in `dir`, newest first.
+fn load_all(dir: &Path) -> Vec<CrashReport> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut reports = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_str::<CrashReport>(&contents).ok())
+        .collect::<Vec<_>>();
+    reports.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    reports
+}
+
+fn prune(dir: &Path) {
+    for report in load_all(dir).iter().skip(MAX_REPORTS) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", report.id)));
+    }
+}
+
+fn record(reporter: &Reporter, report: &CrashReport) {
+    match save(&reporter.dir, report) {
+        Ok(()) => prune(&reporter.dir),
+        Err(e) => tracing::warn!("Failed to save crash report: {e}"),
+    }
+}
+
+fn is_report_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn reports_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join("crash-reports"))
+        .map_err(|e| format!("Failed to resolve the crash reports directory: {}", e))
+}
+
+/// Installs the panic hook, which saves a report before the default hook runs if the user opted
+/// in to crash reporting.
+pub fn init(app: &AppHandle) {
+    let Ok(dir) = reports_dir(app) else {
+        return;
+    };
+    ENABLED.store(
+        settings::load(app).is_ok_and(|s| s.crash_reporting),
+        Ordering::Relaxed,
+    );
+    let reporter = Reporter {
+        dir,
+        app_version: app.package_info().version.to_string(),
+        home: dirs::home_dir().map(|home| home.to_string_lossy().to_string()),
+    };
+    if REPORTER.set(reporter).is_err() {
+        return;
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if ENABLED.load(Ordering::Relaxed)
+            && let Some(reporter) = REPORTER.get()
+        {
+            record(reporter, &reporter.panic_report(info));
+        }
+        previous(info);
+    }));
+}
+
+/// Saves a report for a server that exited with an error, if the user opted in.
+pub fn record_exit(
+    directory: Option<&Path>,
+    terminated: Option<&SidecarTerminated>,
+    uptime: Duration,
+) {
+    let Some(terminated) = terminated else {
+        return;
+    };
+    // Adopted servers exit without a status, and a zero code is a clean shutdown.
+    if matches!(
+        (terminated.code, terminated.signal),
+        (Some(0), None) | (None, None)
+    ) {
+        return;
+    }
+    if ENABLED.load(Ordering::Relaxed)
+        && let Some(reporter) = REPORTER.get()
+    {
+        record(
+            reporter,
+            &reporter.exit_report(directory, terminated, uptime),
+        );
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_crash_reporting(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.crash_reporting)
+}
+
+/// Whether crashes are recorded so they can be reviewed and sent. Reports already saved are
+/// kept when turning it off.
+#[tauri::command]
+#[specta::specta]
+pub fn set_crash_reporting(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.crash_reporting = enabled;
+        Ok(())
+    })?;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    tracing::info!(enabled, "Changed crash reporting consent");
+    Ok(())
+}
+
+/// Saved crash reports, newest first.
+#[tauri::command]
+#[specta::specta]
+pub fn list_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, String> {
+    Ok(load_all(&reports_dir(&app)?))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_crash_report(app: AppHandle, id: String) -> Result<(), String> {
+    if !is_report_id(&id) {
+        return Err(format!("Invalid crash report id: {}", id));
+    }
+    let path = reports_dir(&app)?.join(format!("{id}.json"));
+    std::fs::remove_file(&path).map_err(|e| format!("Failed to delete {}: {}", path.display(), e))
+}
+
+/// Sends a saved report, exactly as `list_crash_reports` shows it. Needs the user's consent.
+#[tauri::command]
+#[specta::specta]
+pub async fn submit_crash_report(app: AppHandle, id: String) -> Result<CrashReport, String> {
+    if !settings::load(&app)?.crash_reporting {
+        return Err("Crash reporting is turned off".to_string());
+    }
+    let Some(url) = constants::CRASH_REPORT_URL else {
+        return Err("This build can't send crash reports".to_string());
+    };
+    let dir = reports_dir(&app)?;
+    let Some(mut report) = load_all(&dir).into_iter().find(|r| r.id == id) else {
+        return Err(format!("No crash report {}", id));
+    };
+    report.submitted_at = None;
+
+    let body = serde_json::to_string(&report)
+        .map_err(|e| format!("Failed to serialize the crash report: {}", e))?;
+    proxy::http_client(&app, SUBMIT_TIMEOUT)?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to send the crash report: {}", e))?;
+
+    report.submitted_at = Some(chrono::Utc::now().to_rfc3339());
+    save(&dir, &report)?;
+    tracing::info!(%id, "Submitted crash report");
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_redacted_exit_reports_and_prunes_old_ones() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let reporter = Reporter {
+            dir: dir.to_path_buf(),
+            app_version: "1.2.3".to_string(),
+            home: Some("/home/me".to_string()),
+        };
+
+        let terminated = SidecarTerminated {
+            code: Some(1),
+            signal: None,
+            stderr: vec!["Error: /home/me/.config/opencode/opencode.json is invalid".to_string()],
+        };
+        let report = reporter.exit_report(
+            Some(Path::new("/home/me/work/app")),
+            &terminated,
+            Duration::from_secs(42),
+        );
+        assert_eq!(report.message, "The server exited with code 1");
+        assert_eq!(report.directory.as_deref(), Some("~/work/app"));
+        assert_eq!(
+            report.stderr,
+            ["Error: ~/.config/opencode/opencode.json is invalid"]
+        );
+        assert_eq!(report.uptime_secs, Some(42));
+        assert!(is_report_id(&report.id));
+
+        for i in 0..MAX_REPORTS + 2 {
+            let mut report = report.clone();
+            report.id = format!("report-{i:02}");
+            report.created_at = format!("2026-01-01T00:00:{i:02}Z");
+            record(&reporter, &report);
+        }
+        let reports = load_all(dir);
+        assert_eq!(reports.len(), MAX_REPORTS);
+        assert_eq!(reports[0].id, format!("report-{:02}", MAX_REPORTS + 1));
+    }
+}
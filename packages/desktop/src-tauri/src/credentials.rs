@@ -0,0 +1,349 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+
+use crate::{service, settings, supervisor};
+
+const TOKENS_FILE: &str = "server-tokens.json";
+const ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// What a bearer token may do. Read-only tokens are limited to the server's routes for viewing
+/// sessions, and pairing secrets can only be exchanged for a token at `POST /pair`.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Read,
+    Full,
+    Pair,
+}
+
+/// The token a pairing secret is exchanged for.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Grant {
+    scope: TokenScope,
+    /// How long the token is valid, in milliseconds.
+    ttl: i64,
+}
+
+/// A token as the server reads it from the tokens file. Only its hash is kept.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct TokenEntry {
+    id: String,
+    hash: String,
+    scope: TokenScope,
+    /// Milliseconds since the epoch.
+    expires_at: i64,
+    /// Set on pairing secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grants: Option<Grant>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+struct TokensFile {
+    tokens: Vec<TokenEntry>,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct ServerToken {
+    pub id: String,
+    pub scope: TokenScope,
+    pub expires_at: String,
+}
+
+/// A newly issued token. `token` is not stored anywhere and can't be shown again.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct IssuedToken {
+    pub id: String,
+    pub token: String,
+    pub scope: TokenScope,
+    pub expires_at: String,
+}
+
+/// Sent when the server was restarted with a rotated password. The password itself stays in Rust,
+/// which adds it to proxied requests and streams.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ServerCredentialsChanged;
+
+/// Where the sidecar looks up bearer tokens, passed to it as `OPENCODE_SERVER_TOKENS_FILE`.
+pub fn tokens_file(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(TOKENS_FILE))
+        .map_err(|e| format!("Failed to resolve the app data directory: {}", e))
+}
+
+fn read_tokens(app: &AppHandle) -> Result<TokensFile, String> {
+    match std::fs::read_to_string(tokens_file(app)?) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse server tokens: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(TokensFile::default()),
+        Err(e) => Err(format!("Failed to read server tokens: {}", e)),
+    }
+}
+
+/// Writes `tokens`, dropping expired ones.
+fn write_tokens(app: &AppHandle, mut tokens: TokensFile) -> Result<(), String> {
+    let now = Utc::now().timestamp_millis();
+    tokens.tokens.retain(|token| token.expires_at > now);
+
+    let path = tokens_file(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let contents = serde_json::to_string(&tokens)
+        .map_err(|e| format!("Failed to serialize server tokens: {}", e))?;
+    write_atomic(&path, contents.as_bytes())
+        .map_err(|e| format!("Failed to write server tokens: {}", e))
+}
+
+/// Writes `contents` to a private temporary file next to `path` and moves it over `path`, so the
+/// server never reads a partly written file and the tokens are never readable by anyone else.
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{name}.{}.tmp", uuid::Uuid::new_v4().simple()));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&temp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    let result = written.and_then(|()| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+fn expiry(millis: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+pub fn issue(app: &AppHandle, scope: TokenScope, ttl: Duration) -> Result<IssuedToken, String> {
+    issue_entry(app, scope, ttl, None)
+}
+
+/// Issues a single-use pairing secret that the server exchanges for a `scope` token valid for
+/// `grant_ttl`, as long as it is redeemed within `ttl`.
+pub fn issue_pairing(
+    app: &AppHandle,
+    scope: TokenScope,
+    grant_ttl: Duration,
+    ttl: Duration,
+) -> Result<IssuedToken, String> {
+    let grant = Grant {
+        scope,
+        ttl: grant_ttl.as_millis() as i64,
+    };
+    issue_entry(app, TokenScope::Pair, ttl, Some(grant))
+}
+
+fn issue_entry(
+    app: &AppHandle,
+    scope: TokenScope,
+    ttl: Duration,
+    grants: Option<Grant>,
+) -> Result<IssuedToken, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let token = format!("oc_{}", uuid::Uuid::new_v4().simple());
+    let expires_at = Utc::now().timestamp_millis() + ttl.as_millis() as i64;
+
+    let mut tokens = read_tokens(app)?;
+    tokens.tokens.push(TokenEntry {
+        id: id.clone(),
+        hash: format!("{:x}", Sha256::digest(token.as_bytes())),
+        scope,
+        expires_at,
+        grants,
+    });
+    write_tokens(app, tokens)?;
+
+    tracing::info!(%id, ?scope, ?ttl, "Issued server token");
+    Ok(IssuedToken {
+        id,
+        token,
+        scope,
+        expires_at: expiry(expires_at),
+    })
+}
+
+/// Issues a read-only bearer token for the local server that expires after `ttl_minutes`. Full
+/// tokens act like the server password, which never reaches the webview, so only pairing hands
+/// them out.
+#[tauri::command]
+#[specta::specta]
+pub fn issue_server_token(
+    app: AppHandle,
+    scope: TokenScope,
+    ttl_minutes: u32,
+) -> Result<IssuedToken, String> {
+    if ttl_minutes == 0 {
+        return Err("Tokens must be valid for at least a minute".to_string());
+    }
+    if scope == TokenScope::Pair {
+        return Err("Pairing secrets are only handed out with the pairing info".to_string());
+    }
+    if scope == TokenScope::Full {
+        return Err("Only read-only tokens can be issued".to_string());
+    }
+    issue(&app, scope, Duration::from_secs(ttl_minutes as u64 * 60))
+}
+
+/// Tokens that have not expired yet.
+#[tauri::command]
+#[specta::specta]
+pub fn list_server_tokens(app: AppHandle) -> Result<Vec<ServerToken>, String> {
+    let now = Utc::now().timestamp_millis();
+    Ok(read_tokens(&app)?
+        .tokens
+        .into_iter()
+        .filter(|token| token.expires_at > now)
+        .map(|token| ServerToken {
+            id: token.id,
+            scope: token.scope,
+            expires_at: expiry(token.expires_at),
+        })
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn revoke_server_token(app: AppHandle, id: String) -> Result<(), String> {
+    let mut tokens = read_tokens(&app)?;
+    tokens.tokens.retain(|token| token.id != id);
+    write_tokens(&app, tokens)?;
+    tracing::info!(%id, "Revoked server token");
+    Ok(())
+}
+
+/// Whether a password last rotated at `rotated_at` is due for rotation.
+pub fn rotation_due(rotated_at: DateTime<Utc>, days: u32, now: DateTime<Utc>) -> bool {
+    now - rotated_at >= chrono::Duration::days(days as i64)
+}
+
+fn record_rotation(app: &AppHandle) -> Result<(), String> {
+    settings::update(app, |s| {
+        s.server_password_rotated_at = Some(Utc::now().to_rfc3339());
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Generates a new server password, restarts the server with it and tells the frontend.
+async fn rotate(app: &AppHandle) -> Result<(), String> {
+    if supervisor::current_spec(app).is_none() {
+        return Err("The server is not managed by the desktop app".to_string());
+    }
+
+    supervisor::rekey(app).await?;
+    // Recorded only once it worked, so a failed rotation is retried at the next check.
+    if let Err(e) = record_rotation(app) {
+        tracing::warn!("Failed to record the password rotation: {e}");
+    }
+    let service = app.clone();
+    match tauri::async_runtime::spawn_blocking(move || service::refresh(&service)).await {
+        Ok(Err(e)) => tracing::warn!("Failed to refresh the server service: {e}"),
+        Err(e) => tracing::warn!("Failed to refresh the server service: {e}"),
+        Ok(Ok(())) => {}
+    }
+    let _ = ServerCredentialsChanged.emit(app);
+    Ok(())
+}
+
+/// Rotates the password once the configured number of days has passed. Passwords without a
+/// recorded rotation start counting now.
+async fn rotate_if_due(app: &AppHandle) -> Result<(), String> {
+    let settings = settings::load(app)?;
+    let Some(days) = settings.password_rotation_days else {
+        return Ok(());
+    };
+    let rotated_at = settings
+        .server_password_rotated_at
+        .and_then(|at| DateTime::parse_from_rfc3339(&at).ok());
+    let Some(rotated_at) = rotated_at else {
+        return record_rotation(app);
+    };
+
+    if !rotation_due(rotated_at.to_utc(), days, Utc::now()) {
+        return Ok(());
+    }
+    // Only a server the app supervises can be restarted with the new password.
+    if supervisor::current_spec(app).is_none() {
+        return Ok(());
+    }
+
+    tracing::info!(days, "Server password is due for rotation");
+    rotate(app).await
+}
+
+/// Checks whether the server password is due for rotation until the app exits.
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(ROTATION_CHECK_INTERVAL).await;
+            if let Err(e) = rotate_if_due(&app).await {
+                tracing::warn!("Failed to rotate server password: {e}");
+            }
+        }
+    });
+}
+
+/// Rotates the server password now and restarts the server with it.
+#[tauri::command]
+#[specta::specta]
+pub async fn rotate_server_credentials(app: AppHandle) -> Result<(), String> {
+    rotate(&app).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_password_rotation_days(app: AppHandle) -> Result<Option<u32>, String> {
+    Ok(settings::load(&app)?.password_rotation_days)
+}
+
+/// Rotates the server password every `days` days, or never when `None`.
+#[tauri::command]
+#[specta::specta]
+pub fn set_password_rotation_days(app: AppHandle, days: Option<u32>) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.password_rotation_days = days;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_is_due_after_the_configured_days() {
+        let rotated_at = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        let later = |days| rotated_at + chrono::Duration::days(days);
+
+        assert!(!rotation_due(rotated_at, 7, later(6)));
+        assert!(rotation_due(rotated_at, 7, later(7)));
+        assert!(rotation_due(rotated_at, 1, later(30)));
+    }
+}
@@ -0,0 +1,219 @@
+use std::sync::Mutex;
+
+use reqwest::Url;
+use tauri::AppHandle;
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_opener::OpenerExt;
+use tauri_specta::Event;
+
+use crate::{instances, logging, projects};
+
+const SCHEME: &str = "opencode";
+const MAX_SESSION_ID: usize = 128;
+
+// Links that arrived before the frontend listened for them, until it takes them.
+static PENDING: Mutex<Option<Vec<DeepLink>>> = Mutex::new(Some(Vec::new()));
+
+/// A validated `opencode://` link, forwarded to the frontend to act on.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeepLink {
+    /// `opencode://open?path=<dir>`, or `opencode://open-project?directory=<dir>`.
+    OpenProject { directory: String },
+    /// `opencode://session/<id>`, optionally with `?directory=<dir>`.
+    Session {
+        id: String,
+        directory: Option<String>,
+    },
+    /// `opencode://logs`, optionally with `?seq=<n>` naming the sidecar log line it is about.
+    /// Handled here by opening the log file, the frontend has no log view.
+    Logs { seq: Option<u32> },
+}
+
+/// A link to the log holding sidecar line `seq`.
+pub fn logs_link(seq: u32) -> String {
+    format!("{SCHEME}://logs?seq={seq}")
+}
+
+fn query(url: &Url, name: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn directory(path: &str) -> Result<String, String> {
+    if !std::path::Path::new(path).is_absolute() {
+        return Err(format!("{path} is not an absolute path"));
+    }
+    Ok(instances::canonical_directory(path)?.display().to_string())
+}
+
+pub fn is_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_SESSION_ID
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn parse(url: &Url) -> Result<DeepLink, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Not an {SCHEME}:// link"));
+    }
+
+    match url.host_str() {
+        Some("open") => {
+            let path = query(url, "path").ok_or("Missing path")?;
+            Ok(DeepLink::OpenProject {
+                directory: directory(&path)?,
+            })
+        }
+        Some("open-project") => {
+            let path = query(url, "directory").ok_or("Missing directory")?;
+            Ok(DeepLink::OpenProject {
+                directory: directory(&path)?,
+            })
+        }
+        Some("session") => {
+            let id = url.path().trim_matches('/');
+            if !is_session_id(id) {
+                return Err(format!("Invalid session id '{id}'"));
+            }
+            Ok(DeepLink::Session {
+                id: id.to_string(),
+                directory: query(url, "directory")
+                    .map(|path| directory(&path))
+                    .transpose()?,
+            })
+        }
+        Some("logs") => Ok(DeepLink::Logs {
+            seq: query(url, "seq")
+                .map(|seq| seq.parse().map_err(|_| format!("Invalid seq '{seq}'")))
+                .transpose()?,
+        }),
+        other => Err(format!("Unsupported link '{}'", other.unwrap_or_default())),
+    }
+}
+
+fn route(app: &AppHandle, url: &Url) {
+    let link = match parse(url) {
+        Ok(link) => link,
+        Err(e) => {
+            tracing::warn!(%url, "Ignoring deep link: {e}");
+            return;
+        }
+    };
+    tracing::info!(?link, "Received deep link");
+
+    let directory = match &link {
+        DeepLink::OpenProject { directory } => Some(directory),
+        DeepLink::Session { directory, .. } => directory.as_ref(),
+        DeepLink::Logs { .. } => return open_log(app),
+    };
+    if let Some(directory) = directory
+        && let Err(e) = projects::add_recent_project(app.clone(), directory.clone())
+    {
+        tracing::warn!("Failed to record recent project: {e}");
+    }
+
+    if let Some(pending) = PENDING.lock().unwrap().as_mut() {
+        pending.push(link);
+        return;
+    }
+    let _ = link.emit(app);
+}
+
+/// Opens the app's log, which has the sidecar's output next to its own.
+fn open_log(app: &AppHandle) {
+    let Some(path) = logging::current_path() else {
+        tracing::warn!("No log file to open");
+        return;
+    };
+    if let Err(e) = app
+        .opener()
+        .open_path(path.display().to_string(), None::<&str>)
+    {
+        tracing::warn!("Failed to open {}: {e}", path.display());
+    }
+}
+
+/// Links that launched the app or arrived before the window was ready. Once taken, further links
+/// are sent as `DeepLink` events, so the frontend listens first and then calls this.
+#[tauri::command]
+#[specta::specta]
+pub fn take_pending_deep_links() -> Vec<DeepLink> {
+    PENDING.lock().unwrap().take().unwrap_or_default()
+}
+
+/// Routes links that launched the app and, through the single instance plugin, links opened
+/// while it runs. This is the only listener, the frontend gets links from here.
+pub fn init(app: &AppHandle) {
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in &urls {
+            route(app, url);
+        }
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            route(&handle, &url);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(link: &str) -> Result<DeepLink, String> {
+        parse(&Url::parse(link).unwrap())
+    }
+
+    #[test]
+    fn parses_session_links() {
+        let Ok(DeepLink::Session { id, directory }) = parse_str("opencode://session/ses_01abc")
+        else {
+            panic!("expected a session link");
+        };
+        assert_eq!(id, "ses_01abc");
+        assert_eq!(directory, None);
+
+        assert!(parse_str("opencode://session/").is_err());
+        assert!(parse_str("opencode://session/..%2Fetc").is_err());
+    }
+
+    #[test]
+    fn parses_log_links() {
+        assert!(matches!(
+            parse_str(&logs_link(42)),
+            Ok(DeepLink::Logs { seq: Some(42) })
+        ));
+        assert!(matches!(
+            parse_str("opencode://logs"),
+            Ok(DeepLink::Logs { seq: None })
+        ));
+        assert!(parse_str("opencode://logs?seq=last").is_err());
+    }
+
+    #[test]
+    fn rejects_relative_and_unknown_links() {
+        assert!(parse_str("opencode://open?path=relative/dir").is_err());
+        assert!(parse_str("opencode://open").is_err());
+        assert!(parse_str("opencode://settings").is_err());
+        assert!(parse_str("https://open?path=/tmp").is_err());
+    }
+
+    #[test]
+    fn open_links_resolve_directories() {
+        let dir = std::env::temp_dir();
+        let link = format!("opencode://open?path={}", dir.display());
+        let Ok(DeepLink::OpenProject { directory }) = parse_str(&link) else {
+            panic!("expected an open link");
+        };
+        assert_eq!(
+            directory,
+            dunce::canonicalize(dir).unwrap().display().to_string()
+        );
+    }
+}
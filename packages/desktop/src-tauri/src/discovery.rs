@@ -0,0 +1,211 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use tauri::{AppHandle, Manager, State};
+
+use crate::settings;
+
+const SERVICE_TYPE: &str = "_opencode._tcp.local.";
+// `opencode serve --mdns` publishes a plain http service named `opencode-<port>`.
+const CLI_SERVICE_TYPE: &str = "_http._tcp.local.";
+const CLI_INSTANCE_PREFIX: &str = "opencode-";
+const DEFAULT_BROWSE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct DiscoveredServer {
+    pub name: String,
+    pub host: String,
+    pub port: u32,
+    pub addresses: Vec<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Default)]
+struct Inner {
+    daemon: Option<ServiceDaemon>,
+    /// Full name of our own advertisement, so browsing can skip it.
+    advertised: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct Discovery {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Discovery {
+    fn daemon(&self) -> Result<ServiceDaemon, String> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(daemon) = &inner.daemon {
+            return Ok(daemon.clone());
+        }
+
+        let daemon =
+            ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS daemon: {}", e))?;
+        inner.daemon = Some(daemon.clone());
+        Ok(daemon)
+    }
+
+    fn advertise(&self, port: u32, version: &str) -> Result<(), String> {
+        self.withdraw();
+
+        let hostname = tauri_plugin_os::hostname();
+        let host = format!("{}.local.", hostname.trim_end_matches(".local"));
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &hostname,
+            &host,
+            (),
+            port as u16,
+            [("version", version)].as_slice(),
+        )
+        .map_err(|e| format!("Failed to build mDNS service: {}", e))?
+        .enable_addr_auto();
+
+        let fullname = info.get_fullname().to_string();
+        self.daemon()?
+            .register(info)
+            .map_err(|e| format!("Failed to advertise server: {}", e))?;
+
+        tracing::info!(%fullname, port, "Advertising server on the local network");
+        self.inner.lock().unwrap().advertised = Some(fullname);
+        Ok(())
+    }
+
+    fn withdraw(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let Inner { daemon, advertised } = &mut *inner;
+        if let (Some(daemon), Some(fullname)) = (daemon, advertised.take()) {
+            let _ = daemon.unregister(&fullname);
+            tracing::info!(%fullname, "Stopped advertising server");
+        }
+    }
+}
+
+/// Discovery only applies while LAN access is allowed too, since it makes the server listen on
+/// all interfaces.
+pub fn is_enabled(app: &AppHandle) -> bool {
+    settings::load(app).is_ok_and(|s| s.lan_discovery && s.allow_lan)
+}
+
+/// Advertises the local server if LAN discovery is enabled.
+pub fn advertise(app: &AppHandle, port: u32) {
+    if !is_enabled(app) {
+        return;
+    }
+    let Some(discovery) = app.try_state::<Discovery>() else {
+        return;
+    };
+
+    let version = app.package_info().version.to_string();
+    if let Err(e) = discovery.advertise(port, &version) {
+        tracing::warn!("{e}");
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_lan_discovery(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.lan_discovery)
+}
+
+/// Enabling makes the next local server listen on all interfaces and advertise itself, and needs
+/// LAN access to be allowed first; disabling stops advertising immediately.
+#[tauri::command]
+#[specta::specta]
+pub fn set_lan_discovery(
+    app: AppHandle,
+    discovery: State<'_, Discovery>,
+    enabled: bool,
+) -> Result<(), String> {
+    settings::update(&app, |s| {
+        if enabled && !s.allow_lan {
+            return Err(
+                "Allow LAN access first: discovery makes the server reachable from other machines"
+                    .to_string(),
+            );
+        }
+        s.lan_discovery = enabled;
+        Ok(())
+    })?;
+
+    if !enabled {
+        discovery.withdraw();
+    }
+
+    Ok(())
+}
+
+/// Browses the local network for other opencode servers for `timeout_ms` (3 seconds by default).
+#[tauri::command]
+#[specta::specta]
+pub async fn discover_servers(
+    discovery: State<'_, Discovery>,
+    timeout_ms: Option<u32>,
+) -> Result<Vec<DiscoveredServer>, String> {
+    let daemon = discovery.daemon()?;
+    let own = discovery.inner.lock().unwrap().advertised.clone();
+    let desktop = daemon
+        .browse(SERVICE_TYPE)
+        .map_err(|e| format!("Failed to browse for servers: {}", e))?;
+    let cli = daemon
+        .browse(CLI_SERVICE_TYPE)
+        .map_err(|e| format!("Failed to browse for servers: {}", e))?;
+
+    let mut servers = BTreeMap::new();
+    let browse = async {
+        loop {
+            let event = tokio::select! {
+                Ok(event) = desktop.recv_async() => event,
+                Ok(event) = cli.recv_async() => event,
+                else => break,
+            };
+
+            if let ServiceEvent::ServiceResolved(info) = event
+                && let Some(server) = to_discovered(&info)
+                && own.as_deref() != Some(info.get_fullname())
+            {
+                servers.insert(info.get_fullname().to_string(), server);
+            }
+        }
+    };
+
+    let timeout = timeout_ms.map_or(DEFAULT_BROWSE_TIMEOUT, |ms| {
+        Duration::from_millis(ms as u64)
+    });
+    let _ = tokio::time::timeout(timeout, browse).await;
+    let _ = daemon.stop_browse(SERVICE_TYPE);
+    let _ = daemon.stop_browse(CLI_SERVICE_TYPE);
+
+    Ok(servers.into_values().collect())
+}
+
+/// Converts a resolved service, skipping http services that are not opencode servers.
+fn to_discovered(info: &ServiceInfo) -> Option<DiscoveredServer> {
+    let fullname = info.get_fullname();
+    let name = match fullname.strip_suffix(CLI_SERVICE_TYPE) {
+        Some(name) if name.starts_with(CLI_INSTANCE_PREFIX) => name,
+        Some(_) => return None,
+        None => fullname.strip_suffix(SERVICE_TYPE).unwrap_or(fullname),
+    }
+    .trim_end_matches('.')
+    .to_string();
+
+    let mut addresses = info
+        .get_addresses()
+        .iter()
+        .map(|addr| addr.to_string())
+        .collect::<Vec<_>>();
+    addresses.sort();
+
+    Some(DiscoveredServer {
+        name,
+        host: info.get_hostname().trim_end_matches('.').to_string(),
+        port: info.get_port() as u32,
+        addresses,
+        version: info.get_property_val_str("version").map(String::from),
+    })
+}
@@ -0,0 +1,328 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    cli, server,
+    server::ServerProbe,
+    shell_env, supervisor,
+    wsl::{self, WslStatus},
+};
+
+const LOW_DISK_MB: u64 = 1024;
+const CRITICAL_DISK_MB: u64 = 100;
+
+#[derive(Clone, Copy, serde::Serialize, specta::Type, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct DoctorReport {
+    /// The worst status among the checks.
+    pub status: CheckStatus,
+    pub checks: Vec<DoctorCheck>,
+}
+
+fn check(name: &str, status: CheckStatus, message: impl Into<String>) -> DoctorCheck {
+    DoctorCheck {
+        name: name.to_string(),
+        status,
+        message: message.into(),
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+pub fn check_sidecar(app: &AppHandle) -> DoctorCheck {
+    let sidecar = cli::get_sidecar_path(app);
+    #[cfg(windows)]
+    let sidecar = sidecar.with_extension("exe");
+
+    let path = sidecar.display().to_string();
+    if !sidecar.exists() {
+        check("sidecar", CheckStatus::Fail, format!("Not found at {path}"))
+    } else if !is_executable(&sidecar) {
+        check(
+            "sidecar",
+            CheckStatus::Fail,
+            format!("{path} is not executable"),
+        )
+    } else {
+        check("sidecar", CheckStatus::Pass, path)
+    }
+}
+
+async fn check_cli_install(app: &AppHandle) -> DoctorCheck {
+    if !cli::is_cli_installed() {
+        return check("cli", CheckStatus::Warn, "The CLI is not installed");
+    }
+    let path = cli::get_cli_install_path()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+
+    let version = tauri::async_runtime::spawn_blocking(cli::installed_cli_version)
+        .await
+        .unwrap_or_else(|e| Err(format!("Failed to get CLI version: {}", e)));
+    match version {
+        Ok(version) if version < app.package_info().version => check(
+            "cli",
+            CheckStatus::Warn,
+            format!(
+                "{path} is version {version}, older than the app's {}",
+                app.package_info().version
+            ),
+        ),
+        Ok(version) => check(
+            "cli",
+            CheckStatus::Pass,
+            format!("{path} is version {version}"),
+        ),
+        Err(e) => check("cli", CheckStatus::Fail, format!("{path}: {e}")),
+    }
+}
+
+async fn check_config(app: &AppHandle) -> DoctorCheck {
+    let Some(raw) = cli::get_raw_config(app).await else {
+        return check(
+            "config",
+            CheckStatus::Fail,
+            "The CLI could not be run to read it",
+        );
+    };
+    match serde_json::from_str::<serde_json::Value>(&raw) {
+        Ok(_) => check("config", CheckStatus::Pass, "Parses"),
+        // The CLI prints what is wrong with the config instead of it.
+        Err(_) => check("config", CheckStatus::Fail, raw.trim().to_string()),
+    }
+}
+
+/// Whether the local server answers on its port, and accepts the app's credentials.
+async fn check_server(app: &AppHandle) -> [DoctorCheck; 2] {
+    let Some(spec) = supervisor::current_spec(app) else {
+        let message = "The server is not managed by the app";
+        return [
+            check("port", CheckStatus::Warn, message),
+            check("auth", CheckStatus::Warn, message),
+        ];
+    };
+
+    let url = spec.url();
+    match server::probe(&url, Some(&spec.password)).await {
+        ServerProbe::Healthy => [
+            check("port", CheckStatus::Pass, format!("{url} is reachable")),
+            check("auth", CheckStatus::Pass, "Credentials accepted"),
+        ],
+        ServerProbe::Unauthorized => [
+            check("port", CheckStatus::Pass, format!("{url} is reachable")),
+            check(
+                "auth",
+                CheckStatus::Fail,
+                "The server rejected the app's credentials",
+            ),
+        ],
+        ServerProbe::Unreachable => [
+            check("port", CheckStatus::Fail, format!("{url} is not reachable")),
+            check(
+                "auth",
+                CheckStatus::Warn,
+                "Not checked, the server is unreachable",
+            ),
+        ],
+    }
+}
+
+async fn check_wsl(app: &AppHandle) -> Option<DoctorCheck> {
+    if !cli::is_wsl_enabled(app) {
+        return None;
+    }
+    Some(match wsl::check_wsl_status(app.clone()).await {
+        Ok(WslStatus::Ready { distro }) => check("wsl", CheckStatus::Pass, distro),
+        Ok(status) => check("wsl", CheckStatus::Fail, format!("{status:?}")),
+        Err(e) => check("wsl", CheckStatus::Fail, e),
+    })
+}
+
+fn find_on_path(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn check_shell(app: &AppHandle) -> DoctorCheck {
+    let shell = shell_env::resolve(app);
+    match find_on_path(&shell.path) {
+        Some(path) => check("shell", CheckStatus::Pass, path.display().to_string()),
+        None => check(
+            "shell",
+            CheckStatus::Fail,
+            format!("{} could not be found", shell.path),
+        ),
+    }
+}
+
+fn disk_status(free_mb: u64) -> CheckStatus {
+    match free_mb {
+        mb if mb < CRITICAL_DISK_MB => CheckStatus::Fail,
+        mb if mb < LOW_DISK_MB => CheckStatus::Warn,
+        _ => CheckStatus::Pass,
+    }
+}
+
+/// Free space on the disk holding `dir`, the `what` directory.
+fn check_disk(name: &str, what: &str, dir: Option<PathBuf>, disks: &sysinfo::Disks) -> DoctorCheck {
+    let Some(dir) = dir else {
+        return check(
+            name,
+            CheckStatus::Warn,
+            format!("The {what} directory could not be resolved"),
+        );
+    };
+
+    let Some(disk) = disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+    else {
+        return check(
+            name,
+            CheckStatus::Warn,
+            format!("The {what} directory's disk was not found"),
+        );
+    };
+
+    let free_mb = disk.available_space() / (1024 * 1024);
+    check(
+        name,
+        disk_status(free_mb),
+        format!("{free_mb} MB free for {}", dir.display()),
+    )
+}
+
+/// Free space on the disks holding the sidecar's state and the CLI's data, which its database and
+/// installs grow.
+fn check_disk_space(app: &AppHandle) -> [DoctorCheck; 2] {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    [
+        check_disk(
+            "disk",
+            "state",
+            app.path().app_local_data_dir().ok(),
+            &disks,
+        ),
+        check_disk("data_disk", "CLI data", cli::data_dir(), &disks),
+    ]
+}
+
+fn report(checks: Vec<DoctorCheck>) -> DoctorReport {
+    let status = checks
+        .iter()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(CheckStatus::Pass);
+    DoctorReport { status, checks }
+}
+
+/// Checks what the app needs to run the server, so problems can be pointed out before they show
+/// up as a failed launch.
+#[tauri::command]
+#[specta::specta]
+pub async fn run_doctor(app: AppHandle) -> DoctorReport {
+    let mut checks = vec![check_sidecar(&app), check_cli_install(&app).await];
+    checks.push(check_config(&app).await);
+    checks.extend(check_server(&app).await);
+    checks.extend(check_wsl(&app).await);
+    checks.push(check_shell(&app));
+    checks.extend(check_disk_space(&app));
+
+    report(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_worst_status() {
+        assert_eq!(report(Vec::new()).status, CheckStatus::Pass);
+
+        let checks = vec![
+            check("sidecar", CheckStatus::Pass, ""),
+            check("cli", CheckStatus::Warn, ""),
+            check("shell", CheckStatus::Pass, ""),
+        ];
+        assert_eq!(report(checks).status, CheckStatus::Warn);
+
+        let checks = vec![
+            check("sidecar", CheckStatus::Fail, ""),
+            check("cli", CheckStatus::Warn, ""),
+        ];
+        let report = report(checks);
+        assert_eq!(report.status, CheckStatus::Fail);
+        assert_eq!(report.checks.len(), 2);
+    }
+
+    #[test]
+    fn grades_free_disk_space() {
+        assert_eq!(disk_status(0), CheckStatus::Fail);
+        assert_eq!(disk_status(CRITICAL_DISK_MB - 1), CheckStatus::Fail);
+        assert_eq!(disk_status(CRITICAL_DISK_MB), CheckStatus::Warn);
+        assert_eq!(disk_status(LOW_DISK_MB - 1), CheckStatus::Warn);
+        assert_eq!(disk_status(LOW_DISK_MB), CheckStatus::Pass);
+    }
+
+    #[test]
+    fn warns_when_a_disk_is_unknown() {
+        let disks = sysinfo::Disks::new();
+        let unresolved = check_disk("disk", "state", None, &disks);
+        assert_eq!(unresolved.status, CheckStatus::Warn);
+        assert_eq!(
+            unresolved.message,
+            "The state directory could not be resolved"
+        );
+
+        let missing = check_disk(
+            "data_disk",
+            "CLI data",
+            Some(PathBuf::from("/data")),
+            &disks,
+        );
+        assert_eq!(missing.name, "data_disk");
+        assert_eq!(missing.status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn finds_programs_by_path() {
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(find_on_path(exe.to_str().unwrap()), Some(exe.clone()));
+        assert!(is_executable(&exe));
+        assert_eq!(find_on_path("/no/such/opencode-shell"), None);
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+
+use crate::settings;
+
+const DOTENV_FILE: &str = ".env";
+
+/// Where to load sidecar environment variables from. Without a path, the `.env` in the
+/// directory the sidecar runs in is used.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default)]
+pub struct DotenvConfig {
+    pub enabled: bool,
+    pub path: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct DotenvPreview {
+    /// The file that would be loaded, if one exists.
+    pub path: Option<String>,
+    /// Names of the variables it sets. Values are left out so secrets don't reach the UI.
+    pub variables: Vec<String>,
+}
+
+fn resolve(config: &DotenvConfig, cwd: Option<&Path>) -> Option<PathBuf> {
+    let path = match &config.path {
+        Some(path) => PathBuf::from(path),
+        None => cwd?.join(DOTENV_FILE),
+    };
+    path.is_file().then_some(path)
+}
+
+fn parse(path: &Path) -> Result<Vec<(String, String)>, String> {
+    dotenvy::from_path_iter(path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map(|vars| {
+            vars.into_iter()
+                .filter(|(name, _)| settings::is_env_name(name))
+                .collect()
+        })
+        .map_err(|e| format!("Failed to load {}: {}", path.display(), e))
+}
+
+/// Variables from the configured `.env` file for a sidecar running in `cwd`. A broken file is
+/// logged and skipped rather than failing the spawn.
+pub fn load(app: &AppHandle, cwd: Option<&Path>) -> Vec<(String, String)> {
+    let Ok(config) = get_dotenv_config(app.clone()) else {
+        return vec![];
+    };
+    if !config.enabled {
+        return vec![];
+    }
+    let Some(path) = resolve(&config, cwd) else {
+        return vec![];
+    };
+
+    match parse(&path) {
+        Ok(vars) => {
+            tracing::info!(path = %path.display(), count = vars.len(), "Loaded .env file");
+            vars
+        }
+        Err(e) => {
+            tracing::warn!("{e}");
+            vec![]
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_dotenv_config(app: AppHandle) -> Result<DotenvConfig, String> {
+    Ok(settings::load(&app)?.dotenv)
+}
+
+/// Takes effect the next time a sidecar starts.
+#[tauri::command]
+#[specta::specta]
+pub fn set_dotenv_config(app: AppHandle, config: DotenvConfig) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.dotenv = DotenvConfig {
+            enabled: config.enabled,
+            path: config.path.filter(|p| !p.trim().is_empty()),
+        };
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Shows which variables a sidecar started in `directory` would get, even while loading is
+/// disabled, so the file can be checked before turning it on.
+#[tauri::command]
+#[specta::specta]
+pub fn preview_dotenv(app: AppHandle, directory: Option<String>) -> Result<DotenvPreview, String> {
+    let config = get_dotenv_config(app)?;
+    let Some(path) = resolve(&config, directory.as_deref().map(Path::new)) else {
+        return Ok(DotenvPreview {
+            path: None,
+            variables: vec![],
+        });
+    };
+
+    let variables = parse(&path)?.into_iter().map(|(name, _)| name).collect();
+    Ok(DotenvPreview {
+        path: Some(path.display().to_string()),
+        variables,
+    })
+}
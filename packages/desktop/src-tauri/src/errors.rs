@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+/// What went wrong, for the frontend to branch on and pick a translated message by.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Not classified yet, so only the English message describes it.
+    Unknown,
+    SidecarNotFound,
+    CliInstallPathUnknown,
+    /// `output` holds what the install script printed.
+    CliInstallScriptFailed,
+    /// `version` was not downloaded yet.
+    CliVersionNotInstalled,
+    CliVersionsUnavailableInWsl,
+    /// `version` is not a semver version.
+    InvalidCliVersion,
+    /// `subcommand` is not one the app may run.
+    CliCommandNotAllowed,
+    /// `argument` has characters that could reach the shell.
+    InvalidCliArgument,
+    CliConfigUnreadable,
+    /// `errors` lists where the config breaks its schema.
+    InvalidCliConfig,
+    /// The user cancelled the operation.
+    Cancelled,
+}
+
+impl ErrorCode {
+    /// What the user can do about it, when there is anything.
+    fn hint(self) -> Option<&'static str> {
+        match self {
+            Self::Unknown
+            | Self::CliCommandNotAllowed
+            | Self::InvalidCliArgument
+            | Self::Cancelled => None,
+            Self::SidecarNotFound => Some("Reinstall the app to restore the bundled server."),
+            Self::CliInstallPathUnknown => Some("Make sure your home directory is set."),
+            Self::CliInstallScriptFailed => {
+                Some("Check that the install directory is writable, then try again.")
+            }
+            Self::CliVersionNotInstalled => Some("Install that version first."),
+            Self::CliVersionsUnavailableInWsl => Some("Turn off WSL to switch CLI versions."),
+            Self::InvalidCliVersion => Some("Use a version like 1.2.3."),
+            Self::CliConfigUnreadable => {
+                Some("Run `opencode debug config` in a terminal to see what is wrong.")
+            }
+            Self::InvalidCliConfig => Some("Check the value against the config schema."),
+        }
+    }
+}
+
+/// A command failure the frontend can branch on and localize. `message` and `hint` are the
+/// English text shown when it has no translation for `code`, and `params` fill in the
+/// translated template.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct DesktopError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub hint: Option<String>,
+    pub params: BTreeMap<String, String>,
+}
+
+impl DesktopError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            hint: code.hint().map(str::to_string),
+            params: BTreeMap::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+impl std::fmt::Display for DesktopError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Errors that are still plain strings pass through as `Unknown`.
+impl From<String> for DesktopError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Unknown, message)
+    }
+}
+
+impl From<DesktopError> for String {
+    fn from(error: DesktopError) -> Self {
+        error.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_errors_are_unknown() {
+        let error = DesktopError::from("Failed to run".to_string());
+        assert_eq!(error.code, ErrorCode::Unknown);
+        assert_eq!(error.hint, None);
+        assert_eq!(String::from(error.clone().with("a", "b")), "Failed to run");
+        assert!(error.params.is_empty());
+    }
+
+    #[test]
+    fn codes_carry_their_hint() {
+        let error = DesktopError::new(ErrorCode::SidecarNotFound, "Sidecar binary not found");
+        assert_eq!(error.hint.as_deref(), ErrorCode::SidecarNotFound.hint());
+        assert!(error.hint.is_some());
+    }
+}
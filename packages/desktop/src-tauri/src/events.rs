@@ -0,0 +1,225 @@
+use std::{convert::Infallible, sync::Mutex, time::Duration};
+
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{response_cache::ResponseCache, server, supervisor};
+
+/// The server sends a heartbeat every 10 seconds, so a quiet stream has dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+const NO_SERVER_POLL: Duration = Duration::from_secs(2);
+/// Events the stream itself uses, which are of no interest to the UI.
+const INTERNAL_EVENTS: [&str; 2] = ["server.connected", "server.heartbeat"];
+
+/// An event from the server the app manages, forwarded from its global event stream.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ServerEvent {
+    /// The project the event belongs to, or `global` for the server itself.
+    pub directory: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub properties: serde_json::Value,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default, PartialEq)]
+pub struct EventStreamStatus {
+    pub connected: bool,
+    /// The server the stream is, or was last, connected to.
+    pub url: Option<String>,
+    /// How often the stream reconnected since the app started.
+    pub reconnects: u32,
+    pub last_error: Option<String>,
+}
+
+/// Sent whenever the event stream connects or drops.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct EventStreamChanged {
+    pub status: EventStreamStatus,
+}
+
+#[derive(Default)]
+pub struct EventStream(Mutex<EventStreamStatus>);
+
+#[derive(serde::Deserialize)]
+struct GlobalEvent {
+    directory: Option<String>,
+    payload: Payload,
+}
+
+#[derive(serde::Deserialize)]
+struct Payload {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    properties: serde_json::Value,
+}
+
+/// Splits a server-sent event stream into the data of each event.
+#[derive(Default)]
+struct SseParser {
+    pending: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feeds the next chunk of the stream and returns the events it completed.
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line = self.pending.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(std::mem::take(&mut self.data).join("\n"));
+                }
+            } else if let Some(data) = line.strip_prefix("data:") {
+                self.data
+                    .push(data.strip_prefix(' ').unwrap_or(data).to_string());
+            }
+        }
+        events
+    }
+}
+
+fn parse(data: &str) -> Option<ServerEvent> {
+    let event = serde_json::from_str::<GlobalEvent>(data).ok()?;
+    if INTERNAL_EVENTS.contains(&event.payload.kind.as_str()) {
+        return None;
+    }
+    Some(ServerEvent {
+        directory: event.directory.unwrap_or_else(|| "global".to_string()),
+        kind: event.payload.kind,
+        properties: event.payload.properties,
+    })
+}
+
+fn set_status(app: &AppHandle, f: impl FnOnce(&mut EventStreamStatus)) {
+    let stream = app.state::<EventStream>();
+    let (changed, status) = {
+        let mut status = stream.0.lock().unwrap();
+        let previous = status.clone();
+        f(&mut status);
+        (*status != previous, status.clone())
+    };
+    if changed {
+        let _ = EventStreamChanged { status }.emit(app);
+    }
+}
+
+/// Forwards events from `spec`'s server until the stream drops.
+async fn forward(app: &AppHandle, spec: &supervisor::SidecarSpec) -> Result<Infallible, String> {
+    let url = reqwest::Url::parse(&spec.url())
+        .and_then(|url| url.join("/global/event"))
+        .map_err(|e| format!("Invalid server url: {}", e))?;
+    let client = server::stream_client_for(&url, IDLE_TIMEOUT)
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut response = client
+        .get(url)
+        .basic_auth("opencode", Some(&spec.password))
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to connect to the event stream: {}", e))?;
+
+    set_status(app, |status| {
+        status.connected = true;
+        status.url = Some(spec.url());
+        status.last_error = None;
+    });
+    tracing::info!(url = %spec.url(), "Connected to the server event stream");
+
+    let mut parser = SseParser::default();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("The event stream dropped: {}", e))?
+    {
+        for event in parser.push(&chunk).iter().filter_map(|data| parse(data)) {
+            app.state::<ResponseCache>().on_event(&event.kind);
+            let _ = event.emit(app);
+        }
+    }
+    Err("The server closed the event stream".to_string())
+}
+
+/// Keeps a connection to the managed server's event stream, reconnecting with backoff when it
+/// drops or the server is replaced.
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+    tokio::spawn(async move {
+        let mut backoff = RECONNECT_MIN;
+        loop {
+            let Some(spec) = supervisor::current_spec(&app) else {
+                tokio::time::sleep(NO_SERVER_POLL).await;
+                continue;
+            };
+
+            let Err(error) = forward(&app, &spec).await;
+            tracing::debug!(url = %spec.url(), "Server event stream ended: {error}");
+            // Without events, cached responses can't be told apart from stale ones.
+            app.state::<ResponseCache>().clear();
+            set_status(&app, |status| {
+                if status.connected {
+                    status.reconnects += 1;
+                    backoff = RECONNECT_MIN;
+                }
+                status.connected = false;
+                status.last_error = Some(error);
+            });
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_MAX);
+        }
+    });
+}
+
+/// Whether events from the server are coming in, so anything they would have changed is known.
+pub fn is_connected(app: &AppHandle) -> bool {
+    app.try_state::<EventStream>()
+        .is_some_and(|stream| stream.0.lock().unwrap().connected)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_event_stream_status(stream: State<'_, EventStream>) -> EventStreamStatus {
+    stream.0.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_events_split_across_chunks() {
+        let mut parser = SseParser::default();
+
+        assert!(parser.push(b"data: {\"a\":").is_empty());
+        assert_eq!(parser.push(b"1}\r\n\r\ndata: x\n"), vec!["{\"a\":1}"]);
+        assert_eq!(parser.push(b"data:y\n: comment\nid: 3\n\n\n"), vec!["x\ny"]);
+        // A multi-byte character split between chunks.
+        let text = "data: é\n\n".as_bytes();
+        assert!(parser.push(&text[..7]).is_empty());
+        assert_eq!(parser.push(&text[7..]), vec!["é"]);
+    }
+
+    #[test]
+    fn skips_stream_events() {
+        let event = parse(
+            r#"{"directory":"/work/app","payload":{"type":"session.idle","properties":{"sessionID":"s1"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(event.directory, "/work/app");
+        assert_eq!(event.kind, "session.idle");
+        assert_eq!(event.properties["sessionID"], "s1");
+
+        assert!(parse(r#"{"payload":{"type":"server.heartbeat","properties":{}}}"#).is_none());
+        assert!(parse("not json").is_none());
+    }
+}
@@ -0,0 +1,125 @@
+use tauri::AppHandle;
+
+use crate::{
+    cli, keychain,
+    server::{self, ServerProbe},
+    settings,
+};
+
+const PASSWORD_ACCOUNT: &str = "external-server-password";
+// `opencode serve` listens here unless told otherwise.
+const DEFAULT_HOSTNAME: &str = "127.0.0.1";
+const DEFAULT_PORT: u32 = 4096;
+
+/// Settings for attaching to an `opencode serve` the user started themselves. Unset fields fall
+/// back to the CLI config's `server` section, then to the CLI defaults.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default)]
+pub struct ExternalServerConfig {
+    pub enabled: bool,
+    pub hostname: Option<String>,
+    pub port: Option<u32>,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct ExternalServerStatus {
+    pub url: String,
+    pub probe: ServerProbe,
+}
+
+/// A healthy external server to use instead of spawning the sidecar.
+pub struct ExternalServer {
+    pub url: String,
+    pub password: Option<String>,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_external_server_config(app: AppHandle) -> Result<ExternalServerConfig, String> {
+    Ok(settings::load(&app)?.external_server)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_external_server_config(
+    app: AppHandle,
+    config: ExternalServerConfig,
+) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.external_server = config;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Stores the `OPENCODE_SERVER_PASSWORD` the external server was started with, if any.
+#[tauri::command]
+#[specta::specta]
+pub fn set_external_server_password(password: Option<String>) -> Result<(), String> {
+    match password.filter(|p| !p.is_empty()) {
+        Some(password) => keychain::set_secret(PASSWORD_ACCOUNT, &password),
+        None => keychain::delete_secret(PASSWORD_ACCOUNT),
+    }
+}
+
+/// Probes the configured external server without changing which server the app is using.
+#[tauri::command]
+#[specta::specta]
+pub async fn detect_external_server(app: AppHandle) -> Result<ExternalServerStatus, String> {
+    let config = get_external_server_config(app.clone())?;
+    let url = resolve_url(&app, &config).await;
+    let password = keychain::get_secret(PASSWORD_ACCOUNT)?;
+    let probe = server::probe(&url, password.as_deref()).await;
+
+    Ok(ExternalServerStatus { url, probe })
+}
+
+/// Returns the external server to attach to, if attaching is enabled and the server is healthy
+/// and accepts our credentials.
+pub async fn find(app: &AppHandle) -> Option<ExternalServer> {
+    let config = get_external_server_config(app.clone()).ok()?;
+    if !config.enabled {
+        return None;
+    }
+
+    let url = resolve_url(app, &config).await;
+    let password = keychain::get_secret(PASSWORD_ACCOUNT).ok().flatten();
+
+    match server::probe(&url, password.as_deref()).await {
+        ServerProbe::Healthy => {
+            tracing::info!(%url, "Attaching to external server");
+            Some(ExternalServer { url, password })
+        }
+        ServerProbe::Unauthorized => {
+            tracing::warn!(%url, "External server rejected credentials, spawning sidecar");
+            None
+        }
+        ServerProbe::Unreachable => {
+            tracing::info!(%url, "External server not reachable, spawning sidecar");
+            None
+        }
+    }
+}
+
+async fn resolve_url(app: &AppHandle, config: &ExternalServerConfig) -> String {
+    let cli_server = match (&config.hostname, config.port) {
+        (Some(_), Some(_)) => None,
+        _ => cli::get_config(app).await.and_then(|c| c.server),
+    };
+
+    let hostname = config
+        .hostname
+        .clone()
+        .or_else(|| cli_server.as_ref().and_then(|s| s.hostname.clone()))
+        .unwrap_or_else(|| DEFAULT_HOSTNAME.to_string());
+    let port = config
+        .port
+        .or_else(|| cli_server.as_ref().and_then(|s| s.port))
+        .unwrap_or(DEFAULT_PORT);
+
+    format!(
+        "http://{}:{}",
+        server::normalize_hostname_for_url(&hostname),
+        port
+    )
+}
@@ -0,0 +1,277 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::{DialogExt, FileDialogBuilder, FilePath};
+
+use crate::{
+    cli,
+    wsl_path::{self, WslPathMode},
+};
+
+/// What the user granted access to by picking it in a dialog.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantKind {
+    /// A folder and everything in it.
+    Folder,
+    File,
+    /// A file that may not exist yet, to be written.
+    SaveTarget,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct GrantedPath {
+    pub path: String,
+    pub kind: GrantKind,
+}
+
+/// Paths picked in dialogs since the app started. Commands acting on paths from the frontend
+/// check these instead of trusting whatever they are given.
+#[derive(Clone, Default)]
+pub struct DialogGrants(Arc<Mutex<Vec<(PathBuf, GrantKind)>>>);
+
+impl DialogGrants {
+    pub fn record(&self, path: &Path, kind: GrantKind) {
+        let mut grants = self.0.lock().unwrap();
+        if !grants.iter().any(|(p, k)| p == path && *k == kind) {
+            grants.push((path.to_path_buf(), kind));
+        }
+    }
+}
+
+fn covers(grants: &[(PathBuf, GrantKind)], path: &Path) -> bool {
+    grants.iter().any(|(granted, kind)| match kind {
+        GrantKind::Folder => path.starts_with(granted),
+        GrantKind::File | GrantKind::SaveTarget => path == granted,
+    })
+}
+
+fn saves_to(grants: &[(PathBuf, GrantKind)], path: &Path) -> bool {
+    grants
+        .iter()
+        .any(|(granted, kind)| *kind == GrantKind::SaveTarget && path == granted)
+}
+
+/// Whether the user picked `path`, or a folder containing it, in one of the dialogs.
+pub fn is_granted(app: &AppHandle, path: &Path) -> bool {
+    let path = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    app.try_state::<DialogGrants>()
+        .is_some_and(|grants| covers(&grants.0.lock().unwrap(), &path))
+}
+
+/// Whether the user picked exactly `path` in a save dialog. Unlike [`is_granted`], folders and
+/// files picked for reading don't count, since writing there would overwrite what they hold.
+pub fn is_save_target(app: &AppHandle, path: &Path) -> bool {
+    let Ok(path) = save_target_path(path) else {
+        return false;
+    };
+    app.try_state::<DialogGrants>()
+        .is_some_and(|grants| saves_to(&grants.0.lock().unwrap(), &path))
+}
+
+fn dialog(app: &AppHandle, title: Option<String>) -> FileDialogBuilder<tauri::Wry> {
+    let mut dialog = app.dialog().file();
+    if let Some(title) = title {
+        dialog = dialog.set_title(title);
+    }
+    dialog
+}
+
+/// Runs a blocking dialog off the async runtime.
+async fn show<T: Send + 'static>(
+    show: impl FnOnce() -> Option<T> + Send + 'static,
+) -> Result<Option<T>, String> {
+    tokio::task::spawn_blocking(show)
+        .await
+        .map_err(|e| format!("Failed to show the dialog: {}", e))
+}
+
+fn local_path(path: FilePath) -> Result<PathBuf, String> {
+    path.into_path()
+        .map_err(|e| format!("The dialog returned an unusable path: {}", e))
+}
+
+/// Canonicalizes a file that may not exist yet through its parent folder.
+fn save_target_path(path: &Path) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("{} is not a file", path.display()))?;
+    let parent = path.parent().unwrap_or(Path::new("."));
+    dunce::canonicalize(parent)
+        .map(|parent| parent.join(name))
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))
+}
+
+/// Resolves a picked path, which for save targets may not exist yet, into the form the frontend
+/// gets: canonical, and inside WSL when the server runs there.
+fn resolve(app: &AppHandle, path: &Path, kind: GrantKind) -> Result<(PathBuf, String), String> {
+    let canonical = match kind {
+        GrantKind::SaveTarget => save_target_path(path)?,
+        GrantKind::Folder | GrantKind::File => dunce::canonicalize(path)
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?,
+    };
+
+    let shown = canonical.to_string_lossy().to_string();
+    let shown = if cli::is_wsl_enabled(app) {
+        wsl_path::translate(app, &shown, WslPathMode::Linux)?
+    } else {
+        shown
+    };
+    Ok((canonical, shown))
+}
+
+fn grant(
+    app: &AppHandle,
+    grants: &DialogGrants,
+    picked: Vec<FilePath>,
+    kind: GrantKind,
+) -> Result<Vec<String>, String> {
+    picked
+        .into_iter()
+        .map(|path| {
+            let (canonical, shown) = resolve(app, &local_path(path)?, kind)?;
+            grants.record(&canonical, kind);
+            tracing::debug!(path = %canonical.display(), ?kind, "Granted dialog path");
+            Ok(shown)
+        })
+        .collect()
+}
+
+/// Asks for a project folder. Returns nothing when the user cancels.
+#[tauri::command]
+#[specta::specta]
+pub async fn pick_project_folder(
+    app: AppHandle,
+    grants: State<'_, DialogGrants>,
+    title: Option<String>,
+) -> Result<Option<String>, String> {
+    let mut dialog = dialog(&app, title).set_can_create_directories(true);
+    if let Some(home) = dirs::home_dir() {
+        dialog = dialog.set_directory(home);
+    }
+    let Some(folder) = show(move || dialog.blocking_pick_folder()).await? else {
+        return Ok(None);
+    };
+
+    Ok(grant(&app, &grants, vec![folder], GrantKind::Folder)?
+        .into_iter()
+        .next())
+}
+
+/// Asks for files to attach to a prompt. Returns none when the user cancels.
+#[tauri::command]
+#[specta::specta]
+pub async fn pick_attachments(
+    app: AppHandle,
+    grants: State<'_, DialogGrants>,
+    title: Option<String>,
+    multiple: bool,
+) -> Result<Vec<String>, String> {
+    let dialog = dialog(&app, title);
+    let files = show(move || {
+        if multiple {
+            dialog.blocking_pick_files()
+        } else {
+            dialog.blocking_pick_file().map(|file| vec![file])
+        }
+    })
+    .await?
+    .unwrap_or_default();
+
+    grant(&app, &grants, files, GrantKind::File)
+}
+
+/// Asks where to save an export, suggesting `file_name`. `extensions` limit the file types
+/// offered, without the leading dot. Returns nothing when the user cancels.
+#[tauri::command]
+#[specta::specta]
+pub async fn pick_export_path(
+    app: AppHandle,
+    grants: State<'_, DialogGrants>,
+    title: Option<String>,
+    file_name: Option<String>,
+    extensions: Vec<String>,
+) -> Result<Option<String>, String> {
+    let mut dialog = dialog(&app, title);
+    if let Some(file_name) = file_name {
+        dialog = dialog.set_file_name(file_name);
+    }
+    if !extensions.is_empty() {
+        let extensions = extensions.iter().map(String::as_str).collect::<Vec<_>>();
+        dialog = dialog.add_filter("Export", &extensions);
+    }
+    let Some(target) = show(move || dialog.blocking_save_file()).await? else {
+        return Ok(None);
+    };
+
+    Ok(grant(&app, &grants, vec![target], GrantKind::SaveTarget)?
+        .into_iter()
+        .next())
+}
+
+/// Whether `path` was picked in a dialog, or lies in a folder that was.
+#[tauri::command]
+#[specta::specta]
+pub fn is_path_granted(app: AppHandle, path: String) -> bool {
+    is_granted(&app, Path::new(&path))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_granted_paths(grants: State<'_, DialogGrants>) -> Vec<GrantedPath> {
+    grants
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(path, kind)| GrantedPath {
+            path: path.to_string_lossy().to_string(),
+            kind: *kind,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_cover_their_contents_and_files_only_themselves() {
+        let grants = vec![
+            (PathBuf::from("/work/app"), GrantKind::Folder),
+            (PathBuf::from("/tmp/notes.md"), GrantKind::File),
+            (
+                PathBuf::from("/exports/session.json"),
+                GrantKind::SaveTarget,
+            ),
+        ];
+
+        assert!(covers(&grants, Path::new("/work/app")));
+        assert!(covers(&grants, Path::new("/work/app/src/main.rs")));
+        assert!(!covers(&grants, Path::new("/work/application")));
+        assert!(covers(&grants, Path::new("/tmp/notes.md")));
+        assert!(!covers(&grants, Path::new("/tmp/other.md")));
+        assert!(covers(&grants, Path::new("/exports/session.json")));
+        assert!(!covers(&grants, Path::new("/exports")));
+    }
+
+    #[test]
+    fn only_save_dialog_picks_are_save_targets() {
+        let grants = vec![
+            (PathBuf::from("/work/app"), GrantKind::Folder),
+            (PathBuf::from("/tmp/notes.md"), GrantKind::File),
+            (
+                PathBuf::from("/exports/session.json"),
+                GrantKind::SaveTarget,
+            ),
+        ];
+
+        assert!(saves_to(&grants, Path::new("/exports/session.json")));
+        assert!(!saves_to(&grants, Path::new("/exports/other.json")));
+        assert!(!saves_to(&grants, Path::new("/work/app/src/main.rs")));
+        assert!(!saves_to(&grants, Path::new("/tmp/notes.md")));
+    }
+}
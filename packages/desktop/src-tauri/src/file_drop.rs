@@ -0,0 +1,363 @@
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use tauri::{AppHandle, DragDropEvent, Manager, WebviewWindow, WindowEvent};
+use tauri_specta::Event;
+
+use crate::{
+    cli,
+    file_dialogs::{self, DialogGrants, GrantKind},
+    project_files, settings,
+    wsl_path::{self, WslPathMode},
+};
+
+const MAX_FILES: usize = 32;
+
+/// How files dropped on a window reach the app. Off by default, since the native handler takes
+/// drops away from the web view's own drag and drop.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default)]
+pub struct FileDropConfig {
+    /// Handles drops natively, forwarding them as `FilesDropped`. Applies to windows opened
+    /// afterwards.
+    pub native: bool,
+    /// Copies dropped files larger than this into the staging area, so later edits or moves of
+    /// the original don't change what was attached.
+    pub stage_above_mb: Option<u32>,
+}
+
+impl FileDropConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.stage_above_mb == Some(0) {
+            return Err("The staging threshold must be at least 1 MB".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct Attachment {
+    /// Where to read the attachment from, which is the staged copy when there is one.
+    pub path: String,
+    /// Where it was dropped from.
+    pub source: String,
+    pub name: String,
+    pub mime: String,
+    pub size_kb: u32,
+    pub directory: bool,
+    pub staged: bool,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct RejectedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Files dropped on a window, checked and described.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct FilesDropped {
+    pub window: String,
+    pub attachments: Vec<Attachment>,
+    pub rejected: Vec<RejectedFile>,
+}
+
+/// The mime type of a file from its extension, or its first bytes when the extension says
+/// nothing.
+fn mime_type(path: &Path, head: &[u8]) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let by_extension = match extension.as_deref() {
+        Some("png") => Some("image/png"),
+        Some("jpg" | "jpeg") => Some("image/jpeg"),
+        Some("gif") => Some("image/gif"),
+        Some("webp") => Some("image/webp"),
+        Some("svg") => Some("image/svg+xml"),
+        Some("pdf") => Some("application/pdf"),
+        Some("json") => Some("application/json"),
+        Some("md" | "markdown") => Some("text/markdown"),
+        Some("html" | "htm") => Some("text/html"),
+        Some("csv") => Some("text/csv"),
+        _ => None,
+    };
+    if let Some(mime) = by_extension {
+        return mime;
+    }
+
+    match head {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xff, 0xd8, 0xff, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [b'%', b'P', b'D', b'F', ..] => "application/pdf",
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'E',
+            b'B',
+            b'P',
+            ..,
+        ] => "image/webp",
+        _ if !project_files::is_binary(head) => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+fn read_head(path: &Path) -> Vec<u8> {
+    let mut head = Vec::with_capacity(project_files::SNIFF_BYTES);
+    if let Ok(file) = std::fs::File::open(path) {
+        let _ = file
+            .take(project_files::SNIFF_BYTES as u64)
+            .read_to_end(&mut head);
+    }
+    head
+}
+
+pub fn staging_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_local_data_dir()
+        .ok()
+        .map(|dir| dir.join("dropped"))
+}
+
+fn stage(app: &AppHandle, path: &Path, name: &str) -> Result<PathBuf, String> {
+    let dir = staging_dir(app)
+        .ok_or_else(|| "Failed to resolve the staging directory".to_string())?
+        .join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create the staging directory: {}", e))?;
+    let target = dir.join(name);
+    std::fs::copy(path, &target).map_err(|e| format!("Failed to stage {name}: {}", e))?;
+    Ok(target)
+}
+
+/// `path` as the frontend and server see it, which is inside WSL when the server runs there.
+pub fn shown(app: &AppHandle, path: &Path) -> Result<String, String> {
+    let path = path.to_string_lossy().to_string();
+    if cli::is_wsl_enabled(app) {
+        return wsl_path::translate(app, &path, WslPathMode::Linux);
+    }
+    Ok(path)
+}
+
+/// The path on this machine for one the frontend got from `shown`, which is inside WSL, like
+/// `/mnt/c/...`, when the server runs there.
+fn local(app: &AppHandle, path: String) -> PathBuf {
+    if cfg!(windows) && cli::is_wsl_enabled(app) && path.starts_with('/') {
+        match wsl_path::translate(app, &path, WslPathMode::Windows) {
+            Ok(windows) => return PathBuf::from(windows),
+            Err(e) => tracing::debug!(path, "Failed to translate WSL path: {e}"),
+        }
+    }
+    PathBuf::from(path)
+}
+
+fn describe(app: &AppHandle, config: &FileDropConfig, path: &Path) -> Result<Attachment, String> {
+    let canonical =
+        dunce::canonicalize(path).map_err(|e| format!("The file can't be read: {}", e))?;
+    let metadata =
+        std::fs::metadata(&canonical).map_err(|e| format!("The file can't be read: {}", e))?;
+    let name = canonical
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| "The path has no file name".to_string())?;
+
+    if metadata.is_dir() {
+        return Ok(Attachment {
+            path: shown(app, &canonical)?,
+            source: shown(app, &canonical)?,
+            name,
+            mime: "inode/directory".to_string(),
+            size_kb: 0,
+            directory: true,
+            staged: false,
+        });
+    }
+    if !metadata.is_file() {
+        return Err("Only files and folders can be attached".to_string());
+    }
+
+    let size = metadata.len();
+    let large = config
+        .stage_above_mb
+        .is_some_and(|mb| size > mb as u64 * 1024 * 1024);
+    let readable = if large {
+        stage(app, &canonical, &name)?
+    } else {
+        canonical.clone()
+    };
+
+    Ok(Attachment {
+        path: shown(app, &readable)?,
+        source: shown(app, &canonical)?,
+        mime: mime_type(&canonical, &read_head(&canonical)).to_string(),
+        name,
+        size_kb: (size.div_ceil(1024)).try_into().unwrap_or(u32::MAX),
+        directory: false,
+        staged: large,
+    })
+}
+
+/// Checks and describes `paths`, recording them as granted since the user handed them over.
+fn ingest(app: &AppHandle, paths: &[PathBuf]) -> (Vec<Attachment>, Vec<RejectedFile>) {
+    let config = settings::load(app).map(|s| s.file_drop).unwrap_or_default();
+    let grants = app.state::<DialogGrants>();
+    let mut attachments = Vec::new();
+    let mut rejected = Vec::new();
+
+    for (index, path) in paths.iter().enumerate() {
+        if index >= MAX_FILES {
+            rejected.push(RejectedFile {
+                path: path.to_string_lossy().to_string(),
+                reason: format!("At most {MAX_FILES} files can be dropped at once"),
+            });
+            continue;
+        }
+        match describe(app, &config, path) {
+            Ok(attachment) => {
+                if let Ok(canonical) = dunce::canonicalize(path) {
+                    let kind = if attachment.directory {
+                        GrantKind::Folder
+                    } else {
+                        GrantKind::File
+                    };
+                    grants.record(&canonical, kind);
+                }
+                attachments.push(attachment);
+            }
+            Err(reason) => rejected.push(RejectedFile {
+                path: path.to_string_lossy().to_string(),
+                reason,
+            }),
+        }
+    }
+    (attachments, rejected)
+}
+
+/// Forwards files dropped on `window` as `FilesDropped`.
+pub fn watch(app: &AppHandle, window: &WebviewWindow) {
+    let app = app.clone();
+    let label = window.label().to_string();
+    window.on_window_event(move |event| {
+        let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event else {
+            return;
+        };
+        let (app, label, paths) = (app.clone(), label.clone(), paths.clone());
+        // Describing files reads them, which must not hold up the event loop.
+        tauri::async_runtime::spawn_blocking(move || {
+            let (attachments, rejected) = ingest(&app, &paths);
+            tracing::info!(
+                window = %label,
+                attachments = attachments.len(),
+                rejected = rejected.len(),
+                "Files dropped"
+            );
+            let _ = FilesDropped {
+                window: label,
+                attachments,
+                rejected,
+            }
+            .emit(&app);
+        });
+    });
+}
+
+/// Whether new windows should leave drops to `watch` instead of the web view.
+pub fn native_enabled(app: &AppHandle) -> bool {
+    settings::load(app).is_ok_and(|s| s.file_drop.native)
+}
+
+/// Removes staged copies left from earlier runs.
+pub fn clear_staging(app: &AppHandle) {
+    if let Some(dir) = staging_dir(app)
+        && dir.exists()
+        && let Err(e) = std::fs::remove_dir_all(&dir)
+    {
+        tracing::warn!("Failed to clear staged files: {e}");
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_file_drop_config(app: AppHandle) -> Result<FileDropConfig, String> {
+    Ok(settings::load(&app)?.file_drop)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_file_drop_config(app: AppHandle, config: FileDropConfig) -> Result<(), String> {
+    config.validate()?;
+
+    settings::update(&app, |s| {
+        s.file_drop = config;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Describes paths the frontend got from a dialog or an earlier drop, the same way as dropped
+/// files. Paths that were never granted are rejected.
+#[tauri::command]
+#[specta::specta]
+pub async fn ingest_paths(app: AppHandle, paths: Vec<String>) -> Result<FilesDropped, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (granted, denied): (Vec<_>, Vec<_>) = paths
+            .into_iter()
+            .map(|path| local(&app, path))
+            .partition(|path| file_dialogs::is_granted(&app, path));
+        let (attachments, mut rejected) = ingest(&app, &granted);
+        rejected.extend(denied.into_iter().map(|path| RejectedFile {
+            path: path.to_string_lossy().to_string(),
+            reason: "The path was not picked or dropped by the user".to_string(),
+        }));
+        FilesDropped {
+            window: String::new(),
+            attachments,
+            rejected,
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to read the files: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_mime_types() {
+        assert_eq!(mime_type(Path::new("a.PNG"), b""), "image/png");
+        assert_eq!(mime_type(Path::new("notes.md"), b""), "text/markdown");
+        assert_eq!(
+            mime_type(Path::new("scan"), b"%PDF-1.7\n"),
+            "application/pdf"
+        );
+        assert_eq!(
+            mime_type(Path::new("photo"), &[0xff, 0xd8, 0xff, 0xe0]),
+            "image/jpeg"
+        );
+        assert_eq!(
+            mime_type(Path::new("Makefile"), b"all:\n\tcc"),
+            "text/plain"
+        );
+        // A multi-byte character cut off by the sniffed length is still text.
+        assert_eq!(
+            mime_type(Path::new("README"), &"héllo".as_bytes()[..2]),
+            "text/plain"
+        );
+        assert_eq!(
+            mime_type(Path::new("a.out"), &[0x7f, b'E', b'L', b'F', 0, 0]),
+            "application/octet-stream"
+        );
+    }
+}
@@ -0,0 +1,737 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use git2::{
+    ApplyLocation, ApplyOptions, BranchType, Delta, Diff, DiffOptions, ErrorCode, Patch,
+    Repository, Sort, Status, StatusOptions, StatusShow, build::CheckoutBuilder,
+};
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+
+use crate::{settings, trust};
+
+/// Keeps huge change sets, like a fresh `node_modules` that isn't ignored, from swamping the UI.
+const MAX_FILES: usize = 2000;
+const DEFAULT_COMMITS: u32 = 20;
+/// Longer patches are cut off, since nobody reviews them line by line.
+const MAX_PATCH_BYTES: usize = 512 * 1024;
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct GitFile {
+    /// Relative to the repository root.
+    pub path: String,
+    /// The change staged in the index, if any.
+    pub staged: Option<ChangeKind>,
+    /// The change in the working tree that is not staged, if any.
+    pub unstaged: Option<ChangeKind>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct GitStatus {
+    pub root: String,
+    /// The checked out branch, or nothing when the head is detached.
+    pub branch: Option<String>,
+    /// The abbreviated commit the head points to, or nothing before the first commit.
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub files: Vec<GitFile>,
+    /// More files changed than are listed.
+    pub truncated: bool,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct DiffStats {
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct CommitInfo {
+    pub id: String,
+    pub summary: String,
+    pub author: String,
+    /// RFC 3339.
+    pub time: String,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct DiffHunk {
+    /// The `@@ -a,b +c,d @@` line.
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct FileDiff {
+    pub path: String,
+    /// Where the file was renamed from.
+    pub old_path: Option<String>,
+    pub change: ChangeKind,
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+    /// The unified diff, without the headers of other files.
+    pub patch: String,
+    /// The patch was longer than is returned.
+    pub truncated: bool,
+}
+
+/// Sent when the status of a watched repository changes.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct GitStatusChanged {
+    pub directory: String,
+    pub status: GitStatus,
+}
+
+/// The last status sent for each watched directory.
+#[derive(Default)]
+pub struct GitStatuses(Mutex<HashMap<PathBuf, GitStatus>>);
+
+fn git_error(e: git2::Error) -> String {
+    format!("Failed to read the git repository: {}", e)
+}
+
+/// The repository `directory` is in, or nothing when it isn't in one.
+fn open(directory: &Path) -> Result<Option<Repository>, String> {
+    match Repository::discover(directory) {
+        Ok(repo) if repo.is_bare() => Ok(None),
+        Ok(repo) => Ok(Some(repo)),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(git_error(e)),
+    }
+}
+
+fn staged_kind(status: Status) -> Option<ChangeKind> {
+    if status.is_index_new() {
+        Some(ChangeKind::Added)
+    } else if status.is_index_deleted() {
+        Some(ChangeKind::Deleted)
+    } else if status.is_index_renamed() {
+        Some(ChangeKind::Renamed)
+    } else if status.is_index_modified() || status.is_index_typechange() {
+        Some(ChangeKind::Modified)
+    } else {
+        None
+    }
+}
+
+fn unstaged_kind(status: Status) -> Option<ChangeKind> {
+    if status.is_wt_new() {
+        Some(ChangeKind::Untracked)
+    } else if status.is_wt_deleted() {
+        Some(ChangeKind::Deleted)
+    } else if status.is_wt_renamed() {
+        Some(ChangeKind::Renamed)
+    } else if status.is_wt_modified() || status.is_wt_typechange() {
+        Some(ChangeKind::Modified)
+    } else {
+        None
+    }
+}
+
+/// The branch HEAD points to, which also works before the first commit.
+fn branch_name(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    head.symbolic_target()?
+        .strip_prefix("refs/heads/")
+        .map(str::to_string)
+}
+
+fn status(repo: &Repository) -> Result<GitStatus, String> {
+    let root = repo
+        .workdir()
+        .map(|dir| {
+            dir.to_string_lossy()
+                .trim_end_matches(['/', '\\'])
+                .to_string()
+        })
+        .unwrap_or_default();
+    let branch = branch_name(repo);
+    let head = repo.head().ok().and_then(|head| head.target());
+
+    let mut upstream = None;
+    let (mut ahead, mut behind) = (0, 0);
+    if let (Some(name), Some(head)) = (&branch, head)
+        && let Ok(tracking) = repo
+            .find_branch(name, BranchType::Local)
+            .and_then(|b| b.upstream())
+    {
+        upstream = tracking.name().ok().flatten().map(str::to_string);
+        if let Some(target) = tracking.get().target()
+            && let Ok((a, b)) = repo.graph_ahead_behind(head, target)
+        {
+            (ahead, behind) = (a as u32, b as u32);
+        }
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .show(StatusShow::IndexAndWorkdir)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut options)).map_err(git_error)?;
+    let files = statuses
+        .iter()
+        .filter_map(|entry| {
+            let status = entry.status();
+            let path = entry.path()?.to_string();
+            if status.is_conflicted() {
+                return Some(GitFile {
+                    path,
+                    staged: Some(ChangeKind::Conflicted),
+                    unstaged: Some(ChangeKind::Conflicted),
+                });
+            }
+            let (staged, unstaged) = (staged_kind(status), unstaged_kind(status));
+            (staged.is_some() || unstaged.is_some()).then_some(GitFile {
+                path,
+                staged,
+                unstaged,
+            })
+        })
+        .collect::<Vec<_>>();
+    let truncated = files.len() > MAX_FILES;
+
+    Ok(GitStatus {
+        root,
+        branch,
+        head: head.map(|oid| oid.to_string()[..7].to_string()),
+        upstream,
+        ahead,
+        behind,
+        files: files.into_iter().take(MAX_FILES).collect(),
+        truncated,
+    })
+}
+
+fn diff_stats(repo: &Repository) -> Result<DiffStats, String> {
+    let tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let stats = repo
+        .diff_tree_to_workdir_with_index(tree.as_ref(), Some(&mut options))
+        .and_then(|diff| diff.stats())
+        .map_err(git_error)?;
+    Ok(DiffStats {
+        files_changed: stats.files_changed() as u32,
+        insertions: stats.insertions() as u32,
+        deletions: stats.deletions() as u32,
+    })
+}
+
+fn recent_commits(repo: &Repository, limit: usize) -> Result<Vec<CommitInfo>, String> {
+    let mut walk = repo.revwalk().map_err(git_error)?;
+    if walk.push_head().is_err() {
+        // Nothing was committed yet.
+        return Ok(Vec::new());
+    }
+    walk.set_sorting(Sort::TIME).map_err(git_error)?;
+
+    walk.take(limit)
+        .map(|oid| {
+            let commit = oid
+                .and_then(|oid| repo.find_commit(oid))
+                .map_err(git_error)?;
+            let time = chrono::DateTime::from_timestamp(commit.time().seconds(), 0)
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default();
+            Ok(CommitInfo {
+                id: commit.id().to_string(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                time,
+            })
+        })
+        .collect()
+}
+
+/// Checks that `path` names a file inside the repository, relative to its root.
+fn relative_path(path: &str) -> Result<&Path, String> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || relative.is_absolute()
+        || relative
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_)))
+    {
+        return Err(format!("{path} is not a path inside the repository"));
+    }
+    Ok(relative)
+}
+
+/// The changes staged in the index, or those in the working tree that are not, limited to
+/// `paths` when given.
+fn diff<'r>(repo: &'r Repository, paths: &[&Path], staged: bool) -> Result<Diff<'r>, String> {
+    let mut options = DiffOptions::new();
+    options.disable_pathspec_match(true);
+    for path in paths {
+        options.pathspec(path);
+    }
+    let diff = if staged {
+        let tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+        repo.diff_tree_to_index(tree.as_ref(), None, Some(&mut options))
+    } else {
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        repo.diff_index_to_workdir(None, Some(&mut options))
+    };
+    diff.map_err(git_error)
+}
+
+fn change_kind(delta: Delta) -> ChangeKind {
+    match delta {
+        Delta::Added | Delta::Copied => ChangeKind::Added,
+        Delta::Deleted => ChangeKind::Deleted,
+        Delta::Renamed => ChangeKind::Renamed,
+        Delta::Untracked => ChangeKind::Untracked,
+        Delta::Conflicted => ChangeKind::Conflicted,
+        _ => ChangeKind::Modified,
+    }
+}
+
+fn file_diffs(repo: &Repository, paths: &[&Path], staged: bool) -> Result<Vec<FileDiff>, String> {
+    let diff = diff(repo, paths, staged)?;
+    let mut files = Vec::new();
+
+    for index in 0..diff.deltas().len() {
+        let Some(mut patch) = Patch::from_diff(&diff, index).map_err(git_error)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let path = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().to_string());
+        let new_path = path(delta.new_file()).or_else(|| path(delta.old_file()));
+        let old_path = path(delta.old_file()).filter(|old| Some(old) != new_path.as_ref());
+        let change = change_kind(delta.status());
+        let binary = delta.flags().is_binary();
+
+        let hunks = (0..patch.num_hunks())
+            .filter_map(|i| patch.hunk(i).ok())
+            .map(|(hunk, _)| DiffHunk {
+                header: String::from_utf8_lossy(hunk.header())
+                    .trim_end()
+                    .to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+            })
+            .collect();
+        let text = patch.to_buf().map_err(git_error)?;
+        let truncated = text.len() > MAX_PATCH_BYTES;
+        let text = String::from_utf8_lossy(&text[..text.len().min(MAX_PATCH_BYTES)]).to_string();
+
+        files.push(FileDiff {
+            path: new_path.unwrap_or_default(),
+            old_path,
+            change,
+            binary,
+            hunks,
+            patch: text,
+            truncated,
+        });
+    }
+    Ok(files)
+}
+
+/// Stages the changes to `path`, or when `unstage` is set moves staged changes back to the working
+/// tree, keeping the rest as is. `hunks` picks hunks by their position in the file's diff; all
+/// of them when unset.
+fn apply_hunks(
+    repo: &Repository,
+    path: &Path,
+    hunks: Option<&[u32]>,
+    unstage: bool,
+) -> Result<(), String> {
+    let mut options = DiffOptions::new();
+    options.disable_pathspec_match(true).pathspec(path);
+    let diff = if unstage {
+        // Applying the staged changes in reverse to the index takes them out again.
+        let tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+        options.reverse(true);
+        repo.diff_tree_to_index(tree.as_ref(), None, Some(&mut options))
+    } else {
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        repo.diff_index_to_workdir(None, Some(&mut options))
+    }
+    .map_err(git_error)?;
+    let Some(delta) = diff.deltas().next() else {
+        return Err(format!("{} has no changes to apply", path.display()));
+    };
+    // Patches only apply to files the index already has, so new files are added as a whole.
+    if delta.status() == Delta::Untracked {
+        if hunks.is_some() {
+            return Err(format!(
+                "{} is new and can only be staged whole",
+                path.display()
+            ));
+        }
+        let mut index = repo.index().map_err(git_error)?;
+        return index
+            .add_path(path)
+            .and_then(|_| index.write())
+            .map_err(|e| format!("Failed to update the index: {}", e));
+    }
+
+    let mut position = 0;
+    let mut apply = ApplyOptions::new();
+    apply.hunk_callback(|hunk| {
+        let selected = hunk.is_none() || hunks.is_none_or(|hunks| hunks.contains(&position));
+        position += 1;
+        selected
+    });
+    repo.apply(&diff, ApplyLocation::Index, Some(&mut apply))
+        .map_err(|e| format!("Failed to update the index: {}", e))
+}
+
+/// Drops every change to `path`, staged or not. Files that are not in the head are deleted.
+fn revert(repo: &Repository, path: &Path) -> Result<(), String> {
+    let head = repo
+        .head()
+        .ok()
+        .and_then(|head| head.peel_to_commit().ok())
+        .filter(|commit| commit.tree().is_ok_and(|tree| tree.get_path(path).is_ok()));
+
+    if let Some(head) = head {
+        repo.reset_default(Some(head.as_object()), [path])
+            .map_err(git_error)?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force().path(path)))
+            .map_err(|e| format!("Failed to restore {}: {}", path.display(), e))?;
+    } else {
+        let mut index = repo.index().map_err(git_error)?;
+        if index.get_path(path, 0).is_some() {
+            index.remove_path(path).map_err(git_error)?;
+            index.write().map_err(git_error)?;
+        }
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| "The repository has no working tree".to_string())?;
+        let file = workdir.join(path);
+        if file.exists() {
+            std::fs::remove_file(&file)
+                .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs `f` on the repository `directory` is in, off the async runtime. Returns nothing when it
+/// isn't in one.
+async fn with_repo<T: Send + 'static>(
+    app: &AppHandle,
+    directory: String,
+    f: impl FnOnce(&Repository) -> Result<T, String> + Send + 'static,
+) -> Result<Option<T>, String> {
+    let directory = dunce::canonicalize(&directory)
+        .map_err(|e| format!("Failed to resolve {}: {}", directory, e))?;
+    if !trust::is_opened(app, &directory)? {
+        return Err(format!("{} was not opened in the app", directory.display()));
+    }
+    tokio::task::spawn_blocking(move || open(&directory)?.map(|repo| f(&repo)).transpose())
+        .await
+        .map_err(|e| format!("Failed to read the git repository: {}", e))?
+}
+
+/// Recomputes the status of the repository at `root` after files in it changed, sending
+/// `GitStatusChanged` when it differs from before.
+pub fn refresh(app: &AppHandle, root: PathBuf) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let status = match open(&root).and_then(|repo| repo.as_ref().map(status).transpose()) {
+            Ok(Some(status)) => status,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::debug!(root = %root.display(), "Failed to refresh git status: {e}");
+                return;
+            }
+        };
+
+        let statuses = app.state::<GitStatuses>();
+        let previous = statuses
+            .0
+            .lock()
+            .unwrap()
+            .insert(root.clone(), status.clone());
+        if previous.as_ref() != Some(&status) {
+            let _ = GitStatusChanged {
+                directory: root.to_string_lossy().to_string(),
+                status,
+            }
+            .emit(&app);
+        }
+    });
+}
+
+/// Forgets the status of `root` once it is no longer watched.
+pub fn forget(app: &AppHandle, root: &Path) {
+    app.state::<GitStatuses>().0.lock().unwrap().remove(root);
+}
+
+/// The branch, upstream and changed files of the repository `directory` is in. Returns nothing
+/// when it isn't in one.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_git_status(
+    app: AppHandle,
+    directory: String,
+) -> Result<Option<GitStatus>, String> {
+    with_repo(&app, directory, status).await
+}
+
+/// Lines added and removed in the working tree and index, compared to the head.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_git_diff_stats(
+    app: AppHandle,
+    directory: String,
+) -> Result<Option<DiffStats>, String> {
+    with_repo(&app, directory, diff_stats).await
+}
+
+/// The latest commits reachable from the head, newest first.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_recent_commits(
+    app: AppHandle,
+    directory: String,
+    limit: Option<u32>,
+) -> Result<Vec<CommitInfo>, String> {
+    let limit = limit.unwrap_or(DEFAULT_COMMITS) as usize;
+    Ok(
+        with_repo(&app, directory, move |repo| recent_commits(repo, limit))
+            .await?
+            .unwrap_or_default(),
+    )
+}
+
+/// Unified diffs of the changed files in `directory`'s repository, limited to `paths` when given,
+/// such as the files a session touched. `staged` picks the changes in the index over those in
+/// the working tree.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_file_diffs(
+    app: AppHandle,
+    directory: String,
+    paths: Option<Vec<String>>,
+    staged: bool,
+) -> Result<Vec<FileDiff>, String> {
+    let paths = paths.unwrap_or_default();
+    paths
+        .iter()
+        .try_for_each(|path| relative_path(path).map(|_| ()))?;
+    Ok(with_repo(&app, directory, move |repo| {
+        let paths = paths.iter().map(Path::new).collect::<Vec<_>>();
+        file_diffs(repo, &paths, staged)
+    })
+    .await?
+    .unwrap_or_default())
+}
+
+/// Runs `f` on `path` in the repository `directory` is in. Changing files takes a trusted
+/// directory, not just an opened one, and a repository whose working tree is trusted too, so a
+/// parent repository found above the trusted root is left alone.
+async fn change(
+    app: &AppHandle,
+    directory: String,
+    path: String,
+    f: impl FnOnce(&Repository, &Path) -> Result<(), String> + Send + 'static,
+) -> Result<(), String> {
+    relative_path(&path)?;
+    let root = dunce::canonicalize(&directory)
+        .map_err(|e| format!("Failed to resolve {}: {}", directory, e))?;
+    let trusted = settings::load(app)?.trusted_paths;
+    if !trust::is_trusted(&trusted, &root) {
+        return Err(format!("{} is not trusted", root.display()));
+    }
+    let changed = with_repo(app, directory.clone(), move |repo| {
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| "The repository has no working tree".to_string())?;
+        let workdir = dunce::canonicalize(workdir)
+            .map_err(|e| format!("Failed to resolve {}: {}", workdir.display(), e))?;
+        if !trust::is_trusted(&trusted, &workdir) {
+            return Err(format!(
+                "{} is outside the trusted directory",
+                workdir.display()
+            ));
+        }
+        f(repo, Path::new(&path))?;
+        tracing::info!(%path, "Changed git file");
+        Ok(())
+    })
+    .await?;
+    changed.ok_or_else(|| format!("{directory} is not in a git repository"))
+}
+
+/// Stages the hunks of `path` at the given positions in its unstaged diff, or the whole file.
+#[tauri::command]
+#[specta::specta]
+pub async fn stage_hunks(
+    app: AppHandle,
+    directory: String,
+    path: String,
+    hunks: Option<Vec<u32>>,
+) -> Result<(), String> {
+    change(&app, directory, path, move |repo, path| {
+        apply_hunks(repo, path, hunks.as_deref(), false)
+    })
+    .await
+}
+
+/// Unstages the hunks of `path` at the given positions in its staged diff, or the whole file.
+#[tauri::command]
+#[specta::specta]
+pub async fn unstage_hunks(
+    app: AppHandle,
+    directory: String,
+    path: String,
+    hunks: Option<Vec<u32>>,
+) -> Result<(), String> {
+    change(&app, directory, path, move |repo, path| {
+        apply_hunks(repo, path, hunks.as_deref(), true)
+    })
+    .await
+}
+
+/// Discards all changes to `path`, staged or not, deleting it when it is new.
+#[tauri::command]
+#[specta::specta]
+pub async fn revert_file(app: AppHandle, directory: String, path: String) -> Result<(), String> {
+    change(&app, directory, path, revert).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_branch_changes_and_commits() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let repo = Repository::init(dir).unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        let before = status(&repo).unwrap();
+        assert_eq!(before.branch.as_deref(), Some("main"));
+        assert_eq!(before.head, None);
+        assert!(recent_commits(&repo, 5).unwrap().is_empty());
+
+        std::fs::write(dir.join("tracked.txt"), "one\ntwo\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("tracked.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        std::fs::write(dir.join("tracked.txt"), "one\n").unwrap();
+        std::fs::write(dir.join("new.txt"), "new\n").unwrap();
+        let after = status(&repo).unwrap();
+        assert_eq!(after.head.as_ref().map(String::len), Some(7));
+        assert_eq!(
+            after.files,
+            vec![
+                GitFile {
+                    path: "new.txt".to_string(),
+                    staged: None,
+                    unstaged: Some(ChangeKind::Untracked),
+                },
+                GitFile {
+                    path: "tracked.txt".to_string(),
+                    staged: None,
+                    unstaged: Some(ChangeKind::Modified),
+                },
+            ]
+        );
+        assert_eq!(
+            diff_stats(&repo).unwrap(),
+            DiffStats {
+                files_changed: 2,
+                insertions: 1,
+                deletions: 1,
+            }
+        );
+        let commits = recent_commits(&repo, 5).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "Initial");
+    }
+
+    #[test]
+    fn stages_hunks_and_reverts_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let repo = Repository::init(dir).unwrap();
+        let original = (1..=20).map(|i| format!("line {i}\n")).collect::<String>();
+        std::fs::write(dir.join("file.txt"), &original).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        let file = Path::new("file.txt");
+        let edited = original
+            .replace("line 1\n", "first\n")
+            .replace("line 20\n", "last\n");
+        std::fs::write(dir.join(file), &edited).unwrap();
+        let unstaged = file_diffs(&repo, &[file], false).unwrap();
+        assert_eq!(unstaged[0].hunks.len(), 2);
+        assert!(unstaged[0].patch.contains("+first"));
+
+        apply_hunks(&repo, file, Some(&[1]), false).unwrap();
+        let staged = file_diffs(&repo, &[file], true).unwrap();
+        assert_eq!(staged[0].hunks.len(), 1);
+        assert!(staged[0].patch.contains("+last"));
+        assert_eq!(file_diffs(&repo, &[file], false).unwrap()[0].hunks.len(), 1);
+
+        apply_hunks(&repo, file, None, true).unwrap();
+        assert!(file_diffs(&repo, &[file], true).unwrap().is_empty());
+
+        std::fs::write(dir.join("new.txt"), "new\n").unwrap();
+        apply_hunks(&repo, Path::new("new.txt"), None, false).unwrap();
+        assert_eq!(
+            file_diffs(&repo, &[], true).unwrap()[0].change,
+            ChangeKind::Added
+        );
+
+        revert(&repo, file).unwrap();
+        revert(&repo, Path::new("new.txt")).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join(file)).unwrap(), original);
+        assert!(!dir.join("new.txt").exists());
+        assert!(status(&repo).unwrap().files.is_empty());
+
+        assert!(relative_path("../outside").is_err());
+        assert!(relative_path("/etc/passwd").is_err());
+        assert!(relative_path("src/./main.rs").is_ok());
+    }
+}
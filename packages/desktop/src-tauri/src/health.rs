@@ -0,0 +1,142 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+use tokio::task::JoinHandle;
+
+use crate::{server::check_health, watchdog};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// Tolerate a couple of slow responses before declaring the server unhealthy.
+const FAILURE_THRESHOLD: u32 = 3;
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Unknown,
+    Healthy,
+    Unhealthy,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct ServerHealth {
+    pub url: Option<String>,
+    pub status: HealthStatus,
+    pub latency_ms: Option<u32>,
+    pub uptime_secs: Option<u32>,
+    pub consecutive_failures: u32,
+}
+
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ServerHealthChanged {
+    pub status: HealthStatus,
+    pub previous: HealthStatus,
+}
+
+#[derive(Default)]
+struct Inner {
+    url: Option<String>,
+    status: Option<HealthStatus>,
+    latency: Option<Duration>,
+    healthy_since: Option<Instant>,
+    consecutive_failures: u32,
+    task: Option<JoinHandle<()>>,
+}
+
+#[derive(Clone, Default)]
+pub struct HealthMonitor {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl HealthMonitor {
+    pub fn snapshot(&self) -> ServerHealth {
+        let inner = self.inner.lock().unwrap();
+        ServerHealth {
+            url: inner.url.clone(),
+            status: inner.status.unwrap_or(HealthStatus::Unknown),
+            latency_ms: inner.latency.map(|v| v.as_millis() as u32),
+            uptime_secs: inner.healthy_since.map(|v| v.elapsed().as_secs() as u32),
+            consecutive_failures: inner.consecutive_failures,
+        }
+    }
+
+    /// Starts polling `url`, replacing any previously monitored server.
+    pub fn start(&self, app: &AppHandle, url: String, password: Option<String>) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(task) = inner.task.take() {
+            task.abort();
+        }
+
+        *inner = Inner {
+            url: Some(url.clone()),
+            ..Default::default()
+        };
+        inner.task = Some(tokio::spawn(poll(app.clone(), self.clone(), url, password)));
+    }
+
+    fn consecutive_failures(&self) -> u32 {
+        self.inner.lock().unwrap().consecutive_failures
+    }
+
+    fn record(&self, latency: Option<Duration>) -> Option<ServerHealthChanged> {
+        let mut inner = self.inner.lock().unwrap();
+        let previous = inner.status.unwrap_or(HealthStatus::Unknown);
+
+        let status = match latency {
+            Some(latency) => {
+                inner.latency = Some(latency);
+                inner.consecutive_failures = 0;
+                if previous != HealthStatus::Healthy {
+                    inner.healthy_since = Some(Instant::now());
+                }
+                HealthStatus::Healthy
+            }
+            None => {
+                inner.consecutive_failures += 1;
+                if inner.consecutive_failures < FAILURE_THRESHOLD {
+                    return None;
+                }
+                inner.latency = None;
+                inner.healthy_since = None;
+                HealthStatus::Unhealthy
+            }
+        };
+
+        inner.status = Some(status);
+        (status != previous).then_some(ServerHealthChanged { status, previous })
+    }
+}
+
+async fn poll(app: AppHandle, monitor: HealthMonitor, url: String, password: Option<String>) {
+    loop {
+        let started = Instant::now();
+        let latency = check_health(&url, password.as_deref())
+            .await
+            .then(|| started.elapsed());
+
+        if let Some(change) = monitor.record(latency) {
+            tracing::info!(%url, status = ?change.status, "Server health changed");
+            let _ = change.emit(&app);
+            if change.status == HealthStatus::Unhealthy {
+                watchdog::unhealthy(&app, &url, password.clone(), monitor.consecutive_failures());
+            }
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+pub fn start(app: &AppHandle, url: String, password: Option<String>) {
+    if let Some(monitor) = app.try_state::<HealthMonitor>() {
+        monitor.start(app, url, password);
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_server_health(monitor: State<'_, HealthMonitor>) -> ServerHealth {
+    monitor.snapshot()
+}
@@ -0,0 +1,248 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_global_shortcut::{
+    Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutEvent, ShortcutState,
+};
+
+use crate::{
+    settings,
+    windows::{MainWindow, QuickPromptWindow},
+};
+
+/// Shortcuts the OS or every app already uses, which a global binding would take away.
+const RESERVED: [&str; 10] = [
+    "alt+Tab",
+    "alt+F4",
+    "alt+Space",
+    "control+alt+Delete",
+    "super+Tab",
+    "super+Space",
+    "super+KeyQ",
+    "super+KeyW",
+    "super+KeyH",
+    "super+KeyL",
+];
+
+/// What pressing the global hotkey does.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    ShowWindow,
+    QuickPrompt,
+}
+
+/// A key combination that works while other apps are focused, like `control+shift+Space`.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct GlobalHotkey {
+    pub shortcut: String,
+    pub action: HotkeyAction,
+}
+
+impl GlobalHotkey {
+    pub fn validate(&self) -> Result<(), String> {
+        parse(&self.shortcut).map(|_| ())
+    }
+}
+
+/// The binding currently registered with the OS.
+#[derive(Default)]
+pub struct Hotkey(Mutex<Option<(Shortcut, HotkeyAction)>>);
+
+/// Parses `shortcut` and refuses ones that would get in the way of typing or of the OS.
+fn parse(shortcut: &str) -> Result<Shortcut, String> {
+    let parsed = shortcut
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut {shortcut:?}: {}", e))?;
+
+    let function_key = matches!(
+        parsed.key,
+        Code::F13
+            | Code::F14
+            | Code::F15
+            | Code::F16
+            | Code::F17
+            | Code::F18
+            | Code::F19
+            | Code::F20
+            | Code::F21
+            | Code::F22
+            | Code::F23
+            | Code::F24
+    );
+    if (parsed.mods - Modifiers::SHIFT).is_empty() && !function_key {
+        return Err(format!(
+            "{shortcut} needs a modifier other than Shift, or it would take over typing"
+        ));
+    }
+    let name = parsed.into_string();
+    if RESERVED.contains(&name.as_str()) {
+        return Err(format!("{shortcut} is reserved by the system"));
+    }
+    Ok(parsed)
+}
+
+fn run(app: &AppHandle, action: HotkeyAction) {
+    // Building a window from the shortcut handler can deadlock on Windows.
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match action {
+            HotkeyAction::ShowWindow => MainWindow::create(&app).map(|_| ()),
+            HotkeyAction::QuickPrompt => QuickPromptWindow::toggle(&app),
+        };
+        if let Err(e) = result {
+            tracing::warn!(?action, "Failed to open window from the global hotkey: {e}");
+        }
+    });
+}
+
+/// Handles global shortcut events, for the plugin builder.
+pub fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state != ShortcutState::Pressed {
+        return;
+    }
+    let action = app
+        .state::<Hotkey>()
+        .0
+        .lock()
+        .unwrap()
+        .filter(|(registered, _)| registered == shortcut)
+        .map(|(_, action)| action);
+    if let Some(action) = action {
+        run(app, action);
+    }
+}
+
+/// Registers `shortcut` in place of the current binding, keeping the current one if that fails.
+fn register(
+    app: &AppHandle,
+    hotkey: &Hotkey,
+    shortcut: Shortcut,
+    action: HotkeyAction,
+) -> Result<(), String> {
+    let mut current = hotkey.0.lock().unwrap();
+    let global = app.global_shortcut();
+
+    let previous = current.map(|(previous, _)| previous);
+    if previous != Some(shortcut) {
+        global.register(shortcut).map_err(|e| {
+            format!(
+                "{} is already used by another application: {}",
+                shortcut.into_string(),
+                e
+            )
+        })?;
+        if let Some(previous) = previous
+            && let Err(e) = global.unregister(previous)
+        {
+            tracing::warn!("Failed to unregister the previous global hotkey: {e}");
+        }
+    }
+
+    *current = Some((shortcut, action));
+    Ok(())
+}
+
+fn unregister(app: &AppHandle, hotkey: &Hotkey) -> Result<(), String> {
+    if let Some((shortcut, _)) = hotkey.0.lock().unwrap().take() {
+        app.global_shortcut()
+            .unregister(shortcut)
+            .map_err(|e| format!("Failed to unregister the global hotkey: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Registers the stored binding at startup, logging why when it can't be.
+pub fn init(app: &AppHandle) {
+    let binding = match settings::load(app) {
+        Ok(settings) => settings.global_hotkey,
+        Err(e) => {
+            tracing::warn!("{e}");
+            return;
+        }
+    };
+    let Some(binding) = binding else {
+        return;
+    };
+
+    match parse(&binding.shortcut)
+        .and_then(|shortcut| register(app, &app.state(), shortcut, binding.action))
+    {
+        Ok(()) => tracing::info!(shortcut = %binding.shortcut, "Registered global hotkey"),
+        Err(e) => tracing::warn!("Failed to register the global hotkey: {e}"),
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_global_hotkey(app: AppHandle) -> Result<Option<GlobalHotkey>, String> {
+    Ok(settings::load(&app)?.global_hotkey)
+}
+
+/// Binds `shortcut` to `action`, replacing any previous binding. Fails without changing anything
+/// when the shortcut is reserved or another application already registered it.
+#[tauri::command]
+#[specta::specta]
+pub fn set_global_hotkey(
+    app: AppHandle,
+    hotkey: State<'_, Hotkey>,
+    shortcut: String,
+    action: HotkeyAction,
+) -> Result<GlobalHotkey, String> {
+    let parsed = parse(&shortcut)?;
+    let previous = *hotkey.0.lock().unwrap();
+    register(&app, &hotkey, parsed, action)?;
+
+    let binding = GlobalHotkey {
+        shortcut: parsed.into_string(),
+        action,
+    };
+    let saved = settings::update(&app, |s| {
+        s.global_hotkey = Some(binding.clone());
+        Ok(())
+    });
+    if let Err(e) = saved {
+        let _ = match previous {
+            Some((shortcut, action)) => register(&app, &hotkey, shortcut, action),
+            None => unregister(&app, &hotkey),
+        };
+        return Err(e);
+    }
+
+    tracing::info!(shortcut = %binding.shortcut, ?action, "Set global hotkey");
+    Ok(binding)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn disable_global_hotkey(app: AppHandle, hotkey: State<'_, Hotkey>) -> Result<(), String> {
+    unregister(&app, &hotkey)?;
+    settings::update(&app, |s| {
+        s.global_hotkey = None;
+        Ok(())
+    })?;
+
+    tracing::info!("Disabled global hotkey");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_shortcuts_that_get_in_the_way() {
+        assert_eq!(
+            parse("Control+Shift+Space").unwrap().into_string(),
+            "shift+control+Space"
+        );
+        assert!(parse("F13").is_ok());
+
+        assert!(parse("").is_err());
+        assert!(parse("Control+Nope").is_err());
+        assert!(parse("KeyK").is_err());
+        assert!(parse("Shift+KeyK").is_err());
+        assert!(parse("Alt+Tab").is_err());
+        assert!(parse("Super+Q").is_err());
+    }
+}
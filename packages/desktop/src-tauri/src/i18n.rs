@@ -0,0 +1,53 @@
+use tauri::AppHandle;
+
+use crate::settings;
+
+/// Accepts language tags like `en`, `pt-BR` or `zh-Hant`.
+pub fn validate_locale(locale: &str) -> Result<(), String> {
+    let mut subtags = locale.split(['-', '_']);
+    let valid = subtags.next().is_some_and(|lang| {
+        (2..=3).contains(&lang.len()) && lang.bytes().all(|b| b.is_ascii_alphabetic())
+    }) && subtags
+        .all(|tag| (1..=8).contains(&tag.len()) && tag.bytes().all(|b| b.is_ascii_alphanumeric()));
+    if !valid {
+        return Err(format!("Invalid locale {locale:?}"));
+    }
+    Ok(())
+}
+
+/// The locale the user picked, or the system's when they did not.
+#[tauri::command]
+#[specta::specta]
+pub fn get_locale(app: AppHandle) -> Result<String, String> {
+    Ok(settings::load(&app)?
+        .locale
+        .or_else(tauri_plugin_os::locale)
+        .unwrap_or_else(|| "en".to_string()))
+}
+
+/// Overrides the system locale, or follows it again when `locale` is missing.
+#[tauri::command]
+#[specta::specta]
+pub fn set_locale(app: AppHandle, locale: Option<String>) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.locale = locale.clone();
+        Ok(())
+    })?;
+    tracing::info!(?locale, "Changed locale");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_language_tags() {
+        for locale in ["en", "pt-BR", "zh-Hant", "sr_Latn_RS", "yue"] {
+            assert!(validate_locale(locale).is_ok(), "{locale}");
+        }
+        for locale in ["", "e", "english", "en-", "en-US!", "12"] {
+            assert!(validate_locale(locale).is_err(), "{locale}");
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    cli::{self, CommandChild},
+    cli_version, keychain, port, server,
+    supervisor::{self, SidecarSpec, Supervisee},
+    trust, wsl_bootstrap,
+};
+
+const HOSTNAME: &str = "127.0.0.1";
+
+/// A sidecar serving a single project, next to the app's shared server.
+struct Instance {
+    spec: SidecarSpec,
+    /// `None` once the supervisor gave up restarting it.
+    child: Option<CommandChild>,
+}
+
+/// Per-project sidecars, keyed by canonical project directory.
+#[derive(Clone, Default)]
+pub struct Instances(Arc<Mutex<BTreeMap<PathBuf, Instance>>>);
+
+impl Instances {
+    fn spec(&self, directory: &Path) -> Option<SidecarSpec> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(directory)
+            .map(|instance| instance.spec.clone())
+    }
+
+    /// Stores the restarted child and its spec, or hands the child back if the instance was
+    /// stopped meanwhile.
+    fn replace_child(
+        &self,
+        directory: &Path,
+        child: CommandChild,
+        spec: SidecarSpec,
+    ) -> Option<CommandChild> {
+        match self.0.lock().unwrap().get_mut(directory) {
+            Some(instance) => {
+                instance.child = Some(child);
+                instance.spec = spec;
+                None
+            }
+            None => Some(child),
+        }
+    }
+
+    /// Specs of the project servers that are running.
+    pub fn specs(&self) -> Vec<SidecarSpec> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|instance| instance.child.is_some())
+            .map(|instance| instance.spec.clone())
+            .collect()
+    }
+
+    /// The password of the project server at `url`.
+    pub fn password(&self, url: &str) -> Option<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .find(|instance| instance.spec.url() == url)
+            .map(|instance| instance.spec.password.clone())
+    }
+
+    /// Processes of the running project servers.
+    pub fn pids(&self) -> Vec<u32> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|instance| instance.child.as_ref()?.pid())
+            .collect()
+    }
+
+    fn clear_child(&self, directory: &Path) {
+        if let Some(instance) = self.0.lock().unwrap().get_mut(directory) {
+            instance.child = None;
+        }
+    }
+}
+
+/// What the frontend learns about a project server. Requests to it go through `server_request`,
+/// which adds the password in Rust.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct InstanceInfo {
+    pub directory: String,
+    pub url: String,
+    /// False once the instance kept crashing and is no longer restarted.
+    pub running: bool,
+}
+
+fn info(directory: &Path, instance: &Instance) -> InstanceInfo {
+    InstanceInfo {
+        directory: directory.display().to_string(),
+        url: instance.spec.url(),
+        running: instance.child.is_some(),
+    }
+}
+
+pub fn canonical_directory(directory: &str) -> Result<PathBuf, String> {
+    let path = dunce::canonicalize(directory)
+        .map_err(|e| format!("Failed to open project directory {directory}: {}", e))?;
+    if !path.is_dir() {
+        return Err(format!("{directory} is not a directory"));
+    }
+    Ok(path)
+}
+
+/// Starts a server for the project, or returns the one already serving it.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_instance(
+    app: AppHandle,
+    instances: State<'_, Instances>,
+    directory: String,
+) -> Result<InstanceInfo, String> {
+    let directory = canonical_directory(&directory)?;
+    if let Some(instance) = instances.0.lock().unwrap().get(&directory) {
+        return Ok(info(&directory, instance));
+    }
+
+    trust::ensure(&app, &directory).await?;
+    wsl_bootstrap::ensure(&app).await?;
+    cli_version::ensure(&app, &directory)
+        .await
+        .map_err(|e| format!("Failed to get the CLI version pinned by the project: {e}"))?;
+
+    let mut spec = SidecarSpec {
+        hostname: HOSTNAME.to_string(),
+        port: port::ephemeral_port(HOSTNAME)?,
+        password: keychain::server_password(),
+        tls: None,
+        directory: Some(directory.clone()),
+        wsl_address: None,
+        socket: None,
+        bridge_port: None,
+    };
+
+    tracing::info!(directory = %directory.display(), port = spec.port, "Starting project server");
+    let (child, health_check, exit) = server::spawn_local_server(app.clone(), &mut spec).await?;
+    if let Err(e) = supervisor::wait_healthy(health_check).await {
+        let _ = child.kill();
+        return Err(format!("Project server failed to become healthy: {e}"));
+    }
+
+    let mut registry = instances.0.lock().unwrap();
+    // Another call may have started the same project while this one waited on the health check.
+    if let Some(existing) = registry.get(&directory) {
+        let _ = child.kill();
+        return Ok(info(&directory, existing));
+    }
+
+    let instance = Instance {
+        spec,
+        child: Some(child),
+    };
+    let started = info(&directory, &instance);
+    registry.insert(directory.clone(), instance);
+    let project = ProjectServer {
+        app: app.clone(),
+        directory: directory.clone(),
+        instances: instances.inner().clone(),
+    };
+    tokio::spawn(supervisor::supervise(project, exit));
+
+    Ok(started)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_instances(instances: State<'_, Instances>) -> Vec<InstanceInfo> {
+    instances
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(directory, instance)| info(directory, instance))
+        .collect()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn stop_instance(
+    instances: State<'_, Instances>,
+    directory: String,
+) -> Result<(), String> {
+    let directory = canonical_directory(&directory)?;
+    let Some(instance) = instances.0.lock().unwrap().remove(&directory) else {
+        return Err(format!("No server is running for {}", directory.display()));
+    };
+
+    if let Some(child) = instance.child {
+        child.shutdown(cli::SHUTDOWN_GRACE).await;
+    }
+    tracing::info!(directory = %directory.display(), "Stopped project server");
+    Ok(())
+}
+
+/// Stops every project server, for app exit.
+pub async fn stop_all(app: &AppHandle) {
+    let Some(instances) = app.try_state::<Instances>() else {
+        return;
+    };
+
+    let children = std::mem::take(&mut *instances.0.lock().unwrap())
+        .into_values()
+        .filter_map(|instance| instance.child);
+    futures::future::join_all(children.map(|child| child.shutdown(cli::SHUTDOWN_GRACE))).await;
+}
+
+/// A project's sidecar, restarted until the instance is stopped or keeps crashing.
+struct ProjectServer {
+    app: AppHandle,
+    directory: PathBuf,
+    instances: Instances,
+}
+
+impl Supervisee for ProjectServer {
+    fn app(&self) -> &AppHandle {
+        &self.app
+    }
+
+    // `stop_instance` removes the instance before stopping it.
+    fn spec(&self) -> Option<SidecarSpec> {
+        self.instances.spec(&self.directory)
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        Some(&self.directory)
+    }
+
+    fn gave_up(&self, _attempts: u32) {
+        self.instances.clear_child(&self.directory);
+    }
+
+    fn restarted(
+        &self,
+        child: CommandChild,
+        spec: SidecarSpec,
+        _attempt: u32,
+    ) -> Option<CommandChild> {
+        self.instances.replace_child(&self.directory, child, spec)
+    }
+}
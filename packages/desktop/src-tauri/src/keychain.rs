@@ -0,0 +1,58 @@
+use keyring::Entry;
+
+const SERVICE: &str = if cfg!(debug_assertions) {
+    "ai.opencode.desktop.dev"
+} else {
+    "ai.opencode.desktop"
+};
+const SERVER_PASSWORD_ACCOUNT: &str = "server-password";
+
+fn entry(account: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, account).map_err(|e| format!("Failed to open keychain entry: {e}"))
+}
+
+pub fn get_secret(account: &str) -> Result<Option<String>, String> {
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read from keychain: {e}")),
+    }
+}
+
+pub fn set_secret(account: &str, secret: &str) -> Result<(), String> {
+    entry(account)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to write to keychain: {e}"))
+}
+
+pub fn delete_secret(account: &str) -> Result<(), String> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete from keychain: {e}")),
+    }
+}
+
+/// Returns the password the local sidecar is protected with, generating and persisting one on
+/// first use. Falls back to a per-launch password when no keychain is available.
+pub fn server_password() -> String {
+    match get_secret(SERVER_PASSWORD_ACCOUNT) {
+        Ok(Some(password)) => return password,
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!("{e}, using an ephemeral server password");
+            return uuid::Uuid::new_v4().to_string();
+        }
+    }
+
+    let password = uuid::Uuid::new_v4().to_string();
+    if let Err(e) = set_secret(SERVER_PASSWORD_ACCOUNT, &password) {
+        tracing::warn!("{e}, server password will not persist across launches");
+    }
+    password
+}
+
+/// Persists `password` as the one the next sidecar is started with. Only `supervisor::rekey`
+/// calls this, so the running sidecar and the keychain never disagree for long.
+pub fn set_server_password(password: &str) -> Result<(), String> {
+    set_secret(SERVER_PASSWORD_ACCOUNT, password)
+}
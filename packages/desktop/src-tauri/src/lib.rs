@@ -1,14 +1,87 @@
+mod alerts;
+mod app_update;
+mod autostart;
+mod backup;
+mod bind_guard;
 mod cli;
+mod cli_channel;
+mod cli_config;
+mod cli_run;
+mod cli_version;
+mod clipboard;
+mod config_watch;
 mod constants;
+mod crash;
+mod crash_reports;
+mod credentials;
+mod deeplink;
+mod diagnostics;
+mod discovery;
+mod doctor;
+mod dotenv;
+mod errors;
+mod events;
+mod external;
+mod file_dialogs;
+mod file_drop;
+mod git;
+mod health;
+mod hotkey;
+mod i18n;
+mod instances;
+mod keychain;
+mod lifecycle;
 #[cfg(target_os = "linux")]
 pub mod linux_display;
 #[cfg(target_os = "linux")]
 pub mod linux_windowing;
 mod logging;
 mod markdown;
+mod models;
+mod onboarding;
+mod open_with;
+mod operations;
+mod orphans;
+mod pairing;
+mod port;
+mod project_files;
+mod projects;
+mod provider_auth;
+mod proxy;
+mod pty;
+mod quick_prompt;
+mod remote;
+mod resources;
+mod response_cache;
+mod search;
+mod second_instance;
 mod server;
+mod server_proxy;
+mod server_socket;
+mod service;
+mod session_archive;
+mod session_export;
+mod settings;
+mod shell_env;
+mod sidecar_logs;
+mod startup;
+mod storage;
+mod supervisor;
+mod taskbar;
+mod telemetry;
+mod tls;
+mod tray;
+mod trust;
+mod tunnel;
+mod update_guard;
+mod watchdog;
+mod watcher;
 mod window_customizer;
+mod window_layout;
 mod windows;
+mod wsl;
+mod wsl_bootstrap;
+mod wsl_path;
 
 use crate::cli::CommandChild;
 use futures::{
@@ -17,9 +90,7 @@ use futures::{
 };
 use std::{
     env,
-    net::TcpListener,
     path::PathBuf,
-    process::Command,
     sync::{Arc, Mutex},
     time::Duration,
 };
@@ -32,17 +103,21 @@ use tokio::{
     time::{sleep, timeout},
 };
 
-use crate::cli::{sqlite_migration::SqliteMigrationProgress, sync_cli};
+use crate::cli::sqlite_migration::SqliteMigrationProgress;
 use crate::constants::*;
+use crate::lifecycle::ServerLifecycle;
 use crate::server::get_saved_server_url;
-use crate::windows::{LoadingWindow, MainWindow};
+use crate::supervisor::{SidecarRestart, SidecarSpec};
+use crate::windows::{LoadingWindow, MainWindow, ProjectWindow, QuickPromptWindow};
 
 #[derive(Clone, serde::Serialize, specta::Type, Debug)]
 struct ServerReadyData {
     url: String,
     username: Option<String>,
+    /// Kept for the requests Rust makes on the webview's behalf, and never sent to it.
+    #[serde(skip)]
     password: Option<String>,
-    is_sidecar: bool
+    is_sidecar: bool,
 }
 
 #[derive(Clone, Copy, serde::Serialize, specta::Type, Debug)]
@@ -53,13 +128,6 @@ enum InitStep {
     Done,
 }
 
-#[derive(serde::Deserialize, specta::Type)]
-#[serde(rename_all = "snake_case")]
-enum WslPathMode {
-    Windows,
-    Linux,
-}
-
 struct InitState {
     current: watch::Receiver<InitStep>,
 }
@@ -67,6 +135,7 @@ struct InitState {
 #[derive(Clone)]
 struct ServerState {
     child: Arc<Mutex<Option<CommandChild>>>,
+    supervisor: Arc<Mutex<Option<supervisor::Supervised>>>,
     status: future::Shared<oneshot::Receiver<Result<ServerReadyData, String>>>,
 }
 
@@ -77,6 +146,7 @@ impl ServerState {
     ) -> Self {
         Self {
             child: Arc::new(Mutex::new(child)),
+            supervisor: Arc::new(Mutex::new(None)),
             status,
         }
     }
@@ -104,11 +174,33 @@ fn kill_sidecar(app: AppHandle) {
         return;
     };
 
+    let _ = lifecycle::transition(&app, ServerLifecycle::Stopping);
     let _ = server_state.kill();
+    let _ = lifecycle::transition(&app, ServerLifecycle::Stopped);
 
     tracing::info!("Killed server");
 }
 
+/// Stops the sidecar gracefully, force-killing it if it does not exit within
+/// `cli::SHUTDOWN_GRACE`.
+#[tauri::command]
+#[specta::specta]
+async fn stop_server(app: AppHandle) {
+    let Some(child) = app
+        .try_state::<ServerState>()
+        .and_then(|state| state.child.lock().unwrap().take())
+    else {
+        tracing::info!("Server not running");
+        return;
+    };
+
+    let _ = lifecycle::transition(&app, ServerLifecycle::Stopping);
+    child.shutdown(cli::SHUTDOWN_GRACE).await;
+    let _ = lifecycle::transition(&app, ServerLifecycle::Stopped);
+
+    tracing::info!("Stopped server");
+}
+
 fn get_logs() -> String {
     logging::tail()
 }
@@ -165,7 +257,7 @@ fn check_app_exists(app_name: &str) -> bool {
 #[cfg(target_os = "windows")]
 fn check_windows_app(_app_name: &str) -> bool {
     // Check if command exists in PATH, including .exe
-    return true;
+    true
 }
 
 #[cfg(target_os = "windows")]
@@ -173,7 +265,10 @@ fn resolve_windows_app_path(app_name: &str) -> Option<String> {
     use std::path::{Path, PathBuf};
 
     // Try to find the command using 'where'
-    let output = Command::new("where").arg(app_name).output().ok()?;
+    let output = std::process::Command::new("where")
+        .arg(app_name)
+        .output()
+        .ok()?;
 
     if !output.status.success() {
         return None;
@@ -237,25 +332,25 @@ fn resolve_windows_app_path(app_name: &str) -> Option<String> {
     };
 
     for path in &paths {
-        if has_ext(path, "cmd") || has_ext(path, "bat") {
-            if let Some(resolved) = resolve_cmd(path) {
-                return Some(resolved);
-            }
+        if (has_ext(path, "cmd") || has_ext(path, "bat"))
+            && let Some(resolved) = resolve_cmd(path)
+        {
+            return Some(resolved);
         }
 
         if path.extension().is_none() {
             let cmd = path.with_extension("cmd");
-            if cmd.exists() {
-                if let Some(resolved) = resolve_cmd(&cmd) {
-                    return Some(resolved);
-                }
+            if cmd.exists()
+                && let Some(resolved) = resolve_cmd(&cmd)
+            {
+                return Some(resolved);
             }
 
             let bat = path.with_extension("bat");
-            if bat.exists() {
-                if let Some(resolved) = resolve_cmd(&bat) {
-                    return Some(resolved);
-                }
+            if bat.exists()
+                && let Some(resolved) = resolve_cmd(&bat)
+            {
+                return Some(resolved);
             }
         }
     }
@@ -341,7 +436,7 @@ fn check_macos_app(app_name: &str) -> bool {
     }
 
     // Also check if command exists in PATH
-    Command::new("which")
+    std::process::Command::new("which")
         .arg(app_name)
         .output()
         .map(|output| output.status.success())
@@ -361,11 +456,11 @@ fn get_display_backend() -> Option<LinuxDisplayBackend> {
     #[cfg(target_os = "linux")]
     {
         let prefer = linux_display::read_wayland().unwrap_or(false);
-        return Some(if prefer {
+        Some(if prefer {
             LinuxDisplayBackend::Wayland
         } else {
             LinuxDisplayBackend::Auto
-        });
+        })
     }
 
     #[cfg(not(target_os = "linux"))]
@@ -378,7 +473,7 @@ fn set_display_backend(_app: AppHandle, _backend: LinuxDisplayBackend) -> Result
     #[cfg(target_os = "linux")]
     {
         let prefer = matches!(_backend, LinuxDisplayBackend::Wayland);
-        return linux_display::write_wayland(&_app, prefer);
+        linux_display::write_wayland(&_app, prefer)
     }
 
     #[cfg(not(target_os = "linux"))]
@@ -386,50 +481,13 @@ fn set_display_backend(_app: AppHandle, _backend: LinuxDisplayBackend) -> Result
 }
 
 #[cfg(target_os = "linux")]
-fn check_linux_app(app_name: &str) -> bool {
-    return true;
-}
-
-#[tauri::command]
-#[specta::specta]
-fn wsl_path(path: String, mode: Option<WslPathMode>) -> Result<String, String> {
-    if !cfg!(windows) {
-        return Ok(path);
-    }
-
-    let flag = match mode.unwrap_or(WslPathMode::Linux) {
-        WslPathMode::Windows => "-w",
-        WslPathMode::Linux => "-u",
-    };
-
-    let output = if path.starts_with('~') {
-        let suffix = path.strip_prefix('~').unwrap_or("");
-        let escaped = suffix.replace('"', "\\\"");
-        let cmd = format!("wslpath {flag} \"$HOME{escaped}\"");
-        Command::new("wsl")
-            .args(["-e", "sh", "-lc", &cmd])
-            .output()
-            .map_err(|e| format!("Failed to run wslpath: {e}"))?
-    } else {
-        Command::new("wsl")
-            .args(["-e", "wslpath", flag, &path])
-            .output()
-            .map_err(|e| format!("Failed to run wslpath: {e}"))?
-    };
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        if stderr.is_empty() {
-            return Err("wslpath failed".to_string());
-        }
-        return Err(stderr);
-    }
-
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+fn check_linux_app(_app_name: &str) -> bool {
+    true
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    startup::launched();
     let builder = make_specta_builder();
 
     #[cfg(debug_assertions)] // <- Only export on non-release builds
@@ -441,19 +499,20 @@ pub fn run() {
         .output();
 
     let mut builder = tauri::Builder::default()
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            // Focus existing window when another instance is launched
-            if let Some(window) = app.get_webview_window(MainWindow::LABEL) {
-                let _ = window.set_focus();
-                let _ = window.unminimize();
-            }
-        }))
+        .plugin(tauri_plugin_single_instance::init(second_instance::handle))
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_os::init())
         .plugin(
             tauri_plugin_window_state::Builder::new()
                 .with_state_flags(window_state_flags())
-                .with_denylist(&[LoadingWindow::LABEL])
+                // The main window is restored per monitor layout by `window_layout`.
+                .with_denylist(&[
+                    MainWindow::LABEL,
+                    LoadingWindow::LABEL,
+                    QuickPromptWindow::LABEL,
+                ])
+                // Project windows save their geometry per project instead of per label.
+                .with_filter(|label| !label.starts_with(ProjectWindow::LABEL_PREFIX))
                 .build(),
         )
         .plugin(tauri_plugin_store::Builder::new().build())
@@ -466,6 +525,11 @@ pub fn run() {
         .plugin(tauri_plugin_notification::init())
         .plugin(crate::window_customizer::PinchZoomDisablePlugin)
         .plugin(tauri_plugin_decorum::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkey::handle)
+                .build(),
+        )
         .invoke_handler(builder.invoke_handler())
         .setup(move |app| {
             let handle = app.handle().clone();
@@ -476,9 +540,21 @@ pub fn run() {
                 .expect("failed to resolve app log dir");
             // Hold the guard in managed state so it lives for the app's lifetime,
             // ensuring all buffered logs are flushed on shutdown.
-            handle.manage(logging::init(&log_dir));
+            handle.manage(logging::init(
+                &log_dir,
+                logging::retention_days(&handle),
+                logging::get_log_level(handle.clone()).ok().flatten(),
+            ));
+            if let Err(e) = startup::measure("settings", || settings::migrate(&handle)) {
+                tracing::error!("{e}");
+            }
 
             builder.mount_events(&handle);
+            // The tray listens to server events, which needs them mounted first.
+            if let Err(e) = tray::create(&handle) {
+                tracing::warn!("Failed to create tray icon: {e}");
+            }
+
             tauri::async_runtime::spawn(initialize(handle));
 
             Ok(())
@@ -491,12 +567,29 @@ pub fn run() {
     builder
         .build(tauri::generate_context!())
         .expect("error while running tauri application")
-        .run(|app, event| {
-            if let RunEvent::Exit = event {
+        .run(|app, event| match event {
+            // Closing the last window asks to exit without a code, unlike quitting from the tray.
+            RunEvent::ExitRequested {
+                code: None, api, ..
+            } if autostart::get_background_mode(app.clone()) => {
+                tracing::info!("Last window closed, keeping the server running in the background");
+                api.prevent_exit();
+            }
+            // Finder "Open With" and dropping onto the dock icon.
+            #[cfg(target_os = "macos")]
+            RunEvent::Opened { urls } => {
+                let files = urls.iter().filter_map(|url| url.to_file_path().ok());
+                open_with::open(app, open_with::project_dirs(files));
+            }
+            RunEvent::Exit => {
                 tracing::info!("Received Exit");
+                pty::close_all(app);
 
-                kill_sidecar(app.clone());
+                tauri::async_runtime::block_on(async {
+                    futures::join!(stop_server(app.clone()), instances::stop_all(app));
+                });
             }
+            _ => {}
         });
 }
 
@@ -505,22 +598,258 @@ fn make_specta_builder() -> tauri_specta::Builder<tauri::Wry> {
         // Then register them (separated by a comma)
         .commands(tauri_specta::collect_commands![
             kill_sidecar,
+            stop_server,
+            supervisor::restart_server,
             cli::install_cli,
+            cli::uninstall_cli,
+            cli_channel::get_cli_channel,
+            cli_channel::switch_cli_channel,
+            cli_config::get_full_config,
+            cli_config::get_config_error,
+            cli_config::set_config_value,
+            cli_config::patch_config,
+            cli_config::open_config_in_editor,
+            cli_config::get_config_path,
+            cli_config::set_config_path,
+            cli_config::resolve_config_path,
+            cli_config::reveal_config_path,
+            cli_version::list_cli_versions,
+            cli_version::install_cli_version,
+            cli_version::activate_cli_version,
+            cli_version::prune_cli_versions,
             await_initialization,
             server::get_default_server_url,
             server::set_default_server_url,
             server::get_wsl_config,
+            server::get_wsl_offline_install,
+            server::set_wsl_offline_install,
+            server::get_wsl_shared_state,
+            server::set_wsl_shared_state,
             server::set_wsl_config,
+            server::get_wsl_distro,
+            server::set_wsl_distro,
+            wsl::list_wsl_distros,
+            wsl::check_wsl_status,
+            wsl::get_wsl_networking,
+            external::get_external_server_config,
+            external::set_external_server_config,
+            external::set_external_server_password,
+            external::detect_external_server,
+            remote::list_remote_profiles,
+            remote::save_remote_profile,
+            remote::delete_remote_profile,
+            remote::get_active_remote_profile,
+            remote::connect_remote,
+            remote::disconnect_remote,
+            discovery::get_lan_discovery,
+            discovery::set_lan_discovery,
+            bind_guard::get_allow_lan,
+            watchdog::get_stall_snapshot,
+            watchdog::get_restart_on_stall,
+            watchdog::set_restart_on_stall,
+            bind_guard::set_allow_lan,
+            discovery::discover_servers,
+            tls::get_local_server_tls,
+            tls::set_local_server_tls,
+            tls::get_tls_fingerprint,
+            server_socket::get_server_socket,
+            server_socket::set_server_socket,
+            pairing::get_pairing_info,
+            instances::start_instance,
+            trust::list_trusted_paths,
+            trust::trust_path,
+            trust::revoke_trust,
+            instances::list_instances,
+            instances::stop_instance,
+            projects::list_recent_projects,
+            projects::add_recent_project,
+            projects::remove_recent_project,
+            projects::pin_recent_project,
+            projects::open_project,
+            deeplink::take_pending_deep_links,
+            settings::get_settings,
+            settings::update_settings,
+            settings::export_settings,
+            settings::import_settings,
+            settings::get_extra_env,
+            settings::set_extra_env,
+            dotenv::get_dotenv_config,
+            dotenv::set_dotenv_config,
+            dotenv::preview_dotenv,
+            autostart::get_autostart,
+            autostart::set_autostart,
+            autostart::get_background_mode,
+            autostart::set_background_mode,
+            shell_env::get_shell_config,
+            shell_env::set_shell_config,
+            shell_env::get_windows_shell_env,
+            shell_env::set_windows_shell_env,
+            proxy::get_proxy_config,
+            proxy::set_proxy_config,
+            proxy::test_proxy,
+            health::get_server_health,
+            doctor::run_doctor,
+            onboarding::get_onboarding_step,
+            onboarding::run_onboarding_step,
+            onboarding::complete_onboarding_step,
+            onboarding::set_default_project_folder,
+            onboarding::reset_onboarding,
+            resources::get_server_stats,
+            resources::get_memory_warning_threshold,
+            resources::set_memory_warning_threshold,
+            credentials::issue_server_token,
+            credentials::list_server_tokens,
+            credentials::revoke_server_token,
+            credentials::rotate_server_credentials,
+            credentials::get_password_rotation_days,
+            credentials::set_password_rotation_days,
+            port::get_sidecar_port,
+            port::get_sidecar_port_range,
+            port::set_sidecar_port_range,
+            port::inspect_port,
+            port::free_port,
+            service::get_server_service_definition,
+            service::install_server_service,
+            service::uninstall_server_service,
+            service::install_background_server,
+            service::remove_background_server,
+            service::start_server_service,
+            service::stop_server_service,
+            service::get_server_service_status,
+            app_update::get_update_channel,
+            app_update::set_update_channel,
+            app_update::check_for_update,
+            app_update::download_update,
+            app_update::install_update,
+            update_guard::get_pending_update,
+            backup::create_backup,
+            backup::list_backups,
+            backup::restore_backup,
+            backup::get_backup_status,
+            backup::set_backup_schedule,
+            storage::get_storage_usage,
+            storage::prune_storage,
+            pty::spawn_terminal,
+            pty::write_terminal,
+            pty::resize_terminal,
+            pty::close_terminal,
+            pty::list_terminals,
+            cli_run::run_cli,
+            provider_auth::list_provider_auth,
+            provider_auth::set_provider_api_key,
+            provider_auth::logout_provider,
+            provider_auth::start_provider_oauth,
+            provider_auth::finish_provider_oauth,
+            models::list_models,
+            hotkey::get_global_hotkey,
+            hotkey::set_global_hotkey,
+            hotkey::disable_global_hotkey,
+            quick_prompt::submit_quick_prompt,
+            quick_prompt::toggle_quick_prompt,
+            windows::open_project_window,
+            windows::list_windows,
+            windows::focus_window,
+            file_dialogs::pick_project_folder,
+            file_dialogs::pick_attachments,
+            file_dialogs::pick_export_path,
+            file_dialogs::is_path_granted,
+            file_dialogs::list_granted_paths,
+            file_drop::get_file_drop_config,
+            file_drop::set_file_drop_config,
+            file_drop::ingest_paths,
+            clipboard::paste_clipboard_image,
+            taskbar::get_taskbar_activity,
+            taskbar::set_taskbar_badge,
+            taskbar::set_taskbar_progress,
+            events::get_event_stream_status,
+            server_proxy::server_request,
+            server_proxy::open_server_stream,
+            watcher::watch_directory,
+            watcher::unwatch_directory,
+            watcher::list_watched_directories,
+            git::get_git_status,
+            git::get_git_diff_stats,
+            git::list_recent_commits,
+            git::get_file_diffs,
+            git::stage_hunks,
+            git::unstage_hunks,
+            git::revert_file,
+            search::start_search,
+            search::cancel_search,
+            operations::cancel_operation,
+            lifecycle::get_server_lifecycle,
+            operations::list_operations,
+            project_files::read_project_file,
+            project_files::write_project_file,
+            session_export::export_session,
+            session_archive::export_sessions,
+            session_archive::import_sessions,
+            crash_reports::get_crash_reporting,
+            crash_reports::set_crash_reporting,
+            crash_reports::list_crash_reports,
+            crash_reports::delete_crash_report,
+            crash_reports::submit_crash_report,
+            i18n::get_locale,
+            i18n::set_locale,
+            telemetry::get_usage_metrics,
+            telemetry::set_usage_metrics,
+            telemetry::get_pending_usage_report,
+            telemetry::purge_usage_metrics,
+            telemetry::record_feature_usage,
+            sidecar_logs::get_sidecar_logs,
+            logging::get_log_file_path,
+            logging::get_log_retention_days,
+            logging::set_log_retention_days,
+            logging::get_log_level,
+            logging::set_log_level,
+            diagnostics::export_diagnostics,
+            startup::get_startup_timings,
             get_display_backend,
             set_display_backend,
             markdown::parse_markdown_command,
             check_app_exists,
-            wsl_path,
+            wsl_path::wsl_path,
+            wsl_path::wsl_paths,
             resolve_app_path
         ])
         .events(tauri_specta::collect_events![
             LoadingWindowComplete,
-            SqliteMigrationProgress
+            SqliteMigrationProgress,
+            SidecarRestart,
+            supervisor::ServerRestartProgress,
+            tunnel::SshTunnelStatus,
+            health::ServerHealthChanged,
+            port::SidecarPortSelected,
+            sidecar_logs::SidecarLog,
+            crash::ServerCrash,
+            second_instance::SecondInstance,
+            deeplink::DeepLink,
+            open_with::ProjectOpened,
+            cli::CliInstallProgress,
+            wsl_bootstrap::WslBootstrapFailed,
+            resources::ServerMemoryWarning,
+            config_watch::ConfigChanged,
+            cli_config::ConfigInvalid,
+            alerts::SidecarAlert,
+            onboarding::OnboardingChanged,
+            credentials::ServerCredentialsChanged,
+            bind_guard::ServerBindRejected,
+            watchdog::ServerStalled,
+            app_update::AppUpdateProgress,
+            update_guard::UpdateDeferred,
+            update_guard::PendingUpdateReady,
+            pty::TerminalOutput,
+            pty::TerminalExited,
+            quick_prompt::QuickPromptSubmitted,
+            file_drop::FilesDropped,
+            events::ServerEvent,
+            events::EventStreamChanged,
+            watcher::FsChanged,
+            git::GitStatusChanged,
+            search::SearchResults,
+            search::SearchFinished,
+            operations::OperationProgress,
+            lifecycle::ServerLifecycleChanged
         ])
         .error_handling(tauri_specta::ErrorHandlingMode::Throw)
 }
@@ -550,11 +879,16 @@ async fn initialize(app: AppHandle) {
     let (init_tx, init_rx) = watch::channel(InitStep::ServerWaiting);
 
     setup_app(&app, init_rx);
+    open_with::open_launch_args(&app);
+    if shell_env::enabled(&app) {
+        shell_env::warm(shell_env::resolve(&app));
+    }
     spawn_cli_sync_task(app.clone());
 
     let (server_ready_tx, server_ready_rx) = oneshot::channel();
     let server_ready_rx = server_ready_rx.shared();
     app.manage(ServerState::new(None, server_ready_rx.clone()));
+    app.manage(lifecycle::Lifecycle::default());
 
     let loading_window_complete = event_once_fut::<LoadingWindowComplete>(&app);
 
@@ -598,31 +932,32 @@ async fn initialize(app: AppHandle) {
 
         async move {
             tracing::info!("Setting up server connection");
-            let server_connection = setup_server_connection(app.clone()).await;
+            let server_connection =
+                startup::measure_async("server_connection", setup_server_connection(app.clone()))
+                    .await;
             tracing::info!("Server connection setup");
 
             // we delay spawning this future so that the timeout is created lazily
             let cli_health_check = match server_connection {
-                ServerConnection::CLI {
+                ServerConnection::Cli {
                     child,
                     health_check,
+                    exit,
+                    spec,
                     url,
                     username,
-                    password,
                 } => {
                     let app = app.clone();
                     Some(
                         async move {
-                            let res = timeout(Duration::from_secs(30), health_check.0).await;
-                            let err = match res {
-                                Ok(Ok(Ok(()))) => None,
-                                Ok(Ok(Err(e))) => Some(e),
-                                Ok(Err(e)) => Some(format!("Health check task failed: {e}")),
-                                Err(_) => Some("Health check timed out".to_string()),
-                            };
-
-                            if let Some(err) = err {
+                            let healthy = startup::measure_async(
+                                "health_check",
+                                supervisor::wait_healthy(health_check),
+                            )
+                            .await;
+                            if let Err(err) = healthy {
                                 let _ = child.kill();
+                                let _ = lifecycle::transition(&app, ServerLifecycle::Crashed);
 
                                 return Err(format!(
                                     "Failed to spawn OpenCode Server ({err}). Logs:\n{}",
@@ -633,23 +968,39 @@ async fn initialize(app: AppHandle) {
                             tracing::info!("CLI health check OK");
 
                             app.state::<ServerState>().set_child(Some(child));
-
-                            Ok(ServerReadyData { url, username,password, is_sidecar: true })
+                            let _ = lifecycle::transition(&app, ServerLifecycle::Running);
+
+                            let password = Some(spec.password.clone());
+                            health::start(&app, url.clone(), password.clone());
+                            discovery::advertise(&app, spec.port);
+                            supervisor::spawn(app.clone(), *spec, exit);
+
+                            Ok(ServerReadyData {
+                                url,
+                                username,
+                                password,
+                                is_sidecar: true,
+                            })
                         }
                         .map(move |res| {
                             let _ = server_ready_tx.send(res);
                         }),
                     )
                 }
-                ServerConnection::Existing { url } => {
+                ServerConnection::Existing { url, password } => {
+                    health::start(&app, url.clone(), password.clone());
                     let _ = server_ready_tx.send(Ok(ServerReadyData {
                         url: url.to_string(),
-                        username: None,
-                        password: None,
+                        username: password.as_ref().map(|_| "opencode".to_string()),
+                        password,
                         is_sidecar: false,
                     }));
                     None
                 }
+                ServerConnection::Failed { message } => {
+                    let _ = server_ready_tx.send(Err(message));
+                    None
+                }
             };
 
             tracing::info!("server connection started");
@@ -669,7 +1020,11 @@ async fn initialize(app: AppHandle) {
     .map_err(|_| ())
     .shared();
 
-    let loading_window = if needs_sqlite_migration
+    let headless = autostart::launched_headless(&app);
+    let loading_window = if headless {
+        tracing::info!("Started in the background, not opening a window");
+        None
+    } else if needs_sqlite_migration
         && timeout(Duration::from_secs(1), loading_task.clone())
             .await
             .is_err()
@@ -680,7 +1035,9 @@ async fn initialize(app: AppHandle) {
         Some(loading_window)
     } else {
         tracing::debug!("Showing main window without loading window");
-        MainWindow::create(&app).expect("Failed to create main window");
+        startup::measure("main_window", || {
+            MainWindow::create(&app).expect("Failed to create main window")
+        });
 
         None
     };
@@ -690,13 +1047,19 @@ async fn initialize(app: AppHandle) {
     tracing::info!("Loading done, completing initialisation");
     let _ = init_tx.send(InitStep::Done);
 
+    if headless {
+        return;
+    }
+
     if loading_window.is_some() {
         loading_window_complete.await;
 
         tracing::info!("Loading window completed");
     }
 
-    MainWindow::create(&app).expect("Failed to create main window");
+    startup::measure("main_window", || {
+        MainWindow::create(&app).expect("Failed to create main window")
+    });
 
     if let Some(loading_window) = loading_window {
         let _ = loading_window.close();
@@ -708,11 +1071,46 @@ fn setup_app(app: &tauri::AppHandle, init_rx: watch::Receiver<InitStep>) {
     app.deep_link().register_all().ok();
 
     app.manage(InitState { current: init_rx });
+    app.manage(health::HealthMonitor::default());
+    app.manage(port::SelectedPort::default());
+    app.manage(sidecar_logs::SidecarLogs::default());
+    app.manage(tunnel::TunnelManager::default());
+    app.manage(discovery::Discovery::default());
+    app.manage(instances::Instances::default());
+    app.manage(resources::ResourceMonitor::default());
+    app.manage(alerts::Alerts::default());
+    app.manage(watchdog::Watchdog::default());
+    app.manage(app_update::AppUpdater::default());
+    app.manage(update_guard::UpdateGuard::default());
+    app.manage(backup::BackupScheduler::default());
+    app.manage(pty::Terminals::default());
+    app.manage(hotkey::Hotkey::default());
+    app.manage(windows::ProjectWindows::default());
+    app.manage(file_dialogs::DialogGrants::default());
+    app.manage(taskbar::Taskbar::default());
+    app.manage(events::EventStream::default());
+    app.manage(response_cache::ResponseCache::default());
+    app.manage(watcher::FsWatchers::default());
+    app.manage(git::GitStatuses::default());
+    app.manage(operations::Operations::default());
+    app.manage(telemetry::Telemetry::default());
+
+    resources::spawn(app);
+    config_watch::spawn(app);
+    credentials::spawn(app);
+    backup::spawn(app);
+    taskbar::spawn(app);
+    events::spawn(app);
+    hotkey::init(app);
+    deeplink::init(app);
+    crash_reports::init(app);
+    telemetry::spawn(app);
+    file_drop::clear_staging(app);
 }
 
 fn spawn_cli_sync_task(app: AppHandle) {
     tokio::spawn(async move {
-        if let Err(e) = sync_cli(app) {
+        if let Err(e) = cli_channel::sync(app).await {
             tracing::error!("Failed to sync CLI: {e}");
         }
     });
@@ -721,17 +1119,25 @@ fn spawn_cli_sync_task(app: AppHandle) {
 enum ServerConnection {
     Existing {
         url: String,
+        password: Option<String>,
     },
-    CLI {
+    Cli {
         url: String,
         username: Option<String>,
-        password: Option<String>,
         child: CommandChild,
         health_check: server::HealthCheck,
+        exit: cli::SidecarExit,
+        spec: Box<SidecarSpec>,
+    },
+    Failed {
+        message: String,
     },
 }
 
 async fn setup_server_connection(app: AppHandle) -> ServerConnection {
+    // First, so the CLI config can be read when the server runs in WSL. A failure only matters
+    // once a sidecar has to be spawned.
+    let bootstrap = wsl_bootstrap::ensure(&app).await;
     let custom_url = get_saved_server_url(&app).await;
 
     tracing::info!(?custom_url, "Attempting server connection");
@@ -742,48 +1148,145 @@ async fn setup_server_connection(app: AppHandle) -> ServerConnection {
         tracing::info!(%url, "Connected to custom server");
         // If the default server is already local, no need to also spawn a sidecar
         if server::is_localhost_url(url) {
-            return ServerConnection::Existing { url: url.clone() };
+            return ServerConnection::Existing {
+                url: url.clone(),
+                password: None,
+            };
         }
         // Remote default server: fall through and also spawn a local sidecar
     }
 
-    let local_port = get_sidecar_port();
-    let hostname = "127.0.0.1";
-    let local_url = format!("http://{hostname}:{local_port}");
+    if let Some((url, password)) = remote::active(&app).await {
+        return ServerConnection::Existing { url, password };
+    }
+
+    if let Some(external) = external::find(&app).await {
+        return ServerConnection::Existing {
+            url: external.url,
+            password: external.password,
+        };
+    }
+
+    if let Some(port) = port::preferred_port() {
+        let url = format!("http://127.0.0.1:{port}");
+
+        tracing::debug!(%url, "Checking health of local server");
+        if server::check_health(&url, None).await {
+            tracing::info!(%url, "Health check OK, using existing server");
+            return ServerConnection::Existing {
+                url,
+                password: None,
+            };
+        }
+    }
+
+    // Other machines can only reach the server if it listens on all interfaces.
+    let hostname = if discovery::is_enabled(&app) {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    };
+
+    // A sidecar this session would refuse to start is not adopted either.
+    let adopted = if bind_guard::is_allowed(&app, hostname, &keychain::server_password()) {
+        orphans::adopt(&app, hostname).await
+    } else {
+        None
+    };
+    let stopped = orphans::cleanup(&app, adopted.as_ref().map(|(pid, _)| *pid)).await;
+    if stopped > 0 {
+        tracing::info!(stopped, "Stopped leftover sidecars");
+    }
 
-    tracing::debug!(url = %local_url, "Checking health of local server");
-    if server::check_health(&local_url, None).await {
-        tracing::info!(url = %local_url, "Health check OK, using existing server");
-        return ServerConnection::Existing { url: local_url };
+    if let Err(message) = bootstrap {
+        return ServerConnection::Failed { message };
+    }
+    if let Err(message) = lifecycle::transition(&app, ServerLifecycle::Starting) {
+        return ServerConnection::Failed { message };
+    }
+    if let Some((pid, mut spec)) = adopted {
+        if let Err(message) = bridge_tls(&app, &mut spec).await {
+            let _ = lifecycle::transition(&app, ServerLifecycle::Crashed);
+            return ServerConnection::Failed { message };
+        }
+        tracing::info!(
+            pid,
+            port = spec.port,
+            "Adopting sidecar from a previous session"
+        );
+        port::set_selected(&app, spec.port);
+        let (child, exit) = cli::adopt(&app, pid);
+
+        return ServerConnection::Cli {
+            url: spec.local_url(),
+            username: Some("opencode".to_string()),
+            child,
+            health_check: server::HealthCheck(tokio::spawn(async { Ok(()) })),
+            exit,
+            spec: Box::new(spec),
+        };
     }
 
-    let password = uuid::Uuid::new_v4().to_string();
+    let socket = server_socket::address(&app);
+    let local_port = match &socket {
+        Some(socket) => server_socket::bridge(socket.clone())
+            .await
+            .inspect(|port| port::set_selected(&app, *port)),
+        None => port::select_port(&app, hostname),
+    };
+    let local_port = match local_port {
+        Ok(port) => port,
+        Err(message) => {
+            let _ = lifecycle::transition(&app, ServerLifecycle::Crashed);
+            return ServerConnection::Failed { message };
+        }
+    };
+    let password = keychain::server_password();
+    let mut spec = SidecarSpec {
+        hostname: hostname.to_string(),
+        port: local_port,
+        password,
+        tls: tls::for_hostname(&app, hostname),
+        directory: None,
+        wsl_address: None,
+        socket,
+        bridge_port: None,
+    };
+    if let Err(message) = bridge_tls(&app, &mut spec).await {
+        let _ = lifecycle::transition(&app, ServerLifecycle::Crashed);
+        return ServerConnection::Failed { message };
+    }
 
     tracing::info!("Spawning new local server");
-    let (child, health_check) =
-        server::spawn_local_server(app, hostname.to_string(), local_port, password.clone());
+    let spawned = startup::measure_async(
+        "sidecar_spawn",
+        server::spawn_local_server(app.clone(), &mut spec),
+    )
+    .await;
+    let (child, health_check, exit) = match spawned {
+        Ok(spawned) => spawned,
+        Err(message) => {
+            let _ = lifecycle::transition(&app, ServerLifecycle::Crashed);
+            return ServerConnection::Failed { message };
+        }
+    };
 
-    ServerConnection::CLI {
-        url: local_url,
+    ServerConnection::Cli {
+        url: spec.local_url(),
         username: Some("opencode".to_string()),
-        password: Some(password),
         child,
         health_check,
+        exit,
+        spec: Box::new(spec),
     }
 }
 
-fn get_sidecar_port() -> u32 {
-    option_env!("OPENCODE_PORT")
-        .map(|s| s.to_string())
-        .or_else(|| std::env::var("OPENCODE_PORT").ok())
-        .and_then(|port_str| port_str.parse().ok())
-        .unwrap_or_else(|| {
-            TcpListener::bind("127.0.0.1:0")
-                .expect("Failed to bind to find free port")
-                .local_addr()
-                .expect("Failed to get local address")
-                .port()
-        }) as u32
+/// Puts an https sidecar behind a plain loopback bridge the webview can use.
+async fn bridge_tls(app: &AppHandle, spec: &mut SidecarSpec) -> Result<(), String> {
+    if spec.tls.is_some() {
+        spec.bridge_port = Some(tls::bridge(app.clone()).await?);
+    }
+    Ok(())
 }
 
 fn sqlite_file_exists() -> bool {
@@ -795,17 +1298,9 @@ fn sqlite_file_exists() -> bool {
 }
 
 fn opencode_db_path() -> Result<PathBuf, &'static str> {
-    let xdg_data_home = env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty());
-
-    let data_home = match xdg_data_home {
-        Some(v) => PathBuf::from(v),
-        None => {
-            let home = dirs::home_dir().ok_or("cannot determine home directory")?;
-            home.join(".local").join("share")
-        }
-    };
-
-    Ok(data_home.join("opencode").join("opencode.db"))
+    cli::data_dir()
+        .map(|dir| dir.join("opencode.db"))
+        .ok_or("cannot determine home directory")
 }
 
 // Creates a `once` listener for the specified event and returns a future that resolves
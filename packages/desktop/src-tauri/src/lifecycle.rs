@@ -0,0 +1,131 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+/// Where the sidecar the app manages is in its life. Servers the app connects to instead are not
+/// tracked.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerLifecycle {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+    /// It exited on its own or never became healthy.
+    Crashed,
+}
+
+impl ServerLifecycle {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Stopped => "stopped",
+            Self::Starting => "starting",
+            Self::Running => "running",
+            Self::Stopping => "stopping",
+            Self::Crashed => "crashed",
+        }
+    }
+
+    /// Whether the sidecar may go from `self` to `to`. Only a stopped or crashed sidecar can be
+    /// started, which keeps two starts from spawning two sidecars.
+    fn can_become(self, to: Self) -> bool {
+        use ServerLifecycle::*;
+        matches!(
+            (self, to),
+            (Stopped | Crashed, Starting)
+                | (Starting, Running | Crashed | Stopping)
+                | (Running, Stopping | Crashed)
+                | (Stopping, Stopped | Starting)
+                | (Crashed, Stopping | Stopped)
+        )
+    }
+}
+
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ServerLifecycleChanged {
+    pub state: ServerLifecycle,
+}
+
+pub struct Lifecycle(Mutex<ServerLifecycle>);
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self(Mutex::new(ServerLifecycle::Stopped))
+    }
+}
+
+fn changed(app: &AppHandle, from: ServerLifecycle, to: ServerLifecycle) {
+    if from != to {
+        tracing::debug!(
+            from = from.as_str(),
+            to = to.as_str(),
+            "Server lifecycle changed"
+        );
+        let _ = ServerLifecycleChanged { state: to }.emit(app);
+    }
+}
+
+/// Moves the sidecar to `to`, refusing moves that don't follow from where it is. Checking and
+/// moving under one lock means only one of several concurrent starts gets through. Returns where
+/// it was.
+pub fn transition(app: &AppHandle, to: ServerLifecycle) -> Result<ServerLifecycle, String> {
+    let lifecycle = app.state::<Lifecycle>();
+    let mut current = lifecycle.0.lock().unwrap();
+    let from = *current;
+    if !from.can_become(to) {
+        return Err(format!(
+            "The server can't be {} while it is {}",
+            to.as_str(),
+            from.as_str()
+        ));
+    }
+    *current = to;
+    drop(current);
+    changed(app, from, to);
+    Ok(from)
+}
+
+/// Puts the sidecar back to `state` after a transition turned out to have nothing to act on.
+pub fn revert(app: &AppHandle, state: ServerLifecycle) {
+    let from = std::mem::replace(&mut *app.state::<Lifecycle>().0.lock().unwrap(), state);
+    changed(app, from, state);
+}
+
+pub fn current(app: &AppHandle) -> ServerLifecycle {
+    app.try_state::<Lifecycle>()
+        .map(|lifecycle| *lifecycle.0.lock().unwrap())
+        .unwrap_or(ServerLifecycle::Stopped)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_server_lifecycle(lifecycle: State<'_, Lifecycle>) -> ServerLifecycle {
+    *lifecycle.0.lock().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ServerLifecycle::*;
+
+    #[test]
+    fn only_stopped_or_crashed_servers_start() {
+        assert!(Stopped.can_become(Starting));
+        assert!(Crashed.can_become(Starting));
+        assert!(!Starting.can_become(Starting));
+        assert!(!Running.can_become(Starting));
+    }
+
+    #[test]
+    fn restarts_go_through_stopping() {
+        for (from, to) in [
+            (Running, Stopping),
+            (Stopping, Starting),
+            (Starting, Running),
+        ] {
+            assert!(from.can_become(to), "{from:?} -> {to:?}");
+        }
+        assert!(!Stopping.can_become(Running));
+        assert!(!Stopped.can_become(Running));
+    }
+}
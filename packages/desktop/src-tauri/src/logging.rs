@@ -1,37 +1,81 @@
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
 use tracing_appender::non_blocking::WorkerGuard;
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{
+    EnvFilter, Registry, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
 
-const MAX_LOG_AGE_DAYS: u64 = 7;
-const TAIL_LINES: usize = 1000;
-
-static LOG_PATH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+use crate::{settings, supervisor};
 
-pub fn init(log_dir: &Path) -> WorkerGuard {
-    std::fs::create_dir_all(log_dir).expect("failed to create log directory");
+const DEFAULT_LOG_RETENTION_DAYS: u32 = 7;
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+const TAIL_LINES: usize = 1000;
 
-    cleanup(log_dir);
+static LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static RETENTION_DAYS: AtomicU32 = AtomicU32::new(DEFAULT_LOG_RETENTION_DAYS);
 
-    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let filename = format!("opencode-desktop_{timestamp}.log");
-    let log_path = log_dir.join(&filename);
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
 
-    LOG_PATH
-        .set(log_path.clone())
-        .expect("logging already initialized");
+impl LogLevel {
+    fn as_directive(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
 
-    let file = File::create(&log_path).expect("failed to create log file");
-    let (non_blocking, guard) = tracing_appender::non_blocking(file);
+    /// The spelling `opencode --log-level` expects.
+    pub fn as_cli_arg(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+}
 
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        if cfg!(debug_assertions) {
+fn filter_for(level: Option<LogLevel>) -> EnvFilter {
+    match level {
+        Some(level) => {
+            let level = level.as_directive();
+            EnvFilter::new(format!(
+                "opencode_lib={level},opencode_desktop={level},sidecar={level}"
+            ))
+        }
+        None if cfg!(debug_assertions) => {
             EnvFilter::new("opencode_lib=debug,opencode_desktop=debug,sidecar=debug")
-        } else {
-            EnvFilter::new("opencode_lib=info,opencode_desktop=info,sidecar=info")
         }
-    });
+        None => EnvFilter::new("opencode_lib=info,opencode_desktop=info,sidecar=info"),
+    }
+}
+
+pub fn init(log_dir: &Path, retention_days: u32, level: Option<LogLevel>) -> WorkerGuard {
+    std::fs::create_dir_all(log_dir).expect("failed to create log directory");
+
+    RETENTION_DAYS.store(retention_days, Ordering::Relaxed);
+    cleanup(log_dir, retention_days);
+
+    let writer = RotatingWriter::new(log_dir.to_path_buf()).expect("failed to create log file");
+    let (non_blocking, guard) = tracing_appender::non_blocking(writer);
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| filter_for(level));
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = FILTER.set(handle);
 
     tracing_subscriber::registry()
         .with(filter)
@@ -42,8 +86,12 @@ pub fn init(log_dir: &Path) -> WorkerGuard {
     guard
 }
 
+pub fn current_path() -> Option<PathBuf> {
+    LOG_PATH.lock().unwrap().clone()
+}
+
 pub fn tail() -> String {
-    let Some(path) = LOG_PATH.get() else {
+    let Some(path) = current_path() else {
         return String::new();
     };
 
@@ -57,15 +105,78 @@ pub fn tail() -> String {
     lines[start..].join("\n")
 }
 
-fn cleanup(log_dir: &Path) {
+/// Writes to a log file in `dir`, starting a new one whenever the current file would grow past
+/// `MAX_LOG_FILE_SIZE`.
+struct RotatingWriter {
+    dir: PathBuf,
+    file: File,
+    written: u64,
+    rotations: u32,
+}
+
+impl RotatingWriter {
+    fn new(dir: PathBuf) -> std::io::Result<Self> {
+        let file = open_log_file(&dir, 0)?;
+        Ok(Self {
+            dir,
+            file,
+            written: 0,
+            rotations: 0,
+        })
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        self.rotations += 1;
+        self.file = open_log_file(&self.dir, self.rotations)?;
+        self.written = 0;
+        cleanup(&self.dir, RETENTION_DAYS.load(Ordering::Relaxed));
+        Ok(())
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_LOG_FILE_SIZE {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_log_file(dir: &Path, rotation: u32) -> std::io::Result<File> {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+    let filename = match rotation {
+        0 => format!("opencode-desktop_{timestamp}.log"),
+        n => format!("opencode-desktop_{timestamp}_{n}.log"),
+    };
+    let path = dir.join(filename);
+
+    let file = File::create(&path)?;
+    *LOG_PATH.lock().unwrap() = Some(path);
+    Ok(file)
+}
+
+fn cleanup(log_dir: &Path, retention_days: u32) {
     let cutoff = std::time::SystemTime::now()
-        - std::time::Duration::from_secs(MAX_LOG_AGE_DAYS * 24 * 60 * 60);
+        - std::time::Duration::from_secs(retention_days as u64 * 24 * 60 * 60);
 
     let Ok(entries) = std::fs::read_dir(log_dir) else {
         return;
     };
 
+    let current = current_path();
     for entry in entries.flatten() {
+        if current.as_deref() == Some(entry.path().as_path()) {
+            continue;
+        }
         if let Ok(meta) = entry.metadata()
             && let Ok(modified) = meta.modified()
             && modified < cutoff
@@ -74,3 +185,78 @@ fn cleanup(log_dir: &Path) {
         }
     }
 }
+
+pub fn retention_days(app: &AppHandle) -> u32 {
+    get_log_retention_days(app.clone()).unwrap_or(DEFAULT_LOG_RETENTION_DAYS)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_log_retention_days(app: AppHandle) -> Result<u32, String> {
+    Ok(settings::load(&app)?
+        .log_retention_days
+        .unwrap_or(DEFAULT_LOG_RETENTION_DAYS))
+}
+
+/// Sets how many days old log files are kept, removing older ones right away.
+#[tauri::command]
+#[specta::specta]
+pub fn set_log_retention_days(app: AppHandle, days: u32) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.log_retention_days = Some(days);
+        Ok(())
+    })?;
+
+    RETENTION_DAYS.store(days, Ordering::Relaxed);
+    if let Some(dir) = current_path().as_deref().and_then(Path::parent) {
+        cleanup(dir, days);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_log_file_path() -> Option<String> {
+    current_path().map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_log_level(app: AppHandle) -> Result<Option<LogLevel>, String> {
+    Ok(settings::load(&app)?.log_level)
+}
+
+/// Level passed to the sidecar's `--log-level`, defaulting to warnings only.
+pub fn sidecar_log_level(app: &AppHandle) -> &'static str {
+    get_log_level(app.clone())
+        .ok()
+        .flatten()
+        .map_or("WARN", LogLevel::as_cli_arg)
+}
+
+/// Applies `level` to the desktop's own logging immediately and restarts the sidecar so it
+/// picks up the new level too.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_log_level(app: AppHandle, level: LogLevel) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.log_level = Some(level);
+        Ok(())
+    })?;
+
+    if let Some(handle) = FILTER.get() {
+        handle
+            .reload(filter_for(Some(level)))
+            .map_err(|e| format!("Failed to update log filter: {}", e))?;
+    }
+
+    tracing::info!(?level, "Log level changed");
+
+    // The new process reads the level from the store.
+    if supervisor::current_spec(&app).is_some() {
+        supervisor::restart_server(app).await?;
+    }
+
+    Ok(())
+}
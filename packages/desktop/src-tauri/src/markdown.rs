@@ -42,17 +42,32 @@ create_formatter!(ExternalLinkFormatter, {
 });
 
 pub fn parse_markdown(input: &str) -> String {
+    let mut options = options();
+    options.render.r#unsafe = true;
+    render(input, &options)
+}
+
+/// Renders markdown from elsewhere, such as model output, with any raw HTML escaped.
+pub fn parse_untrusted_markdown(input: &str) -> String {
+    let mut options = options();
+    options.render.escape = true;
+    render(input, &options)
+}
+
+fn options() -> Options<'static> {
     let mut options = Options::default();
     options.extension.strikethrough = true;
     options.extension.table = true;
     options.extension.tasklist = true;
     options.extension.autolink = true;
-    options.render.r#unsafe = true;
+    options
+}
 
+fn render(input: &str, options: &Options) -> String {
     let arena = Arena::new();
-    let doc = parse_document(&arena, input, &options);
+    let doc = parse_document(&arena, input, options);
     let mut html = String::new();
-    ExternalLinkFormatter::format_document(doc, &options, &mut html).unwrap_or_default();
+    ExternalLinkFormatter::format_document(doc, options, &mut html).unwrap_or_default();
     html
 }
 
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, time::Duration};
+
+use reqwest::Method;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{cli, constants::MODELS_STORE, supervisor};
+
+const CACHE_KEY: &str = "models";
+const CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Where a model list came from. The CLI only knows model ids, so lists from it lack details.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSource {
+    Server,
+    Cli,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct ModelInfo {
+    pub id: String,
+    pub name: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub reasoning: Option<bool>,
+    pub attachment: Option<bool>,
+    pub context_limit: Option<u32>,
+    pub output_limit: Option<u32>,
+    /// `alpha`, `beta`, `deprecated` or `active`.
+    pub status: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct ModelList {
+    /// Sorted by provider, then by name.
+    pub models: Vec<ModelInfo>,
+    /// The default model id of each provider.
+    pub defaults: HashMap<String, String>,
+    pub source: ModelSource,
+    pub fetched_at: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ServerProviders {
+    providers: Vec<ServerProvider>,
+    #[serde(default)]
+    default: HashMap<String, String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ServerProvider {
+    id: String,
+    name: String,
+    #[serde(default)]
+    models: HashMap<String, ServerModel>,
+}
+
+#[derive(serde::Deserialize)]
+struct ServerModel {
+    id: String,
+    name: String,
+    capabilities: Option<Capabilities>,
+    limit: Option<Limit>,
+    status: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct Capabilities {
+    reasoning: bool,
+    attachment: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct Limit {
+    context: f64,
+    output: f64,
+}
+
+fn sorted(mut models: Vec<ModelInfo>) -> Vec<ModelInfo> {
+    models.sort_by(|a, b| {
+        a.provider_name
+            .to_lowercase()
+            .cmp(&b.provider_name.to_lowercase())
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    models
+}
+
+fn from_server(providers: ServerProviders) -> Vec<ModelInfo> {
+    let models = providers
+        .providers
+        .into_iter()
+        .flat_map(|provider| {
+            provider.models.into_values().map(move |model| ModelInfo {
+                id: model.id,
+                name: model.name,
+                provider_id: provider.id.clone(),
+                provider_name: provider.name.clone(),
+                reasoning: model.capabilities.as_ref().map(|c| c.reasoning),
+                attachment: model.capabilities.as_ref().map(|c| c.attachment),
+                context_limit: model.limit.as_ref().map(|l| l.context as u32),
+                output_limit: model.limit.as_ref().map(|l| l.output as u32),
+                status: model.status,
+            })
+        })
+        .collect();
+    sorted(models)
+}
+
+/// Parses `opencode models`, which prints one `provider/model` per line.
+fn from_cli(output: &str) -> Vec<ModelInfo> {
+    let models = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.contains(char::is_whitespace))
+        .filter_map(|line| line.split_once('/'))
+        .filter(|(provider, model)| !provider.is_empty() && !model.is_empty())
+        .map(|(provider, model)| ModelInfo {
+            id: model.to_string(),
+            name: model.to_string(),
+            provider_id: provider.to_string(),
+            provider_name: provider.to_string(),
+            reasoning: None,
+            attachment: None,
+            context_limit: None,
+            output_limit: None,
+            status: None,
+        })
+        .collect();
+    sorted(models)
+}
+
+fn fresh(list: &ModelList, now: chrono::DateTime<chrono::Utc>) -> bool {
+    chrono::DateTime::parse_from_rfc3339(&list.fetched_at)
+        .ok()
+        .and_then(|fetched| (now - fetched.to_utc()).to_std().ok())
+        .is_some_and(|age| age < CACHE_TTL)
+}
+
+fn cached(app: &AppHandle) -> Option<ModelList> {
+    let store = app.store(MODELS_STORE).ok()?;
+    serde_json::from_value(store.get(CACHE_KEY)?).ok()
+}
+
+fn cache(app: &AppHandle, list: &ModelList) -> Result<(), String> {
+    let store = app
+        .store(MODELS_STORE)
+        .map_err(|e| format!("Failed to open models store: {}", e))?;
+    store.set(CACHE_KEY, serde_json::json!(list));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save models: {}", e))
+}
+
+/// Forgets the cached model list, so the next `list_models` fetches it again.
+pub fn invalidate(app: &AppHandle) {
+    let Ok(store) = app.store(MODELS_STORE) else {
+        return;
+    };
+    if store.delete(CACHE_KEY) {
+        let _ = store.save();
+        tracing::debug!("Invalidated cached models");
+    }
+}
+
+async fn fetch(app: &AppHandle) -> Result<ModelList, String> {
+    let (models, defaults, source) = if supervisor::current_spec(app).is_some() {
+        let res = supervisor::request(app, Method::GET, "/config/providers", None, REQUEST_TIMEOUT)
+            .await?;
+        let providers: ServerProviders =
+            serde_json::from_value(res).map_err(|e| format!("Failed to parse providers: {}", e))?;
+        let defaults = providers.default.clone();
+        (from_server(providers), defaults, ModelSource::Server)
+    } else {
+        let output = cli::get_models(app)
+            .await
+            .ok_or_else(|| "Failed to run opencode models".to_string())?;
+        (from_cli(&output), HashMap::new(), ModelSource::Cli)
+    };
+
+    Ok(ModelList {
+        models,
+        defaults,
+        source,
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// The models of every configured provider. Served from a cache for a while unless `refresh` is
+/// set; the cache is dropped whenever the config or provider credentials change.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_models(app: AppHandle, refresh: bool) -> Result<ModelList, String> {
+    if !refresh
+        && let Some(list) = cached(&app)
+        && fresh(&list, chrono::Utc::now())
+        // A list from the CLI lacks details the server can fill in once it runs.
+        && (list.source == ModelSource::Server || supervisor::current_spec(&app).is_none())
+    {
+        return Ok(list);
+    }
+
+    let list = fetch(&app).await?;
+    tracing::info!(
+        count = list.models.len(),
+        source = ?list.source,
+        "Fetched models"
+    );
+    if let Err(e) = cache(&app, &list) {
+        tracing::warn!("{e}");
+    }
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_server_providers() {
+        let providers = serde_json::from_value(serde_json::json!({
+            "providers": [{
+                "id": "openai",
+                "name": "OpenAI",
+                "models": {
+                    "gpt-5": {
+                        "id": "gpt-5",
+                        "name": "GPT-5",
+                        "capabilities": { "reasoning": true, "attachment": true, "toolcall": true },
+                        "limit": { "context": 400000, "output": 128000 },
+                        "status": "active",
+                    },
+                    "gpt-4o": { "id": "gpt-4o", "name": "GPT-4o" },
+                },
+            }],
+            "default": { "openai": "gpt-5" },
+        }))
+        .unwrap();
+
+        let models = from_server(providers);
+        assert_eq!(
+            models.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(),
+            ["gpt-4o", "gpt-5"]
+        );
+        assert_eq!(models[0].reasoning, None);
+        assert_eq!(models[1].reasoning, Some(true));
+        assert_eq!(models[1].context_limit, Some(400000));
+        assert_eq!(models[1].provider_name, "OpenAI");
+    }
+
+    #[test]
+    fn parses_cli_output() {
+        let models =
+            from_cli("openai/gpt-5\n  anthropic/claude-sonnet-4-5\nWarning: slow network\n/\n");
+        assert_eq!(
+            models
+                .iter()
+                .map(|m| format!("{}/{}", m.provider_id, m.id))
+                .collect::<Vec<_>>(),
+            ["anthropic/claude-sonnet-4-5", "openai/gpt-5"]
+        );
+    }
+
+    #[test]
+    fn cache_expires() {
+        let now = chrono::Utc::now();
+        let list = |fetched: chrono::DateTime<chrono::Utc>| ModelList {
+            models: Vec::new(),
+            defaults: HashMap::new(),
+            source: ModelSource::Server,
+            fetched_at: fetched.to_rfc3339(),
+        };
+
+        assert!(fresh(&list(now - chrono::Duration::minutes(1)), now));
+        assert!(!fresh(&list(now - chrono::Duration::hours(1)), now));
+        assert!(!fresh(&list(now + chrono::Duration::minutes(1)), now));
+    }
+}
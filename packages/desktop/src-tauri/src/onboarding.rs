@@ -0,0 +1,212 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{
+    cli,
+    doctor::{self, CheckStatus},
+    settings, wsl,
+};
+
+/// The first-run steps, in the order the wizard goes through them.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    VerifySidecar,
+    InstallCli,
+    DetectWsl,
+    ProjectFolder,
+    Done,
+}
+
+const STEPS: [OnboardingStep; 4] = [
+    OnboardingStep::VerifySidecar,
+    OnboardingStep::InstallCli,
+    OnboardingStep::DetectWsl,
+    OnboardingStep::ProjectFolder,
+];
+
+/// Steps the user has finished or skipped, kept in the settings store.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    pub completed: Vec<OnboardingStep>,
+    pub default_project_folder: Option<String>,
+}
+
+/// Sent whenever the wizard moves to another step.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct OnboardingChanged {
+    pub step: OnboardingStep,
+}
+
+/// What the backend found when running a step.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct StepOutcome {
+    pub step: OnboardingStep,
+    pub status: CheckStatus,
+    pub message: String,
+    /// The step needed nothing from the user and was completed.
+    pub completed: bool,
+}
+
+/// Steps that don't apply on this platform.
+fn applies(step: OnboardingStep) -> bool {
+    step != OnboardingStep::DetectWsl || cfg!(windows)
+}
+
+/// The first step that is neither completed nor inapplicable.
+pub fn current_step(completed: &[OnboardingStep]) -> OnboardingStep {
+    STEPS
+        .into_iter()
+        .find(|step| applies(*step) && !completed.contains(step))
+        .unwrap_or(OnboardingStep::Done)
+}
+
+fn state(app: &AppHandle) -> Result<OnboardingState, String> {
+    Ok(settings::load(app)?.onboarding)
+}
+
+fn complete(app: &AppHandle, step: OnboardingStep) -> Result<OnboardingStep, String> {
+    let mut next = OnboardingStep::Done;
+    settings::update(app, |s| {
+        if !s.onboarding.completed.contains(&step) {
+            s.onboarding.completed.push(step);
+        }
+        next = current_step(&s.onboarding.completed);
+        Ok(())
+    })?;
+
+    tracing::info!(?step, ?next, "Completed onboarding step");
+    let _ = OnboardingChanged { step: next }.emit(app);
+    Ok(next)
+}
+
+/// The step the wizard is on, `Done` once first run is over.
+#[tauri::command]
+#[specta::specta]
+pub fn get_onboarding_step(app: AppHandle) -> Result<OnboardingStep, String> {
+    Ok(current_step(&state(&app)?.completed))
+}
+
+/// Runs the backend side of `step`. Steps that turn out to need nothing from the user are
+/// completed right away.
+#[tauri::command]
+#[specta::specta]
+pub async fn run_onboarding_step(
+    app: AppHandle,
+    step: OnboardingStep,
+) -> Result<StepOutcome, String> {
+    let (status, message, done) = match step {
+        OnboardingStep::VerifySidecar => {
+            let check = doctor::check_sidecar(&app);
+            let passed = check.status == CheckStatus::Pass;
+            (check.status, check.message, passed)
+        }
+        OnboardingStep::InstallCli if cli::is_cli_installed() => (
+            CheckStatus::Pass,
+            "The CLI is already installed".to_string(),
+            true,
+        ),
+        OnboardingStep::InstallCli => (
+            CheckStatus::Warn,
+            "The CLI is not installed".to_string(),
+            false,
+        ),
+        OnboardingStep::DetectWsl if !applies(step) => (
+            CheckStatus::Pass,
+            "WSL is only used on Windows".to_string(),
+            true,
+        ),
+        OnboardingStep::DetectWsl => match wsl::check_wsl_status(app.clone()).await? {
+            wsl::WslStatus::Ready { distro } => (
+                CheckStatus::Pass,
+                format!("WSL is ready with {distro}"),
+                false,
+            ),
+            status => (CheckStatus::Warn, format!("{status:?}"), false),
+        },
+        OnboardingStep::ProjectFolder => match state(&app)?.default_project_folder {
+            Some(folder) => (CheckStatus::Pass, folder, true),
+            None => (
+                CheckStatus::Warn,
+                "No default project folder".to_string(),
+                false,
+            ),
+        },
+        OnboardingStep::Done => (CheckStatus::Pass, "Onboarding is done".to_string(), false),
+    };
+
+    if done {
+        complete(&app, step)?;
+    }
+    Ok(StepOutcome {
+        step,
+        status,
+        message,
+        completed: done,
+    })
+}
+
+/// Marks `step` as finished or skipped by the user, returning the next one.
+#[tauri::command]
+#[specta::specta]
+pub fn complete_onboarding_step(
+    app: AppHandle,
+    step: OnboardingStep,
+) -> Result<OnboardingStep, String> {
+    complete(&app, step)
+}
+
+/// Sets where new projects are looked for, completing that step.
+#[tauri::command]
+#[specta::specta]
+pub fn set_default_project_folder(app: AppHandle, path: String) -> Result<OnboardingStep, String> {
+    if !Path::new(&path).is_dir() {
+        return Err(format!("{path} is not a directory"));
+    }
+    settings::update(&app, |s| {
+        s.onboarding.default_project_folder = Some(path.clone());
+        Ok(())
+    })?;
+    complete(&app, OnboardingStep::ProjectFolder)
+}
+
+/// Starts the wizard over, keeping the chosen project folder.
+#[tauri::command]
+#[specta::specta]
+pub fn reset_onboarding(app: AppHandle) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.onboarding.completed.clear();
+        Ok(())
+    })?;
+    let _ = OnboardingChanged {
+        step: current_step(&[]),
+    }
+    .emit(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_advance_in_order() {
+        assert_eq!(current_step(&[]), OnboardingStep::VerifySidecar);
+        assert_eq!(
+            current_step(&[OnboardingStep::InstallCli]),
+            OnboardingStep::VerifySidecar
+        );
+
+        let after_cli = current_step(&[OnboardingStep::VerifySidecar, OnboardingStep::InstallCli]);
+        if cfg!(windows) {
+            assert_eq!(after_cli, OnboardingStep::DetectWsl);
+        } else {
+            assert_eq!(after_cli, OnboardingStep::ProjectFolder);
+        }
+
+        assert_eq!(current_step(&STEPS), OnboardingStep::Done);
+    }
+}
@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+
+use crate::{
+    instances::{InstanceInfo, Instances},
+    projects,
+};
+
+// Deep links are delivered through the deep-link plugin instead.
+const DEEP_LINK_PREFIX: &str = "opencode://";
+
+/// Sent once a project passed to the app, on the command line or through "Open With", has a
+/// server running for it.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ProjectOpened {
+    pub directory: String,
+    pub instance: InstanceInfo,
+}
+
+/// The project directories named by launch arguments, resolved against `cwd`.
+pub fn project_paths(args: &[String], cwd: &Path) -> Vec<PathBuf> {
+    project_dirs(
+        args.iter()
+            .filter(|arg| !arg.starts_with('-') && !arg.starts_with(DEEP_LINK_PREFIX))
+            .map(|arg| cwd.join(arg)),
+    )
+}
+
+/// Existing directories among `paths`, with each file standing for the directory it is in.
+pub fn project_dirs(paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let mut paths = paths
+        .into_iter()
+        .filter_map(|path| dunce::canonicalize(path).ok())
+        .filter_map(|path| {
+            if path.is_dir() {
+                Some(path)
+            } else {
+                path.parent().map(Path::to_path_buf)
+            }
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Records each project as recently opened and starts or attaches to its server.
+pub fn open(app: &AppHandle, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    // Paths can arrive before initialization has set up the registry; this is a no-op after.
+    app.manage(Instances::default());
+
+    for path in paths {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let directory = path.display().to_string();
+            tracing::info!(%directory, "Opening project passed to the app");
+
+            let instances = app.state::<Instances>();
+            match projects::open_project(app.clone(), instances, directory.clone()).await {
+                Ok(instance) => {
+                    let _ = ProjectOpened {
+                        directory,
+                        instance,
+                    }
+                    .emit(&app);
+                }
+                Err(e) => tracing::warn!(%directory, "Failed to open project: {e}"),
+            }
+        });
+    }
+}
+
+/// Opens the projects the app was launched with.
+pub fn open_launch_args(app: &AppHandle) {
+    let Ok(cwd) = std::env::current_dir() else {
+        return;
+    };
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    open(app, project_paths(&args, &cwd));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_paths_skip_flags_and_links() {
+        let temp = tempfile::tempdir().unwrap();
+        let cwd = temp.path();
+        std::fs::create_dir_all(cwd.join("project")).unwrap();
+        std::fs::write(cwd.join("project/notes.txt"), "").unwrap();
+
+        let args = [
+            "project",
+            "project/notes.txt",
+            "--background",
+            "opencode://open",
+            "missing",
+        ]
+        .map(String::from);
+        let found = project_paths(&args, cwd);
+
+        assert_eq!(found.len(), 1);
+        assert!(found[0].ends_with("project"));
+    }
+}
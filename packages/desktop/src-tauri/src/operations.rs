@@ -0,0 +1,253 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use futures::future::{self, Either};
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+use tokio::sync::Notify;
+
+use crate::errors::{DesktopError, ErrorCode};
+
+/// Long-running work the frontend can follow and cancel.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    CliInstall,
+    Backup,
+    Search,
+    SessionExport,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OperationStatus {
+    Started,
+    /// `percent` is missing while the amount of work is unknown, and `step` names what is being
+    /// done.
+    Progress {
+        percent: Option<u32>,
+        step: Option<String>,
+    },
+    Finished,
+    Cancelled,
+    Failed {
+        message: String,
+    },
+}
+
+/// Sent whenever a running operation moves on, keyed by the id it was started with.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct OperationProgress {
+    pub operation_id: String,
+    pub kind: OperationKind,
+    pub status: OperationStatus,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct OperationInfo {
+    pub id: String,
+    pub kind: OperationKind,
+}
+
+#[derive(Default)]
+struct Cancel {
+    flag: Arc<AtomicBool>,
+    notify: Notify,
+}
+
+impl Cancel {
+    async fn cancelled(&self) {
+        let notified = self.notify.notified();
+        if !self.flag.load(Ordering::Relaxed) {
+            notified.await;
+        }
+    }
+}
+
+/// The operations running now, by id.
+#[derive(Default)]
+pub struct Operations(Mutex<HashMap<String, (OperationKind, Arc<Cancel>)>>);
+
+/// A running operation. It leaves the registry when dropped.
+pub struct Operation {
+    app: AppHandle,
+    id: String,
+    kind: OperationKind,
+    cancel: Arc<Cancel>,
+}
+
+/// Registers an operation under `id`, or a new id when the caller has none, and announces it.
+pub fn begin(
+    app: &AppHandle,
+    kind: OperationKind,
+    id: Option<String>,
+) -> Result<Operation, String> {
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    if id.is_empty() || id.len() > 64 {
+        return Err(format!("Invalid operation id {id:?}"));
+    }
+    let cancel = Arc::new(Cancel::default());
+    {
+        let operations = app.state::<Operations>();
+        let mut operations = operations.0.lock().unwrap();
+        if operations.contains_key(&id) {
+            return Err(format!("Operation {id} is already running"));
+        }
+        operations.insert(id.clone(), (kind, cancel.clone()));
+    }
+
+    let operation = Operation {
+        app: app.clone(),
+        id,
+        kind,
+        cancel,
+    };
+    operation.emit(OperationStatus::Started);
+    Ok(operation)
+}
+
+/// Reports progress for every running operation of `kind`, for work that doesn't know which
+/// operation it belongs to.
+pub fn report(app: &AppHandle, kind: OperationKind, percent: Option<u32>, step: &str) {
+    let Some(operations) = app.try_state::<Operations>() else {
+        return;
+    };
+    let ids = operations
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, (running, _))| *running == kind)
+        .map(|(id, _)| id.clone())
+        .collect::<Vec<_>>();
+    for operation_id in ids {
+        let _ = OperationProgress {
+            operation_id,
+            kind,
+            status: OperationStatus::Progress {
+                percent,
+                step: Some(step.to_string()),
+            },
+        }
+        .emit(app);
+    }
+}
+
+impl Operation {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Set once the operation is cancelled, for blocking work to check between steps.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancel.flag.clone()
+    }
+
+    fn emit(&self, status: OperationStatus) {
+        let _ = OperationProgress {
+            operation_id: self.id.clone(),
+            kind: self.kind,
+            status,
+        }
+        .emit(&self.app);
+    }
+
+    pub fn progress(&self, percent: Option<u32>, step: &str) {
+        self.emit(OperationStatus::Progress {
+            percent,
+            step: Some(step.to_string()),
+        });
+    }
+
+    /// Announces that the operation ended on its own, or was cancelled.
+    pub fn finish(&self) {
+        self.emit(if self.cancel.flag.load(Ordering::Relaxed) {
+            OperationStatus::Cancelled
+        } else {
+            OperationStatus::Finished
+        });
+    }
+
+    /// Runs `work` until it is done or the operation is cancelled, which drops it, and announces
+    /// the outcome.
+    pub async fn run<T, E>(&self, work: impl Future<Output = Result<T, E>>) -> Result<T, E>
+    where
+        E: From<DesktopError> + std::fmt::Display,
+    {
+        let work = std::pin::pin!(work);
+        let cancelled = std::pin::pin!(self.cancel.cancelled());
+        let res = match future::select(work, cancelled).await {
+            Either::Left((res, _)) => res,
+            Either::Right(_) => Err(DesktopError::new(ErrorCode::Cancelled, "Cancelled").into()),
+        };
+        match &res {
+            Err(e) if !self.cancel.flag.load(Ordering::Relaxed) => {
+                self.emit(OperationStatus::Failed {
+                    message: e.to_string(),
+                })
+            }
+            _ => self.finish(),
+        }
+        res
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if let Some(operations) = self.app.try_state::<Operations>() {
+            operations.0.lock().unwrap().remove(&self.id);
+        }
+    }
+}
+
+/// Cancels a running operation. Returns false when it had already finished.
+#[tauri::command]
+#[specta::specta]
+pub fn cancel_operation(operations: State<'_, Operations>, operation_id: String) -> bool {
+    let Some((_, cancel)) = operations.0.lock().unwrap().get(&operation_id).cloned() else {
+        return false;
+    };
+    tracing::info!(%operation_id, "Cancelling operation");
+    cancel.flag.store(true, Ordering::Relaxed);
+    cancel.notify.notify_waiters();
+    true
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_operations(operations: State<'_, Operations>) -> Vec<OperationInfo> {
+    operations
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, (kind, _))| OperationInfo {
+            id: id.clone(),
+            kind: *kind,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelling_wakes_waiters() {
+        let cancel = Arc::new(Cancel::default());
+        let waiter = cancel.clone();
+        let task = std::thread::spawn(move || futures::executor::block_on(waiter.cancelled()));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        cancel.flag.store(true, Ordering::Relaxed);
+        cancel.notify.notify_waiters();
+        task.join().unwrap();
+
+        // Already cancelled, so it returns without another notification.
+        futures::executor::block_on(cancel.cancelled());
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use sha2::{Digest, Sha256};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, Signal, System};
+use tauri::{AppHandle, Manager};
+
+use crate::{cli, keychain, logging, port, resources, server, supervisor::SidecarSpec, tls};
+
+const PID_DIR: &str = "sidecars";
+
+/// Written for each running sidecar so one left behind by a crashed app can be found again.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct PidFile {
+    pid: u32,
+    /// Seconds since the epoch, to tell the sidecar apart from a process that reused its pid.
+    start_time: u64,
+    /// App version that started it, since the sidecar ships with the app.
+    version: String,
+    hostname: String,
+    port: u32,
+    tls: bool,
+    directory: Option<PathBuf>,
+    /// `env_fingerprint` at spawn time, so a sidecar started with other settings isn't adopted.
+    #[serde(default)]
+    env: String,
+}
+
+fn pid_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(PID_DIR))
+        .map_err(|e| format!("Failed to resolve the app data directory: {}", e))
+}
+
+/// Where a sidecar writes its logs once the session that started it is gone, as `<pid>.log`.
+pub fn detached_log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = pid_dir(app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+fn log_file(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{pid}.log"))
+}
+
+/// Hash of everything the sidecar gets from settings: its environment and log level.
+fn env_fingerprint(app: &AppHandle, directory: Option<&Path>) -> String {
+    let mut envs = cli::sidecar_envs(app, directory);
+    envs.sort();
+    let mut hasher = Sha256::new();
+    for (key, value) in envs {
+        hasher.update(format!("{key}={value}\0"));
+    }
+    hasher.update(logging::sidecar_log_level(app));
+    format!("{:x}", hasher.finalize())
+}
+
+/// Follows the detached log of an adopted sidecar, from its start.
+pub struct LogTail {
+    path: PathBuf,
+    offset: u64,
+    partial: String,
+}
+
+impl LogTail {
+    pub fn new(app: &AppHandle, pid: u32) -> Option<Self> {
+        Some(Self {
+            path: log_file(&pid_dir(app).ok()?, pid),
+            offset: 0,
+            partial: String::new(),
+        })
+    }
+
+    /// Complete lines written since the last call.
+    pub fn read_lines(&mut self) -> Vec<String> {
+        let Ok(mut file) = std::fs::File::open(&self.path) else {
+            return Vec::new();
+        };
+        let mut chunk = Vec::new();
+        if file.seek(SeekFrom::Start(self.offset)).is_err() || file.read_to_end(&mut chunk).is_err()
+        {
+            return Vec::new();
+        }
+        self.offset += chunk.len() as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&chunk));
+
+        let Some(end) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let rest = self.partial.split_off(end + 1);
+        let lines = std::mem::replace(&mut self.partial, rest);
+        lines.lines().map(str::to_string).collect()
+    }
+}
+
+pub fn processes() -> System {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().without_tasks(),
+    );
+    system
+}
+
+fn write_pid_file(app: &AppHandle, pid: u32, spec: &SidecarSpec) -> Result<(), String> {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[Pid::from_u32(pid)]),
+        false,
+        ProcessRefreshKind::nothing(),
+    );
+    let process = system
+        .process(Pid::from_u32(pid))
+        .ok_or("Process already exited")?;
+
+    let file = PidFile {
+        pid,
+        start_time: process.start_time(),
+        version: app.package_info().version.to_string(),
+        hostname: spec.hostname.clone(),
+        port: spec.port,
+        tls: spec.tls.is_some(),
+        directory: spec.directory.clone(),
+        env: env_fingerprint(app, spec.directory.as_deref()),
+    };
+    let dir = pid_dir(app)?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let contents =
+        serde_json::to_string(&file).map_err(|e| format!("Failed to serialize pid file: {}", e))?;
+    std::fs::write(dir.join(format!("{pid}.json")), contents)
+        .map_err(|e| format!("Failed to write pid file: {}", e))
+}
+
+/// Records a spawned sidecar until `forget` is called for it.
+pub fn record(app: &AppHandle, pid: u32, spec: &SidecarSpec) {
+    if let Err(e) = write_pid_file(app, pid, spec) {
+        tracing::warn!(pid, "Failed to record sidecar: {e}");
+    }
+}
+
+/// Removes the record of a sidecar that exited, and its detached log.
+pub fn forget(app: &AppHandle, pid: u32) {
+    if let Ok(dir) = pid_dir(app) {
+        let _ = std::fs::remove_file(dir.join(format!("{pid}.json")));
+        let _ = std::fs::remove_file(log_file(&dir, pid));
+    }
+}
+
+/// Signals `pid` and everything it started, since the recorded process may be the shell that
+/// started the CLI. Windows has no graceful signal, so there it always kills.
+pub fn signal_tree(pid: u32, force: bool) -> Vec<u32> {
+    let system = processes();
+    let parents = system
+        .processes()
+        .iter()
+        .map(|(pid, process)| (pid.as_u32(), process.parent().map(Pid::as_u32)))
+        .collect::<HashMap<_, _>>();
+
+    let tree = resources::process_tree(&parents, pid);
+    for pid in &tree {
+        if let Some(process) = system.process(Pid::from_u32(*pid)) {
+            if force {
+                process.kill();
+            } else {
+                process
+                    .kill_with(Signal::Term)
+                    .unwrap_or_else(|| process.kill());
+            }
+        }
+    }
+    tree.into_iter().collect()
+}
+
+fn read_pid_files(app: &AppHandle) -> Vec<(PathBuf, Option<PidFile>)> {
+    let Ok(entries) =
+        pid_dir(app).and_then(|dir| std::fs::read_dir(dir).map_err(|e| e.to_string()))
+    else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let file = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| serde_json::from_str(&contents).ok());
+            (path, file)
+        })
+        .collect()
+}
+
+/// Sidecars recorded by a previous session that are still running. Records of ones that exited
+/// are removed.
+fn leftovers(app: &AppHandle) -> Vec<PidFile> {
+    let files = read_pid_files(app);
+    if files.is_empty() {
+        return Vec::new();
+    }
+
+    let system = processes();
+    // Sidecars this session already started, for projects opened at launch, are left alone.
+    let launched = sysinfo::get_current_pid()
+        .ok()
+        .and_then(|pid| system.process(pid))
+        .map(|process| process.start_time());
+
+    files
+        .into_iter()
+        .filter_map(|(path, file)| {
+            if let Some(file) = &file
+                && launched.is_some_and(|launched| file.start_time >= launched)
+            {
+                return None;
+            }
+
+            let alive = file.filter(|file| {
+                system
+                    .process(Pid::from_u32(file.pid))
+                    .is_some_and(|process| process.start_time() == file.start_time)
+            });
+            if alive.is_none() {
+                let _ = std::fs::remove_file(&path);
+                let _ = std::fs::remove_file(path.with_extension("log"));
+            }
+            alive
+        })
+        .collect()
+}
+
+/// Finds a healthy main sidecar from a previous session that this session would have started the
+/// same way, so it can be used instead of spawning a new one. One whose port, hostname or
+/// environment no longer match the settings is left for `cleanup`, and a fresh one is spawned.
+pub async fn adopt(app: &AppHandle, hostname: &str) -> Option<(u32, SidecarSpec)> {
+    let version = app.package_info().version.to_string();
+    let tls = tls::for_hostname(app, hostname);
+    let env = env_fingerprint(app, None);
+
+    for file in leftovers(app) {
+        if file.directory.is_some()
+            || file.version != version
+            || file.hostname != hostname
+            || file.tls != tls.is_some()
+        {
+            continue;
+        }
+        if file.env != env || !port::accepts(app, file.port) {
+            tracing::info!(
+                pid = file.pid,
+                "Leftover sidecar was started with other settings, not adopting it"
+            );
+            continue;
+        }
+
+        let spec = SidecarSpec {
+            hostname: file.hostname,
+            port: file.port,
+            password: keychain::server_password(),
+            tls: tls.clone(),
+            directory: None,
+            // One listening on a WSL distro's address fails the health check below and is cleaned
+            // up instead, since that address may have changed.
+            wsl_address: None,
+            socket: None,
+            bridge_port: None,
+        };
+        // A rotated password means the sidecar was started with a different one.
+        if server::check_health(&spec.url(), Some(&spec.password)).await {
+            return Some((file.pid, spec));
+        }
+        tracing::info!(
+            pid = file.pid,
+            "Leftover sidecar is not healthy, not adopting it"
+        );
+    }
+
+    None
+}
+
+/// Stops sidecars recorded by a previous session that are still running, except `keep`, so they
+/// release their ports. Returns how many were stopped.
+pub async fn cleanup(app: &AppHandle, keep: Option<u32>) -> u32 {
+    let mut sidecars = 0;
+    let mut stopped = Vec::new();
+    for file in leftovers(app) {
+        if Some(file.pid) == keep {
+            continue;
+        }
+
+        tracing::info!(
+            pid = file.pid,
+            port = file.port,
+            directory = ?file.directory,
+            "Stopping sidecar left over from a previous session"
+        );
+        forget(app, file.pid);
+        stopped.extend(signal_tree(file.pid, false));
+        sidecars += 1;
+    }
+
+    if stopped.is_empty() {
+        return 0;
+    }
+
+    let deadline = tokio::time::Instant::now() + cli::SHUTDOWN_GRACE;
+    loop {
+        let system = processes();
+        let running = stopped
+            .iter()
+            .filter_map(|pid| system.process(Pid::from_u32(*pid)))
+            .collect::<Vec<_>>();
+        if running.is_empty() {
+            break;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            tracing::warn!(
+                count = running.len(),
+                "Leftover sidecars did not exit in time, killing them"
+            );
+            for process in running {
+                process.kill();
+            }
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    sidecars
+}
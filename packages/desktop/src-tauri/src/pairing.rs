@@ -0,0 +1,138 @@
+use std::{net::IpAddr, time::Duration};
+
+use tauri::AppHandle;
+
+use crate::{
+    credentials::{self, TokenScope},
+    server, supervisor, tls,
+};
+
+const READ_ONLY_TOKEN_TTL: Duration = Duration::from_secs(60 * 60);
+const FULL_TOKEN_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// How long a pairing secret can be redeemed, e.g. while its QR code is on screen.
+const PAIRING_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// What another device needs to connect to the local server, e.g. rendered as a QR code.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct PairingInfo {
+    /// Addresses of this machine on the local network, the mDNS name first.
+    pub hosts: Vec<String>,
+    pub port: u32,
+    pub secure: bool,
+    /// Single-use secret the device sends as a bearer token to `POST /pair`, which answers with
+    /// the `scope` token it uses from then on.
+    pub secret: String,
+    pub scope: TokenScope,
+    /// When the secret can no longer be redeemed.
+    pub expires_at: String,
+    /// Certificate fingerprint to pin when `secure` is set.
+    pub fingerprint: Option<String>,
+    /// `opencode://pair` link carrying all of the above.
+    pub link: String,
+}
+
+fn lan_addresses() -> Vec<IpAddr> {
+    let mut addresses = if_addrs::get_if_addrs()
+        .inspect_err(|e| tracing::warn!("Failed to list network interfaces: {e}"))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|iface| !iface.is_loopback())
+        .map(|iface| iface.ip())
+        // Link-local IPv6 addresses need a zone id, which phones rarely accept.
+        .filter(|ip| matches!(ip, IpAddr::V4(_)))
+        .collect::<Vec<_>>();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+fn pairing_link(info: &PairingInfo) -> String {
+    let mut link = reqwest::Url::parse("opencode://pair").expect("valid pairing url");
+    {
+        let mut query = link.query_pairs_mut();
+        for host in &info.hosts {
+            query.append_pair("host", host);
+        }
+        query
+            .append_pair("port", &info.port.to_string())
+            .append_pair("secret", &info.secret);
+        if info.scope == TokenScope::Read {
+            query.append_pair("scope", "read");
+        }
+        if let Some(fingerprint) = &info.fingerprint {
+            query.append_pair("fingerprint", fingerprint);
+        }
+    }
+    link.to_string()
+}
+
+/// Connection details for the local server. Fails unless the server listens on all interfaces,
+/// which LAN discovery turns on. The device gets a token that expires after 30 days, or after an
+/// hour and can't change anything with `read_only`.
+#[tauri::command]
+#[specta::specta]
+pub fn get_pairing_info(app: AppHandle, read_only: bool) -> Result<PairingInfo, String> {
+    let Some(spec) = supervisor::current_spec(&app) else {
+        return Err("The server is not managed by the desktop app".to_string());
+    };
+    if server::is_loopback_hostname(&spec.hostname) {
+        return Err(
+            "The server only accepts local connections, allow LAN access, enable LAN discovery and restart it"
+                .to_string(),
+        );
+    }
+
+    let fingerprint = match spec.tls {
+        Some(_) => Some(tls::get_tls_fingerprint(app.clone())?),
+        None => None,
+    };
+
+    let hostname = tauri_plugin_os::hostname();
+    let mut hosts = vec![format!("{}.local", hostname.trim_end_matches(".local"))];
+    hosts.extend(lan_addresses().iter().map(IpAddr::to_string));
+
+    let (scope, ttl) = if read_only {
+        (TokenScope::Read, READ_ONLY_TOKEN_TTL)
+    } else {
+        (TokenScope::Full, FULL_TOKEN_TTL)
+    };
+    let issued = credentials::issue_pairing(&app, scope, ttl, PAIRING_TTL)?;
+
+    let mut info = PairingInfo {
+        hosts,
+        port: spec.port,
+        secure: spec.tls.is_some(),
+        secret: issued.token,
+        scope,
+        expires_at: issued.expires_at,
+        fingerprint,
+        link: String::new(),
+    };
+    info.link = pairing_link(&info);
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairing_link_encodes_every_host() {
+        let info = PairingInfo {
+            hosts: vec!["desk.local".to_string(), "192.168.1.5".to_string()],
+            port: 4096,
+            secure: false,
+            secret: "a b&c".to_string(),
+            scope: TokenScope::Read,
+            expires_at: String::new(),
+            fingerprint: None,
+            link: String::new(),
+        };
+
+        assert_eq!(
+            pairing_link(&info),
+            "opencode://pair?host=desk.local&host=192.168.1.5&port=4096&secret=a+b%26c&scope=read"
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use sysinfo::Pid;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+use tauri_specta::Event;
+
+use crate::{ServerState, cli, instances::Instances, orphans, resources, settings};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct PortRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct SidecarPortSelected {
+    pub port: u32,
+    /// The port that was asked for but found occupied, if any.
+    pub conflict: Option<u32>,
+}
+
+#[derive(Clone, Default)]
+pub struct SelectedPort(Arc<Mutex<Option<u32>>>);
+
+/// Port explicitly requested through `OPENCODE_PORT`, either at build time or at runtime.
+pub fn preferred_port() -> Option<u32> {
+    option_env!("OPENCODE_PORT")
+        .map(|s| s.to_string())
+        .or_else(|| std::env::var("OPENCODE_PORT").ok())
+        .and_then(|port_str| port_str.parse().ok())
+}
+
+pub fn is_port_free(hostname: &str, port: u32) -> bool {
+    let Ok(port) = u16::try_from(port) else {
+        return false;
+    };
+    TcpListener::bind((hostname, port)).is_ok()
+}
+
+/// Picks the port the sidecar should listen on: the preferred port if it is free, otherwise the
+/// first free port of the configured range, otherwise any port the OS hands out.
+pub fn select_port(app: &AppHandle, hostname: &str) -> Result<u32, String> {
+    let preferred = preferred_port();
+    let range = get_sidecar_port_range(app.clone()).ok().flatten();
+
+    let port = preferred
+        .filter(|port| is_port_free(hostname, *port))
+        .or_else(|| {
+            range
+                .as_ref()
+                .and_then(|range| first_free_port(hostname, range))
+        })
+        .map(Ok)
+        .unwrap_or_else(|| ephemeral_port(hostname))?;
+
+    let conflict = preferred.filter(|preferred| *preferred != port);
+    if let Some(conflict) = conflict {
+        tracing::warn!(
+            conflict,
+            port,
+            "Preferred sidecar port is in use, falling back"
+        );
+    }
+
+    set_selected(app, port);
+    let _ = SidecarPortSelected { port, conflict }.emit(app);
+
+    Ok(port)
+}
+
+/// Whether `select_port` could have picked `port` with the current settings.
+pub fn accepts(app: &AppHandle, port: u32) -> bool {
+    if let Some(preferred) = preferred_port() {
+        return port == preferred;
+    }
+    match get_sidecar_port_range(app.clone()).ok().flatten() {
+        Some(range) => (range.start..=range.end).contains(&port),
+        None => true,
+    }
+}
+
+fn first_free_port(hostname: &str, range: &PortRange) -> Option<u32> {
+    (range.start..=range.end).find(|port| is_port_free(hostname, *port))
+}
+
+pub fn ephemeral_port(hostname: &str) -> Result<u32, String> {
+    TcpListener::bind((hostname, 0))
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port() as u32)
+        .map_err(|e| format!("Failed to find a free port: {e}"))
+}
+
+/// Records the port of the sidecar in use, which was not necessarily picked by `select_port`.
+pub fn set_selected(app: &AppHandle, port: u32) {
+    if let Some(selected) = app.try_state::<SelectedPort>() {
+        *selected.0.lock().unwrap() = Some(port);
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_port(selected: State<'_, SelectedPort>) -> Option<u32> {
+    *selected.0.lock().unwrap()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_port_range(app: AppHandle) -> Result<Option<PortRange>, String> {
+    Ok(settings::load(&app)?.sidecar_port_range)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_sidecar_port_range(app: AppHandle, range: Option<PortRange>) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.sidecar_port_range = range;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// A process listening on a port.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: String,
+    pub path: String,
+    pub is_opencode: bool,
+    /// An opencode server this session did not start, e.g. one left behind by a crash.
+    pub stale: bool,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct PortInspection {
+    pub port: u32,
+    pub free: bool,
+    pub owners: Vec<PortOwner>,
+}
+
+pub fn is_opencode(name: &str, path: &str) -> bool {
+    let name = name.to_lowercase();
+    let path = path.to_lowercase();
+    name.contains("opencode") || path.contains("opencode")
+}
+
+/// Processes belonging to the sidecars this session runs, shells in between included.
+fn session_pids(app: &AppHandle) -> HashSet<u32> {
+    let mut roots = app
+        .try_state::<Instances>()
+        .map(|instances| instances.pids())
+        .unwrap_or_default();
+    if let Some(state) = app.try_state::<ServerState>() {
+        roots.extend(state.child.lock().unwrap().as_ref().and_then(|c| c.pid()));
+    }
+
+    let system = orphans::processes();
+    let parents = system
+        .processes()
+        .iter()
+        .map(|(pid, process)| (pid.as_u32(), process.parent().map(Pid::as_u32)))
+        .collect::<HashMap<_, _>>();
+    let mut pids = roots
+        .iter()
+        .flat_map(|root| resources::process_tree(&parents, *root))
+        .collect::<HashSet<_>>();
+    pids.insert(std::process::id());
+    pids
+}
+
+fn inspect(app: &AppHandle, port: u32) -> Result<PortInspection, String> {
+    let Ok(port16) = u16::try_from(port) else {
+        return Err(format!("Invalid port {port}"));
+    };
+    let listeners =
+        listeners::get_all().map_err(|e| format!("Failed to list listening processes: {}", e))?;
+    let session = session_pids(app);
+
+    let mut owners = listeners
+        .into_iter()
+        .filter(|l| l.protocol == listeners::Protocol::TCP && l.socket.port() == port16)
+        .map(|l| l.process)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .map(|process| {
+            let is_opencode = is_opencode(&process.name, &process.path);
+            PortOwner {
+                stale: is_opencode && !session.contains(&process.pid),
+                is_opencode,
+                pid: process.pid,
+                name: process.name,
+                path: process.path,
+            }
+        })
+        .collect::<Vec<_>>();
+    owners.sort_by_key(|owner| owner.pid);
+
+    Ok(PortInspection {
+        port,
+        free: owners.is_empty() && is_port_free("127.0.0.1", port),
+        owners,
+    })
+}
+
+/// Which processes hold `port`, so an occupied port can be explained.
+#[tauri::command]
+#[specta::specta]
+pub fn inspect_port(app: AppHandle, port: u32) -> Result<PortInspection, String> {
+    inspect(&app, port)
+}
+
+/// Stops the stale opencode servers holding `port` once the user agrees to it. Other programs
+/// are never touched. Returns whether the port was freed.
+#[tauri::command]
+#[specta::specta]
+pub async fn free_port(app: AppHandle, port: u32) -> Result<bool, String> {
+    let inspection = inspect(&app, port)?;
+    if inspection.free {
+        return Ok(true);
+    }
+    if let Some(owner) = inspection.owners.iter().find(|owner| !owner.stale) {
+        return Err(format!(
+            "Port {port} is used by {} (pid {}), which is not a stale opencode server",
+            owner.name, owner.pid
+        ));
+    }
+
+    let pids = inspection
+        .owners
+        .iter()
+        .map(|owner| owner.pid.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let message = format!(
+        "Port {port} is held by an opencode server that this app did not start (pid {pids}).\n\nStop it?"
+    );
+    let confirmed = tokio::task::spawn_blocking({
+        let app = app.clone();
+        move || {
+            app.dialog()
+                .message(message)
+                .title("Port in use")
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "Stop".to_string(),
+                    "Cancel".to_string(),
+                ))
+                .blocking_show()
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to ask for confirmation: {}", e))?;
+    if !confirmed {
+        return Ok(false);
+    }
+
+    let mut stopped = Vec::new();
+    for owner in &inspection.owners {
+        tracing::info!(pid = owner.pid, port, "Stopping stale opencode server");
+        orphans::forget(&app, owner.pid);
+        stopped.extend(orphans::signal_tree(owner.pid, false));
+    }
+
+    let deadline = tokio::time::Instant::now() + cli::SHUTDOWN_GRACE;
+    while !is_port_free("127.0.0.1", port) {
+        if tokio::time::Instant::now() >= deadline {
+            tracing::warn!(
+                port,
+                "Stale opencode server did not exit in time, killing it"
+            );
+            for owner in &inspection.owners {
+                orphans::signal_tree(owner.pid, true);
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    Ok(is_port_free("127.0.0.1", port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_occupied_ports_in_range() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let taken = listener.local_addr().unwrap().port() as u32;
+
+        assert!(!is_port_free("127.0.0.1", taken));
+        let range = PortRange {
+            start: taken,
+            end: taken.saturating_add(20).min(u16::MAX as u32),
+        };
+        let port = first_free_port("127.0.0.1", &range).expect("no free port in range");
+        assert_ne!(port, taken);
+    }
+
+    #[test]
+    fn recognizes_opencode_processes() {
+        assert!(is_opencode(
+            "opencode-cli",
+            "/Applications/OpenCode.app/Contents/MacOS/opencode-cli"
+        ));
+        assert!(is_opencode("bun", "/home/me/.opencode/bin/opencode"));
+        assert!(!is_opencode("node", "/usr/bin/node"));
+    }
+}
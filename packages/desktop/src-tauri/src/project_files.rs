@@ -0,0 +1,270 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use tauri::AppHandle;
+
+use crate::{settings, trust};
+
+/// Whole files above this size have to be read a range at a time.
+const MAX_READ_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_WRITE_BYTES: usize = 5 * 1024 * 1024;
+/// How much of a file is checked for binary content, as git does.
+pub const SNIFF_BYTES: usize = 8000;
+
+/// Lines of a file, counted from 1. Without an end the range runs to the end of the file.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: Option<u32>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct ProjectFile {
+    /// The canonical path the file was read from.
+    pub path: String,
+    /// Missing for binary files.
+    pub contents: Option<String>,
+    pub binary: bool,
+    pub size_kb: u32,
+    /// The range was cut short because it exceeded the size limit.
+    pub truncated: bool,
+}
+
+/// Whether the first bytes of a file are binary: a NUL byte, or invalid UTF-8 in the first
+/// `SNIFF_BYTES` of it.
+pub fn is_binary(head: &[u8]) -> bool {
+    let head = &head[..head.len().min(SNIFF_BYTES)];
+    if head.contains(&0) {
+        return true;
+    }
+    // A multi-byte character may be cut off at the end of the sample.
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        Err(e) => e.error_len().is_some(),
+    }
+}
+
+/// Resolves `path`, which may not exist yet, with its links and `..` segments followed.
+fn resolve(path: &str) -> Result<PathBuf, String> {
+    match dunce::canonicalize(path) {
+        Ok(canonical) => Ok(canonical),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let path = Path::new(path);
+            let name = path
+                .file_name()
+                .ok_or_else(|| format!("{} is not a file", path.display()))?;
+            let parent = path.parent().unwrap_or(Path::new("."));
+            dunce::canonicalize(parent)
+                .map(|parent| parent.join(name))
+                .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))
+        }
+        Err(e) => Err(format!("Failed to resolve {}: {}", path, e)),
+    }
+}
+
+fn read(path: &Path, range: Option<LineRange>) -> Result<ProjectFile, String> {
+    let failed = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+    let mut file = File::open(path).map_err(failed)?;
+    let size = file.metadata().map_err(failed)?.len();
+    let shown = |contents, binary, truncated| ProjectFile {
+        path: path.to_string_lossy().to_string(),
+        contents,
+        binary,
+        size_kb: size.div_ceil(1024) as u32,
+        truncated,
+    };
+
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    (&mut file)
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .map_err(failed)?;
+    if is_binary(&head) {
+        return Ok(shown(None, true, false));
+    }
+    let mut reader = BufReader::new(std::io::Cursor::new(head).chain(file));
+
+    let Some(range) = range else {
+        if size > MAX_READ_BYTES {
+            return Err(format!(
+                "{} is too large to open whole ({} MB)",
+                path.display(),
+                size.div_ceil(1024 * 1024)
+            ));
+        }
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents).map_err(failed)?;
+        let contents = String::from_utf8(contents)
+            .map_err(|_| format!("{} is not UTF-8 text", path.display()))?;
+        return Ok(shown(Some(contents), false, false));
+    };
+
+    let mut contents = Vec::new();
+    let mut line = Vec::new();
+    let mut truncated = false;
+    for number in 1.. {
+        if range.end.is_some_and(|end| number > end) {
+            break;
+        }
+        line.clear();
+        if reader.read_until(b'\n', &mut line).map_err(failed)? == 0 {
+            break;
+        }
+        if number < range.start {
+            continue;
+        }
+        if (contents.len() + line.len()) as u64 > MAX_READ_BYTES {
+            truncated = true;
+            break;
+        }
+        contents.extend_from_slice(&line);
+    }
+    let contents =
+        String::from_utf8(contents).map_err(|_| format!("{} is not UTF-8 text", path.display()))?;
+    Ok(shown(Some(contents), false, truncated))
+}
+
+/// Replaces `path` with `contents` through a temporary file next to it, so a crash or full disk
+/// never leaves a half-written file behind. The file keeps its permissions.
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    let failed = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    let existing = match std::fs::metadata(path) {
+        Ok(metadata) if !metadata.is_file() => {
+            return Err(format!("{} is not a file", path.display()));
+        }
+        Ok(metadata) => Some(metadata),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(failed(e)),
+    };
+    if existing.is_some() {
+        let mut head = Vec::with_capacity(SNIFF_BYTES);
+        File::open(path)
+            .and_then(|f| f.take(SNIFF_BYTES as u64).read_to_end(&mut head))
+            .map_err(failed)?;
+        if is_binary(&head) {
+            return Err(format!("{} is a binary file", path.display()));
+        }
+    }
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4()));
+    let written = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            if let Some(metadata) = &existing {
+                file.set_permissions(metadata.permissions())?;
+            }
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp, path));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp);
+        return Err(failed(e));
+    }
+    Ok(())
+}
+
+/// Reads a text file in a folder the user opened, whole or `range` of its lines. Binary files
+/// come back without contents.
+#[tauri::command]
+#[specta::specta]
+pub async fn read_project_file(
+    app: AppHandle,
+    path: String,
+    range: Option<LineRange>,
+) -> Result<ProjectFile, String> {
+    let path = resolve(&path)?;
+    if !trust::is_opened(&app, &path)? {
+        return Err(format!("{} was not opened in the app", path.display()));
+    }
+    tokio::task::spawn_blocking(move || read(&path, range))
+        .await
+        .map_err(|e| format!("Failed to read the file: {}", e))?
+}
+
+/// Saves a text file in a trusted project, creating it if needed. Binary files are never
+/// overwritten.
+#[tauri::command]
+#[specta::specta]
+pub async fn write_project_file(
+    app: AppHandle,
+    path: String,
+    contents: String,
+) -> Result<(), String> {
+    if contents.len() > MAX_WRITE_BYTES {
+        return Err(format!(
+            "The file is too large to save ({} MB)",
+            contents.len().div_ceil(1024 * 1024)
+        ));
+    }
+    let path = resolve(&path)?;
+    if !trust::is_trusted(&settings::load(&app)?.trusted_paths, &path) {
+        return Err(format!("{} is not in a trusted project", path.display()));
+    }
+    let shown = path.display().to_string();
+    tokio::task::spawn_blocking(move || write(&path, &contents))
+        .await
+        .map_err(|e| format!("Failed to write the file: {}", e))??;
+    tracing::debug!(path = %shown, "Saved project file");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_binary_content() {
+        assert!(!is_binary(b"fn main() {}\n"));
+        assert!(is_binary(b"PNG\0\0\0"));
+        assert!(is_binary(&[0xff, 0xfe, b'a']));
+        // A character cut off by the sample size is still text.
+        assert!(!is_binary(&"aé".as_bytes()[..2]));
+    }
+
+    #[test]
+    fn reads_ranges_and_writes_atomically() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let file = dir.join("notes.txt");
+
+        write(&file, "one\ntwo\nthree\nfour\n").unwrap();
+        let whole = read(&file, None).unwrap();
+        assert_eq!(whole.contents.as_deref(), Some("one\ntwo\nthree\nfour\n"));
+        assert!(!whole.binary);
+
+        let range = LineRange {
+            start: 2,
+            end: Some(3),
+        };
+        assert_eq!(
+            read(&file, Some(range)).unwrap().contents.as_deref(),
+            Some("two\nthree\n")
+        );
+        let tail = LineRange {
+            start: 4,
+            end: None,
+        };
+        assert_eq!(
+            read(&file, Some(tail)).unwrap().contents.as_deref(),
+            Some("four\n")
+        );
+
+        write(&file, "replaced").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "replaced");
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+
+        let image = dir.join("image.bin");
+        std::fs::write(&image, b"\x89PNG\0\0").unwrap();
+        assert!(read(&image, None).unwrap().binary);
+        assert!(write(&image, "text").is_err());
+
+        assert_eq!(
+            resolve(dir.join("new.txt").to_str().unwrap()).unwrap(),
+            dunce::canonicalize(dir).unwrap().join("new.txt")
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use tauri::{AppHandle, State};
+
+use crate::{
+    instances::{self, InstanceInfo, Instances},
+    settings,
+};
+
+/// Unpinned projects beyond this many are forgotten, least recently opened first.
+const MAX_RECENT: usize = 20;
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct RecentProject {
+    pub path: String,
+    #[serde(default)]
+    pub pinned: bool,
+    pub last_opened: String,
+}
+
+fn read_projects(app: &AppHandle) -> Result<Vec<RecentProject>, String> {
+    Ok(settings::load(app)?.recent_projects)
+}
+
+fn write_projects(app: &AppHandle, projects: &[RecentProject]) -> Result<(), String> {
+    settings::update(app, |s| {
+        s.recent_projects = projects.to_vec();
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Moves `path` to the front of the list, keeping its pin, and drops the oldest unpinned entries.
+fn touch(projects: &mut Vec<RecentProject>, path: String, now: String) {
+    let pinned = match projects.iter().position(|p| p.path == path) {
+        Some(index) => projects.remove(index).pinned,
+        None => false,
+    };
+    projects.insert(
+        0,
+        RecentProject {
+            path,
+            pinned,
+            last_opened: now,
+        },
+    );
+
+    let mut unpinned = 0;
+    projects.retain(|p| {
+        if !p.pinned {
+            unpinned += 1;
+        }
+        p.pinned || unpinned <= MAX_RECENT
+    });
+}
+
+/// Pinned projects first, then the rest, each most recently opened first.
+fn sorted(mut projects: Vec<RecentProject>) -> Vec<RecentProject> {
+    // The stored list is already most recent first, and the sort is stable.
+    projects.sort_by_key(|p| !p.pinned);
+    projects
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_recent_projects(app: AppHandle) -> Result<Vec<RecentProject>, String> {
+    Ok(sorted(read_projects(&app)?))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn add_recent_project(app: AppHandle, path: String) -> Result<Vec<RecentProject>, String> {
+    let path = instances::canonical_directory(&path)?.display().to_string();
+
+    let mut projects = read_projects(&app)?;
+    touch(&mut projects, path, chrono::Utc::now().to_rfc3339());
+    write_projects(&app, &projects)?;
+
+    Ok(sorted(projects))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn remove_recent_project(app: AppHandle, path: String) -> Result<Vec<RecentProject>, String> {
+    let mut projects = read_projects(&app)?;
+    projects.retain(|p| p.path != path);
+    write_projects(&app, &projects)?;
+
+    Ok(sorted(projects))
+}
+
+/// Pinned projects are listed first and never dropped from the list.
+#[tauri::command]
+#[specta::specta]
+pub fn pin_recent_project(
+    app: AppHandle,
+    path: String,
+    pinned: bool,
+) -> Result<Vec<RecentProject>, String> {
+    let mut projects = read_projects(&app)?;
+    let Some(project) = projects.iter_mut().find(|p| p.path == path) else {
+        return Err(format!("{path} is not a recent project"));
+    };
+    project.pinned = pinned;
+    write_projects(&app, &projects)?;
+
+    Ok(sorted(projects))
+}
+
+/// Records the project as opened and starts a server running in it, or attaches to the one
+/// already serving it.
+#[tauri::command]
+#[specta::specta]
+pub async fn open_project(
+    app: AppHandle,
+    instances: State<'_, Instances>,
+    path: String,
+) -> Result<InstanceInfo, String> {
+    add_recent_project(app.clone(), path.clone())?;
+    instances::start_instance(app, instances, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(path: &str, pinned: bool) -> RecentProject {
+        RecentProject {
+            path: path.to_string(),
+            pinned,
+            last_opened: String::new(),
+        }
+    }
+
+    #[test]
+    fn touch_keeps_pins_and_evicts_oldest_unpinned() {
+        let mut projects = vec![project("/pinned", true)];
+        projects.extend((0..MAX_RECENT).map(|i| project(&format!("/p{i}"), false)));
+
+        touch(&mut projects, "/pinned".to_string(), "now".to_string());
+        assert_eq!(projects[0].path, "/pinned");
+        assert!(projects[0].pinned);
+        assert_eq!(projects.len(), MAX_RECENT + 1);
+
+        touch(&mut projects, "/new".to_string(), "now".to_string());
+        assert_eq!(projects.len(), MAX_RECENT + 1);
+        assert_eq!(projects[0].path, "/new");
+        assert!(projects.iter().any(|p| p.path == "/pinned"));
+        let last = format!("/p{}", MAX_RECENT - 1);
+        assert!(!projects.iter().any(|p| p.path == last));
+
+        let sorted = sorted(projects);
+        assert_eq!(sorted[0].path, "/pinned");
+        assert_eq!(sorted[1].path, "/new");
+    }
+}
@@ -0,0 +1,229 @@
+use std::{collections::HashMap, time::Duration};
+
+use reqwest::Method;
+use tauri::AppHandle;
+
+use crate::{models, supervisor::request};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+// The callback of an automatic OAuth flow only returns once the user finished in the browser.
+const OAUTH_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethodKind {
+    Oauth,
+    Api,
+}
+
+/// A way to log in to a provider. Its index in `methods` is what OAuth commands take.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct AuthMethod {
+    #[serde(rename = "type")]
+    pub kind: AuthMethodKind,
+    pub label: String,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct ProviderAuth {
+    pub id: String,
+    pub name: String,
+    /// Set when the server has credentials for the provider, from any source.
+    pub connected: bool,
+    /// Login methods besides an API key, offered by plugins.
+    pub methods: Vec<AuthMethod>,
+}
+
+/// Where to send the user to authorize the app with a provider.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct OauthAuthorization {
+    pub url: String,
+    /// `auto` when the provider redirects back on its own, `code` when the user pastes a code.
+    pub method: String,
+    pub instructions: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ProviderList {
+    all: Vec<ProviderEntry>,
+    connected: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ProviderEntry {
+    id: String,
+    name: String,
+}
+
+/// Connected providers first, then the rest, each by name.
+fn merge(list: ProviderList, mut methods: HashMap<String, Vec<AuthMethod>>) -> Vec<ProviderAuth> {
+    let mut providers = list
+        .all
+        .into_iter()
+        .map(|provider| ProviderAuth {
+            connected: list.connected.contains(&provider.id),
+            methods: methods.remove(&provider.id).unwrap_or_default(),
+            id: provider.id,
+            name: provider.name,
+        })
+        .collect::<Vec<_>>();
+    providers.sort_by(|a, b| {
+        b.connected
+            .cmp(&a.connected)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    providers
+}
+
+fn auth_path(provider_id: &str) -> Result<String, String> {
+    if provider_id.is_empty() || provider_id.contains(['/', '?', '#']) {
+        return Err(format!("Invalid provider id {provider_id:?}"));
+    }
+    Ok(format!("/auth/{provider_id}"))
+}
+
+/// Makes the server reload providers, so changed credentials take effect.
+async fn reload(app: &AppHandle) -> Result<(), String> {
+    models::invalidate(app);
+    request(app, Method::POST, "/global/dispose", None, REQUEST_TIMEOUT)
+        .await
+        .map(|_| ())
+}
+
+/// Every provider the server knows, whether it has credentials and how to log in to it.
+#[tauri::command]
+#[specta::specta]
+pub async fn list_provider_auth(app: AppHandle) -> Result<Vec<ProviderAuth>, String> {
+    let list = request(&app, Method::GET, "/provider", None, REQUEST_TIMEOUT).await?;
+    let methods = request(&app, Method::GET, "/provider/auth", None, REQUEST_TIMEOUT).await?;
+
+    Ok(merge(
+        serde_json::from_value(list).map_err(|e| format!("Failed to parse providers: {}", e))?,
+        serde_json::from_value(methods)
+            .map_err(|e| format!("Failed to parse provider auth methods: {}", e))?,
+    ))
+}
+
+/// Stores `key` as the provider's API key, like `opencode auth login` does.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_provider_api_key(
+    app: AppHandle,
+    provider_id: String,
+    key: String,
+) -> Result<(), String> {
+    if key.trim().is_empty() {
+        return Err("The API key cannot be empty".to_string());
+    }
+    let body = serde_json::json!({ "type": "api", "key": key.trim() });
+    request(
+        &app,
+        Method::PUT,
+        &auth_path(&provider_id)?,
+        Some(body),
+        REQUEST_TIMEOUT,
+    )
+    .await?;
+
+    tracing::info!(provider_id, "Stored provider API key");
+    reload(&app).await
+}
+
+/// Removes the provider's stored credentials, like `opencode auth logout` does.
+#[tauri::command]
+#[specta::specta]
+pub async fn logout_provider(app: AppHandle, provider_id: String) -> Result<(), String> {
+    request(
+        &app,
+        Method::DELETE,
+        &auth_path(&provider_id)?,
+        None,
+        REQUEST_TIMEOUT,
+    )
+    .await?;
+
+    tracing::info!(provider_id, "Removed provider credentials");
+    reload(&app).await
+}
+
+/// Starts logging in to the provider with OAuth `method`. Returns where to send the user, or
+/// nothing when the method needs no browser.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_provider_oauth(
+    app: AppHandle,
+    provider_id: String,
+    method: u32,
+) -> Result<Option<OauthAuthorization>, String> {
+    auth_path(&provider_id)?;
+    let res = request(
+        &app,
+        Method::POST,
+        &format!("/provider/{provider_id}/oauth/authorize"),
+        Some(serde_json::json!({ "method": method })),
+        REQUEST_TIMEOUT,
+    )
+    .await?;
+
+    serde_json::from_value(res).map_err(|e| format!("Failed to parse the authorization: {}", e))
+}
+
+/// Finishes an OAuth login started with `start_provider_oauth`. Methods that return a `code`
+/// need it passed here; `auto` ones wait for the browser to redirect back.
+#[tauri::command]
+#[specta::specta]
+pub async fn finish_provider_oauth(
+    app: AppHandle,
+    provider_id: String,
+    method: u32,
+    code: Option<String>,
+) -> Result<(), String> {
+    auth_path(&provider_id)?;
+    request(
+        &app,
+        Method::POST,
+        &format!("/provider/{provider_id}/oauth/callback"),
+        Some(serde_json::json!({ "method": method, "code": code })),
+        OAUTH_TIMEOUT,
+    )
+    .await?;
+
+    tracing::info!(provider_id, "Logged in to provider");
+    reload(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_connected_providers_first() {
+        let list: ProviderList = serde_json::from_value(serde_json::json!({
+            "all": [
+                { "id": "openai", "name": "OpenAI", "models": {} },
+                { "id": "anthropic", "name": "Anthropic" },
+                { "id": "zai", "name": "Z.AI" },
+            ],
+            "connected": ["zai"],
+        }))
+        .unwrap();
+        let methods = serde_json::from_value(serde_json::json!({
+            "anthropic": [{ "type": "oauth", "label": "Claude Pro/Max" }],
+        }))
+        .unwrap();
+
+        let providers = merge(list, methods);
+        assert_eq!(
+            providers.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(),
+            ["zai", "anthropic", "openai"]
+        );
+        assert!(providers[0].connected);
+        assert_eq!(
+            providers[1].methods,
+            [AuthMethod {
+                kind: AuthMethodKind::Oauth,
+                label: "Claude Pro/Max".to_string(),
+            }]
+        );
+    }
+}
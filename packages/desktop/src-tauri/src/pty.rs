@@ -0,0 +1,409 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        mpsc,
+    },
+};
+
+use portable_pty::{ChildKiller, CommandBuilder, MasterPty, PtySize, native_pty_system};
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{cli, instances, shell_env, trust};
+
+const MAX_TERMINALS: usize = 16;
+const READ_BUFFER: usize = 16 * 1024;
+
+/// What runs in a terminal: the user's shell, or the opencode TUI.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalKind {
+    Shell,
+    Opencode,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct TerminalInfo {
+    pub id: u32,
+    pub kind: TerminalKind,
+    pub directory: Option<String>,
+    pub cols: u32,
+    pub rows: u32,
+}
+
+/// Output of a terminal, in the order it was written.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct TerminalOutput {
+    pub id: u32,
+    pub data: String,
+}
+
+/// Sent once the program in a terminal exits, after its last output.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct TerminalExited {
+    pub id: u32,
+    pub code: Option<u32>,
+}
+
+struct Terminal {
+    info: TerminalInfo,
+    master: Box<dyn MasterPty + Send>,
+    /// Input for the terminal's writer thread, which stops once this is dropped.
+    input: mpsc::Sender<Vec<u8>>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+}
+
+#[derive(Clone, Default)]
+pub struct Terminals {
+    terminals: Arc<Mutex<BTreeMap<u32, Terminal>>>,
+    /// Terminals still starting, which count towards `MAX_TERMINALS` before they are inserted.
+    starting: Arc<AtomicUsize>,
+    next_id: Arc<AtomicU32>,
+}
+
+/// A place among the `MAX_TERMINALS` held while a terminal starts, given back when dropped.
+struct Slot<'a>(&'a AtomicUsize);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Terminals {
+    /// Holds a place for a new terminal, checked under the same lock the terminals are inserted
+    /// with, so concurrent spawns can't go past the limit.
+    fn reserve(&self) -> Result<Slot<'_>, String> {
+        let terminals = self.terminals.lock().unwrap();
+        if terminals.len() + self.starting.load(Ordering::Relaxed) >= MAX_TERMINALS {
+            return Err(format!("At most {MAX_TERMINALS} terminals can be open"));
+        }
+        self.starting.fetch_add(1, Ordering::Relaxed);
+        Ok(Slot(&self.starting))
+    }
+}
+
+fn size(cols: u32, rows: u32) -> PtySize {
+    PtySize {
+        rows: rows.clamp(1, u16::MAX as u32) as u16,
+        cols: cols.clamp(1, u16::MAX as u32) as u16,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// Takes the longest valid UTF-8 prefix out of `pending`, leaving a character split across
+/// reads for the next one. Invalid bytes are replaced.
+fn decode(pending: &mut Vec<u8>) -> String {
+    let mut out = String::new();
+    loop {
+        match std::str::from_utf8(pending) {
+            Ok(text) => {
+                out.push_str(text);
+                pending.clear();
+                return out;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                out.push_str(std::str::from_utf8(&pending[..valid]).unwrap_or_default());
+                match e.error_len() {
+                    Some(len) => {
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        pending.drain(..valid + len);
+                    }
+                    None => {
+                        pending.drain(..valid);
+                        return out;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn command(
+    app: &AppHandle,
+    kind: TerminalKind,
+    directory: Option<&PathBuf>,
+) -> Result<CommandBuilder, String> {
+    let mut cmd = match kind {
+        TerminalKind::Shell => CommandBuilder::new(shell_env::resolve(app).path),
+        TerminalKind::Opencode => {
+            if cfg!(windows) && cli::is_wsl_enabled(app) {
+                return Err("The opencode TUI can't be opened while the server runs in WSL".into());
+            }
+            let binary = cli::pinned_binary(app, directory.map(PathBuf::as_path))
+                .unwrap_or_else(|| cli::get_sidecar_path(app));
+            let mut cmd = CommandBuilder::new(binary);
+            if shell_env::enabled(app)
+                && let Some(shell_env) = shell_env::get(&shell_env::resolve(app)).await
+            {
+                for (key, value) in shell_env.iter() {
+                    cmd.env(key, value);
+                }
+            }
+            for (key, value) in cli::sidecar_envs(app, directory.map(PathBuf::as_path)) {
+                cmd.env(key, value);
+            }
+            cmd
+        }
+    };
+
+    cmd.env("TERM", "xterm-256color");
+    cmd.env("COLORTERM", "truecolor");
+    match directory {
+        Some(directory) => cmd.cwd(directory),
+        None => {
+            if let Some(home) = dirs::home_dir() {
+                cmd.cwd(home);
+            }
+        }
+    }
+    Ok(cmd)
+}
+
+/// Writes input to the terminal on its own thread, so a program that isn't reading its input
+/// only holds up its own terminal.
+fn feed(id: u32, mut writer: Box<dyn Write + Send>) -> mpsc::Sender<Vec<u8>> {
+    let (input, received) = mpsc::channel::<Vec<u8>>();
+    std::thread::spawn(move || {
+        for data in received {
+            if let Err(e) = writer.write_all(&data).and_then(|()| writer.flush()) {
+                tracing::debug!(id, "Failed to write to terminal: {e}");
+                break;
+            }
+        }
+    });
+    input
+}
+
+fn forget(app: &AppHandle, id: u32) -> Option<Terminal> {
+    app.state::<Terminals>()
+        .terminals
+        .lock()
+        .unwrap()
+        .remove(&id)
+}
+
+/// Forwards the terminal's output until the program exits, then forgets the terminal.
+fn pump(
+    app: AppHandle,
+    id: u32,
+    mut reader: Box<dyn Read + Send>,
+    mut child: Box<dyn portable_pty::Child + Send + Sync>,
+) {
+    let output = std::thread::spawn({
+        let app = app.clone();
+        move || {
+            let mut buffer = vec![0; READ_BUFFER];
+            let mut pending = Vec::new();
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => {
+                        pending.extend_from_slice(&buffer[..read]);
+                        let data = decode(&mut pending);
+                        if !data.is_empty() {
+                            let _ = TerminalOutput { id, data }.emit(&app);
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    std::thread::spawn(move || {
+        let code = child.wait().ok().map(|status| status.exit_code());
+        // A pseudo console keeps its output open after the program exits, until it is closed.
+        if cfg!(windows) {
+            drop(forget(&app, id));
+        }
+        let _ = output.join();
+
+        tracing::info!(id, ?code, "Terminal exited");
+        drop(forget(&app, id));
+        let _ = TerminalExited { id, code }.emit(&app);
+    });
+}
+
+/// Opens a terminal running `kind` in `directory`, which the user is asked to trust first, or
+/// the home directory, and starts streaming its output as `TerminalOutput` events.
+#[tauri::command]
+#[specta::specta]
+pub async fn spawn_terminal(
+    app: AppHandle,
+    terminals: State<'_, Terminals>,
+    kind: TerminalKind,
+    directory: Option<String>,
+    cols: u32,
+    rows: u32,
+) -> Result<TerminalInfo, String> {
+    let slot = terminals.reserve()?;
+
+    let directory = directory
+        .as_deref()
+        .map(instances::canonical_directory)
+        .transpose()?;
+    // The TUI loads the project's config just like a server would, and a shell runs whatever the
+    // folder sets up for it.
+    if let Some(directory) = &directory {
+        trust::ensure(&app, directory).await?;
+    }
+
+    let cmd = command(&app, kind, directory.as_ref()).await?;
+    let pair = native_pty_system()
+        .openpty(size(cols, rows))
+        .map_err(|e| format!("Failed to open a terminal: {}", e))?;
+    let child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| format!("Failed to start the terminal program: {}", e))?;
+    // The child holds its own handle, and output only ends once every handle is closed.
+    drop(pair.slave);
+
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to read from the terminal: {}", e))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("Failed to write to the terminal: {}", e))?;
+
+    let id = terminals.next_id.fetch_add(1, Ordering::Relaxed);
+    let info = TerminalInfo {
+        id,
+        kind,
+        directory: directory.map(|d| d.to_string_lossy().to_string()),
+        cols,
+        rows,
+    };
+    let mut open = terminals.terminals.lock().unwrap();
+    open.insert(
+        id,
+        Terminal {
+            info: info.clone(),
+            master: pair.master,
+            input: feed(id, writer),
+            killer: child.clone_killer(),
+        },
+    );
+    drop(slot);
+    drop(open);
+    pump(app, id, reader, child);
+
+    tracing::info!(id, ?kind, directory = ?info.directory, "Opened terminal");
+    Ok(info)
+}
+
+/// Sends keyboard input to the terminal.
+#[tauri::command]
+#[specta::specta]
+pub fn write_terminal(
+    terminals: State<'_, Terminals>,
+    id: u32,
+    data: String,
+) -> Result<(), String> {
+    let terminals = terminals.terminals.lock().unwrap();
+    let terminal = terminals
+        .get(&id)
+        .ok_or_else(|| format!("No terminal {id}"))?;
+    terminal
+        .input
+        .send(data.into_bytes())
+        .map_err(|_| format!("Failed to write to terminal {id}: it stopped reading input"))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn resize_terminal(
+    terminals: State<'_, Terminals>,
+    id: u32,
+    cols: u32,
+    rows: u32,
+) -> Result<(), String> {
+    let mut terminals = terminals.terminals.lock().unwrap();
+    let terminal = terminals
+        .get_mut(&id)
+        .ok_or_else(|| format!("No terminal {id}"))?;
+    terminal
+        .master
+        .resize(size(cols, rows))
+        .map_err(|e| format!("Failed to resize terminal {id}: {}", e))?;
+    terminal.info.cols = cols;
+    terminal.info.rows = rows;
+    Ok(())
+}
+
+/// Kills the program in the terminal. `TerminalExited` follows once it is gone.
+#[tauri::command]
+#[specta::specta]
+pub fn close_terminal(terminals: State<'_, Terminals>, id: u32) -> Result<(), String> {
+    let mut terminals = terminals.terminals.lock().unwrap();
+    let terminal = terminals
+        .get_mut(&id)
+        .ok_or_else(|| format!("No terminal {id}"))?;
+    terminal
+        .killer
+        .kill()
+        .map_err(|e| format!("Failed to close terminal {id}: {}", e))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_terminals(terminals: State<'_, Terminals>) -> Vec<TerminalInfo> {
+    terminals
+        .terminals
+        .lock()
+        .unwrap()
+        .values()
+        .map(|terminal| terminal.info.clone())
+        .collect()
+}
+
+/// Kills every terminal, for app exit.
+pub fn close_all(app: &AppHandle) {
+    let Some(terminals) = app.try_state::<Terminals>() else {
+        return;
+    };
+    for terminal in std::mem::take(&mut *terminals.terminals.lock().unwrap()).into_values() {
+        let mut killer = terminal.killer;
+        let _ = killer.kill();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_keeps_split_characters_for_the_next_read() {
+        let bytes = "héllo".as_bytes();
+        let mut pending = bytes[..2].to_vec();
+        assert_eq!(decode(&mut pending), "h");
+        assert_eq!(pending, [0xc3]);
+
+        pending.extend_from_slice(&bytes[2..]);
+        assert_eq!(decode(&mut pending), "éllo");
+        assert!(pending.is_empty());
+
+        let mut invalid = vec![b'a', 0xff, b'b'];
+        assert_eq!(decode(&mut invalid), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn starting_terminals_count_towards_the_limit() {
+        let terminals = Terminals::default();
+        let mut slots = (0..MAX_TERMINALS)
+            .map(|_| terminals.reserve().unwrap())
+            .collect::<Vec<_>>();
+        assert!(terminals.reserve().is_err());
+
+        slots.pop();
+        assert!(terminals.reserve().is_ok());
+    }
+}
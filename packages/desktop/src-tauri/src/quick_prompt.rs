@@ -0,0 +1,96 @@
+use std::{path::PathBuf, time::Duration};
+
+use reqwest::Method;
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{instances, settings, supervisor, trust, windows::QuickPromptWindow};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Sent once a prompt from the quick prompt window was handed to the server, so the main window
+/// can open its session.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct QuickPromptSubmitted {
+    pub session_id: String,
+    pub directory: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct Session {
+    id: String,
+}
+
+/// The project a prompt goes to: the one asked for, or the one opened last.
+fn target(app: &AppHandle, directory: Option<String>) -> Result<Option<PathBuf>, String> {
+    let directory = match directory {
+        Some(directory) => Some(directory),
+        None => settings::load(app)?
+            .recent_projects
+            .into_iter()
+            .next()
+            .map(|project| project.path),
+    };
+    directory
+        .as_deref()
+        .map(instances::canonical_directory)
+        .transpose()
+}
+
+/// Starts a new session in `directory`, or the most recent project, with `text` as its first
+/// prompt. Returns as soon as the server accepted it and closes the quick prompt window.
+#[tauri::command]
+#[specta::specta]
+pub async fn submit_quick_prompt(
+    app: AppHandle,
+    text: String,
+    directory: Option<String>,
+) -> Result<QuickPromptSubmitted, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("The prompt is empty".to_string());
+    }
+    let directory = target(&app, directory)?;
+    if let Some(directory) = &directory {
+        trust::ensure(&app, directory).await?;
+    }
+
+    let session = supervisor::request_in(
+        &app,
+        directory.as_deref(),
+        Method::POST,
+        "/session",
+        Some(serde_json::json!({})),
+        REQUEST_TIMEOUT,
+    )
+    .await?;
+    let session: Session = serde_json::from_value(session)
+        .map_err(|e| format!("Failed to parse the new session: {}", e))?;
+
+    let body = serde_json::json!({ "parts": [{ "type": "text", "text": text }] });
+    supervisor::request_in(
+        &app,
+        directory.as_deref(),
+        Method::POST,
+        &format!("/session/{}/prompt_async", session.id),
+        Some(body),
+        REQUEST_TIMEOUT,
+    )
+    .await?;
+
+    let submitted = QuickPromptSubmitted {
+        session_id: session.id,
+        directory: directory.map(|d| d.to_string_lossy().to_string()),
+    };
+    tracing::info!(session_id = %submitted.session_id, directory = ?submitted.directory, "Submitted quick prompt");
+    QuickPromptWindow::close(&app);
+    let _ = submitted.clone().emit(&app);
+    Ok(submitted)
+}
+
+/// Opens the quick prompt window, or closes it when it is focused.
+#[tauri::command]
+#[specta::specta]
+pub async fn toggle_quick_prompt(app: AppHandle) -> Result<(), String> {
+    QuickPromptWindow::toggle(&app).map_err(|e| format!("Failed to open quick prompt: {}", e))
+}
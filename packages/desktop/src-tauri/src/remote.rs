@@ -0,0 +1,215 @@
+use tauri::AppHandle;
+
+use crate::{
+    ServerReadyData, health, keychain,
+    server::{self, ServerProbe},
+    settings,
+    tunnel::{self, SshTunnel},
+};
+
+/// A named opencode server on another machine. The password lives in the keychain, keyed by
+/// profile name.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct RemoteProfile {
+    pub name: String,
+    pub hostname: String,
+    pub port: u32,
+    /// Connect over https instead of http.
+    #[serde(default)]
+    pub secure: bool,
+    /// When set, `hostname` and `port` are resolved on the ssh host rather than locally.
+    #[serde(default)]
+    pub ssh: Option<SshTunnel>,
+}
+
+impl RemoteProfile {
+    pub fn url(&self) -> String {
+        self.url_for(&self.hostname, self.port)
+    }
+
+    fn url_for(&self, hostname: &str, port: u32) -> String {
+        let scheme = if self.secure { "https" } else { "http" };
+        format!(
+            "{scheme}://{}:{}",
+            server::normalize_hostname_for_url(hostname),
+            port
+        )
+    }
+
+    /// Opens the SSH tunnel if the profile uses one and returns the url to reach the server at.
+    async fn connect_url(&self, app: &AppHandle) -> Result<String, String> {
+        tunnel::close(app);
+
+        match &self.ssh {
+            Some(ssh) => {
+                let local_port = tunnel::open(app, self, ssh).await?;
+                Ok(self.url_for("127.0.0.1", local_port))
+            }
+            None => Ok(self.url()),
+        }
+    }
+}
+
+fn password_account(name: &str) -> String {
+    format!("remote-profile:{name}")
+}
+
+fn read_profiles(app: &AppHandle) -> Result<Vec<RemoteProfile>, String> {
+    Ok(settings::load(app)?.remote_profiles)
+}
+
+fn write_profiles(app: &AppHandle, profiles: &[RemoteProfile]) -> Result<(), String> {
+    settings::update(app, |s| {
+        s.remote_profiles = profiles.to_vec();
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+fn find_profile(app: &AppHandle, name: &str) -> Result<RemoteProfile, String> {
+    read_profiles(app)?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("No remote profile named '{name}'"))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_remote_profiles(app: AppHandle) -> Result<Vec<RemoteProfile>, String> {
+    read_profiles(&app)
+}
+
+/// Creates the profile, or replaces the one with the same name. A `None` password keeps the
+/// stored one; an empty string removes it.
+#[tauri::command]
+#[specta::specta]
+pub fn save_remote_profile(
+    app: AppHandle,
+    profile: RemoteProfile,
+    password: Option<String>,
+) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if profile.hostname.trim().is_empty() {
+        return Err("Hostname cannot be empty".to_string());
+    }
+    if profile.port == 0 || profile.port > u16::MAX as u32 {
+        return Err(format!("Invalid port {}", profile.port));
+    }
+    if let Some(ssh) = &profile.ssh {
+        ssh.validate()?;
+    }
+
+    match password.as_deref() {
+        Some("") => keychain::delete_secret(&password_account(&profile.name))?,
+        Some(password) => keychain::set_secret(&password_account(&profile.name), password)?,
+        None => {}
+    }
+
+    let mut profiles = read_profiles(&app)?;
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+
+    write_profiles(&app, &profiles)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_remote_profile(app: AppHandle, name: String) -> Result<(), String> {
+    let mut profiles = read_profiles(&app)?;
+    profiles.retain(|p| p.name != name);
+    write_profiles(&app, &profiles)?;
+
+    if get_active_remote_profile(app.clone())?.as_deref() == Some(name.as_str()) {
+        disconnect_remote(app)?;
+    }
+
+    keychain::delete_secret(&password_account(&name))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_active_remote_profile(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(settings::load(&app)?.active_remote_profile)
+}
+
+/// Checks that the profile's server is reachable and accepts its credentials, then makes it the
+/// server the app uses, now and on the next launch.
+#[tauri::command]
+#[specta::specta]
+pub async fn connect_remote(app: AppHandle, profile: String) -> Result<ServerReadyData, String> {
+    let profile = find_profile(&app, &profile)?;
+    let password = keychain::get_secret(&password_account(&profile.name))?;
+    let url = profile.connect_url(&app).await?;
+
+    let err = match server::probe(&url, password.as_deref()).await {
+        ServerProbe::Healthy => None,
+        ServerProbe::Unauthorized => Some(format!(
+            "{} rejected the credentials of '{}'",
+            profile.url(),
+            profile.name
+        )),
+        ServerProbe::Unreachable => Some(format!("Could not reach {}", profile.url())),
+    };
+    if let Some(err) = err {
+        tunnel::close(&app);
+        return Err(err);
+    }
+
+    settings::update(&app, |s| {
+        s.active_remote_profile = Some(profile.name.clone());
+        Ok(())
+    })?;
+
+    tracing::info!(profile = %profile.name, %url, "Connected to remote server");
+    health::start(&app, url.clone(), password.clone());
+
+    Ok(ServerReadyData {
+        url,
+        username: password.as_ref().map(|_| "opencode".to_string()),
+        password,
+        is_sidecar: false,
+    })
+}
+
+/// Goes back to the local server on the next launch.
+#[tauri::command]
+#[specta::specta]
+pub fn disconnect_remote(app: AppHandle) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.active_remote_profile = None;
+        Ok(())
+    })?;
+    tunnel::close(&app);
+
+    Ok(())
+}
+
+/// The active profile's url and password, if one is set and its server is healthy.
+pub async fn active(app: &AppHandle) -> Option<(String, Option<String>)> {
+    let name = get_active_remote_profile(app.clone()).ok().flatten()?;
+    let profile = find_profile(app, &name).ok()?;
+    let password = keychain::get_secret(&password_account(&name))
+        .ok()
+        .flatten();
+    let url = match profile.connect_url(app).await {
+        Ok(url) => url,
+        Err(e) => {
+            tracing::warn!(profile = %name, %e, "Remote server unavailable, using local server");
+            return None;
+        }
+    };
+
+    if server::probe(&url, password.as_deref()).await != ServerProbe::Healthy {
+        tracing::warn!(profile = %name, %url, "Remote server unavailable, using local server");
+        tunnel::close(app);
+        return None;
+    }
+
+    tracing::info!(profile = %name, %url, "Using remote server profile");
+    Some((url, password))
+}
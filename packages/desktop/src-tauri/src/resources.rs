@@ -0,0 +1,219 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{ServerState, settings};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+pub const DEFAULT_MEMORY_WARNING_MB: u32 = 2048;
+
+/// Resource usage of the sidecar and every process it started.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct ServerStats {
+    pub pid: u32,
+    /// Summed over the process tree, so 100 is one fully used core.
+    pub cpu_percent: f32,
+    pub memory_mb: u32,
+    pub processes: u32,
+}
+
+/// Sent when the sidecar's memory use rises above the configured threshold. Not repeated until
+/// it has dropped back below.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ServerMemoryWarning {
+    pub pid: u32,
+    pub memory_mb: u32,
+    pub threshold_mb: u32,
+}
+
+#[derive(Default)]
+struct Inner {
+    system: System,
+    latest: Option<ServerStats>,
+    warned: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct ResourceMonitor {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ResourceMonitor {
+    pub fn latest(&self) -> Option<ServerStats> {
+        self.inner.lock().unwrap().latest.clone()
+    }
+
+    /// Samples the tree rooted at `pid`, returning a warning the first time it crosses
+    /// `threshold_mb`.
+    fn sample(&self, pid: Option<u32>, threshold_mb: u32) -> Option<ServerMemoryWarning> {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(pid) = pid else {
+            inner.latest = None;
+            inner.warned = false;
+            return None;
+        };
+
+        inner.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cpu()
+                .with_memory()
+                .without_tasks(),
+        );
+
+        let processes = inner.system.processes();
+        let parents = processes
+            .iter()
+            .filter(|(_, process)| process.thread_kind().is_none())
+            .map(|(pid, process)| (pid.as_u32(), process.parent().map(Pid::as_u32)))
+            .collect::<HashMap<_, _>>();
+        let tree = process_tree(&parents, pid);
+        if tree.is_empty() {
+            inner.latest = None;
+            return None;
+        }
+
+        let (cpu, memory) = tree
+            .iter()
+            .filter_map(|pid| processes.get(&Pid::from_u32(*pid)))
+            .fold((0.0, 0), |(cpu, memory), process| {
+                (cpu + process.cpu_usage(), memory + process.memory())
+            });
+        let stats = ServerStats {
+            pid,
+            cpu_percent: cpu,
+            memory_mb: (memory / (1024 * 1024)) as u32,
+            processes: tree.len() as u32,
+        };
+
+        let over = stats.memory_mb >= threshold_mb;
+        let warning = (over && !inner.warned).then_some(ServerMemoryWarning {
+            pid,
+            memory_mb: stats.memory_mb,
+            threshold_mb,
+        });
+        inner.warned = over;
+        inner.latest = Some(stats);
+        warning
+    }
+}
+
+/// `root` and its descendants among the running processes, given as pid to parent pid.
+pub fn process_tree(parents: &HashMap<u32, Option<u32>>, root: u32) -> HashSet<u32> {
+    let mut tree = HashSet::new();
+    if !parents.contains_key(&root) {
+        return tree;
+    }
+    tree.insert(root);
+
+    // Each pass adds the children of what was found so far.
+    loop {
+        let children = parents
+            .iter()
+            .filter(|(pid, parent)| {
+                !tree.contains(*pid) && parent.is_some_and(|parent| tree.contains(&parent))
+            })
+            .map(|(pid, _)| *pid)
+            .collect::<Vec<_>>();
+        if children.is_empty() {
+            return tree;
+        }
+        tree.extend(children);
+    }
+}
+
+fn sidecar_pid(app: &AppHandle) -> Option<u32> {
+    app.try_state::<ServerState>()
+        .and_then(|state| state.child.lock().unwrap().as_ref().and_then(|c| c.pid()))
+}
+
+/// Samples the sidecar until the app exits.
+pub fn spawn(app: &AppHandle) {
+    let Some(monitor) = app.try_state::<ResourceMonitor>() else {
+        return;
+    };
+    let monitor = monitor.inner().clone();
+    let app = app.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let pid = sidecar_pid(&app);
+            let threshold = memory_warning_threshold(&app);
+            let sampled = tokio::task::spawn_blocking({
+                let monitor = monitor.clone();
+                move || monitor.sample(pid, threshold)
+            })
+            .await;
+
+            if let Ok(Some(warning)) = sampled {
+                tracing::warn!(
+                    pid = warning.pid,
+                    memory_mb = warning.memory_mb,
+                    threshold_mb = warning.threshold_mb,
+                    "Server memory use is above the warning threshold"
+                );
+                let _ = warning.emit(&app);
+            }
+
+            tokio::time::sleep(SAMPLE_INTERVAL).await;
+        }
+    });
+}
+
+fn memory_warning_threshold(app: &AppHandle) -> u32 {
+    get_memory_warning_threshold(app.clone()).unwrap_or(DEFAULT_MEMORY_WARNING_MB)
+}
+
+/// The latest sample, or `None` while no local server is running.
+#[tauri::command]
+#[specta::specta]
+pub fn get_server_stats(monitor: State<'_, ResourceMonitor>) -> Option<ServerStats> {
+    monitor.latest()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_memory_warning_threshold(app: AppHandle) -> Result<u32, String> {
+    Ok(settings::load(&app)?
+        .memory_warning_mb
+        .unwrap_or(DEFAULT_MEMORY_WARNING_MB))
+}
+
+/// Sets the memory use, in megabytes, above which `ServerMemoryWarning` is sent.
+#[tauri::command]
+#[specta::specta]
+pub fn set_memory_warning_threshold(app: AppHandle, threshold_mb: u32) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.memory_warning_mb = Some(threshold_mb);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_tree_follows_descendants_only() {
+        let parents = HashMap::from([
+            (1, None),
+            (10, Some(1)),
+            (11, Some(10)),
+            (12, Some(11)),
+            (20, Some(1)),
+        ]);
+
+        assert_eq!(process_tree(&parents, 10), HashSet::from([10, 11, 12]));
+        assert_eq!(process_tree(&parents, 20), HashSet::from([20]));
+        assert!(process_tree(&parents, 99).is_empty());
+    }
+}
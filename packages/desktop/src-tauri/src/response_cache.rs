@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Entries also expire on their own, in case an event that should have dropped them was missed.
+const TTL: Duration = Duration::from_secs(60);
+const CAPACITY: usize = 256;
+
+/// GET routes whose responses are cached, each with everything below it.
+const CACHEABLE: [&str; 10] = [
+    "/agent",
+    "/command",
+    "/config",
+    "/file",
+    "/lsp",
+    "/mcp",
+    "/project",
+    "/provider",
+    "/session",
+    "/vcs",
+];
+
+/// What a server event or request makes stale.
+#[derive(Debug, PartialEq, Eq)]
+enum Stale {
+    All,
+    Routes(&'static [&'static str]),
+}
+
+fn under(route: &str, prefix: &str) -> bool {
+    route == prefix
+        || route
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The routes a server event means have changed.
+fn stale_after_event(kind: &str) -> Option<Stale> {
+    let family = kind.split('.').next().unwrap_or_default();
+    match (family, kind) {
+        (_, "global.disposed" | "server.instance.disposed" | "installation.updated") => {
+            Some(Stale::All)
+        }
+        ("session" | "message" | "todo" | "command", _) => Some(Stale::Routes(&["/session"])),
+        ("file", _) => Some(Stale::Routes(&["/file", "/vcs"])),
+        ("project" | "worktree", _) => Some(Stale::Routes(&["/project"])),
+        ("lsp", _) => Some(Stale::Routes(&["/lsp"])),
+        ("mcp", _) => Some(Stale::Routes(&["/mcp", "/agent"])),
+        ("vcs", _) => Some(Stale::Routes(&["/vcs", "/file"])),
+        _ => None,
+    }
+}
+
+/// The routes a non-GET request to `route` may have changed. Config feeds everything else.
+fn stale_after_request(route: &str) -> Stale {
+    if under(route, "/config") || under(route, "/global/config") {
+        return Stale::All;
+    }
+    CACHEABLE
+        .iter()
+        .find(|prefix| under(route, prefix))
+        .map(|prefix| Stale::Routes(std::slice::from_ref(prefix)))
+        .unwrap_or(Stale::Routes(&[]))
+}
+
+/// Identifies a response by the server, project and request it answered.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub server: String,
+    pub directory: Option<String>,
+    /// The path with its query.
+    pub path: String,
+}
+
+struct Entry {
+    route: String,
+    value: serde_json::Value,
+    stored_at: Instant,
+    used: u64,
+}
+
+/// A least recently used cache of GET responses.
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<CacheKey, Entry>,
+    tick: u64,
+    /// Bumped whenever entries are dropped as stale, so responses requested before then aren't
+    /// stored.
+    generation: u64,
+}
+
+impl Lru {
+    fn get(&mut self, key: &CacheKey, now: Instant) -> Option<serde_json::Value> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        if now.duration_since(entry.stored_at) > TTL {
+            self.entries.remove(key);
+            return None;
+        }
+        entry.used = self.tick;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, route: &str, value: serde_json::Value, now: Instant) {
+        self.tick += 1;
+        if self.entries.len() >= CAPACITY
+            && !self.entries.contains_key(&key)
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| key.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(
+            key,
+            Entry {
+                route: route.to_string(),
+                value,
+                stored_at: now,
+                used: self.tick,
+            },
+        );
+    }
+
+    fn drop_stale(&mut self, stale: &Stale) {
+        self.generation += 1;
+        match stale {
+            Stale::All => self.entries.clear(),
+            Stale::Routes(routes) => self
+                .entries
+                .retain(|_, entry| !routes.iter().any(|prefix| under(&entry.route, prefix))),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ResponseCache(Mutex<Lru>);
+
+impl ResponseCache {
+    /// Whether GET responses for `route` are cached.
+    pub fn cacheable(route: &str) -> bool {
+        CACHEABLE.iter().any(|prefix| under(route, prefix))
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<serde_json::Value> {
+        self.0.lock().unwrap().get(key, Instant::now())
+    }
+
+    /// Marks the start of a request, whose response `insert` only stores if nothing went stale
+    /// in the meantime.
+    pub fn generation(&self) -> u64 {
+        self.0.lock().unwrap().generation
+    }
+
+    pub fn insert(&self, key: CacheKey, route: &str, value: serde_json::Value, generation: u64) {
+        let mut lru = self.0.lock().unwrap();
+        if lru.generation == generation {
+            lru.insert(key, route, value, Instant::now());
+        }
+    }
+
+    /// Drops the responses a server event of type `kind` outdated.
+    pub fn on_event(&self, kind: &str) {
+        if let Some(stale) = stale_after_event(kind) {
+            self.0.lock().unwrap().drop_stale(&stale);
+        }
+    }
+
+    /// Drops the responses a successful non-GET request to `route` may have outdated.
+    pub fn on_request(&self, route: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .drop_stale(&stale_after_request(route));
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().drop_stale(&Stale::All);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(path: &str) -> CacheKey {
+        CacheKey {
+            server: "http://127.0.0.1:4096".to_string(),
+            directory: None,
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn expires_and_evicts_least_recently_used() {
+        let start = Instant::now();
+        let mut lru = Lru::default();
+        lru.insert(key("/session"), "/session", 1.into(), start);
+        assert_eq!(lru.get(&key("/session"), start), Some(1.into()));
+        assert_eq!(lru.get(&key("/session"), start + TTL * 2), None);
+
+        for i in 0..CAPACITY {
+            lru.insert(key(&format!("/file?path={i}")), "/file", i.into(), start);
+        }
+        // Touching the first entry makes the second the least recently used.
+        lru.get(&key("/file?path=0"), start);
+        lru.insert(key("/agent"), "/agent", 0.into(), start);
+
+        assert_eq!(lru.entries.len(), CAPACITY);
+        assert!(lru.get(&key("/file?path=0"), start).is_some());
+        assert!(lru.get(&key("/file?path=1"), start).is_none());
+    }
+
+    #[test]
+    fn events_and_writes_drop_what_they_change() {
+        let start = Instant::now();
+        let mut lru = Lru::default();
+        for route in ["/session", "/session/s1/message", "/file", "/provider"] {
+            lru.insert(key(route), route, 0.into(), start);
+        }
+
+        lru.drop_stale(&stale_after_event("message.part.updated").unwrap());
+        assert!(lru.get(&key("/session/s1/message"), start).is_none());
+        assert!(lru.get(&key("/session"), start).is_none());
+        assert!(lru.get(&key("/file"), start).is_some());
+
+        assert_eq!(stale_after_event("tui.toast.show"), None);
+        assert_eq!(stale_after_request("/config"), Stale::All);
+        assert_eq!(stale_after_request("/pty/1"), Stale::Routes(&[]));
+
+        lru.drop_stale(&stale_after_request("/provider/anthropic/oauth/authorize"));
+        assert!(lru.get(&key("/provider"), start).is_none());
+        assert!(lru.get(&key("/file"), start).is_some());
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use grep_matcher::Matcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{
+    BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch,
+};
+use ignore::{
+    WalkBuilder, WalkState,
+    overrides::{Override, OverrideBuilder},
+};
+use tauri::{AppHandle, State};
+use tauri_specta::Event;
+
+use crate::{
+    operations::{self, OperationKind, Operations},
+    trust,
+};
+
+const DEFAULT_MAX_RESULTS: usize = 2000;
+const MAX_RESULTS: usize = 20_000;
+const MAX_CONTEXT_LINES: usize = 10;
+/// Longer lines, usually minified code, are cut off.
+const MAX_LINE_CHARS: usize = 500;
+const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+#[derive(Clone, serde::Deserialize, specta::Type, Debug)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// Treat `pattern` as a regular expression rather than literal text.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    /// Globs of files to search, relative to the project. Globs starting with `!` exclude files.
+    #[serde(default)]
+    pub globs: Vec<String>,
+    /// Lines to include before and after each match.
+    #[serde(default)]
+    pub context_lines: u32,
+    pub max_results: Option<u32>,
+}
+
+/// A matched range within a line, in UTF-16 code units as the webview indexes strings.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct MatchRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct SearchLine {
+    pub line: u32,
+    pub text: String,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct SearchMatch {
+    pub line: u32,
+    pub text: String,
+    pub ranges: Vec<MatchRange>,
+    pub before: Vec<SearchLine>,
+    pub after: Vec<SearchLine>,
+}
+
+/// The matches found in one file of a running search.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct SearchResults {
+    pub search_id: String,
+    /// Relative to the searched folder.
+    pub path: String,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Sent once a search has gone through every file, hit its result limit or was cancelled.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct SearchFinished {
+    pub search_id: String,
+    pub files: u32,
+    pub matches: u32,
+    /// The result limit was reached before every file was searched.
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+fn utf16_len(bytes: &[u8]) -> u32 {
+    String::from_utf8_lossy(bytes).encode_utf16().count() as u32
+}
+
+fn line_text(bytes: &[u8]) -> String {
+    let bytes = bytes
+        .strip_suffix(b"\n")
+        .map(|b| b.strip_suffix(b"\r").unwrap_or(b))
+        .unwrap_or(bytes);
+    String::from_utf8_lossy(bytes)
+        .chars()
+        .take(MAX_LINE_CHARS)
+        .collect()
+}
+
+/// Collects the matches of one file with the lines around them.
+struct Collector<'a> {
+    matcher: &'a RegexMatcher,
+    cancel: &'a AtomicBool,
+    limit: usize,
+    matches: Vec<SearchMatch>,
+    before: Vec<SearchLine>,
+    limited: bool,
+}
+
+impl Sink for Collector<'_> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _: &Searcher, found: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        if self.matches.len() >= self.limit {
+            self.limited = true;
+            return Ok(false);
+        }
+
+        let bytes = found.bytes();
+        let text = line_text(bytes);
+        let shown = text.encode_utf16().count() as u32;
+        let mut ranges = Vec::new();
+        self.matcher
+            .find_iter(bytes, |m| {
+                let start = utf16_len(&bytes[..m.start()]);
+                if start < shown {
+                    ranges.push(MatchRange {
+                        start,
+                        end: utf16_len(&bytes[..m.end()]).min(shown),
+                    });
+                }
+                true
+            })
+            .map_err(std::io::Error::other)?;
+
+        self.matches.push(SearchMatch {
+            line: found.line_number().unwrap_or_default() as u32,
+            text,
+            ranges,
+            before: std::mem::take(&mut self.before),
+            after: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    fn context(&mut self, _: &Searcher, context: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        let line = SearchLine {
+            line: context.line_number().unwrap_or_default() as u32,
+            text: line_text(context.bytes()),
+        };
+        match context.kind() {
+            SinkContextKind::After => {
+                if let Some(last) = self.matches.last_mut() {
+                    last.after.push(line);
+                }
+            }
+            _ => self.before.push(line),
+        }
+        Ok(!self.cancel.load(Ordering::Relaxed))
+    }
+}
+
+/// A validated query, ready to run over a folder.
+struct Search {
+    root: PathBuf,
+    matcher: RegexMatcher,
+    overrides: Override,
+    context: usize,
+    limit: usize,
+}
+
+impl Search {
+    fn new(root: PathBuf, query: &SearchQuery) -> Result<Self, String> {
+        if query.pattern.is_empty() {
+            return Err("The search pattern is empty".to_string());
+        }
+        let matcher = RegexMatcherBuilder::new()
+            .case_insensitive(!query.case_sensitive)
+            .word(query.whole_word)
+            .fixed_strings(!query.regex)
+            .line_terminator(Some(b'\n'))
+            .build(&query.pattern)
+            .map_err(|e| format!("Invalid search pattern: {}", e))?;
+
+        let mut overrides = OverrideBuilder::new(&root);
+        for glob in &query.globs {
+            overrides
+                .add(glob)
+                .map_err(|e| format!("Invalid glob {}: {}", glob, e))?;
+        }
+        let overrides = overrides
+            .build()
+            .map_err(|e| format!("Invalid globs: {}", e))?;
+
+        Ok(Self {
+            root,
+            matcher,
+            overrides,
+            context: (query.context_lines as usize).min(MAX_CONTEXT_LINES),
+            limit: query
+                .max_results
+                .map_or(DEFAULT_MAX_RESULTS, |max| max as usize)
+                .min(MAX_RESULTS),
+        })
+    }
+
+    /// Searches every file git doesn't ignore, passing each file's matches to `on_file` as soon
+    /// as it is done. Returns the number of files and matches, and whether the limit was hit.
+    fn run(
+        &self,
+        cancel: &AtomicBool,
+        on_file: &(dyn Fn(String, Vec<SearchMatch>) + Sync),
+    ) -> (u32, u32, bool) {
+        let found = AtomicUsize::new(0);
+        let files = AtomicUsize::new(0);
+        let truncated = AtomicBool::new(false);
+
+        WalkBuilder::new(&self.root)
+            .require_git(false)
+            .overrides(self.overrides.clone())
+            .max_filesize(Some(MAX_FILE_SIZE))
+            .build_parallel()
+            .run(|| {
+                let mut searcher = SearcherBuilder::new()
+                    .line_number(true)
+                    .before_context(self.context)
+                    .after_context(self.context)
+                    .binary_detection(BinaryDetection::quit(0))
+                    .build();
+                let (found, files, truncated) = (&found, &files, &truncated);
+
+                Box::new(move |entry| {
+                    if cancel.load(Ordering::Relaxed) || truncated.load(Ordering::Relaxed) {
+                        return WalkState::Quit;
+                    }
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                        return WalkState::Continue;
+                    }
+
+                    let mut collector = Collector {
+                        matcher: &self.matcher,
+                        cancel,
+                        limit: self.limit.saturating_sub(found.load(Ordering::Relaxed)),
+                        matches: Vec::new(),
+                        before: Vec::new(),
+                        limited: false,
+                    };
+                    if let Err(e) = searcher.search_path(&self.matcher, entry.path(), &mut collector)
+                    {
+                        tracing::debug!(path = %entry.path().display(), "Failed to search file: {e}");
+                    }
+                    let mut matches = collector.matches;
+                    if collector.limited {
+                        truncated.store(true, Ordering::Relaxed);
+                    }
+                    if matches.is_empty() {
+                        return WalkState::Continue;
+                    }
+
+                    // Other threads may have used up the limit while this file was searched.
+                    let taken = found
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                            Some((n + matches.len()).min(self.limit))
+                        })
+                        .unwrap_or_default();
+                    let room = self.limit - taken;
+                    if room < matches.len() {
+                        truncated.store(true, Ordering::Relaxed);
+                    }
+                    matches.truncate(room);
+                    if matches.is_empty() {
+                        return WalkState::Quit;
+                    }
+
+                    files.fetch_add(1, Ordering::Relaxed);
+                    let path = entry.path().strip_prefix(&self.root).unwrap_or(entry.path());
+                    on_file(path.to_string_lossy().to_string(), matches);
+                    WalkState::Continue
+                })
+            });
+
+        (
+            files.load(Ordering::Relaxed) as u32,
+            found.load(Ordering::Relaxed) as u32,
+            truncated.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Searches a folder the user opened, sending `SearchResults` for each file with matches and
+/// `SearchFinished` at the end. Returns the id to tell the search's events apart and cancel it.
+#[tauri::command]
+#[specta::specta]
+pub fn start_search(
+    app: AppHandle,
+    directory: String,
+    query: SearchQuery,
+) -> Result<String, String> {
+    let root = dunce::canonicalize(&directory)
+        .map_err(|e| format!("Failed to resolve {}: {}", directory, e))?;
+    if !trust::is_opened(&app, &root)? {
+        return Err(format!("{} was not opened in the app", root.display()));
+    }
+    let search = Search::new(root, &query)?;
+
+    let operation = operations::begin(&app, OperationKind::Search, None)?;
+    let search_id = operation.id().to_string();
+    let cancel = operation.cancel_flag();
+
+    let id = search_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let on_file = |path, matches| {
+            let _ = SearchResults {
+                search_id: id.clone(),
+                path,
+                matches,
+            }
+            .emit(&app);
+        };
+        let (files, matches, truncated) = search.run(&cancel, &on_file);
+
+        operation.finish();
+        drop(operation);
+        tracing::debug!(root = %search.root.display(), files, matches, "Search finished");
+        let _ = SearchFinished {
+            search_id: id,
+            files,
+            matches,
+            truncated,
+            cancelled: cancel.load(Ordering::Relaxed),
+        }
+        .emit(&app);
+    });
+    Ok(search_id)
+}
+
+/// Stops a running search. Returns false when it had already finished.
+#[tauri::command]
+#[specta::specta]
+pub fn cancel_search(operations: State<'_, Operations>, search_id: String) -> bool {
+    operations::cancel_operation(operations, search_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Mutex};
+
+    use super::*;
+
+    fn query(pattern: &str) -> SearchQuery {
+        SearchQuery {
+            pattern: pattern.to_string(),
+            regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            globs: Vec::new(),
+            context_lines: 0,
+            max_results: None,
+        }
+    }
+
+    fn search(root: &Path, query: &SearchQuery) -> (Vec<(String, Vec<SearchMatch>)>, bool) {
+        let results = Mutex::new(Vec::new());
+        let (_, _, truncated) = Search::new(root.to_path_buf(), query)
+            .unwrap()
+            .run(&AtomicBool::new(false), &|path, matches| {
+                results.lock().unwrap().push((path, matches))
+            });
+        let mut results = results.into_inner().unwrap();
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        (results, truncated)
+    }
+
+    #[test]
+    fn searches_files_git_does_not_ignore() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "fn main() {\n    let a = foo(1);\n    let b = Foo(2);\n}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn é_foo() {}\n").unwrap();
+        std::fs::write(root.join("target/out.rs"), "foo\n").unwrap();
+        std::fs::write(root.join("bin.dat"), b"foo\0bar").unwrap();
+
+        let (results, truncated) = search(root, &query("foo"));
+        assert!(!truncated);
+        let paths = results
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                Path::new("src").join("lib.rs").to_str().unwrap(),
+                Path::new("src").join("main.rs").to_str().unwrap(),
+            ]
+        );
+        assert_eq!(results[0].1[0].ranges, [MatchRange { start: 9, end: 12 }]);
+        assert_eq!(results[1].1.len(), 2);
+
+        // Regex, case and context.
+        let mut query = query(r"F\w+\(");
+        query.regex = true;
+        query.case_sensitive = true;
+        query.context_lines = 1;
+        query.globs = vec!["*.rs".to_string(), "!lib.rs".to_string()];
+        let (results, _) = search(root, &query);
+        assert_eq!(results.len(), 1);
+        let found = &results[0].1[0];
+        assert_eq!(found.line, 3);
+        assert_eq!(found.before[0].text, "    let a = foo(1);");
+        assert_eq!(found.after[0].text, "}");
+
+        let mut query = self::query("let");
+        query.max_results = Some(1);
+        let (results, truncated) = search(root, &query);
+        assert!(truncated);
+        assert_eq!(results.iter().map(|(_, m)| m.len()).sum::<usize>(), 1);
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+use tauri_specta::Event;
+
+use crate::{open_with, windows::MainWindow};
+
+/// Sent to the running app when it is launched again, with what the second launch was given.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct SecondInstance {
+    pub args: Vec<String>,
+    pub cwd: String,
+    /// Project directories named by the arguments, resolved against `cwd`. Each is opened.
+    pub directories: Vec<String>,
+}
+
+/// Brings the running app to the front and forwards the second launch's arguments to it.
+pub fn handle(app: &AppHandle, args: Vec<String>, cwd: String) {
+    // The first argument is the executable.
+    let args = args.into_iter().skip(1).collect::<Vec<_>>();
+    tracing::info!(?args, %cwd, "App launched again, focusing the running instance");
+
+    // The window may not exist yet, for example after a background launch. Building it from the
+    // plugin callback can deadlock on Windows.
+    tauri::async_runtime::spawn({
+        let app = app.clone();
+        async move {
+            if let Err(e) = MainWindow::create(&app) {
+                tracing::warn!("Failed to open window: {e}");
+            }
+        }
+    });
+
+    let paths = open_with::project_paths(&args, Path::new(&cwd));
+    let directories = paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    open_with::open(app, paths);
+
+    let _ = SecondInstance {
+        args,
+        cwd,
+        directories,
+    }
+    .emit(app);
+}
@@ -2,13 +2,14 @@ use std::time::{Duration, Instant};
 
 use tauri::AppHandle;
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogResult};
-use tauri_plugin_store::StoreExt;
 use tokio::task::JoinHandle;
 
 use crate::{
     cli,
-    cli::CommandChild,
-    constants::{DEFAULT_SERVER_URL_KEY, SETTINGS_STORE, WSL_ENABLED_KEY},
+    cli::{CommandChild, SidecarExit},
+    settings,
+    supervisor::SidecarSpec,
+    tls, wsl,
 };
 
 #[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default)]
@@ -19,36 +20,16 @@ pub struct WslConfig {
 #[tauri::command]
 #[specta::specta]
 pub fn get_default_server_url(app: AppHandle) -> Result<Option<String>, String> {
-    let store = app
-        .store(SETTINGS_STORE)
-        .map_err(|e| format!("Failed to open settings store: {}", e))?;
-
-    let value = store.get(DEFAULT_SERVER_URL_KEY);
-    match value {
-        Some(v) => Ok(v.as_str().map(String::from)),
-        None => Ok(None),
-    }
+    Ok(settings::load(&app)?.default_server_url)
 }
 
 #[tauri::command]
 #[specta::specta]
 pub async fn set_default_server_url(app: AppHandle, url: Option<String>) -> Result<(), String> {
-    let store = app
-        .store(SETTINGS_STORE)
-        .map_err(|e| format!("Failed to open settings store: {}", e))?;
-
-    match url {
-        Some(u) => {
-            store.set(DEFAULT_SERVER_URL_KEY, serde_json::Value::String(u));
-        }
-        None => {
-            store.delete(DEFAULT_SERVER_URL_KEY);
-        }
-    }
-
-    store
-        .save()
-        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    settings::update(&app, |s| {
+        s.default_server_url = url;
+        Ok(())
+    })?;
 
     Ok(())
 }
@@ -56,15 +37,7 @@ pub async fn set_default_server_url(app: AppHandle, url: Option<String>) -> Resu
 #[tauri::command]
 #[specta::specta]
 pub fn get_wsl_config(_app: AppHandle) -> Result<WslConfig, String> {
-    // let store = app
-    //     .store(SETTINGS_STORE)
-    //     .map_err(|e| format!("Failed to open settings store: {}", e))?;
-
-    // let enabled = store
-    //     .get(WSL_ENABLED_KEY)
-    //     .as_ref()
-    //     .and_then(|v| v.as_bool())
-    //     .unwrap_or(false);
+    // let enabled = settings::load(&app)?.wsl_enabled;
 
     Ok(WslConfig { enabled: false })
 }
@@ -72,19 +45,78 @@ pub fn get_wsl_config(_app: AppHandle) -> Result<WslConfig, String> {
 #[tauri::command]
 #[specta::specta]
 pub fn set_wsl_config(app: AppHandle, config: WslConfig) -> Result<(), String> {
-    let store = app
-        .store(SETTINGS_STORE)
-        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+    settings::update(&app, |s| {
+        s.wsl_enabled = config.enabled;
+        Ok(())
+    })?;
 
-    store.set(WSL_ENABLED_KEY, serde_json::Value::Bool(config.enabled));
+    Ok(())
+}
 
-    store
-        .save()
-        .map_err(|e| format!("Failed to save settings: {}", e))?;
+#[tauri::command]
+#[specta::specta]
+pub fn get_wsl_distro(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(settings::load(&app)?.wsl_distro)
+}
+
+/// Sets the distro the server runs in. `None` falls back to the default WSL distro.
+#[tauri::command]
+#[specta::specta]
+pub fn set_wsl_distro(app: AppHandle, distro: Option<String>) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.wsl_distro = distro.filter(|d| !d.trim().is_empty());
+        Ok(())
+    })?;
 
     Ok(())
 }
 
+#[tauri::command]
+#[specta::specta]
+pub fn get_wsl_offline_install(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.wsl_offline_install)
+}
+
+/// Whether the CLI is copied into WSL from the app bundle rather than downloaded, for machines
+/// that can't reach opencode.ai.
+#[tauri::command]
+#[specta::specta]
+pub fn set_wsl_offline_install(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.wsl_offline_install = enabled;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_wsl_shared_state(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.wsl_shared_state)
+}
+
+/// Whether the WSL sidecar keeps its state in the app's data directory on Windows instead of
+/// inside WSL. Existing sessions are not moved, and it takes effect once the server restarts.
+#[tauri::command]
+#[specta::specta]
+pub fn set_wsl_shared_state(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.wsl_shared_state = enabled;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Arguments selecting the configured distro, to be placed before `-e` on a `wsl` invocation.
+pub fn wsl_distro_args(app: &AppHandle) -> Vec<String> {
+    match get_wsl_distro(app.clone()).ok().flatten() {
+        Some(distro) => vec!["-d".to_string(), distro],
+        None => vec![],
+    }
+}
+
 pub async fn get_saved_server_url(app: &tauri::AppHandle) -> Option<String> {
     if let Some(url) = get_default_server_url(app.clone()).ok().flatten() {
         tracing::info!(%url, "Using desktop-specific custom URL");
@@ -101,16 +133,19 @@ pub async fn get_saved_server_url(app: &tauri::AppHandle) -> Option<String> {
     None
 }
 
-pub fn spawn_local_server(
+/// Starts the sidecar described by `spec` and checks on its health. The WSL distro's address is
+/// looked up again first, since the distro gets a new one whenever WSL restarts.
+pub async fn spawn_local_server(
     app: AppHandle,
-    hostname: String,
-    port: u32,
-    password: String,
-) -> (CommandChild, HealthCheck) {
-    let (child, exit) = cli::serve(&app, &hostname, port, &password);
+    spec: &mut SidecarSpec,
+) -> Result<(CommandChild, HealthCheck, SidecarExit), String> {
+    spec.wsl_address = wsl::nat_address(&app).await;
+    let (child, exit) = cli::serve(&app, spec).await?;
+    let health_exit = exit.clone();
+    let url = spec.url();
+    let password = spec.password.clone();
 
     let health_check = HealthCheck(tokio::spawn(async move {
-        let url = format!("http://{hostname}:{port}");
         let timestamp = Instant::now();
 
         let ready = async {
@@ -125,7 +160,7 @@ pub fn spawn_local_server(
         };
 
         let terminated = async {
-            match exit.await {
+            match health_exit.await {
                 Ok(payload) => Err(format!(
                     "Sidecar terminated before becoming healthy (code={:?} signal={:?})",
                     payload.code, payload.signal
@@ -140,30 +175,68 @@ pub fn spawn_local_server(
         }
     }));
 
-    (child, health_check)
+    Ok((child, health_check, exit))
 }
 
 pub struct HealthCheck(pub JoinHandle<Result<(), String>>);
 
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerProbe {
+    Healthy,
+    /// The server answered but rejected the credentials.
+    Unauthorized,
+    Unreachable,
+}
+
 pub async fn check_health(url: &str, password: Option<&str>) -> bool {
-    let Ok(url) = reqwest::Url::parse(url) else {
-        return false;
-    };
+    probe(url, password).await == ServerProbe::Healthy
+}
+
+/// A client for requests to the server at `url`.
+pub fn client_for(url: &reqwest::Url, timeout: Duration) -> reqwest::Result<reqwest::Client> {
+    builder_for(url).timeout(timeout).build()
+}
+
+/// A client for long-lived responses from the server at `url`, which fails once nothing arrived
+/// for `idle`.
+pub fn stream_client_for(url: &reqwest::Url, idle: Duration) -> reqwest::Result<reqwest::Client> {
+    builder_for(url).read_timeout(idle).build()
+}
+
+/// A client for connections to the server at `url` that are upgraded to another protocol, like
+/// websockets, and stay open as long as either end wants.
+pub fn upgrade_client_for(url: &reqwest::Url) -> reqwest::Result<reqwest::Client> {
+    builder_for(url).build()
+}
 
-    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(7));
+fn builder_for(url: &reqwest::Url) -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder();
 
-    if url_is_localhost(&url) {
+    if url_is_localhost(url) {
         // Some environments set proxy variables (HTTP_PROXY/HTTPS_PROXY/ALL_PROXY) without
         // excluding loopback. reqwest respects these by default, which can prevent the desktop
         // app from reaching its own local sidecar server.
         builder = builder.no_proxy();
+
+        // The local sidecar serves a self-signed certificate when TLS is enabled.
+        if let Some(cert) = tls::trusted_certificate() {
+            builder = builder.add_root_certificate(cert);
+        }
     };
 
-    let Ok(client) = builder.build() else {
-        return false;
+    builder
+}
+
+pub async fn probe(url: &str, password: Option<&str>) -> ServerProbe {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return ServerProbe::Unreachable;
+    };
+    let Ok(client) = client_for(&url, Duration::from_secs(7)) else {
+        return ServerProbe::Unreachable;
     };
     let Ok(health_url) = url.join("/global/health") else {
-        return false;
+        return ServerProbe::Unreachable;
     };
 
     let mut req = client.get(health_url);
@@ -172,10 +245,45 @@ pub async fn check_health(url: &str, password: Option<&str>) -> bool {
         req = req.basic_auth("opencode", Some(password));
     }
 
-    req.send()
+    match req.send().await {
+        Ok(r) if r.status().is_success() => ServerProbe::Healthy,
+        Ok(r) if r.status() == reqwest::StatusCode::UNAUTHORIZED => ServerProbe::Unauthorized,
+        _ => ServerProbe::Unreachable,
+    }
+}
+
+/// Ids of the sessions the server at `url` is still working on.
+pub async fn busy_sessions(url: &str, password: Option<&str>) -> Result<Vec<String>, String> {
+    let url = reqwest::Url::parse(url).map_err(|e| format!("Invalid server url: {}", e))?;
+    let client = client_for(&url, Duration::from_secs(5))
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let status_url = url
+        .join("/session/status")
+        .map_err(|e| format!("Invalid server url: {}", e))?;
+
+    let mut req = client.get(status_url);
+    if let Some(password) = password {
+        req = req.basic_auth("opencode", Some(password));
+    }
+    let body = req
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to fetch session status: {}", e))?
+        .text()
         .await
-        .map(|r| r.status().is_success())
-        .unwrap_or(false)
+        .map_err(|e| format!("Failed to read session status: {}", e))?;
+
+    let statuses: std::collections::HashMap<String, serde_json::Value> =
+        serde_json::from_str(&body)
+            .map_err(|e| format!("Failed to parse session status: {}", e))?;
+    let mut busy = statuses
+        .into_iter()
+        .filter(|(_, status)| status.get("type").and_then(|t| t.as_str()) != Some("idle"))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    busy.sort();
+    Ok(busy)
 }
 
 pub fn is_localhost_url(url: &str) -> bool {
@@ -183,18 +291,20 @@ pub fn is_localhost_url(url: &str) -> bool {
 }
 
 fn url_is_localhost(url: &reqwest::Url) -> bool {
-    url.host_str().is_some_and(|host| {
-        host.eq_ignore_ascii_case("localhost")
-            || host
-                .parse::<std::net::IpAddr>()
-                .is_ok_and(|ip| ip.is_loopback())
-    })
+    url.host_str().is_some_and(is_loopback_hostname)
+}
+
+pub fn is_loopback_hostname(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
 }
 
 /// Converts a bind address hostname to a valid URL hostname for connection.
 /// - `0.0.0.0` and `::` are wildcard bind addresses, not valid connect targets
 /// - IPv6 addresses need brackets in URLs (e.g., `::1` -> `[::1]`)
-fn normalize_hostname_for_url(hostname: &str) -> String {
+pub fn normalize_hostname_for_url(hostname: &str) -> String {
     // Wildcard bind addresses -> localhost equivalents
     if hostname == "0.0.0.0" {
         return "127.0.0.1".to_string();
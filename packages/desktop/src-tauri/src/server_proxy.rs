@@ -0,0 +1,532 @@
+use std::time::Duration;
+
+use reqwest::Method;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    ServerState, events,
+    instances::Instances,
+    response_cache::{CacheKey, ResponseCache},
+    server, supervisor,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long to wait for the server to come up before giving up on a request.
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a url from `open_server_stream` waits for the webview to connect.
+const STREAM_ACCEPT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+/// Request headers passed on to the server for streams, which event streams and websockets need.
+const STREAM_HEADERS: [&str; 9] = [
+    "accept",
+    "cache-control",
+    "connection",
+    "last-event-id",
+    "sec-websocket-extensions",
+    "sec-websocket-key",
+    "sec-websocket-protocol",
+    "sec-websocket-version",
+    "upgrade",
+];
+
+/// Paths the webview may call through `server_request`, each with everything below it.
+/// Provider credentials are left to their own commands.
+const ALLOWED_PATHS: [&str; 22] = [
+    "/agent",
+    "/command",
+    "/config",
+    "/experimental",
+    "/file",
+    "/find",
+    "/formatter",
+    "/global/config",
+    "/global/dispose",
+    "/global/health",
+    "/instance",
+    "/lsp",
+    "/mcp",
+    "/path",
+    "/permission",
+    "/project",
+    "/provider",
+    "/pty",
+    "/question",
+    "/session",
+    "/skill",
+    "/vcs",
+];
+/// Paths the webview may only read. Config is written through `patch_config`, which checks it
+/// against the schema first.
+const READ_ONLY_PATHS: [&str; 2] = ["/config", "/global/config"];
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ServerRequestError {
+    /// The path or method is not on the allow-list.
+    NotAllowed { path: String },
+    /// No server is connected yet.
+    NoServer,
+    /// The server could not be reached, or did not answer in time.
+    Unreachable { message: String, timed_out: bool },
+    /// The server answered with an error status.
+    Status { status: u32, body: String },
+}
+
+/// Whether `route` is `prefix` or below it.
+fn under(route: &str, prefix: &str) -> bool {
+    route == prefix
+        || route
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The path without its query, if it may be proxied.
+fn allowed(path: &str) -> Option<&str> {
+    let route = path.split(['?', '#']).next().unwrap_or_default();
+    if !route.starts_with('/')
+        || route.starts_with("//")
+        || route.contains('\\')
+        || route
+            .split('/')
+            .any(|segment| segment == ".." || segment == ".")
+    {
+        return None;
+    }
+    let route = route.trim_end_matches('/');
+    ALLOWED_PATHS
+        .iter()
+        .any(|prefix| under(route, prefix))
+        .then_some(route)
+}
+
+fn writable(route: &str) -> bool {
+    !READ_ONLY_PATHS.iter().any(|prefix| under(route, prefix))
+}
+
+/// Whether `route` streams, so it is opened with `open_server_stream` rather than requested: the
+/// event streams and terminal connections.
+fn streamable(route: &str) -> bool {
+    matches!(route, "/event" | "/global/event")
+        || route
+            .strip_prefix("/pty/")
+            .and_then(|rest| rest.strip_suffix("/connect"))
+            .is_some_and(|id| !id.is_empty() && !id.contains('/'))
+}
+
+fn method(method: &str) -> Option<Method> {
+    match method.to_ascii_uppercase().as_str() {
+        "GET" => Some(Method::GET),
+        "POST" => Some(Method::POST),
+        "PUT" => Some(Method::PUT),
+        "PATCH" => Some(Method::PATCH),
+        "DELETE" => Some(Method::DELETE),
+        _ => None,
+    }
+}
+
+/// The url and credentials of the server the app is connected to: the managed sidecar, or the
+/// remote or external server it attached to at startup. `server` picks the project server at
+/// that url instead.
+async fn target(app: &AppHandle, server: Option<&str>) -> Option<(String, String, Option<String>)> {
+    if let Some(url) = server {
+        let password = app.state::<Instances>().password(url)?;
+        return Some((url.to_string(), "opencode".to_string(), Some(password)));
+    }
+    if let Some(spec) = supervisor::current_spec(app) {
+        return Some((spec.url(), "opencode".to_string(), Some(spec.password)));
+    }
+    let status = app.state::<ServerState>().status.clone();
+    let ready = tokio::time::timeout(READY_TIMEOUT, status)
+        .await
+        .ok()?
+        .ok()?
+        .ok()?;
+    let username = ready.username.unwrap_or_else(|| "opencode".to_string());
+    Some((ready.url, username, ready.password))
+}
+
+fn unreachable(e: reqwest::Error) -> ServerRequestError {
+    ServerRequestError::Unreachable {
+        message: e.to_string(),
+        timed_out: e.is_timeout(),
+    }
+}
+
+/// Calls the connected server, or the project server at `server`, on behalf of the webview,
+/// adding its credentials. `directory` scopes the request to a project. Returns the JSON response, or the raw text when the server
+/// did not answer with JSON. GET responses for lists and trees are cached until a server event
+/// or a write outdates them.
+#[tauri::command]
+#[specta::specta]
+pub async fn server_request(
+    app: AppHandle,
+    method: String,
+    path: String,
+    body: Option<serde_json::Value>,
+    directory: Option<String>,
+    server: Option<String>,
+) -> Result<serde_json::Value, ServerRequestError> {
+    let not_allowed = || ServerRequestError::NotAllowed { path: path.clone() };
+    let http_method = self::method(&method).ok_or_else(not_allowed)?;
+    let route = allowed(&path).ok_or_else(not_allowed)?;
+    if http_method != Method::GET && !writable(route) {
+        return Err(not_allowed());
+    }
+
+    let (base, username, password) = target(&app, server.as_deref())
+        .await
+        .ok_or(ServerRequestError::NoServer)?;
+    let mut url = reqwest::Url::parse(&base)
+        .and_then(|url| url.join(&path))
+        .map_err(|e| ServerRequestError::Unreachable {
+            message: format!("Invalid server url: {}", e),
+            timed_out: false,
+        })?;
+    // The url parser resolves encoded dot segments, so check what it made of the path too.
+    let route = allowed(url.path()).ok_or_else(not_allowed)?.to_string();
+    if http_method != Method::GET && !writable(&route) {
+        return Err(not_allowed());
+    }
+
+    let cache = app.state::<ResponseCache>();
+    let key = (http_method == Method::GET
+        && ResponseCache::cacheable(&route)
+        && events::is_connected(&app))
+    .then(|| CacheKey {
+        server: base.clone(),
+        directory: directory.clone(),
+        path: path.clone(),
+    });
+    if let Some(value) = key.as_ref().and_then(|key| cache.get(key)) {
+        return Ok(value);
+    }
+    let generation = cache.generation();
+
+    if let Some(directory) = &directory {
+        url.query_pairs_mut().append_pair("directory", directory);
+    }
+    let client = server::client_for(&url, REQUEST_TIMEOUT).map_err(unreachable)?;
+
+    let read_only = http_method == Method::GET;
+    let mut req = client.request(http_method, url);
+    if let Some(password) = &password {
+        req = req.basic_auth(&username, Some(password));
+    }
+    if let Some(body) = body {
+        req = req
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+    }
+
+    let response = req.send().await.map_err(unreachable)?;
+    let status = response.status();
+    let text = response.text().await.map_err(unreachable)?;
+    tracing::debug!(%method, %path, %status, "Proxied server request");
+    if !status.is_success() {
+        return Err(ServerRequestError::Status {
+            status: status.as_u16() as u32,
+            body: text,
+        });
+    }
+    if !read_only {
+        cache.on_request(&route);
+    }
+
+    let value = if text.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+    };
+    if let Some(key) = key {
+        cache.insert(key, &route, value.clone(), generation);
+    }
+    Ok(value)
+}
+
+/// The method, request target and headers of an HTTP request head, once all of it arrived.
+fn parse_head(head: &[u8]) -> Option<(String, String, Vec<(String, String)>)> {
+    let head = std::str::from_utf8(head).ok()?;
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?.to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    Some((method, target, headers))
+}
+
+/// Compares in time that doesn't depend on where the inputs differ, so a stream secret can't be
+/// guessed a byte at a time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Reads a request head, returning it with any bytes that followed it.
+async fn read_head(client: &mut TcpStream) -> Result<(Vec<u8>, usize), String> {
+    let mut head = Vec::new();
+    loop {
+        let mut buffer = [0; 1024];
+        let read = client
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read the stream request: {}", e))?;
+        if read == 0 || head.len() + read > MAX_REQUEST_HEAD {
+            return Err("Invalid stream request".to_string());
+        }
+        head.extend_from_slice(&buffer[..read]);
+        if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok((head, end + 4));
+        }
+    }
+}
+
+/// Forwards the first connection to `listener` that asks for `/<secret>` to `url`, with the
+/// server's credentials added, and answers it with the server's response: a websocket once
+/// upgraded, or the streamed body. Other local processes can reach the port too, so connections
+/// without the secret are turned away. The secret is never passed on, since `url` is built here.
+async fn forward_stream(
+    listener: TcpListener,
+    secret: String,
+    url: reqwest::Url,
+    username: String,
+    password: Option<String>,
+) -> Result<(), String> {
+    let deadline = tokio::time::Instant::now() + STREAM_ACCEPT_TIMEOUT;
+    let expected = format!("/{secret}");
+    let (mut client, head, end, headers) = loop {
+        let (mut client, _) = tokio::time::timeout_at(deadline, listener.accept())
+            .await
+            .map_err(|_| "Nothing connected to the server stream".to_string())?
+            .map_err(|e| format!("Failed to accept the server stream: {}", e))?;
+        let (head, end) = match tokio::time::timeout_at(deadline, read_head(&mut client)).await {
+            Ok(Ok(head)) => head,
+            Ok(Err(e)) => {
+                tracing::debug!("{e}");
+                continue;
+            }
+            Err(_) => return Err("Nothing connected to the server stream".to_string()),
+        };
+        let Some((method, target, headers)) = parse_head(&head[..end]) else {
+            tracing::debug!("Invalid stream request");
+            continue;
+        };
+        if !constant_time_eq(target.as_bytes(), expected.as_bytes()) {
+            tracing::warn!("Rejected a server stream connection without its secret");
+            let _ = client
+                .write_all(b"HTTP/1.1 403 Forbidden\r\nConnection: close\r\n\r\n")
+                .await;
+            continue;
+        }
+        if method != "GET" {
+            return Err(format!("Streams can't be opened with {method}"));
+        }
+        break (client, head, end, headers);
+    };
+    drop(listener);
+
+    let http = server::upgrade_client_for(&url)
+        .map_err(|e| format!("Failed to connect to the server: {}", e))?;
+    let mut req = http.get(url);
+    for (name, value) in &headers {
+        if STREAM_HEADERS.contains(&name.as_str()) {
+            req = req.header(name, value);
+        }
+    }
+    if let Some(password) = &password {
+        req = req.basic_auth(&username, Some(password));
+    }
+    let response = req
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the server: {}", e))?;
+
+    let status = response.status();
+    let upgrade = status == reqwest::StatusCode::SWITCHING_PROTOCOLS;
+    let mut reply = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default()
+    );
+    for (name, value) in response.headers() {
+        let hop = matches!(
+            name.as_str(),
+            "connection" | "content-length" | "keep-alive" | "transfer-encoding"
+        );
+        if !hop || (upgrade && name == "connection") {
+            reply.push_str(&format!(
+                "{}: {}\r\n",
+                name,
+                value.to_str().unwrap_or_default()
+            ));
+        }
+    }
+    // The body ends when the connection closes, since it is passed on as it arrives.
+    if !upgrade {
+        reply.push_str("Connection: close\r\n");
+    }
+    reply.push_str("\r\n");
+    client
+        .write_all(reply.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to answer the stream request: {}", e))?;
+
+    if upgrade {
+        let mut server = response
+            .upgrade()
+            .await
+            .map_err(|e| format!("Failed to upgrade the server stream: {}", e))?;
+        server
+            .write_all(&head[end..])
+            .await
+            .map_err(|e| format!("Failed to forward the stream: {}", e))?;
+        let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+        return Ok(());
+    }
+
+    let mut response = response;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read the server stream: {}", e))?
+    {
+        if client.write_all(&chunk).await.is_err() {
+            break;
+        }
+    }
+    let _ = client.shutdown().await;
+    Ok(())
+}
+
+/// Opens a one-time loopback url for a streaming request, which `server_request` can't answer:
+/// an event stream, or a terminal connection as a websocket. The first connection to it within a
+/// few seconds is forwarded to the connected server, or the project server at `server`, with its
+/// credentials added so they never reach the webview. The url carries a random secret that the
+/// connection must present, and no other connection is accepted.
+#[tauri::command]
+#[specta::specta]
+pub async fn open_server_stream(
+    app: AppHandle,
+    path: String,
+    directory: Option<String>,
+    server: Option<String>,
+) -> Result<String, ServerRequestError> {
+    let not_allowed = || ServerRequestError::NotAllowed { path: path.clone() };
+    let (base, username, password) = target(&app, server.as_deref())
+        .await
+        .ok_or(ServerRequestError::NoServer)?;
+    let mut url = reqwest::Url::parse(&base)
+        .and_then(|url| url.join(&path))
+        .map_err(|e| ServerRequestError::Unreachable {
+            message: format!("Invalid server url: {}", e),
+            timed_out: false,
+        })?;
+    // Checked after parsing, which resolves dot segments, encoded ones too.
+    if !streamable(url.path()) {
+        return Err(not_allowed());
+    }
+    if let Some(directory) = &directory {
+        url.query_pairs_mut().append_pair("directory", directory);
+    }
+
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .map_err(|e| ServerRequestError::Unreachable {
+                message: format!("Failed to listen for the server stream: {}", e),
+                timed_out: false,
+            })?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| ServerRequestError::Unreachable {
+            message: format!("Failed to listen for the server stream: {}", e),
+            timed_out: false,
+        })?
+        .port();
+
+    let secret = uuid::Uuid::new_v4().simple().to_string();
+    let stream = format!("http://127.0.0.1:{port}/{secret}");
+    tokio::spawn(async move {
+        if let Err(e) = forward_stream(listener, secret, url, username, password).await {
+            tracing::debug!("Server stream ended: {e}");
+        }
+    });
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_allowed_paths_are_proxied() {
+        assert_eq!(allowed("/session"), Some("/session"));
+        assert_eq!(
+            allowed("/session/abc/message?limit=20"),
+            Some("/session/abc/message")
+        );
+        assert_eq!(allowed("/global/health"), Some("/global/health"));
+        assert_eq!(allowed("/file/"), Some("/file"));
+
+        assert_eq!(allowed("/sessions"), None);
+        assert_eq!(allowed("/auth/anthropic"), None);
+        assert_eq!(allowed("/global/event"), None);
+        assert_eq!(allowed("/session/../auth/anthropic"), None);
+        assert_eq!(allowed("//evil.example/session"), None);
+        assert_eq!(allowed("https://evil.example/session"), None);
+        assert_eq!(allowed("session"), None);
+
+        let url = reqwest::Url::parse("http://127.0.0.1:4096")
+            .and_then(|url| url.join("/session/%2e%2e/auth/anthropic"))
+            .unwrap();
+        assert_eq!(allowed(url.path()), None);
+    }
+
+    #[test]
+    fn config_is_read_only() {
+        assert!(!writable("/config"));
+        assert!(!writable("/global/config"));
+        assert!(writable("/config-like"));
+        assert!(writable("/session/abc"));
+    }
+
+    #[test]
+    fn only_streams_are_opened_as_streams() {
+        assert!(streamable("/global/event"));
+        assert!(streamable("/event"));
+        assert!(streamable("/pty/pty_1/connect"));
+
+        assert!(!streamable("/pty/connect"));
+        assert!(!streamable("/pty/a/b/connect"));
+        assert!(!streamable("/session"));
+    }
+
+    #[test]
+    fn parses_request_heads() {
+        let (method, target, headers) = parse_head(
+            b"GET /secret HTTP/1.1\r\nHost: 127.0.0.1\r\nSec-WebSocket-Key: abc==\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(method, "GET");
+        assert_eq!(target, "/secret");
+        assert!(headers.contains(&("sec-websocket-key".to_string(), "abc==".to_string())));
+    }
+
+    #[test]
+    fn stream_secrets_must_match_exactly() {
+        assert!(constant_time_eq(b"/abc", b"/abc"));
+        assert!(!constant_time_eq(b"/abd", b"/abc"));
+        assert!(!constant_time_eq(b"/ab", b"/abc"));
+        assert!(!constant_time_eq(b"/", b"/abc"));
+    }
+
+    #[test]
+    fn parses_methods() {
+        assert_eq!(method("get"), Some(Method::GET));
+        assert_eq!(method("DELETE"), Some(Method::DELETE));
+        assert_eq!(method("CONNECT"), None);
+    }
+}
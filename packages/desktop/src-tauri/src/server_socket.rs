@@ -0,0 +1,174 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tauri::AppHandle;
+#[cfg(unix)]
+use tauri::Manager;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+
+use crate::{cli, discovery, settings};
+
+#[cfg(unix)]
+const SOCKET_FILE: &str = "opencode.sock";
+// `sun_path` holds 104 bytes on macOS, including the terminating nul.
+#[cfg(unix)]
+const MAX_SOCKET_PATH: usize = 103;
+
+/// Where the local server should listen instead of a TCP port, if socket mode is on: a unix
+/// socket in a directory only this user can open, or a named pipe on Windows.
+pub fn address(app: &AppHandle) -> Option<PathBuf> {
+    if !get_server_socket(app.clone()).unwrap_or(false) {
+        return None;
+    }
+    // A WSL sidecar can't listen on a Windows pipe, and other machines need a TCP port.
+    if cli::is_wsl_enabled(app) || discovery::is_enabled(app) {
+        tracing::info!("Socket mode does not apply to WSL or LAN servers, using a TCP port");
+        return None;
+    }
+
+    socket_path(app)
+        .inspect_err(|e| tracing::warn!("{e}, using a TCP port"))
+        .ok()
+}
+
+#[cfg(unix)]
+fn socket_path(app: &AppHandle) -> Result<PathBuf, String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("server");
+    std::fs::create_dir_all(&dir)
+        .and_then(|()| std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)))
+        .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+
+    let path = dir.join(SOCKET_FILE);
+    if path.as_os_str().len() > MAX_SOCKET_PATH {
+        return Err(format!("Socket path {} is too long", path.display()));
+    }
+    Ok(path)
+}
+
+/// A pipe name nobody can guess, so no other user can claim it first.
+#[cfg(windows)]
+fn socket_path(_app: &AppHandle) -> Result<PathBuf, String> {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    Ok(PathBuf::from(format!(r"\\.\pipe\opencode-desktop-{id}")))
+}
+
+/// Removes a socket left behind by a sidecar that didn't exit cleanly, which would keep the next
+/// one from listening.
+#[cfg(unix)]
+pub fn remove_stale(socket: &Path) {
+    use std::os::unix::fs::FileTypeExt;
+
+    if std::fs::symlink_metadata(socket).is_ok_and(|meta| meta.file_type().is_socket()) {
+        let _ = std::fs::remove_file(socket);
+    }
+}
+
+/// Pipes close with the process that opened them.
+#[cfg(windows)]
+pub fn remove_stale(_socket: &Path) {}
+
+/// Forwards connections on a loopback port to the server's socket, for the webview and the HTTP
+/// clients that only speak TCP. The OS picks the port, so it never conflicts with another
+/// server, and requests still need the server password.
+pub async fn bridge(socket: PathBuf) -> Result<u32, String> {
+    tracing::info!(socket = %socket.display(), "Bridging server socket");
+    forward(move || {
+        let socket = socket.clone();
+        async move { connect(&socket).await }
+    })
+    .await
+}
+
+/// Listens on a free loopback port and pipes every connection to what `connect` opens.
+pub async fn forward<C, F, S>(connect: C) -> Result<u32, String>
+where
+    C: Fn() -> F + Send + 'static,
+    F: Future<Output = std::io::Result<S>> + Send + 'static,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let listener = TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .map_err(|e| format!("Failed to listen for the server bridge: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to listen for the server bridge: {}", e))?
+        .port() as u32;
+
+    tracing::info!(port, "Listening for the server bridge");
+    tokio::spawn(async move {
+        loop {
+            let mut client = match listener.accept().await {
+                Ok((client, _)) => client,
+                Err(e) => {
+                    tracing::warn!(%e, "Failed to accept server bridge connection");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let server = connect();
+            tokio::spawn(async move {
+                match server.await {
+                    Ok(mut server) => {
+                        let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+                    }
+                    Err(e) => tracing::debug!(%e, "Server is not accepting bridged connections"),
+                }
+            });
+        }
+    });
+
+    Ok(port)
+}
+
+#[cfg(unix)]
+async fn connect(socket: &Path) -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(socket).await
+}
+
+#[cfg(windows)]
+async fn connect(pipe: &Path) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    // Every instance of the pipe is taken until the server opens another one.
+    const ERROR_PIPE_BUSY: i32 = 231;
+    const BUSY_RETRIES: u32 = 50;
+
+    let mut retries = 0;
+    loop {
+        match ClientOptions::new().open(pipe) {
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && retries < BUSY_RETRIES => {
+                retries += 1;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_server_socket(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.server_socket)
+}
+
+/// Whether the local server listens on a unix socket (a named pipe on Windows) instead of a TCP
+/// port. Takes effect the next time the server starts.
+#[tauri::command]
+#[specta::specta]
+pub fn set_server_socket(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.server_socket = enabled;
+        Ok(())
+    })?;
+
+    Ok(())
+}
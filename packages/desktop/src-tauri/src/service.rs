@@ -0,0 +1,727 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+#[cfg(windows)]
+use crate::{autostart, orphans, port as ports, server};
+use crate::{cli, credentials, keychain, settings};
+
+pub const DEFAULT_SERVICE_PORT: u32 = 4096;
+const SERVICE_HOSTNAME: &str = "127.0.0.1";
+#[cfg(target_os = "linux")]
+const UNIT_NAME: &str = "opencode-server.service";
+
+/// The persistent server, as far as the OS service manager knows about it.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct ServiceStatus {
+    /// False where no service manager is supported.
+    pub supported: bool,
+    pub installed: bool,
+    pub running: bool,
+    pub url: String,
+    /// The launchd agent or systemd unit file.
+    pub definition_path: Option<String>,
+}
+
+/// How the service runs the server.
+struct ServiceCommand {
+    program: PathBuf,
+    args: Vec<String>,
+    env: Vec<(&'static str, String)>,
+}
+
+fn port(app: &AppHandle) -> u32 {
+    settings::load(app)
+        .ok()
+        .and_then(|s| s.server_service_port)
+        .unwrap_or(DEFAULT_SERVICE_PORT)
+}
+
+fn url(app: &AppHandle) -> String {
+    format!("http://{SERVICE_HOSTNAME}:{}", port(app))
+}
+
+/// The installed CLI when there is one, since it outlives app updates, otherwise the sidecar.
+fn program(app: &AppHandle) -> PathBuf {
+    cli::get_cli_install_path()
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| cli::get_sidecar_path(app))
+}
+
+fn service_command(app: &AppHandle) -> Result<ServiceCommand, String> {
+    let mut env = vec![
+        ("OPENCODE_SERVER_USERNAME", "opencode".to_string()),
+        ("OPENCODE_SERVER_PASSWORD", keychain::server_password()),
+        ("OPENCODE_CLIENT", "desktop".to_string()),
+        (
+            "OPENCODE_SERVER_TOKENS_FILE",
+            credentials::tokens_file(app)?.display().to_string(),
+        ),
+    ];
+    if let Ok(path) = std::env::var("PATH") {
+        env.push(("PATH", path));
+    }
+
+    Ok(ServiceCommand {
+        program: program(app),
+        args: vec![
+            "serve".to_string(),
+            "--hostname".to_string(),
+            SERVICE_HOSTNAME.to_string(),
+            "--port".to_string(),
+            port(app).to_string(),
+        ],
+        env,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_log_dir()
+        .map(|dir| dir.join("server-service.log"))
+        .map_err(|e| format!("Failed to resolve the log directory: {}", e))
+}
+
+/// Writes a file only the user can read, since it holds the server password. It is created
+/// private, and an existing one is restricted before the new contents go in. On Windows the app
+/// data directory already is private.
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict {}: {}", path.display(), e))?;
+    }
+    file.write_all(contents.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn remove(path: &Path) -> Result<(), String> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn run(program: &str, args: &[&str]) -> Result<std::process::Output, String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {program}: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(format!("{program} {} failed: {stderr}", args.join(" ")));
+    }
+    Ok(output)
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn launchd_plist(label: &str, command: &ServiceCommand, log: &Path) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    };
+    let string = |s: &str| format!("        <string>{}</string>\n", escape(s));
+
+    let mut arguments = string(&command.program.display().to_string());
+    for arg in &command.args {
+        arguments.push_str(&string(arg));
+    }
+    let env = command
+        .env
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "        <key>{}</key>\n        <string>{}</string>\n",
+                escape(key),
+                escape(value)
+            )
+        })
+        .collect::<String>();
+    let log = escape(&log.display().to_string());
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>EnvironmentVariables</key>
+    <dict>
+{env}    </dict>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        escape(label)
+    )
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn systemd_unit(command: &ServiceCommand, env_file: &Path) -> String {
+    // Quoting as systemd splits `ExecStart`, with `%` escaped from specifier expansion.
+    let quote = |s: &str| {
+        format!(
+            "\"{}\"",
+            s.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('%', "%%")
+        )
+    };
+    let exec = std::iter::once(command.program.display().to_string())
+        .chain(command.args.iter().cloned())
+        .map(|arg| quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        "[Unit]\nDescription=OpenCode server\nAfter=network.target\n\n[Service]\nExecStart={exec}\nEnvironmentFile={}\nRestart=on-failure\nRestartSec=5\n\n[Install]\nWantedBy=default.target\n",
+        quote(&env_file.display().to_string())
+    )
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn env_file_contents(command: &ServiceCommand) -> String {
+    command
+        .env
+        .iter()
+        .map(|(key, value)| format!("{key}={}\n", value.replace('\n', " ")))
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn label(app: &AppHandle) -> String {
+    format!("{}.server", app.config().identifier)
+}
+
+#[cfg(target_os = "macos")]
+fn definition_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Failed to resolve the home directory")?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", label(app))))
+}
+
+#[cfg(target_os = "macos")]
+fn definition(app: &AppHandle) -> Result<String, String> {
+    Ok(launchd_plist(
+        &label(app),
+        &service_command(app)?,
+        &log_path(app)?,
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn install(app: &AppHandle) -> Result<(), String> {
+    let path = definition_path(app)?;
+    // Reloading picks up a changed definition.
+    let _ = run("launchctl", &["unload", &path.display().to_string()]);
+    write_private(&path, &definition(app)?)?;
+    start(app)
+}
+
+#[cfg(target_os = "macos")]
+fn uninstall(app: &AppHandle) -> Result<(), String> {
+    let path = definition_path(app)?;
+    if path.exists() {
+        let _ = stop(app);
+    }
+    remove(&path)
+}
+
+#[cfg(target_os = "macos")]
+fn start(app: &AppHandle) -> Result<(), String> {
+    run(
+        "launchctl",
+        &["load", &definition_path(app)?.display().to_string()],
+    )
+    .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+fn stop(app: &AppHandle) -> Result<(), String> {
+    run(
+        "launchctl",
+        &["unload", &definition_path(app)?.display().to_string()],
+    )
+    .map(|_| ())
+}
+
+#[cfg(target_os = "macos")]
+fn is_running(app: &AppHandle) -> bool {
+    run("launchctl", &["list", &label(app)])
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("\"PID\" = "))
+}
+
+#[cfg(target_os = "linux")]
+fn definition_path(_app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = dirs::config_dir().ok_or("Failed to resolve the config directory")?;
+    Ok(dir.join("systemd/user").join(UNIT_NAME))
+}
+
+#[cfg(target_os = "linux")]
+fn env_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join("server-service.env"))
+        .map_err(|e| format!("Failed to resolve the app data directory: {}", e))
+}
+
+#[cfg(target_os = "linux")]
+fn definition(app: &AppHandle) -> Result<String, String> {
+    Ok(systemd_unit(&service_command(app)?, &env_file_path(app)?))
+}
+
+#[cfg(target_os = "linux")]
+fn systemctl(args: &[&str]) -> Result<std::process::Output, String> {
+    let args = std::iter::once("--user")
+        .chain(args.iter().copied())
+        .collect::<Vec<_>>();
+    run("systemctl", &args)
+}
+
+#[cfg(target_os = "linux")]
+fn install(app: &AppHandle) -> Result<(), String> {
+    write_private(
+        &env_file_path(app)?,
+        &env_file_contents(&service_command(app)?),
+    )?;
+    write_private(&definition_path(app)?, &definition(app)?)?;
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", UNIT_NAME])?;
+    systemctl(&["restart", UNIT_NAME]).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn uninstall(app: &AppHandle) -> Result<(), String> {
+    let path = definition_path(app)?;
+    if path.exists() {
+        let _ = systemctl(&["disable", "--now", UNIT_NAME]);
+    }
+    remove(&path)?;
+    remove(&env_file_path(app)?)?;
+    systemctl(&["daemon-reload"]).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn start(_app: &AppHandle) -> Result<(), String> {
+    systemctl(&["start", UNIT_NAME]).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn stop(_app: &AppHandle) -> Result<(), String> {
+    systemctl(&["stop", UNIT_NAME]).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(_app: &AppHandle) -> bool {
+    systemctl(&["is-active", "--quiet", UNIT_NAME]).is_ok()
+}
+
+#[cfg(windows)]
+const RUN_VALUE: &str = "OpenCode Server";
+
+#[cfg(windows)]
+fn service_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to resolve the app data directory: {}", e))
+}
+
+/// The batch script running the server in a loop.
+#[cfg(windows)]
+fn definition_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(service_dir(app)?.join("server-service.cmd"))
+}
+
+/// Starts the script without a console window.
+#[cfg(windows)]
+fn launcher_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(service_dir(app)?.join("server-service.vbs"))
+}
+
+/// Left behind by `stop` so the script's loop ends instead of restarting the server.
+#[cfg(windows)]
+fn stop_file(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(service_dir(app)?.join("server-service.stop"))
+}
+
+/// Where the script records the pid of the server it started, for `stop`.
+#[cfg(windows)]
+fn pid_file(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(service_dir(app)?.join("server-service.pid"))
+}
+
+/// Where the WSL server records its pid, inside the distro.
+#[cfg(any(windows, test))]
+const WSL_PID_FILE: &str = "$HOME/.opencode/server-service.pid";
+
+#[cfg(any(windows, test))]
+fn posix_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// The line starting the server in WSL, where the Windows environment is not inherited.
+#[cfg(any(windows, test))]
+fn wsl_command_line(command: &ServiceCommand, distro_args: &[String]) -> String {
+    let env = command
+        .env
+        .iter()
+        .filter(|(key, _)| !matches!(*key, "PATH" | "OPENCODE_SERVER_TOKENS_FILE"))
+        .map(|(key, value)| format!("{key}={}", posix_quote(value)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // `exec` keeps the shell's pid, so the one recorded is the server's.
+    let line = format!(
+        "echo $$ > {WSL_PID_FILE}; {env} exec $HOME/.opencode/bin/opencode {}",
+        command.args.join(" ")
+    );
+    let distro = distro_args
+        .iter()
+        .map(|arg| format!("\"{arg}\" "))
+        .collect::<String>();
+    format!("wsl {distro}-e bash -lc \"{line}\"")
+}
+
+/// A batch script that reruns the server whenever it exits, until the stop file appears. The
+/// server's pid is written to `pid_file`, or to [`WSL_PID_FILE`] in WSL.
+#[cfg(any(windows, test))]
+fn batch_script(
+    command: &ServiceCommand,
+    wsl: Option<&[String]>,
+    stop_file: &Path,
+    pid_file: &Path,
+) -> String {
+    // `%` starts a variable reference in batch files.
+    let escape = |s: &str| s.replace('%', "%%");
+    let ps_quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+
+    let mut script = "@echo off\r\n".to_string();
+    let run = match wsl {
+        Some(distro_args) => wsl_command_line(command, distro_args),
+        None => {
+            for (key, value) in &command.env {
+                script.push_str(&format!("set \"{key}={}\"\r\n", escape(value)));
+            }
+            // Batch files can't learn the pid of what they start, PowerShell can.
+            format!(
+                "powershell -NoProfile -NonInteractive -Command \"$p = Start-Process -FilePath {} -ArgumentList {} -NoNewWindow -PassThru; Set-Content -LiteralPath {} $p.Id; $p.WaitForExit()\"",
+                ps_quote(&command.program.display().to_string()),
+                ps_quote(&command.args.join(" ")),
+                ps_quote(&pid_file.display().to_string()),
+            )
+        }
+    };
+    let stop_file = escape(&stop_file.display().to_string());
+
+    script.push_str(&format!(
+        ":loop\r\nif exist \"{stop_file}\" exit /b 0\r\n{}\r\nping -n 6 127.0.0.1 >nul\r\ngoto loop\r\n",
+        escape(&run)
+    ));
+    script
+}
+
+#[cfg(windows)]
+fn definition(app: &AppHandle) -> Result<String, String> {
+    let command = service_command(app)?;
+    let wsl = cli::is_wsl_enabled(app).then(|| server::wsl_distro_args(app));
+    Ok(batch_script(
+        &command,
+        wsl.as_deref(),
+        &stop_file(app)?,
+        &pid_file(app)?,
+    ))
+}
+
+#[cfg(windows)]
+fn run_value(app: &AppHandle) -> Result<String, String> {
+    Ok(format!("wscript.exe \"{}\"", launcher_path(app)?.display()))
+}
+
+#[cfg(windows)]
+fn install(app: &AppHandle) -> Result<(), String> {
+    let _ = stop(app);
+    write_private(&definition_path(app)?, &definition(app)?)?;
+    write_private(
+        &launcher_path(app)?,
+        &format!(
+            "CreateObject(\"WScript.Shell\").Run \"\"\"{}\"\"\", 0, False\r\n",
+            definition_path(app)?.display()
+        ),
+    )?;
+
+    // A login item rather than a scheduled task, since logon tasks need admin rights.
+    let output = autostart::reg(&[
+        "add",
+        autostart::RUN_KEY,
+        "/v",
+        RUN_VALUE,
+        "/t",
+        "REG_SZ",
+        "/d",
+        &run_value(app)?,
+        "/f",
+    ])?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    start(app)
+}
+
+#[cfg(windows)]
+fn uninstall(app: &AppHandle) -> Result<(), String> {
+    let _ = stop(app);
+    let _ = autostart::reg(&["delete", autostart::RUN_KEY, "/v", RUN_VALUE, "/f"]);
+    remove(&definition_path(app)?)?;
+    remove(&launcher_path(app)?)?;
+    remove(&pid_file(app)?)?;
+    remove(&stop_file(app)?)
+}
+
+#[cfg(windows)]
+fn start(app: &AppHandle) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+    remove(&stop_file(app)?)?;
+    std::process::Command::new("wscript.exe")
+        .arg(launcher_path(app)?)
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .spawn()
+        .map_err(|e| format!("Failed to start the server service: {}", e))?;
+    Ok(())
+}
+
+/// Stops the server the script recorded, once the stop file keeps the script from restarting it.
+/// A recorded pid is only signalled while it still belongs to opencode, since Windows reuses pids.
+#[cfg(windows)]
+fn stop(app: &AppHandle) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+
+    write_private(&stop_file(app)?, "")?;
+
+    if cli::is_wsl_enabled(app) {
+        let script = format!(
+            "f=\"{WSL_PID_FILE}\"; pid=$(cat \"$f\" 2>/dev/null) || exit 0; \
+             grep -qa opencode \"/proc/$pid/cmdline\" 2>/dev/null && kill \"$pid\"; rm -f \"$f\""
+        );
+        let _ = std::process::Command::new("wsl")
+            .args(server::wsl_distro_args(app))
+            .args(["-e", "sh", "-c", &script])
+            .creation_flags(cli::CREATE_NO_WINDOW.0)
+            .output();
+        return Ok(());
+    }
+
+    let pid_file = pid_file(app)?;
+    let Some(pid) = std::fs::read_to_string(&pid_file)
+        .ok()
+        .and_then(|pid| pid.trim().parse::<u32>().ok())
+    else {
+        return Ok(());
+    };
+    let system = orphans::processes();
+    let is_server = system
+        .process(sysinfo::Pid::from_u32(pid))
+        .is_some_and(|process| {
+            let path = process
+                .exe()
+                .map(|exe| exe.display().to_string())
+                .unwrap_or_default();
+            ports::is_opencode(&process.name().to_string_lossy(), &path)
+        });
+    if is_server {
+        orphans::signal_tree(pid, true);
+    } else {
+        tracing::warn!(
+            pid,
+            "Recorded server service pid is not opencode, not stopping it"
+        );
+    }
+    remove(&pid_file)
+}
+
+#[cfg(windows)]
+fn is_running(app: &AppHandle) -> bool {
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port(app) as u16));
+    std::net::TcpStream::connect_timeout(&addr, std::time::Duration::from_millis(500)).is_ok()
+        && !stop_file(app).is_ok_and(|path| path.exists())
+}
+
+/// The launchd agent, systemd unit or Windows batch script that `install_server_service` would
+/// write.
+#[tauri::command]
+#[specta::specta]
+pub fn get_server_service_definition(app: AppHandle) -> Result<String, String> {
+    definition(&app)
+}
+
+/// Installs `opencode serve` as a user service that runs at login and is restarted when it
+/// exits, independently of the app. On Windows it is a login item running the server natively or
+/// in WSL, whichever the app uses. Point the app at the service's url to use it.
+#[tauri::command]
+#[specta::specta]
+pub fn install_server_service(app: AppHandle, port: Option<u32>) -> Result<ServiceStatus, String> {
+    settings::update(&app, |s| {
+        s.server_service_port = port;
+        Ok(())
+    })?;
+    install(&app)?;
+    tracing::info!(port = self::port(&app), "Installed server service");
+    get_server_service_status(app)
+}
+
+/// Rewrites an installed service with the current password and restarts it, so it keeps
+/// accepting the app after the password was rotated.
+pub fn refresh(app: &AppHandle) -> Result<(), String> {
+    if !definition_path(app).is_ok_and(|path| path.is_file()) {
+        return Ok(());
+    }
+    install(app)?;
+    tracing::info!("Refreshed server service credentials");
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn uninstall_server_service(app: AppHandle) -> Result<(), String> {
+    uninstall(&app)?;
+    tracing::info!("Uninstalled server service");
+    Ok(())
+}
+
+/// Keeps the server running from login on, the same as `install_server_service` on the default
+/// port.
+#[tauri::command]
+#[specta::specta]
+pub fn install_background_server(app: AppHandle) -> Result<ServiceStatus, String> {
+    install_server_service(app, None)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn remove_background_server(app: AppHandle) -> Result<(), String> {
+    uninstall_server_service(app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn start_server_service(app: AppHandle) -> Result<(), String> {
+    start(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn stop_server_service(app: AppHandle) -> Result<(), String> {
+    stop(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_server_service_status(app: AppHandle) -> Result<ServiceStatus, String> {
+    let path = definition_path(&app).ok();
+    let installed = path.as_ref().is_some_and(|path| path.is_file());
+    Ok(ServiceStatus {
+        supported: path.is_some(),
+        installed,
+        running: installed && is_running(&app),
+        url: url(&app),
+        definition_path: path.map(|path| path.display().to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> ServiceCommand {
+        ServiceCommand {
+            program: PathBuf::from("/Users/me/My Apps/opencode"),
+            args: vec![
+                "serve".to_string(),
+                "--port".to_string(),
+                "4096".to_string(),
+            ],
+            env: vec![("OPENCODE_SERVER_PASSWORD", "a<b".to_string())],
+        }
+    }
+
+    #[test]
+    fn definitions_quote_the_command() {
+        let unit = systemd_unit(&command(), Path::new("/home/me/server.env"));
+        assert!(
+            unit.contains(
+                "ExecStart=\"/Users/me/My Apps/opencode\" \"serve\" \"--port\" \"4096\"\n"
+            )
+        );
+        assert!(unit.contains("EnvironmentFile=\"/home/me/server.env\"\n"));
+        assert_eq!(
+            env_file_contents(&command()),
+            "OPENCODE_SERVER_PASSWORD=a<b\n"
+        );
+
+        let plist = launchd_plist("ai.opencode.server", &command(), Path::new("/tmp/log"));
+        assert!(plist.contains("<string>/Users/me/My Apps/opencode</string>"));
+        assert!(plist.contains("<string>a&lt;b</string>"));
+    }
+
+    #[test]
+    fn batch_script_loops_until_stopped() {
+        let stop = Path::new(r"C:\Users\me\100%\server.stop");
+        let pid = Path::new(r"C:\Users\me\O'Code\server.pid");
+        let native = batch_script(&command(), None, stop, pid);
+        assert!(native.contains("set \"OPENCODE_SERVER_PASSWORD=a<b\"\r\n"));
+        assert!(native.contains(
+            "Start-Process -FilePath '/Users/me/My Apps/opencode' -ArgumentList 'serve --port 4096'"
+        ));
+        assert!(
+            native.contains(r"Set-Content -LiteralPath 'C:\Users\me\O''Code\server.pid' $p.Id")
+        );
+        assert!(native.contains("if exist \"C:\\Users\\me\\100%%\\server.stop\" exit /b 0\r\n"));
+        assert!(native.ends_with("goto loop\r\n"));
+
+        let wsl = batch_script(
+            &command(),
+            Some(&["-d".to_string(), "Ubuntu".to_string()]),
+            stop,
+            pid,
+        );
+        assert!(!wsl.contains("set \""));
+        assert!(wsl.contains(
+            "wsl \"-d\" \"Ubuntu\" -e bash -lc \"echo $$ > $HOME/.opencode/server-service.pid; OPENCODE_SERVER_PASSWORD='a<b' exec $HOME/.opencode/bin/opencode serve --port 4096\"\r\n"
+        ));
+    }
+}
@@ -0,0 +1,502 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use reqwest::Method;
+use tauri::{AppHandle, Manager, State};
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    cli::{self, CommandEvent},
+    deeplink, file_dialogs,
+    operations::{self, Operation, OperationKind},
+    session_export, supervisor, trust,
+};
+
+const FORMAT: &str = "opencode-sessions";
+const VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+/// File parts saved in the archive point at it with urls starting with this.
+const ARCHIVE_URL: &str = "archive:";
+/// Local files bigger than this stay linked instead of being copied into the archive.
+const MAX_ATTACHMENT_BYTES: u64 = 50 * 1024 * 1024;
+const MAX_SESSION_BYTES: u64 = 200 * 1024 * 1024;
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct ArchivedSession {
+    pub id: String,
+    pub title: String,
+    /// The project the session belonged to when it was exported.
+    pub directory: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    format: String,
+    version: u32,
+    exported_at: String,
+    sessions: Vec<ArchivedSession>,
+}
+
+/// Where imported attachments are kept, one folder per session.
+fn attachments_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join("imported"))
+        .map_err(|e| format!("Failed to resolve the attachments directory: {}", e))
+}
+
+fn file_parts(messages: &mut [serde_json::Value]) -> impl Iterator<Item = &mut serde_json::Value> {
+    messages
+        .iter_mut()
+        .filter_map(|message| message.get_mut("parts")?.as_array_mut())
+        .flatten()
+        .filter(|part| part["type"] == "file")
+}
+
+/// The local file a `file://` attachment points at, if it can be copied into the archive.
+/// `readable` decides whether the canonical path may be read at all.
+fn local_file(url: &str, readable: &impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    let path = reqwest::Url::parse(url)
+        .ok()
+        .filter(|url| url.scheme() == "file")?
+        .to_file_path()
+        .ok()?;
+    let path = dunce::canonicalize(path).ok()?;
+    let metadata = path.metadata().ok()?;
+    (metadata.is_file() && metadata.len() <= MAX_ATTACHMENT_BYTES && readable(&path))
+        .then_some(path)
+}
+
+/// Drops attachments that still point at files on the exporting machine, which were too big to
+/// archive or gone by then, so the imported session doesn't reference paths on this one.
+fn strip_local_files(messages: &mut [serde_json::Value]) {
+    for parts in messages
+        .iter_mut()
+        .filter_map(|message| message.get_mut("parts")?.as_array_mut())
+    {
+        parts.retain(|part| {
+            part["type"] != "file"
+                || !part["url"]
+                    .as_str()
+                    .is_some_and(|url| url.starts_with("file:"))
+        });
+    }
+}
+
+fn zip_error(e: zip::result::ZipError) -> String {
+    format!("Failed to write the archive: {}", e)
+}
+
+/// Writes `sessions` with the local files they attach into a zip at `target`. Only files
+/// `readable` allows are copied, the others stay linked.
+fn pack(
+    target: &Path,
+    sessions: Vec<(serde_json::Value, Vec<serde_json::Value>)>,
+    readable: impl Fn(&Path) -> bool,
+) -> Result<Vec<ArchivedSession>, String> {
+    let file = File::create(target).map_err(|e| format!("Failed to create the archive: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().large_file(true);
+    let mut archived = Vec::new();
+
+    for (info, mut messages) in sessions {
+        let id = info["id"].as_str().unwrap_or_default().to_string();
+        if !deeplink::is_session_id(&id) {
+            return Err(format!("Invalid session id: {}", id));
+        }
+
+        for (n, part) in file_parts(&mut messages).enumerate() {
+            let Some(path) = part["url"]
+                .as_str()
+                .and_then(|url| local_file(url, &readable))
+            else {
+                continue;
+            };
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let entry = format!("attachments/{id}/{n}-{}", session_export::safe_name(&name));
+            let mut source = File::open(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            zip.start_file(&entry, options).map_err(zip_error)?;
+            std::io::copy(&mut source, &mut zip)
+                .map_err(|e| format!("Failed to write the archive: {}", e))?;
+            part["url"] = format!("{ARCHIVE_URL}{entry}").into();
+        }
+
+        zip.start_file(format!("sessions/{id}.json"), options)
+            .map_err(zip_error)?;
+        serde_json::to_writer(
+            &mut zip,
+            &serde_json::json!({ "info": info, "messages": messages }),
+        )
+        .map_err(|e| format!("Failed to write the archive: {}", e))?;
+
+        archived.push(ArchivedSession {
+            id,
+            title: info["title"].as_str().unwrap_or_default().to_string(),
+            directory: info["directory"].as_str().map(str::to_string),
+        });
+    }
+
+    let manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        sessions: archived.clone(),
+    };
+    zip.start_file(MANIFEST, SimpleFileOptions::default())
+        .map_err(zip_error)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)
+        .map_err(|e| format!("Failed to write the archive: {}", e))?;
+    zip.finish()
+        .map_err(zip_error)?
+        .flush()
+        .map_err(|e| format!("Failed to write the archive: {}", e))?;
+    Ok(archived)
+}
+
+fn read_entry(zip: &mut ZipArchive<File>, name: &str, limit: u64) -> Result<Vec<u8>, String> {
+    let entry = zip
+        .by_name(name)
+        .map_err(|e| format!("The archive is missing {}: {}", name, e))?;
+    if entry.size() > limit {
+        return Err(format!("{} in the archive is too large", name));
+    }
+    let mut bytes = Vec::new();
+    entry
+        .take(limit)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {} from the archive: {}", name, e))?;
+    Ok(bytes)
+}
+
+/// Extracts the sessions in `archive` into `staging` as files `opencode import` reads, with
+/// their attachments saved under `attachments`.
+fn unpack(
+    archive: &Path,
+    staging: &Path,
+    attachments: &Path,
+) -> Result<Vec<ArchivedSession>, String> {
+    let file = File::open(archive).map_err(|e| format!("Failed to open the archive: {}", e))?;
+    let mut zip =
+        ZipArchive::new(file).map_err(|e| format!("Failed to read the archive: {}", e))?;
+    let manifest = serde_json::from_slice::<Manifest>(&read_entry(&mut zip, MANIFEST, 1 << 20)?)
+        .map_err(|_| "This is not a session archive".to_string())?;
+    if manifest.format != FORMAT {
+        return Err("This is not a session archive".to_string());
+    }
+    if manifest.version > VERSION {
+        return Err("The archive was made by a newer version of the app".to_string());
+    }
+    std::fs::create_dir_all(staging)
+        .map_err(|e| format!("Failed to create {}: {}", staging.display(), e))?;
+
+    for session in &manifest.sessions {
+        let id = &session.id;
+        if !deeplink::is_session_id(id) {
+            return Err(format!("Invalid session id in the archive: {}", id));
+        }
+        let bytes = read_entry(&mut zip, &format!("sessions/{id}.json"), MAX_SESSION_BYTES)?;
+        let mut data = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .map_err(|e| format!("Failed to read session {}: {}", id, e))?;
+        // `opencode import` goes by the id inside, which could name another session.
+        if data["info"]["id"].as_str() != Some(id) {
+            return Err(format!("Session {} in the archive has a different id", id));
+        }
+
+        let messages = data["messages"]
+            .as_array_mut()
+            .map(Vec::as_mut_slice)
+            .unwrap_or_default();
+        strip_local_files(messages);
+        for part in file_parts(messages) {
+            let Some(entry) = part["url"]
+                .as_str()
+                .and_then(|url| url.strip_prefix(ARCHIVE_URL))
+                .and_then(|entry| entry.strip_prefix(&format!("attachments/{id}/")))
+                .filter(|name| *name == session_export::safe_name(name))
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let bytes = read_entry(
+                &mut zip,
+                &format!("attachments/{id}/{entry}"),
+                MAX_ATTACHMENT_BYTES,
+            )?;
+            let dir = attachments.join(id);
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            let path = dir.join(&entry);
+            std::fs::write(&path, bytes)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            if let Ok(url) = reqwest::Url::from_file_path(&path) {
+                part["url"] = url.to_string().into();
+            }
+        }
+
+        let path = staging.join(format!("{id}.json"));
+        std::fs::write(&path, data.to_string())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(manifest.sessions)
+}
+
+/// Runs `opencode import` on a session file in `staging`, which writes it into the database the
+/// server reads.
+async fn import(app: &AppHandle, staging: &Path, id: &str) -> Result<(), String> {
+    let (mut events, _child) =
+        cli::spawn_command(app, &format!("import {id}.json"), &[], Some(staging))
+            .await
+            .map_err(|e| format!("Failed to run opencode import: {}", e))?;
+    let mut output = Vec::new();
+    while let Some(event) = events.next().await {
+        match event {
+            CommandEvent::Stdout(line) | CommandEvent::Stderr(line) => {
+                output.push(line.trim_end().to_string())
+            }
+            CommandEvent::Error(e) => return Err(format!("opencode import failed: {e}")),
+            CommandEvent::Terminated(payload) if payload.code == Some(0) => return Ok(()),
+            CommandEvent::Terminated(_) => {
+                return Err(format!(
+                    "opencode import failed for session {id}: {}",
+                    output.join("\n")
+                ));
+            }
+        }
+    }
+    Err("opencode import exited without a status".to_string())
+}
+
+/// Saves `session_ids` with their messages and the local files they attach into a zip archive
+/// that `import_sessions` can load on another machine. Without `path` the user picks where to
+/// save it. Returns the sessions saved, or nothing when the user cancels. It reports progress
+/// and can be cancelled as `operation_id`.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_sessions(
+    app: AppHandle,
+    grants: State<'_, file_dialogs::DialogGrants>,
+    session_ids: Vec<String>,
+    directory: Option<String>,
+    path: Option<String>,
+    operation_id: Option<String>,
+) -> Result<Option<Vec<ArchivedSession>>, String> {
+    if session_ids.is_empty() {
+        return Err("No sessions to export".to_string());
+    }
+    let operation = operations::begin(&app, OperationKind::SessionExport, operation_id)?;
+    operation
+        .run(export_archive(
+            &app,
+            &operation,
+            grants,
+            &session_ids,
+            directory,
+            path,
+        ))
+        .await
+}
+
+async fn export_archive(
+    app: &AppHandle,
+    operation: &Operation,
+    grants: State<'_, file_dialogs::DialogGrants>,
+    session_ids: &[String],
+    directory: Option<String>,
+    path: Option<String>,
+) -> Result<Option<Vec<ArchivedSession>>, String> {
+    let mut sessions = Vec::new();
+    for (n, id) in session_ids.iter().enumerate() {
+        operation.progress(Some((n * 100 / session_ids.len()) as u32), "fetching");
+        sessions.push(session_export::fetch(app, id, directory.as_deref().map(Path::new)).await?);
+    }
+
+    let file_name = format!("sessions_{}.zip", chrono::Local::now().format("%Y-%m-%d"));
+    let Some(target) =
+        session_export::save_target(app, grants, path, "Export sessions", file_name, "zip").await?
+    else {
+        return Ok(None);
+    };
+
+    operation.progress(Some(100), "writing");
+    let (written, handle) = (target.clone(), app.clone());
+    // The webview can point attachments anywhere, so only files the user opened are read.
+    let readable = move |path: &Path| trust::is_opened(&handle, path).unwrap_or(false);
+    // Cleaned up from the task itself, which keeps going if this future is dropped on cancel.
+    let archived = tokio::task::spawn_blocking(move || {
+        pack(&written, sessions, readable).inspect_err(|_| {
+            let _ = std::fs::remove_file(&written);
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to export the sessions: {}", e))??;
+    tracing::info!(path = %target.display(), sessions = archived.len(), "Exported session archive");
+    Ok(Some(archived))
+}
+
+/// Imports the sessions in an archive made by `export_sessions`, which must have been picked in a
+/// dialog. Sessions that already exist are left as they are. The server reloads afterwards so
+/// the sessions show up.
+#[tauri::command]
+#[specta::specta]
+pub async fn import_sessions(app: AppHandle, path: String) -> Result<Vec<ArchivedSession>, String> {
+    if !file_dialogs::is_granted(&app, Path::new(&path)) {
+        return Err(format!("{} was not picked in a dialog", path));
+    }
+    let staging = app
+        .path()
+        .app_local_data_dir()
+        .map(|dir| {
+            dir.join("session-import")
+                .join(uuid::Uuid::new_v4().to_string())
+        })
+        .map_err(|e| format!("Failed to resolve the import directory: {}", e))?;
+    let attachments = attachments_dir(&app)?;
+
+    let (archive, dir) = (PathBuf::from(&path), staging.clone());
+    let unpacked = tokio::task::spawn_blocking(move || unpack(&archive, &dir, &attachments))
+        .await
+        .map_err(|e| format!("Failed to read the archive: {}", e));
+
+    let mut res = unpacked.and_then(|unpacked| unpacked);
+    if let Ok(sessions) = &res {
+        for session in sessions {
+            if let Err(e) = import(&app, &staging, &session.id).await {
+                res = Err(e);
+                break;
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(&staging);
+    let sessions = res?;
+
+    tracing::info!(%path, sessions = sessions.len(), "Imported session archive");
+    if let Err(e) =
+        supervisor::request(&app, Method::POST, "/global/dispose", None, RELOAD_TIMEOUT).await
+    {
+        tracing::warn!("Failed to reload the server after importing sessions: {e}");
+    }
+    // `opencode import` can exit cleanly without writing anything, so check the server sees them.
+    for session in &sessions {
+        supervisor::request_in(
+            &app,
+            session.directory.as_deref().map(Path::new),
+            Method::GET,
+            &format!("/session/{}", session.id),
+            None,
+            RELOAD_TIMEOUT,
+        )
+        .await
+        .map_err(|e| format!("Session {} was not imported: {}", session.id, e))?;
+    }
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_sessions_with_their_attachments() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let attachment = dir.join("notes one.txt");
+        std::fs::write(&attachment, "hello").unwrap();
+        let url = reqwest::Url::from_file_path(&attachment)
+            .unwrap()
+            .to_string();
+
+        let info = serde_json::json!({ "id": "ses_1", "title": "Notes", "directory": "/work" });
+        let messages = vec![serde_json::json!({
+            "info": { "id": "msg_1", "role": "user" },
+            "parts": [
+                { "type": "file", "mime": "text/plain", "url": url },
+                { "type": "file", "mime": "image/png", "url": "data:image/png;base64,iVBORw==" },
+                { "type": "text", "text": "hi" },
+                { "type": "file", "mime": "text/plain", "url": "file:///elsewhere/gone.txt" }
+            ]
+        })];
+        let archive = dir.join("sessions.zip");
+        let archived = pack(&archive, vec![(info, messages)], |_| true).unwrap();
+        assert_eq!(archived[0].id, "ses_1");
+        assert_eq!(archived[0].directory.as_deref(), Some("/work"));
+
+        let (staging, attachments) = (dir.join("staging"), dir.join("imported"));
+        assert_eq!(unpack(&archive, &staging, &attachments).unwrap(), archived);
+
+        let data = serde_json::from_str::<serde_json::Value>(
+            &std::fs::read_to_string(staging.join("ses_1.json")).unwrap(),
+        )
+        .unwrap();
+        let parts = &data["messages"][0]["parts"];
+        let imported = attachments.join("ses_1/0-notes_one.txt");
+        assert_eq!(
+            parts[0]["url"],
+            reqwest::Url::from_file_path(&imported).unwrap().to_string()
+        );
+        assert_eq!(std::fs::read_to_string(&imported).unwrap(), "hello");
+        assert_eq!(parts[1]["url"], "data:image/png;base64,iVBORw==");
+        assert_eq!(parts.as_array().unwrap().len(), 3);
+        assert_eq!(data["info"]["title"], "Notes");
+
+        std::fs::write(dir.join("other.zip"), b"not a zip").unwrap();
+        assert!(unpack(&dir.join("other.zip"), &staging, &attachments).is_err());
+    }
+
+    #[test]
+    fn leaves_files_that_were_not_opened_out() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let secret = dir.join("id_ed25519");
+        std::fs::write(&secret, "key").unwrap();
+        let url = reqwest::Url::from_file_path(&secret).unwrap().to_string();
+
+        let info = serde_json::json!({ "id": "ses_1", "title": "Keys" });
+        let messages = vec![serde_json::json!({
+            "parts": [{ "type": "file", "mime": "text/plain", "url": url }]
+        })];
+        let archive = dir.join("sessions.zip");
+        pack(&archive, vec![(info, messages)], |_| false).unwrap();
+
+        let mut zip = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        assert!(
+            zip.file_names()
+                .all(|name| !name.starts_with("attachments/"))
+        );
+        let session = read_entry(&mut zip, "sessions/ses_1.json", MAX_SESSION_BYTES).unwrap();
+        assert!(!String::from_utf8(session).unwrap().contains(ARCHIVE_URL));
+    }
+
+    #[test]
+    fn rejects_sessions_whose_id_differs_from_the_manifest() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let archive = dir.join("sessions.zip");
+        let info = serde_json::json!({ "id": "ses_1", "title": "Notes" });
+        pack(&archive, vec![(info, Vec::new())], |_| true).unwrap();
+
+        // Rewrite the session file under the manifest's id to claim another session.
+        let mut source = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let manifest = read_entry(&mut source, MANIFEST, 1 << 20).unwrap();
+        let forged = dir.join("forged.zip");
+        let mut zip = ZipWriter::new(File::create(&forged).unwrap());
+        zip.start_file(MANIFEST, SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(&manifest).unwrap();
+        zip.start_file("sessions/ses_1.json", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(br#"{"info":{"id":"ses_2","title":"Other"},"messages":[]}"#)
+            .unwrap();
+        zip.finish().unwrap();
+
+        let (staging, attachments) = (dir.join("staging"), dir.join("imported"));
+        assert!(unpack(&forged, &staging, &attachments).is_err());
+        assert!(!staging.join("ses_1.json").exists());
+    }
+}
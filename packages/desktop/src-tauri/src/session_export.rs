@@ -0,0 +1,550 @@
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use base64::Engine;
+use reqwest::Method;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    deeplink, file_dialogs, markdown,
+    operations::{self, OperationKind},
+    supervisor,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Tool output beyond this many characters is cut off in Markdown and HTML.
+const MAX_TOOL_OUTPUT: usize = 4000;
+const STYLE: &str = "body{font:15px/1.6 system-ui,sans-serif;max-width:860px;margin:2rem auto;padding:0 1rem;color:#1d1d1f}pre{background:#f5f5f7;padding:.75rem;overflow-x:auto;border-radius:6px}code{font-family:ui-monospace,monospace;font-size:13px}blockquote{color:#6e6e73;border-left:3px solid #d2d2d7;margin:0;padding-left:1rem}img{max-width:100%}h2{border-top:1px solid #d2d2d7;padding-top:1rem}";
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// What to do with files attached to messages as data urls.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentMode {
+    /// Keep them inside the export.
+    Embed,
+    /// Save them to a folder next to the export and link to them.
+    Link,
+}
+
+#[derive(serde::Deserialize)]
+struct Session {
+    #[serde(default)]
+    title: String,
+    time: SessionTime,
+}
+
+#[derive(serde::Deserialize)]
+struct SessionTime {
+    created: f64,
+}
+
+#[derive(serde::Deserialize)]
+struct Message {
+    info: MessageInfo,
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(serde::Deserialize)]
+struct MessageInfo {
+    role: String,
+    time: SessionTime,
+    #[serde(rename = "modelID")]
+    model_id: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Part {
+    Text {
+        text: String,
+        #[serde(default)]
+        synthetic: bool,
+        #[serde(default)]
+        ignored: bool,
+    },
+    Reasoning {
+        text: String,
+    },
+    File {
+        mime: String,
+        filename: Option<String>,
+        url: String,
+    },
+    Tool {
+        tool: String,
+        state: ToolState,
+    },
+    Patch {
+        files: Vec<String>,
+    },
+    Agent {
+        name: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(serde::Deserialize)]
+struct ToolState {
+    status: String,
+    #[serde(default)]
+    input: serde_json::Value,
+    title: Option<String>,
+    output: Option<String>,
+    error: Option<String>,
+}
+
+fn time(ms: f64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms as i64)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// A code block whose fence is longer than any run of backticks in `code`.
+fn fence(code: &str, lang: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in code.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat((longest + 1).max(3));
+    format!(
+        "{fence}{lang}\n{}\n{fence}\n\n",
+        code.trim_end_matches('\n')
+    )
+}
+
+fn cut(text: &str) -> String {
+    match text.char_indices().nth(MAX_TOOL_OUTPUT) {
+        Some((end, _)) => format!("{}\n…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// A file name made of characters that are safe everywhere.
+pub fn safe_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .take(80)
+        .collect::<String>();
+    let name = name.trim_matches(['.', '_']);
+    if name.is_empty() {
+        "session".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn render_markdown(session: &Session, messages: &[Message]) -> String {
+    let title = if session.title.is_empty() {
+        "Session"
+    } else {
+        &session.title
+    };
+    let mut out = format!(
+        "# {title}\n\n_Started {} · {} messages_\n\n",
+        time(session.time.created),
+        messages.len()
+    );
+
+    for message in messages {
+        let role = match message.info.role.as_str() {
+            "user" => "User",
+            _ => "Assistant",
+        };
+        let _ = write!(out, "## {role}");
+        if let Some(model) = &message.info.model_id {
+            let _ = write!(out, " · {model}");
+        }
+        let _ = writeln!(out, " · {}\n", time(message.info.time.created));
+
+        for part in &message.parts {
+            match part {
+                Part::Text {
+                    text,
+                    synthetic: false,
+                    ignored: false,
+                } => {
+                    let _ = writeln!(out, "{}\n", text.trim_end());
+                }
+                Part::Reasoning { text } if !text.trim().is_empty() => {
+                    for line in text.trim_end().lines() {
+                        let _ = writeln!(out, "> {line}");
+                    }
+                    out.push('\n');
+                }
+                Part::File {
+                    mime,
+                    filename,
+                    url,
+                } => {
+                    let name = filename.as_deref().unwrap_or("attachment");
+                    let image = if mime.starts_with("image/") { "!" } else { "" };
+                    let _ = writeln!(out, "{image}[{name}](<{url}>)\n");
+                }
+                Part::Tool { tool, state } => {
+                    let _ = write!(out, "**Tool `{tool}`**");
+                    if let Some(title) = state.title.as_deref().filter(|t| !t.is_empty()) {
+                        let _ = write!(out, " · {title}");
+                    }
+                    let _ = writeln!(out, " ({})\n", state.status);
+                    if !state.input.is_null() {
+                        let input = serde_json::to_string_pretty(&state.input).unwrap_or_default();
+                        out.push_str(&fence(&input, "json"));
+                    }
+                    if let Some(output) = state.output.as_deref().or(state.error.as_deref()) {
+                        out.push_str(&fence(&cut(output), ""));
+                    }
+                }
+                Part::Patch { files } if !files.is_empty() => {
+                    let _ = writeln!(out, "_Changed {}_\n", files.join(", "));
+                }
+                Part::Agent { name } => {
+                    let _ = writeln!(out, "_@{name}_\n");
+                }
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(session: &Session, messages: &[Message]) -> String {
+    format!(
+        "<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&session.title),
+        markdown::parse_untrusted_markdown(&render_markdown(session, messages))
+    )
+}
+
+/// Saves the data url attachments of `messages` into `dir`, pointing their parts at the saved
+/// files through `prefix`.
+fn link_attachments(
+    messages: &mut [serde_json::Value],
+    dir: &Path,
+    prefix: &str,
+) -> Result<(), String> {
+    let mut used = HashSet::new();
+    let parts = messages
+        .iter_mut()
+        .filter_map(|message| message.get_mut("parts")?.as_array_mut())
+        .flatten()
+        .filter(|part| part["type"] == "file");
+
+    for part in parts {
+        let Some((header, data)) = part["url"]
+            .as_str()
+            .and_then(|url| url.strip_prefix("data:"))
+            .and_then(|url| url.split_once(','))
+        else {
+            continue;
+        };
+        if !header.ends_with(";base64") {
+            continue;
+        }
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| format!("Failed to decode an attachment: {}", e))?;
+
+        let base = safe_name(part["filename"].as_str().unwrap_or("attachment"));
+        let mut name = base.clone();
+        let mut n = 1;
+        while !used.insert(name.clone()) {
+            n += 1;
+            name = format!("{n}-{base}");
+        }
+
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let target = dir.join(&name);
+        std::fs::write(&target, bytes)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        part["url"] = format!("{prefix}/{name}").into();
+    }
+    Ok(())
+}
+
+fn export(
+    target: &Path,
+    format: ExportFormat,
+    attachments: AttachmentMode,
+    session: serde_json::Value,
+    mut messages: Vec<serde_json::Value>,
+) -> Result<(), String> {
+    if attachments == AttachmentMode::Link {
+        let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+        let folder = format!("{}_files", safe_name(&stem));
+        let dir = target.with_file_name(&folder);
+        link_attachments(&mut messages, &dir, &folder)?;
+    }
+
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "session": session,
+            "messages": messages,
+        }))
+        .map_err(|e| format!("Failed to serialize the session: {}", e))?,
+        ExportFormat::Markdown | ExportFormat::Html => {
+            let parse = |e: serde_json::Error| format!("Failed to read the session: {}", e);
+            let session = serde_json::from_value::<Session>(session).map_err(parse)?;
+            let messages = messages
+                .into_iter()
+                .map(serde_json::from_value::<Message>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(parse)?;
+            if format == ExportFormat::Html {
+                render_html(&session, &messages)
+            } else {
+                render_markdown(&session, &messages)
+            }
+        }
+    };
+    std::fs::write(target, contents)
+        .map_err(|e| format!("Failed to write {}: {}", target.display(), e))
+}
+
+/// Fetches a session and its messages, with their parts, from the managed server.
+pub async fn fetch(
+    app: &AppHandle,
+    session_id: &str,
+    directory: Option<&Path>,
+) -> Result<(serde_json::Value, Vec<serde_json::Value>), String> {
+    if !deeplink::is_session_id(session_id) {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+    let session = supervisor::request_in(
+        app,
+        directory,
+        Method::GET,
+        &format!("/session/{session_id}"),
+        None,
+        REQUEST_TIMEOUT,
+    )
+    .await?;
+    let messages = supervisor::request_in(
+        app,
+        directory,
+        Method::GET,
+        &format!("/session/{session_id}/message"),
+        None,
+        REQUEST_TIMEOUT,
+    )
+    .await?;
+    let messages = serde_json::from_value::<Vec<serde_json::Value>>(messages)
+        .map_err(|e| format!("Failed to read the session: {}", e))?;
+    Ok((session, messages))
+}
+
+/// Where to save an export: `path` if it was picked in a save dialog before, or else where the
+/// user picks now. Nothing when they cancel.
+pub async fn save_target(
+    app: &AppHandle,
+    grants: State<'_, file_dialogs::DialogGrants>,
+    path: Option<String>,
+    title: &str,
+    file_name: String,
+    extension: &str,
+) -> Result<Option<PathBuf>, String> {
+    match path {
+        Some(path) if file_dialogs::is_save_target(app, Path::new(&path)) => {
+            Ok(Some(PathBuf::from(path)))
+        }
+        Some(path) => Err(format!("{} was not picked in a save dialog", path)),
+        None => Ok(file_dialogs::pick_export_path(
+            app.clone(),
+            grants,
+            Some(title.to_string()),
+            Some(file_name),
+            vec![extension.to_string()],
+        )
+        .await?
+        .map(PathBuf::from)),
+    }
+}
+
+/// Saves a session's transcript as Markdown, HTML or the server's JSON. Without `path` the user
+/// picks where in a save dialog; a given `path` must have been picked in one before. Returns
+/// the file written, or nothing when the user cancels. It can be cancelled as `operation_id`.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_session(
+    app: AppHandle,
+    session_id: String,
+    format: ExportFormat,
+    path: Option<String>,
+    directory: Option<String>,
+    attachments: Option<AttachmentMode>,
+    operation_id: Option<String>,
+) -> Result<Option<String>, String> {
+    let operation = operations::begin(&app, OperationKind::SessionExport, operation_id)?;
+    operation
+        .run(export_to(
+            &app,
+            &session_id,
+            format,
+            path,
+            directory,
+            attachments,
+        ))
+        .await
+}
+
+async fn export_to(
+    app: &AppHandle,
+    session_id: &str,
+    format: ExportFormat,
+    path: Option<String>,
+    directory: Option<String>,
+    attachments: Option<AttachmentMode>,
+) -> Result<Option<String>, String> {
+    let (session, messages) = fetch(app, session_id, directory.as_deref().map(Path::new)).await?;
+
+    let title = session["title"].as_str().unwrap_or_default();
+    let file_name = format!("{}.{}", safe_name(title), format.extension());
+    let Some(target) = save_target(
+        app,
+        app.state::<file_dialogs::DialogGrants>(),
+        path,
+        "Export session",
+        file_name,
+        format.extension(),
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let attachments = attachments.unwrap_or(AttachmentMode::Embed);
+    let written = target.clone();
+    tokio::task::spawn_blocking(move || export(&written, format, attachments, session, messages))
+        .await
+        .map_err(|e| format!("Failed to export the session: {}", e))??;
+
+    tracing::info!(session_id, path = %target.display(), ?format, "Exported session");
+    Ok(Some(target.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> (serde_json::Value, Vec<serde_json::Value>) {
+        let session = serde_json::json!({
+            "id": "ses_1",
+            "title": "Fix <the> build",
+            "time": { "created": 0.0, "updated": 0.0 }
+        });
+        let messages = serde_json::json!([
+            {
+                "info": { "id": "m1", "role": "user", "time": { "created": 0.0 } },
+                "parts": [
+                    { "type": "text", "text": "Why does it fail?" },
+                    { "type": "text", "text": "context", "synthetic": true },
+                    { "type": "file", "mime": "image/png", "filename": "shot 1.png", "url": "data:image/png;base64,iVBORw==" }
+                ]
+            },
+            {
+                "info": { "id": "m2", "role": "assistant", "modelID": "gpt-5", "time": { "created": 0.0 } },
+                "parts": [
+                    { "type": "step-start" },
+                    { "type": "reasoning", "text": "Check the logs" },
+                    { "type": "tool", "tool": "bash", "callID": "c1", "state": {
+                        "status": "completed", "title": "cargo build", "input": { "command": "cargo build" },
+                        "output": "```\nerror<script>\n```", "metadata": {}, "time": { "start": 0, "end": 1 }
+                    } },
+                    { "type": "text", "text": "A missing **import**." }
+                ]
+            }
+        ]);
+        (session, serde_json::from_value(messages).unwrap())
+    }
+
+    #[test]
+    fn renders_transcripts() {
+        let (session, messages) = transcript();
+        let session = serde_json::from_value::<Session>(session).unwrap();
+        let messages = messages
+            .into_iter()
+            .map(|m| serde_json::from_value::<Message>(m).unwrap())
+            .collect::<Vec<_>>();
+
+        let md = render_markdown(&session, &messages);
+        assert!(md.starts_with("# Fix <the> build\n"));
+        assert!(md.contains("Why does it fail?"));
+        assert!(!md.contains("context"));
+        assert!(md.contains("![shot 1.png](<data:image/png;base64,iVBORw==>)"));
+        assert!(md.contains("## Assistant · gpt-5"));
+        assert!(md.contains("> Check the logs"));
+        assert!(md.contains("**Tool `bash`** · cargo build (completed)"));
+        // The output's own fence doesn't end the block around it.
+        assert!(md.contains("````\n```\nerror<script>\n```\n````"));
+
+        let html = render_html(&session, &messages);
+        assert!(html.contains("<title>Fix &lt;the&gt; build</title>"));
+        assert!(html.contains("<strong>import</strong>"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn links_attachments_next_to_the_export() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let target = dir.join("Fix build.md");
+
+        let (session, messages) = transcript();
+        export(
+            &target,
+            ExportFormat::Markdown,
+            AttachmentMode::Link,
+            session,
+            messages,
+        )
+        .unwrap();
+
+        let md = std::fs::read_to_string(&target).unwrap();
+        assert!(md.contains("![shot 1.png](<Fix_build_files/shot_1.png>)"));
+        assert_eq!(
+            std::fs::read(dir.join("Fix_build_files/shot_1.png")).unwrap(),
+            [0x89, b'P', b'N', b'G']
+        );
+    }
+}
@@ -0,0 +1,741 @@
+use std::{collections::BTreeMap, fs, sync::Mutex};
+
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    app_update::UpdateChannel, backup::BackupSchedule, cli, cli_channel::CliChannel,
+    cli_config::strip_jsonc, constants::SETTINGS_STORE, diagnostics, dotenv::DotenvConfig,
+    external::ExternalServerConfig, file_drop::FileDropConfig, hotkey::GlobalHotkey, i18n,
+    logging::LogLevel, onboarding::OnboardingState, port::PortRange, projects::RecentProject,
+    proxy::ProxyConfig, remote::RemoteProfile, trust::TrustedPath, windows::WindowGeometry,
+};
+
+/// Bumped whenever stored settings need migrating; `MIGRATIONS[n]` upgrades from version `n`.
+pub const SETTINGS_VERSION: u32 = 1;
+const VERSION_KEY: &str = "settingsVersion";
+const EXPORT_FORMAT: &str = "opencode-desktop-settings";
+const MIGRATIONS: [fn(&mut Map<String, Value>); SETTINGS_VERSION as usize] = [migrate_v0];
+
+// Serializes read-modify-write cycles so concurrent updates don't drop each other's changes.
+static UPDATE: Mutex<()> = Mutex::new(());
+
+/// Every desktop setting. Each field lives under its own camelCase key in the settings store,
+/// and a missing or malformed value reads as the default. The Linux display setting is not
+/// included since it must be read before the app starts.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    #[serde(default, deserialize_with = "lenient")]
+    pub default_server_url: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub wsl_enabled: bool,
+    #[serde(default, deserialize_with = "lenient")]
+    pub wsl_distro: Option<String>,
+    /// Installs the CLI in WSL from the Linux binary bundled with the app instead of downloading it.
+    #[serde(default, deserialize_with = "lenient")]
+    pub wsl_offline_install: bool,
+    /// Points the WSL sidecar's `XDG_STATE_HOME` at the app's data directory on Windows. Off keeps
+    /// it inside WSL, where it is faster. Its sessions stay in WSL either way, and backups and
+    /// storage usage reach them through the `\\wsl.localhost` share.
+    #[serde(default, deserialize_with = "lenient")]
+    pub wsl_shared_state: bool,
+    #[serde(default, deserialize_with = "lenient")]
+    pub sidecar_port_range: Option<PortRange>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub log_retention_days: Option<u32>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub log_level: Option<LogLevel>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub external_server: ExternalServerConfig,
+    #[serde(default, deserialize_with = "lenient")]
+    pub remote_profiles: Vec<RemoteProfile>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub active_remote_profile: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub lan_discovery: bool,
+    /// Lets the server listen on addresses other machines can reach.
+    #[serde(default, deserialize_with = "lenient")]
+    pub allow_lan: bool,
+    /// Restarts the sidecar when it is running but stops answering health checks.
+    #[serde(default, deserialize_with = "lenient")]
+    pub restart_on_stall: bool,
+    /// Port of the server installed as a user service.
+    #[serde(default, deserialize_with = "lenient")]
+    pub server_service_port: Option<u32>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub local_server_tls: bool,
+    /// Serves the local server on a unix socket or named pipe instead of a TCP port.
+    #[serde(default, deserialize_with = "lenient")]
+    pub server_socket: bool,
+    #[serde(default, deserialize_with = "lenient")]
+    pub recent_projects: Vec<RecentProject>,
+    /// Extra environment variables for every CLI invocation.
+    #[serde(default, deserialize_with = "lenient")]
+    pub extra_env: BTreeMap<String, String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub dotenv: DotenvConfig,
+    #[serde(default, deserialize_with = "lenient")]
+    pub proxy: ProxyConfig,
+    /// Overrides `$SHELL` for launching CLI commands.
+    #[serde(default, deserialize_with = "lenient")]
+    pub shell_path: Option<String>,
+    /// Arguments before the command line, `-il -c` by default for POSIX shells.
+    #[serde(default, deserialize_with = "lenient")]
+    pub shell_args: Option<Vec<String>>,
+    /// Gives the sidecar the PowerShell profile's environment on Windows without WSL.
+    #[serde(default, deserialize_with = "lenient")]
+    pub windows_shell_env: bool,
+    /// Keeps the app running under the tray once its windows are closed.
+    #[serde(default, deserialize_with = "lenient")]
+    pub background_mode: bool,
+    /// Memory use of the sidecar, in megabytes, that triggers a warning.
+    #[serde(default, deserialize_with = "lenient")]
+    pub memory_warning_mb: Option<u32>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub cli_channel: CliChannel,
+    #[serde(default, deserialize_with = "lenient")]
+    pub onboarding: OnboardingState,
+    /// Picked by the user, otherwise the channel the build was released on.
+    #[serde(default, deserialize_with = "lenient")]
+    pub update_channel: Option<UpdateChannel>,
+    /// Random id placing this install in staged update rollouts.
+    #[serde(default, deserialize_with = "lenient")]
+    pub install_id: Option<String>,
+    /// Rotates the server password after this many days.
+    #[serde(default, deserialize_with = "lenient")]
+    pub password_rotation_days: Option<u32>,
+    /// When the server password was last rotated, as RFC 3339.
+    #[serde(default, deserialize_with = "lenient")]
+    pub server_password_rotated_at: Option<String>,
+    /// Backs up the server's state on this schedule, or never when unset.
+    #[serde(default, deserialize_with = "lenient")]
+    pub backup_schedule: Option<BackupSchedule>,
+    /// Project directories the user allowed servers to run in.
+    #[serde(default, deserialize_with = "lenient")]
+    pub trusted_paths: Vec<TrustedPath>,
+    /// Summons the app while other apps are focused, or nothing when unset.
+    #[serde(default, deserialize_with = "lenient")]
+    pub global_hotkey: Option<GlobalHotkey>,
+    /// Geometry of each project's window, keyed by project directory.
+    #[serde(default, deserialize_with = "lenient")]
+    pub project_windows: BTreeMap<String, WindowGeometry>,
+    /// Geometry of the main window for each monitor layout it was used with.
+    #[serde(default, deserialize_with = "lenient")]
+    pub window_layouts: BTreeMap<String, WindowGeometry>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub file_drop: FileDropConfig,
+    /// Saves crash reports the user can review and send. Off until they opt in.
+    #[serde(default, deserialize_with = "lenient")]
+    pub crash_reporting: bool,
+    /// Counts coarse usage events and uploads them. Off until the user opts in.
+    #[serde(default, deserialize_with = "lenient")]
+    pub usage_metrics: bool,
+    /// Language tag the backend reports to the frontend, instead of the system's.
+    #[serde(default, deserialize_with = "lenient")]
+    pub locale: Option<String>,
+    /// Config file the CLI is pointed at through `OPENCODE_CONFIG`, for configs kept outside the
+    /// global config directory.
+    #[serde(default, deserialize_with = "lenient")]
+    pub config_path: Option<String>,
+}
+
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).unwrap_or_default())
+}
+
+impl Settings {
+    /// Checks the fields that differ from `previous`, or all of them without it, so a value the
+    /// app no longer accepts doesn't block changes to unrelated settings.
+    fn validate(&self, previous: Option<&Settings>) -> Result<(), String> {
+        let current = serde_json::json!(self);
+        let previous = previous.map(|previous| serde_json::json!(previous));
+        let changed = |key: &str| {
+            previous
+                .as_ref()
+                .is_none_or(|previous| previous.get(key) != current.get(key))
+        };
+
+        if changed("sidecarPortRange")
+            && let Some(range) = &self.sidecar_port_range
+            && (range.start == 0 || range.start > range.end || range.end > u16::MAX as u32)
+        {
+            return Err(format!("Invalid port range {}-{}", range.start, range.end));
+        }
+        if changed("logRetentionDays") && self.log_retention_days == Some(0) {
+            return Err("Log retention must be at least one day".to_string());
+        }
+        if changed("memoryWarningMb") && self.memory_warning_mb == Some(0) {
+            return Err("Memory warning threshold must be at least 1 MB".to_string());
+        }
+        if changed("serverServicePort")
+            && self
+                .server_service_port
+                .is_some_and(|port| port == 0 || port > u16::MAX as u32)
+        {
+            return Err("Invalid server service port".to_string());
+        }
+        if changed("passwordRotationDays") && self.password_rotation_days == Some(0) {
+            return Err("Password rotation must be at least one day".to_string());
+        }
+        if changed("backupSchedule")
+            && let Some(schedule) = &self.backup_schedule
+        {
+            schedule.validate()?;
+        }
+        if changed("globalHotkey")
+            && let Some(hotkey) = &self.global_hotkey
+        {
+            hotkey.validate()?;
+        }
+        if changed("remoteProfiles")
+            && let Some(profile) = self
+                .remote_profiles
+                .iter()
+                .find(|p| p.name.trim().is_empty() || p.hostname.trim().is_empty())
+        {
+            return Err(format!("Remote profile '{}' is incomplete", profile.name));
+        }
+        if changed("locale")
+            && let Some(locale) = &self.locale
+        {
+            i18n::validate_locale(locale)?;
+        }
+        if changed("proxy") {
+            self.proxy.validate()?;
+        }
+        if changed("fileDrop") {
+            self.file_drop.validate()?;
+        }
+        if changed("cliChannel") {
+            self.cli_channel.validate()?;
+        }
+        if changed("configPath")
+            && self
+                .config_path
+                .as_deref()
+                .is_some_and(|p| p.trim().is_empty())
+        {
+            return Err("Config path cannot be empty".to_string());
+        }
+        if changed("shellPath")
+            && self
+                .shell_path
+                .as_deref()
+                .is_some_and(|p| p.trim().is_empty())
+        {
+            return Err("Shell path cannot be empty".to_string());
+        }
+        if changed("shellArgs") && self.shell_args.as_ref().is_some_and(|args| args.is_empty()) {
+            return Err("Shell arguments cannot be empty".to_string());
+        }
+        if changed("extraEnv")
+            && let Some(name) = self.extra_env.keys().find(|name| !is_env_name(name))
+        {
+            return Err(format!("'{name}' is not a valid environment variable name"));
+        }
+        if changed("extraEnv")
+            && let Some(name) = self
+                .extra_env
+                .iter()
+                .find(|(_, v)| v.contains('\0'))
+                .map(|(k, _)| k)
+        {
+            return Err(format!("The value of '{name}' contains a NUL character"));
+        }
+        Ok(())
+    }
+}
+
+/// Names are restricted to what POSIX shells accept, since they are spliced into the WSL launch
+/// script unquoted.
+pub fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn load(app: &AppHandle) -> Result<Settings, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    let entries = store.entries().into_iter().collect::<Map<_, _>>();
+    serde_json::from_value(Value::Object(entries))
+        .map_err(|e| format!("Failed to read settings: {}", e))
+}
+
+/// Applies `change` to the current settings and persists the result.
+pub fn update(
+    app: &AppHandle,
+    change: impl FnOnce(&mut Settings) -> Result<(), String>,
+) -> Result<Settings, String> {
+    let _guard = UPDATE.lock().unwrap();
+
+    let mut settings = load(app)?;
+    let previous = settings.clone();
+    change(&mut settings)?;
+    settings.validate(Some(&previous))?;
+    save(app, &settings)?;
+
+    Ok(settings)
+}
+
+fn save(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    let Value::Object(values) = serde_json::json!(settings) else {
+        unreachable!("settings serialize to an object");
+    };
+    for (key, value) in values {
+        if value.is_null() {
+            store.delete(&key);
+        } else {
+            store.set(key, value);
+        }
+    }
+
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+/// Brings the stored settings up to `SETTINGS_VERSION`. Settings written by a newer version of
+/// the app are left untouched.
+pub fn migrate(app: &AppHandle) -> Result<(), String> {
+    let _guard = UPDATE.lock().unwrap();
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    let version = store
+        .get(VERSION_KEY)
+        .and_then(|v| v.as_u64())
+        .map_or(0, |v| v as u32);
+    if version >= SETTINGS_VERSION {
+        if version > SETTINGS_VERSION {
+            tracing::warn!(
+                version,
+                "Settings were written by a newer version of the app"
+            );
+        }
+        return Ok(());
+    }
+
+    let mut values = store.entries().into_iter().collect::<Map<_, _>>();
+    let before = values.clone();
+    apply_migrations(&mut values, version);
+
+    for key in before.keys().filter(|key| !values.contains_key(*key)) {
+        store.delete(key);
+    }
+    for (key, value) in values {
+        if before.get(&key) != Some(&value) {
+            store.set(key, value);
+        }
+    }
+    store.set(VERSION_KEY, serde_json::json!(SETTINGS_VERSION));
+
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    tracing::info!(from = version, to = SETTINGS_VERSION, "Migrated settings");
+    Ok(())
+}
+
+fn apply_migrations(values: &mut Map<String, Value>, from: u32) {
+    for migration in &MIGRATIONS[from as usize..] {
+        migration(values);
+    }
+}
+
+/// Drops values the old per-setting commands could leave behind in an unusable state.
+fn migrate_v0(values: &mut Map<String, Value>) {
+    for key in ["defaultServerUrl", "wslDistro"] {
+        if values
+            .get(key)
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| s.trim().is_empty())
+        {
+            values.remove(key);
+        }
+    }
+
+    let active = values
+        .get("activeRemoteProfile")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    if let Some(active) = active {
+        let exists = values
+            .get("remoteProfiles")
+            .and_then(|v| v.as_array())
+            .is_some_and(|profiles| {
+                profiles
+                    .iter()
+                    .any(|p| p.get("name").and_then(|n| n.as_str()) == Some(active.as_str()))
+            });
+        if !exists {
+            values.remove("activeRemoteProfile");
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_extra_env(app: AppHandle) -> Result<BTreeMap<String, String>, String> {
+    Ok(load(&app)?.extra_env)
+}
+
+/// Replaces the extra environment variables. They apply to the sidecar from its next start.
+#[tauri::command]
+#[specta::specta]
+pub fn set_extra_env(app: AppHandle, env: BTreeMap<String, String>) -> Result<(), String> {
+    update(&app, |s| {
+        s.extra_env = env;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_settings(app: AppHandle) -> Result<Settings, String> {
+    load(&app)
+}
+
+/// Replaces all settings at once, except the ones `replace` keeps. Like the individual setters,
+/// most changes take effect the next time the server starts.
+#[tauri::command]
+#[specta::specta]
+pub fn update_settings(app: AppHandle, settings: Settings) -> Result<Settings, String> {
+    update(&app, |current| {
+        replace(current, settings);
+        Ok(())
+    })
+}
+
+/// Replaces `current` with `replacement`, but keeps the trusted directories, LAN access, extra
+/// environment and shell overrides. Those let the server run code or reach other machines, so
+/// only their own commands change them.
+fn replace(current: &mut Settings, mut replacement: Settings) {
+    replacement.trusted_paths = std::mem::take(&mut current.trusted_paths);
+    replacement.allow_lan = current.allow_lan;
+    replacement.extra_env = std::mem::take(&mut current.extra_env);
+    replacement.shell_path = current.shell_path.take();
+    replacement.shell_args = current.shell_args.take();
+    *current = replacement;
+}
+
+/// The file written by `export_settings`. Settings stay raw JSON so exports from older versions
+/// can be migrated on import.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsExport {
+    format: String,
+    version: u32,
+    settings: Map<String, Value>,
+    /// Global CLI config files by name, as JSON with their credentials redacted.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    cli_config: BTreeMap<String, String>,
+}
+
+/// Writes all desktop settings, and optionally the CLI's global config files, to `path`.
+/// Passwords stay in the keychain and are not exported, and neither are the API keys and other
+/// credentials in the CLI config.
+#[tauri::command]
+#[specta::specta]
+pub fn export_settings(
+    app: AppHandle,
+    path: String,
+    include_cli_config: bool,
+) -> Result<(), String> {
+    let Value::Object(settings) = serde_json::json!(load(&app)?) else {
+        unreachable!("settings serialize to an object");
+    };
+
+    let mut cli_config = BTreeMap::new();
+    if include_cli_config && let Some(dir) = cli::global_config_dir() {
+        for name in cli::GLOBAL_CONFIG_FILES {
+            let Ok(contents) = fs::read_to_string(dir.join(name)) else {
+                continue;
+            };
+            // A file that can't be parsed can't be redacted either.
+            let Ok(mut config) = serde_json::from_str::<Value>(&strip_jsonc(&contents)) else {
+                tracing::warn!(name, "Not exporting unparsable CLI config file");
+                continue;
+            };
+            diagnostics::redact(&mut config);
+            cli_config.insert(name.to_string(), config_json(&config)?);
+        }
+    }
+
+    let export = SettingsExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: SETTINGS_VERSION,
+        settings,
+        cli_config,
+    };
+    let contents = serde_json::to_string_pretty(&export)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {path}: {}", e))?;
+
+    tracing::info!(%path, "Exported settings");
+    Ok(())
+}
+
+fn parse_export(contents: &str) -> Result<SettingsExport, String> {
+    let mut export = serde_json::from_str::<SettingsExport>(contents)
+        .map_err(|e| format!("Not a settings export: {}", e))?;
+    if export.format != EXPORT_FORMAT {
+        return Err("Not a settings export".to_string());
+    }
+    if export.version > SETTINGS_VERSION {
+        return Err("The settings were exported by a newer version of the app".to_string());
+    }
+    if let Some(name) = export
+        .cli_config
+        .keys()
+        .find(|name| !cli::GLOBAL_CONFIG_FILES.contains(&name.as_str()))
+    {
+        return Err(format!("Unexpected CLI config file '{name}'"));
+    }
+    if let Some(name) = export
+        .cli_config
+        .iter()
+        .find(|(_, contents)| serde_json::from_str::<Value>(&strip_jsonc(contents)).is_err())
+        .map(|(name, _)| name)
+    {
+        return Err(format!("CLI config file '{name}' is not valid JSON"));
+    }
+
+    apply_migrations(&mut export.settings, export.version);
+    Ok(export)
+}
+
+fn config_json(config: &Value) -> Result<String, String> {
+    serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize CLI config: {}", e))
+}
+
+/// Puts back the credentials the export redacted from the config in place of `imported`, and
+/// drops the ones it doesn't have.
+fn restore_redacted(imported: &mut Value, current: Option<&Value>) {
+    match imported {
+        Value::Object(map) => {
+            map.retain(|key, value| {
+                let current = current.and_then(|current| current.get(key));
+                if value.as_str() != Some(diagnostics::REDACTED) {
+                    restore_redacted(value, current);
+                    return true;
+                }
+                match current {
+                    Some(current) => {
+                        *value = current.clone();
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                restore_redacted(item, current.and_then(|current| current.get(i)));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replaces the desktop settings `replace` doesn't keep with the ones exported to `path`, and the
+/// CLI's global config files too when `include_cli_config` is set. Existing CLI config files are kept as `.bak`, and
+/// keep the credentials the export left out.
+#[tauri::command]
+#[specta::specta]
+pub fn import_settings(
+    app: AppHandle,
+    path: String,
+    include_cli_config: bool,
+) -> Result<Settings, String> {
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {path}: {}", e))?;
+    let export = parse_export(&contents)?;
+    let imported: Settings = serde_json::from_value(Value::Object(export.settings))
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    // Validate before touching anything, so a bad file leaves both settings and CLI config as
+    // they were.
+    imported.validate(None)?;
+
+    if include_cli_config && !export.cli_config.is_empty() {
+        let dir = cli::global_config_dir()
+            .ok_or_else(|| "Failed to resolve the CLI config directory".to_string())?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        for (name, contents) in &export.cli_config {
+            let target = dir.join(name);
+            let current = fs::read_to_string(&target)
+                .ok()
+                .and_then(|current| serde_json::from_str::<Value>(&strip_jsonc(&current)).ok());
+            let mut config = serde_json::from_str::<Value>(&strip_jsonc(contents))
+                .map_err(|e| format!("CLI config file '{name}' is not valid JSON: {}", e))?;
+            restore_redacted(&mut config, current.as_ref());
+
+            if target.exists() {
+                fs::copy(&target, dir.join(format!("{name}.bak")))
+                    .map_err(|e| format!("Failed to back up {}: {}", target.display(), e))?;
+            }
+            fs::write(&target, config_json(&config)?)
+                .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        }
+    }
+
+    let settings = update(&app, |current| {
+        replace(current, imported);
+        Ok(())
+    })?;
+
+    tracing::info!(%path, "Imported settings");
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_values_read_as_defaults() {
+        let settings: Settings = serde_json::from_value(serde_json::json!({
+            "wslDistro": "Ubuntu",
+            "lanDiscovery": "yes",
+            "logRetentionDays": -3,
+            "unknownKey": 1,
+        }))
+        .unwrap();
+
+        assert_eq!(settings.wsl_distro.as_deref(), Some("Ubuntu"));
+        assert!(!settings.lan_discovery);
+        assert_eq!(settings.log_retention_days, None);
+    }
+
+    #[test]
+    fn v0_migration_drops_dangling_values() {
+        let mut values = serde_json::json!({
+            "defaultServerUrl": " ",
+            "wslDistro": "Ubuntu",
+            "activeRemoteProfile": "gone",
+            "remoteProfiles": [{ "name": "work", "hostname": "box", "port": 4096 }],
+        })
+        .as_object()
+        .cloned()
+        .unwrap();
+
+        apply_migrations(&mut values, 0);
+
+        assert!(!values.contains_key("defaultServerUrl"));
+        assert_eq!(values["wslDistro"], "Ubuntu");
+        assert!(!values.contains_key("activeRemoteProfile"));
+    }
+
+    #[test]
+    fn validation_skips_unchanged_fields() {
+        let previous = Settings {
+            log_retention_days: Some(0),
+            ..Default::default()
+        };
+        let changed = Settings {
+            memory_warning_mb: Some(512),
+            ..previous.clone()
+        };
+
+        assert!(changed.validate(Some(&previous)).is_ok());
+        assert!(changed.validate(None).is_err());
+    }
+
+    #[test]
+    fn replacing_settings_cannot_trust_a_directory() {
+        let mut current = Settings {
+            extra_env: BTreeMap::from([("A".to_string(), "1".to_string())]),
+            ..Default::default()
+        };
+        let replacement = Settings {
+            trusted_paths: vec![TrustedPath {
+                path: "/".to_string(),
+                trusted_at: String::new(),
+            }],
+            allow_lan: true,
+            extra_env: BTreeMap::new(),
+            shell_path: Some("/tmp/evil".to_string()),
+            memory_warning_mb: Some(512),
+            ..Default::default()
+        };
+
+        replace(&mut current, replacement);
+
+        assert!(current.trusted_paths.is_empty());
+        assert!(!current.allow_lan);
+        assert_eq!(current.extra_env.len(), 1);
+        assert_eq!(current.shell_path, None);
+        assert_eq!(current.memory_warning_mb, Some(512));
+    }
+
+    #[test]
+    fn env_names_must_be_shell_safe() {
+        assert!(is_env_name("HTTPS_PROXY"));
+        assert!(is_env_name("_private2"));
+        assert!(!is_env_name(""));
+        assert!(!is_env_name("2FA"));
+        assert!(!is_env_name("A=B"));
+        assert!(!is_env_name("X;rm -rf ~"));
+    }
+
+    #[test]
+    fn import_rejects_foreign_cli_config_files() {
+        let export = serde_json::json!({
+            "format": EXPORT_FORMAT,
+            "version": SETTINGS_VERSION,
+            "settings": { "lanDiscovery": true },
+            "cliConfig": { "../../.bashrc": "rm -rf ~" },
+        });
+
+        assert!(parse_export(&export.to_string()).is_err());
+    }
+
+    #[test]
+    fn import_keeps_redacted_credentials() {
+        let mut imported = serde_json::json!({
+            "provider": {
+                "openai": { "options": { "apiKey": diagnostics::REDACTED, "baseURL": "https://a" } },
+                "anthropic": { "options": { "apiKey": diagnostics::REDACTED } },
+            },
+        });
+        let current = serde_json::json!({
+            "provider": { "openai": { "options": { "apiKey": "sk-live", "baseURL": "https://b" } } },
+        });
+
+        restore_redacted(&mut imported, Some(&current));
+
+        assert_eq!(
+            imported,
+            serde_json::json!({
+                "provider": {
+                    "openai": { "options": { "apiKey": "sk-live", "baseURL": "https://a" } },
+                    "anthropic": { "options": {} },
+                },
+            })
+        );
+    }
+}
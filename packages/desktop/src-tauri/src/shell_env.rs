@@ -0,0 +1,498 @@
+use std::{
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::{
+    FutureExt,
+    future::{BoxFuture, Shared},
+};
+use tauri::AppHandle;
+
+use crate::{settings, startup};
+
+/// How long a captured environment is reused before the login shell is run again.
+const CACHE_TTL: Duration = Duration::from_secs(30 * 60);
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+// Shell startup files may print banners before the environment, so output is read after this.
+const MARKER: &str = "__OPENCODE_SHELL_ENV__";
+// Describe the capturing shell rather than the user's session.
+const SKIPPED: &[&str] = &["_", "OLDPWD", "PWD", "SHLVL"];
+
+pub type ShellEnv = Arc<Vec<(String, String)>>;
+
+/// The shell CLI commands are launched through, and the arguments that precede the command line.
+#[derive(Clone, serde::Serialize, specta::Type, Debug, PartialEq)]
+pub struct UserShell {
+    pub path: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ShellKind {
+    /// sh, bash, zsh and the like.
+    Posix,
+    Fish,
+    Nushell,
+    Xonsh,
+    PowerShell,
+    Cmd,
+}
+
+impl ShellKind {
+    fn of(path: &str) -> Self {
+        match program_name(path).as_str() {
+            "fish" => Self::Fish,
+            "nu" => Self::Nushell,
+            "xonsh" => Self::Xonsh,
+            "pwsh" | "powershell" => Self::PowerShell,
+            "cmd" => Self::Cmd,
+            _ => Self::Posix,
+        }
+    }
+
+    /// Arguments that run the same startup files, or profile, as a terminal would.
+    fn default_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Posix | Self::Fish | Self::Nushell | Self::Xonsh => &["-il", "-c"],
+            Self::PowerShell => &["-NoLogo", "-NonInteractive", "-Command"],
+            Self::Cmd => &["/c"],
+        }
+    }
+
+    /// `program` as a string literal of the shell. Inside single quotes only the quote itself
+    /// needs care in POSIX shells, while fish and xonsh also treat backslashes as escapes there,
+    /// and nushell's single quotes can't contain a single quote at all.
+    fn quote(self, program: &str) -> String {
+        match self {
+            Self::Posix => format!("'{}'", program.replace('\'', r"'\''")),
+            Self::Fish | Self::Xonsh => {
+                format!("'{}'", program.replace('\\', r"\\").replace('\'', r"\'"))
+            }
+            Self::Nushell if !program.contains('\'') => format!("'{program}'"),
+            Self::Nushell => format!("\"{}\"", program.replace('\\', r"\\").replace('"', "\\\"")),
+            Self::PowerShell => format!("'{}'", program.replace('\'', "''")),
+            Self::Cmd => format!("\"{program}\""),
+        }
+    }
+
+    /// Runs `program` with `args`, which are passed through as they are. The program is only
+    /// quoted when it has to be, to keep command lines readable in logs.
+    fn command_line(self, program: &str, args: &str) -> String {
+        let plain = program
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+".contains(c));
+        let program = if plain {
+            program.to_string()
+        } else {
+            self.quote(program)
+        };
+
+        match self {
+            // A quoted string would be a value rather than a command in all three.
+            Self::Nushell => format!("^{program} {args}"),
+            Self::Xonsh => format!("![{program} {args}]"),
+            Self::PowerShell if !plain => format!("& {program} {args}"),
+            _ => format!("{program} {args}"),
+        }
+    }
+
+    /// Prints `MARKER` followed by the environment, and the separator between variables.
+    fn capture_line(self, shell: &UserShell) -> (String, char) {
+        match self {
+            Self::Posix | Self::Fish | Self::Nushell | Self::Xonsh => (
+                format!(
+                    "{}; {}",
+                    command_line(shell, "printf", &format!("'%s' {MARKER}")),
+                    command_line(shell, "env", "-0")
+                ),
+                '\0',
+            ),
+            Self::PowerShell => (
+                format!(
+                    "[Console]::Out.Write('{MARKER}'); Get-ChildItem env: | ForEach-Object {{ [Console]::Out.Write($_.Name + '=' + $_.Value + [char]0) }}"
+                ),
+                '\0',
+            ),
+            Self::Cmd => (format!("echo {MARKER}& set"), '\n'),
+        }
+    }
+}
+
+impl UserShell {
+    pub fn command(&self, line: &str) -> Command {
+        let mut cmd = Command::new(&self.path);
+        cmd.args(&self.args).arg(line);
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+        }
+
+        cmd
+    }
+}
+
+/// The lowercase file name without `.exe`, for either path separator.
+fn program_name(path: &str) -> String {
+    let name = path
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(path)
+        .to_lowercase();
+    match name.strip_suffix(".exe") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+#[cfg(windows)]
+fn default_shell() -> String {
+    let on_path = |name: &str| {
+        std::env::var_os("PATH").and_then(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(name))
+                .find(|path| path.is_file())
+        })
+    };
+
+    on_path("pwsh.exe")
+        .or_else(|| on_path("powershell.exe"))
+        .map(|path| path.display().to_string())
+        .or_else(|| std::env::var("ComSpec").ok())
+        .unwrap_or_else(|| "cmd.exe".to_string())
+}
+
+#[cfg(not(windows))]
+fn default_shell() -> String {
+    std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+}
+
+/// The shell from settings, falling back to `$SHELL`, or PowerShell and then cmd on Windows.
+pub fn resolve(app: &AppHandle) -> UserShell {
+    let settings = settings::load(app).unwrap_or_default();
+    let path = settings.shell_path.unwrap_or_else(default_shell);
+    let args = settings.shell_args.unwrap_or_else(|| {
+        ShellKind::of(&path)
+            .default_args()
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect()
+    });
+
+    UserShell { path, args }
+}
+
+/// Whether CLI commands get the shell's environment. Always on except on native Windows, where
+/// the sidecar only goes through the PowerShell profile when asked to.
+pub fn enabled(app: &AppHandle) -> bool {
+    !cfg!(windows) || get_windows_shell_env(app.clone()).unwrap_or(false)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_shell_config(app: AppHandle) -> UserShell {
+    resolve(&app)
+}
+
+/// `None` restores the default for that part. The environment is captured again with the new
+/// shell the next time a CLI command runs.
+#[tauri::command]
+#[specta::specta]
+pub fn set_shell_config(
+    app: AppHandle,
+    path: Option<String>,
+    args: Option<Vec<String>>,
+) -> Result<UserShell, String> {
+    if let Some(path) = &path
+        && !Path::new(path).is_file()
+    {
+        return Err(format!("{path} is not a file"));
+    }
+
+    settings::update(&app, |s| {
+        s.shell_path = path;
+        s.shell_args = args;
+        Ok(())
+    })?;
+
+    Ok(resolve(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_windows_shell_env(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.windows_shell_env)
+}
+
+/// Takes effect the next time the sidecar starts.
+#[tauri::command]
+#[specta::specta]
+pub fn set_windows_shell_env(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.windows_shell_env = enabled;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Resolves to `None` when the capture failed, so a broken shell isn't retried on every spawn.
+type Capture = Shared<BoxFuture<'static, Option<ShellEnv>>>;
+
+struct Snapshot {
+    shell: UserShell,
+    captured: Instant,
+    env: Capture,
+}
+
+static CACHE: Mutex<Option<Snapshot>> = Mutex::new(None);
+static REFRESHING: AtomicBool = AtomicBool::new(false);
+
+/// Formats a command running `program` for the shell, quoted the way that shell expects.
+pub fn command_line(shell: &UserShell, program: &str, args: &str) -> String {
+    ShellKind::of(&shell.path).command_line(program, args)
+}
+
+/// Captures the environment in the background so the first sidecar spawn doesn't wait on it.
+pub fn warm(shell: UserShell) {
+    // The capture runs on its own thread and the cache keeps its result.
+    drop(pending(&shell));
+}
+
+/// The environment a login shell sets up, captured once and cached. While a stale snapshot is
+/// refreshed in the background it is still returned, so only callers arriving before the first
+/// capture finished wait for it.
+pub async fn get(shell: &UserShell) -> Option<ShellEnv> {
+    pending(shell).await
+}
+
+/// The cached capture for `shell`, starting one on a blocking thread if there is none. The lock
+/// is only held to look the capture up, and concurrent callers share the one that is running.
+fn pending(shell: &UserShell) -> Capture {
+    let mut cache = CACHE.lock().unwrap();
+    if let Some(snapshot) = cache.as_ref().filter(|s| s.shell == *shell) {
+        if snapshot.captured.elapsed() >= CACHE_TTL && !REFRESHING.swap(true, Ordering::SeqCst) {
+            let shell = shell.clone();
+            let refresh = start_capture(shell.clone());
+            tauri::async_runtime::spawn(async move {
+                refresh.clone().await;
+                *CACHE.lock().unwrap() = Some(Snapshot {
+                    shell,
+                    captured: Instant::now(),
+                    env: refresh,
+                });
+                REFRESHING.store(false, Ordering::SeqCst);
+            });
+        }
+        return snapshot.env.clone();
+    }
+
+    let env = start_capture(shell.clone());
+    *cache = Some(Snapshot {
+        shell: shell.clone(),
+        captured: Instant::now(),
+        env: env.clone(),
+    });
+    env
+}
+
+fn start_capture(shell: UserShell) -> Capture {
+    let task = tauri::async_runtime::spawn_blocking(move || {
+        startup::measure("shell_env", || capture(&shell))
+    });
+    async move { task.await.ok().flatten() }.boxed().shared()
+}
+
+fn capture(shell: &UserShell) -> Option<ShellEnv> {
+    let started = Instant::now();
+    match run_capture(shell) {
+        Ok(env) => {
+            tracing::info!(
+                shell = shell.path,
+                count = env.len(),
+                elapsed = ?started.elapsed(),
+                "Captured login shell environment"
+            );
+            Some(Arc::new(env))
+        }
+        Err(e) => {
+            tracing::warn!(
+                shell = shell.path,
+                "{e}, spawning through the login shell instead"
+            );
+            None
+        }
+    }
+}
+
+fn run_capture(shell: &UserShell) -> Result<Vec<(String, String)>, String> {
+    let name = &shell.path;
+    let (line, separator) = ShellKind::of(name).capture_line(shell);
+    let mut child = shell
+        .command(&line)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {name}: {}", e))?;
+
+    let mut stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = tx.send(stdout.read_to_end(&mut output).map(|_| output));
+    });
+
+    let deadline = Instant::now() + CAPTURE_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{name} took longer than {CAPTURE_TIMEOUT:?} to start"
+                ));
+            }
+            Err(e) => return Err(format!("Failed to wait for {name}: {}", e)),
+        }
+    }
+
+    // A daemon started from the startup files can keep stdout open after the shell exits.
+    let output = rx
+        .recv_timeout(Duration::from_secs(1))
+        .map_err(|_| format!("{name} left its output open"))?
+        .map_err(|e| format!("Failed to read the shell environment: {}", e))?;
+    parse(&String::from_utf8_lossy(&output), separator)
+        .ok_or_else(|| format!("{name} did not print its environment"))
+}
+
+fn parse(output: &str, separator: char) -> Option<Vec<(String, String)>> {
+    let (_, env) = output.split_once(MARKER)?;
+    Some(
+        env.split(separator)
+            .map(|entry| entry.trim_end_matches('\r'))
+            .filter_map(|entry| entry.split_once('='))
+            .filter(|(name, _)| !name.is_empty() && !SKIPPED.contains(name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_skips_banner_and_shell_state() {
+        let output =
+            format!("Welcome!\n{MARKER}PATH=/usr/bin:/opt/bin\0MULTI=a\nb\0SHLVL=2\0EQ=x=y\0");
+        let env = parse(&output, '\0').unwrap();
+        assert_eq!(
+            env,
+            vec![
+                ("PATH".to_string(), "/usr/bin:/opt/bin".to_string()),
+                ("MULTI".to_string(), "a\nb".to_string()),
+                ("EQ".to_string(), "x=y".to_string()),
+            ]
+        );
+        assert_eq!(parse("no marker", '\0'), None);
+
+        let output = format!("{MARKER}\r\nPath=C:\\Windows\r\nPROMPT=$P$G\r\n");
+        assert_eq!(
+            parse(&output, '\n').unwrap(),
+            vec![
+                ("Path".to_string(), "C:\\Windows".to_string()),
+                ("PROMPT".to_string(), "$P$G".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_args_follow_the_shell() {
+        assert_eq!(ShellKind::of("/bin/zsh").default_args(), ["-il", "-c"]);
+        assert_eq!(
+            ShellKind::of("C:\\Program Files\\PowerShell\\7\\pwsh.exe"),
+            ShellKind::PowerShell
+        );
+        assert_eq!(ShellKind::of("CMD.EXE"), ShellKind::Cmd);
+        assert_eq!(ShellKind::of("/opt/homebrew/bin/fish"), ShellKind::Fish);
+        assert_eq!(ShellKind::of("/usr/bin/nu"), ShellKind::Nushell);
+        assert_eq!(ShellKind::of("/usr/bin/xonsh"), ShellKind::Xonsh);
+    }
+
+    #[test]
+    fn command_line_quotes_only_when_needed() {
+        let shell = |path: &str| UserShell {
+            path: path.to_string(),
+            args: vec![],
+        };
+
+        assert_eq!(
+            command_line(&shell("/bin/zsh"), "/opt/opencode-cli", "serve"),
+            "/opt/opencode-cli serve"
+        );
+        assert_eq!(
+            command_line(
+                &shell("/usr/bin/fish"),
+                "/Applications/Open Code/cli",
+                "serve"
+            ),
+            "'/Applications/Open Code/cli' serve"
+        );
+        assert_eq!(
+            command_line(&shell("/usr/local/bin/nu"), "env", "-0"),
+            "^env -0"
+        );
+    }
+
+    #[test]
+    fn command_line_quotes_for_each_shell() {
+        let shell = |path: &str| UserShell {
+            path: path.to_string(),
+            args: vec![],
+        };
+        let program = r"/Users/o'neil/Open Code\cli";
+
+        for (shell_path, expected) in [
+            ("/bin/zsh", r"'/Users/o'\''neil/Open Code\cli' serve"),
+            ("/bin/bash", r"'/Users/o'\''neil/Open Code\cli' serve"),
+            ("/usr/bin/fish", r"'/Users/o\'neil/Open Code\\cli' serve"),
+            (
+                "/usr/local/bin/nu",
+                r#"^"/Users/o'neil/Open Code\\cli" serve"#,
+            ),
+            (
+                "/usr/bin/xonsh",
+                r"!['/Users/o\'neil/Open Code\\cli' serve]",
+            ),
+            ("/usr/bin/pwsh", r"& '/Users/o''neil/Open Code\cli' serve"),
+        ] {
+            assert_eq!(command_line(&shell(shell_path), program, "serve"), expected);
+        }
+
+        let spaces = "/opt/Open Code/cli";
+        assert_eq!(
+            command_line(&shell("/usr/local/bin/nu"), spaces, "serve"),
+            "^'/opt/Open Code/cli' serve"
+        );
+        assert_eq!(
+            command_line(&shell("/bin/bash"), "/opt/$HOME `x`/cli", "serve"),
+            "'/opt/$HOME `x`/cli' serve"
+        );
+        assert_eq!(
+            command_line(&shell("/usr/bin/xonsh"), "env", "-0"),
+            "![env -0]"
+        );
+    }
+}
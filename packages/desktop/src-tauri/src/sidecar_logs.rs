@@ -0,0 +1,261 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{alerts, logging::LogLevel};
+
+const CAPACITY: usize = 5_000;
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A single line of sidecar output. `seq` keeps increasing across restarts, so it can be used as
+/// the `offset` of the next `get_sidecar_logs` call.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct SidecarLog {
+    pub seq: u32,
+    pub stream: LogStream,
+    pub line: String,
+    pub timestamp: String,
+    /// Set when the line is a log entry the server wrote, rather than other output.
+    pub level: Option<LogLevel>,
+    pub message: Option<String>,
+    pub request_id: Option<String>,
+    pub session_id: Option<String>,
+}
+
+/// What could be read out of a line of sidecar output.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogFields {
+    pub level: Option<LogLevel>,
+    pub message: Option<String>,
+    pub request_id: Option<String>,
+    pub session_id: Option<String>,
+}
+
+const REQUEST_ID_KEYS: [&str; 3] = ["requestID", "requestId", "request_id"];
+const SESSION_ID_KEYS: [&str; 3] = ["sessionID", "sessionId", "session_id"];
+
+fn parse_level(level: &str) -> Option<LogLevel> {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" | "debug" => Some(LogLevel::Debug),
+        "info" => Some(LogLevel::Info),
+        "warn" | "warning" => Some(LogLevel::Warn),
+        "error" | "fatal" => Some(LogLevel::Error),
+        _ => None,
+    }
+}
+
+/// A JSON log line, with pino's numeric levels understood too.
+fn parse_json(line: &str) -> Option<LogFields> {
+    let Value::Object(entry) = serde_json::from_str(line).ok()? else {
+        return None;
+    };
+    let string = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| entry.get(*key)?.as_str().map(String::from))
+    };
+
+    let level = match entry.get("level").or_else(|| entry.get("severity")) {
+        Some(Value::String(level)) => parse_level(level),
+        Some(Value::Number(level)) => level.as_u64().map(|level| match level {
+            ..=20 => LogLevel::Debug,
+            21..=30 => LogLevel::Info,
+            31..=40 => LogLevel::Warn,
+            _ => LogLevel::Error,
+        }),
+        _ => None,
+    };
+
+    Some(LogFields {
+        level,
+        message: string(&["message", "msg"]),
+        request_id: string(&REQUEST_ID_KEYS),
+        session_id: string(&SESSION_ID_KEYS),
+    })
+}
+
+/// A line in the server's text format: `INFO  2025-01-01T00:00:00 +5ms key=value message`.
+fn parse_text(line: &str) -> Option<LogFields> {
+    let (level, rest) = line.split_once(' ')?;
+    let level = parse_level(level)?;
+    let field = |keys: &[&str]| {
+        rest.split_whitespace().find_map(|word| {
+            let (key, value) = word.split_once('=')?;
+            keys.contains(&key).then(|| value.to_string())
+        })
+    };
+
+    let mut words = rest.trim_start().splitn(3, ' ');
+    let message = match (words.next(), words.next(), words.next()) {
+        (Some(_), Some(diff), Some(message)) if diff.starts_with('+') => message,
+        _ => rest.trim_start(),
+    };
+
+    Some(LogFields {
+        level: Some(level),
+        message: Some(message.to_string()),
+        request_id: field(&REQUEST_ID_KEYS),
+        session_id: field(&SESSION_ID_KEYS),
+    })
+}
+
+/// Reads the level and ids out of a line the server logged, in JSON or its text format.
+pub fn parse(line: &str) -> LogFields {
+    let line = line.trim();
+    parse_json(line)
+        .or_else(|| parse_text(line))
+        .unwrap_or_default()
+}
+
+/// Logs a line of sidecar output at the level the server gave it.
+fn trace(fields: &LogFields, line: &str) {
+    let request_id = fields.request_id.as_deref();
+    let session_id = fields.session_id.as_deref();
+    match fields.level {
+        Some(LogLevel::Debug) => tracing::debug!(request_id, session_id, "{line}"),
+        Some(LogLevel::Warn) => tracing::warn!(request_id, session_id, "{line}"),
+        Some(LogLevel::Error) => tracing::error!(request_id, session_id, "{line}"),
+        Some(LogLevel::Info) | None => tracing::info!(request_id, session_id, "{line}"),
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    lines: VecDeque<SidecarLog>,
+    next_seq: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct SidecarLogs {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SidecarLogs {
+    fn push(&self, stream: LogStream, line: String, fields: LogFields) -> SidecarLog {
+        let mut inner = self.inner.lock().unwrap();
+        let entry = SidecarLog {
+            seq: inner.next_seq,
+            stream,
+            line,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            level: fields.level,
+            message: fields.message,
+            request_id: fields.request_id,
+            session_id: fields.session_id,
+        };
+
+        inner.next_seq = inner.next_seq.wrapping_add(1);
+        if inner.lines.len() == CAPACITY {
+            inner.lines.pop_front();
+        }
+        inner.lines.push_back(entry.clone());
+
+        entry
+    }
+
+    /// The last `limit` lines, oldest first.
+    pub fn tail(&self, limit: usize) -> Vec<SidecarLog> {
+        let inner = self.inner.lock().unwrap();
+        let skip = inner.lines.len().saturating_sub(limit);
+        inner.lines.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn range(&self, offset: u32, limit: usize) -> Vec<SidecarLog> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .lines
+            .iter()
+            .skip_while(|entry| entry.seq < offset)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Logs a line of sidecar output, records it and forwards it to the frontend.
+pub fn record(app: &AppHandle, stream: LogStream, line: String) {
+    let fields = parse(&line);
+    trace(&fields, &line);
+
+    let Some(logs) = app.try_state::<SidecarLogs>() else {
+        return;
+    };
+
+    let entry = logs.push(stream, line, fields);
+    alerts::check(app, &entry);
+    let _ = entry.emit(app);
+}
+
+/// Returns buffered lines starting at sequence number `offset`, oldest first.
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_logs(
+    logs: State<'_, SidecarLogs>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Vec<SidecarLog> {
+    let limit = limit.map_or(CAPACITY, |limit| limit as usize);
+    logs.range(offset.unwrap_or(0), limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_lines_past_capacity() {
+        let logs = SidecarLogs::default();
+        for i in 0..CAPACITY + 10 {
+            logs.push(LogStream::Stdout, i.to_string(), LogFields::default());
+        }
+
+        let all = logs.range(0, usize::MAX);
+        assert_eq!(all.len(), CAPACITY);
+        assert_eq!(all[0].seq, 10);
+
+        let page = logs.range(CAPACITY as u32, 3);
+        assert_eq!(
+            page.iter().map(|l| l.line.as_str()).collect::<Vec<_>>(),
+            ["5000", "5001", "5002"]
+        );
+    }
+
+    #[test]
+    fn parses_json_and_text_log_lines() {
+        let json =
+            parse(r#"{"level":"warn","msg":"slow","requestID":"req_1","sessionID":"ses_1"}"#);
+        assert_eq!(
+            json,
+            LogFields {
+                level: Some(LogLevel::Warn),
+                message: Some("slow".to_string()),
+                request_id: Some("req_1".to_string()),
+                session_id: Some("ses_1".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(r#"{"level":50,"msg":"boom"}"#).level,
+            Some(LogLevel::Error)
+        );
+
+        let text = parse("ERROR 2025-01-01T00:00:00 +5ms service=session sessionID=ses_2 failed");
+        assert_eq!(text.level, Some(LogLevel::Error));
+        assert_eq!(text.session_id.as_deref(), Some("ses_2"));
+        assert_eq!(
+            text.message.as_deref(),
+            Some("service=session sessionID=ses_2 failed")
+        );
+
+        assert_eq!(parse("Listening on 127.0.0.1:4096"), LogFields::default());
+    }
+}
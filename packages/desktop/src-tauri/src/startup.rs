@@ -0,0 +1,62 @@
+use std::{
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+static LAUNCHED: OnceLock<Instant> = OnceLock::new();
+static PHASES: Mutex<Vec<StartupPhase>> = Mutex::new(Vec::new());
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct StartupPhase {
+    pub name: String,
+    /// Milliseconds from launch until the phase began.
+    pub start_ms: u32,
+    pub duration_ms: u32,
+}
+
+/// Marks the moment phase start times are measured from. Call first thing on launch.
+pub fn launched() {
+    LAUNCHED.get_or_init(Instant::now);
+}
+
+/// Records a phase that began at `started` and has just finished. Only the first run of each
+/// phase is kept, so later restarts don't replace the launch timings.
+pub fn record(name: &str, started: Instant) {
+    let duration = started.elapsed();
+    let launched = *LAUNCHED.get_or_init(|| started);
+
+    let mut phases = PHASES.lock().unwrap();
+    if phases.iter().any(|phase| phase.name == name) {
+        return;
+    }
+
+    tracing::info!(phase = name, ?duration, "Startup phase finished");
+    phases.push(StartupPhase {
+        name: name.to_string(),
+        start_ms: started.saturating_duration_since(launched).as_millis() as u32,
+        duration_ms: duration.as_millis() as u32,
+    });
+}
+
+pub fn measure<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let result = f();
+    record(name, started);
+    result
+}
+
+pub async fn measure_async<T>(name: &str, future: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    let result = future.await;
+    record(name, started);
+    result
+}
+
+/// How long each startup phase took, in the order they began. Phases still running are left out.
+#[tauri::command]
+#[specta::specta]
+pub fn get_startup_timings() -> Vec<StartupPhase> {
+    let mut phases = PHASES.lock().unwrap().clone();
+    phases.sort_by_key(|phase| phase.start_ms);
+    phases
+}
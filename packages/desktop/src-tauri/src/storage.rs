@@ -0,0 +1,265 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    backup, cli, cli_config, cli_version, file_drop, logging,
+    wsl_path::{self, WslPathMode},
+};
+
+const SESSIONS_NOT_PRUNABLE: &str =
+    "Sessions can't be pruned, back them up and delete them instead";
+
+/// What the app keeps on disk, grouped by what it is for.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageCategory {
+    Logs,
+    /// The server's sessions, history and storage, which are never pruned.
+    Sessions,
+    Caches,
+    /// Cached CLI versions.
+    Binaries,
+    Backups,
+}
+
+impl StorageCategory {
+    const ALL: [Self; 5] = [
+        Self::Logs,
+        Self::Sessions,
+        Self::Caches,
+        Self::Binaries,
+        Self::Backups,
+    ];
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct CategoryUsage {
+    pub category: StorageCategory,
+    pub size_kb: u32,
+    pub files: u32,
+    pub paths: Vec<String>,
+    pub prunable: bool,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct StorageUsage {
+    pub total_kb: u32,
+    pub categories: Vec<CategoryUsage>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Size {
+    bytes: u64,
+    files: u32,
+}
+
+impl std::ops::AddAssign for Size {
+    fn add_assign(&mut self, other: Self) {
+        self.bytes += other.bytes;
+        self.files += other.files;
+    }
+}
+
+fn kb(bytes: u64) -> u32 {
+    (bytes / 1024).try_into().unwrap_or(u32::MAX)
+}
+
+/// Total size of the files under `path`, which may be a single file. Symlinks are not followed.
+fn size_of(path: &Path) -> Size {
+    let Ok(metadata) = path.symlink_metadata() else {
+        return Size::default();
+    };
+    if metadata.is_file() {
+        return Size {
+            bytes: metadata.len(),
+            files: 1,
+        };
+    }
+    if !metadata.is_dir() {
+        return Size::default();
+    }
+
+    let mut size = Size::default();
+    for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+        size += size_of(&entry.path());
+    }
+    size
+}
+
+/// Where the sidecar caches packages, under the `XDG_CACHE_HOME` it inherits, or in its home in
+/// WSL.
+fn cache_dir(app: &AppHandle) -> Option<PathBuf> {
+    if cfg!(windows) && cli::is_wsl_enabled(app) {
+        return wsl_path::translate(app, "~/.cache/opencode", WslPathMode::Windows)
+            .inspect_err(|e| tracing::debug!("Failed to resolve the WSL cache directory: {e}"))
+            .ok()
+            .map(PathBuf::from);
+    }
+    cli::cache_dir()
+}
+
+fn paths(app: &AppHandle, category: StorageCategory) -> Vec<PathBuf> {
+    let path = app.path();
+    match category {
+        StorageCategory::Logs => path.app_log_dir().into_iter().collect(),
+        StorageCategory::Sessions => backup::state_dir(app)
+            .into_iter()
+            .chain(backup::data_dir(app))
+            .collect(),
+        StorageCategory::Caches => path
+            .app_cache_dir()
+            .into_iter()
+            .chain(cache_dir(app))
+            .chain(cli_config::schema_cache(app))
+            .chain(file_drop::staging_dir(app))
+            .collect(),
+        StorageCategory::Binaries => cli_version::versions_dir().into_iter().collect(),
+        StorageCategory::Backups => backup::backups_dir(app).into_iter().collect(),
+    }
+}
+
+fn usage(app: &AppHandle) -> StorageUsage {
+    let categories = StorageCategory::ALL
+        .into_iter()
+        .map(|category| {
+            let paths = paths(app, category);
+            let mut size = Size::default();
+            for path in &paths {
+                size += size_of(path);
+            }
+            CategoryUsage {
+                category,
+                size_kb: kb(size.bytes),
+                files: size.files,
+                paths: paths
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+                prunable: category != StorageCategory::Sessions,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    StorageUsage {
+        total_kb: categories
+            .iter()
+            .fold(0u32, |total, c| total.saturating_add(c.size_kb)),
+        categories,
+    }
+}
+
+/// Removes the entries of `dir` other than `keep`, skipping those that are in use.
+fn clear_dir(dir: &Path, keep: Option<&Path>) {
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if keep == Some(path.as_path()) {
+            continue;
+        }
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        if let Err(e) = removed {
+            tracing::debug!(path = %path.display(), "Failed to prune: {e}");
+        }
+    }
+}
+
+fn prune(app: &AppHandle, category: StorageCategory) -> Result<(), String> {
+    match category {
+        StorageCategory::Sessions => return Err(SESSIONS_NOT_PRUNABLE.to_string()),
+        StorageCategory::Logs => {
+            let current = logging::current_path();
+            for dir in paths(app, category) {
+                clear_dir(&dir, current.as_deref());
+            }
+        }
+        StorageCategory::Caches => {
+            for path in paths(app, category) {
+                if path.is_dir() {
+                    clear_dir(&path, None);
+                } else {
+                    let _ = std::fs::remove_file(&path);
+                }
+            }
+        }
+        StorageCategory::Binaries => {
+            cli_version::prune_cli_versions(app.clone(), 0)?;
+        }
+        StorageCategory::Backups => {
+            for backup in backup::list_backups(app.clone())? {
+                if backup.automatic
+                    && let Err(e) = std::fs::remove_file(&backup.path)
+                {
+                    tracing::warn!(name = %backup.name, "Failed to prune state backup: {e}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How much space logs, sessions, caches, CLI binaries and backups take up.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_storage_usage(app: AppHandle) -> Result<StorageUsage, String> {
+    tokio::task::spawn_blocking(move || usage(&app))
+        .await
+        .map_err(|e| format!("Storage usage task failed: {}", e))
+}
+
+/// Reclaims the space taken by `categories`: old logs, caches, CLI versions nothing needs and
+/// automatic backups. Returns how many kilobytes were freed.
+#[tauri::command]
+#[specta::specta]
+pub async fn prune_storage(
+    app: AppHandle,
+    categories: Vec<StorageCategory>,
+) -> Result<u32, String> {
+    // Checked up front so nothing is pruned when the request is refused.
+    if categories.contains(&StorageCategory::Sessions) {
+        return Err(SESSIONS_NOT_PRUNABLE.to_string());
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let before = usage(&app).total_kb;
+        for category in &categories {
+            prune(&app, *category)?;
+        }
+        let freed = before.saturating_sub(usage(&app).total_kb);
+
+        tracing::info!(?categories, freed_kb = freed, "Pruned storage");
+        Ok(freed)
+    })
+    .await
+    .map_err(|e| format!("Prune task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_nested_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("a/b")).unwrap();
+        std::fs::write(dir.join("one"), [0; 10]).unwrap();
+        std::fs::write(dir.join("a/b/two"), [0; 5]).unwrap();
+
+        assert_eq!(
+            size_of(dir),
+            Size {
+                bytes: 15,
+                files: 2
+            }
+        );
+        assert_eq!(size_of(&dir.join("one")).bytes, 10);
+        assert_eq!(size_of(&dir.join("missing")), Size::default());
+
+        clear_dir(dir, Some(&dir.join("one")));
+        assert_eq!(size_of(dir).files, 1);
+    }
+}
@@ -0,0 +1,559 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+use tokio::{task::JoinHandle, time::timeout};
+
+use crate::{
+    ServerState,
+    cli::{self, CommandChild, SidecarExit, SidecarTerminated},
+    crash::{self, CrashLoop},
+    crash_reports, health, keychain,
+    lifecycle::{self, ServerLifecycle},
+    server, telemetry,
+    tls::TlsFiles,
+};
+
+pub const MAX_RESTARTS: u32 = 5;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(30);
+// A sidecar that stays up this long is considered stable again and gets a fresh retry budget.
+pub const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+pub struct SidecarSpec {
+    pub hostname: String,
+    pub port: u32,
+    pub password: String,
+    /// Set when the sidecar serves https.
+    pub tls: Option<TlsFiles>,
+    /// Working directory of the sidecar, when it serves a single project.
+    pub directory: Option<PathBuf>,
+    /// The WSL distro's address the sidecar listens on instead of `hostname`, when WSL doesn't
+    /// forward localhost to it. `server::spawn_local_server` fills it in on every spawn.
+    pub wsl_address: Option<String>,
+    /// The unix socket or named pipe the sidecar listens on instead of `port`, which is then a
+    /// bridge to it.
+    pub socket: Option<PathBuf>,
+    /// Loopback port of the plain http bridge to an https sidecar, see `tls::bridge`.
+    pub bridge_port: Option<u32>,
+}
+
+impl SidecarSpec {
+    /// Url to reach the sidecar at, which differs from the bind address for wildcard hostnames.
+    pub fn url(&self) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        let host = self.wsl_address.as_deref().unwrap_or(&self.hostname);
+        format!(
+            "{scheme}://{}:{}",
+            server::normalize_hostname_for_url(host),
+            self.port
+        )
+    }
+
+    /// Url the webview reaches the sidecar at, which goes through the bridge for https.
+    pub fn local_url(&self) -> String {
+        match self.bridge_port {
+            Some(port) => format!("http://127.0.0.1:{port}"),
+            None => self.url(),
+        }
+    }
+}
+
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SidecarRestart {
+    Scheduled {
+        attempt: u32,
+        max_attempts: u32,
+        delay_ms: u32,
+        code: Option<i32>,
+        signal: Option<i32>,
+    },
+    Restarted {
+        attempt: u32,
+    },
+    GaveUp {
+        attempts: u32,
+    },
+}
+
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerRestartProgress {
+    Stopping,
+    Starting,
+    Ready,
+    Failed { message: String },
+}
+
+/// The supervisor task watching the current sidecar, along with what it needs to respawn it.
+pub struct Supervised {
+    spec: SidecarSpec,
+    task: JoinHandle<()>,
+}
+
+/// Starts supervising the sidecar, replacing any previous supervisor.
+pub fn spawn(app: AppHandle, spec: SidecarSpec, exit: SidecarExit) {
+    let shared = SharedServer {
+        app: app.clone(),
+        spec: spec.clone(),
+    };
+    let task = tokio::spawn(supervise(shared, exit));
+    let previous = app
+        .state::<ServerState>()
+        .supervisor
+        .lock()
+        .unwrap()
+        .replace(Supervised { spec, task });
+
+    if let Some(previous) = previous {
+        previous.task.abort();
+    }
+}
+
+/// The spec of the sidecar currently being supervised, if the desktop app spawned one.
+pub fn current_spec(app: &AppHandle) -> Option<SidecarSpec> {
+    app.state::<ServerState>()
+        .supervisor
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|supervised| supervised.spec.clone())
+}
+
+/// Calls the server the app manages and returns the parsed JSON response.
+pub async fn request(
+    app: &AppHandle,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+    timeout: Duration,
+) -> Result<serde_json::Value, String> {
+    request_in(app, None, method, path, body, timeout).await
+}
+
+/// Like `request`, for the project in `directory` instead of the server's own directory.
+pub async fn request_in(
+    app: &AppHandle,
+    directory: Option<&std::path::Path>,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+    timeout: Duration,
+) -> Result<serde_json::Value, String> {
+    let Some(spec) = current_spec(app) else {
+        return Err("The server is not managed by the desktop app".to_string());
+    };
+    let mut url = reqwest::Url::parse(&spec.url())
+        .and_then(|url| url.join(path))
+        .map_err(|e| format!("Invalid server url: {}", e))?;
+    if let Some(directory) = directory {
+        url.query_pairs_mut()
+            .append_pair("directory", &directory.to_string_lossy());
+    }
+    let client = server::client_for(&url, timeout)
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut req = client
+        .request(method, url)
+        .basic_auth("opencode", Some(&spec.password));
+    if let Some(body) = body {
+        req = req
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+    }
+
+    let response = req
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the server: {}", e))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read the server response: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("The server refused {path} ({status}): {text}"));
+    }
+    if text.is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Failed to parse the server response: {}", e))
+}
+
+pub async fn wait_healthy(health_check: server::HealthCheck) -> Result<(), String> {
+    match timeout(HEALTH_TIMEOUT, health_check.0).await {
+        Ok(Ok(res)) => res,
+        Ok(Err(e)) => Err(format!("Health check task failed: {e}")),
+        Err(_) => Err("Health check timed out".to_string()),
+    }
+}
+
+/// Stops the sidecar and starts a fresh one on the same port, so changes to the environment or
+/// config are picked up without relaunching the app.
+#[tauri::command]
+#[specta::specta]
+pub async fn restart_server(app: AppHandle) -> Result<(), String> {
+    let res = restart(&app).await;
+    if let Err(message) = &res {
+        tracing::error!(%message, "Failed to restart server");
+        let _ = ServerRestartProgress::Failed {
+            message: message.clone(),
+        }
+        .emit(&app);
+    }
+    res
+}
+
+/// Restarts the supervised sidecar protected by a newly generated password, which is the only
+/// way the server password is rotated. The keychain only gets the password once the server runs
+/// with it, so the next launch can still authenticate when the restart fails.
+pub async fn rekey(app: &AppHandle) -> Result<(), String> {
+    let password = uuid::Uuid::new_v4().to_string();
+    let previous = match app
+        .state::<ServerState>()
+        .supervisor
+        .lock()
+        .unwrap()
+        .as_mut()
+    {
+        Some(supervised) => std::mem::replace(&mut supervised.spec.password, password.clone()),
+        None => return Err("The server is not managed by the desktop app".to_string()),
+    };
+
+    if let Err(e) = restart_server(app.clone()).await {
+        // Unless another restart replaced the spec meanwhile, go back to the password the
+        // keychain still has.
+        if let Some(supervised) = app
+            .state::<ServerState>()
+            .supervisor
+            .lock()
+            .unwrap()
+            .as_mut()
+            .filter(|supervised| supervised.spec.password == password)
+        {
+            supervised.spec.password = previous;
+        }
+        return Err(e);
+    }
+
+    keychain::set_server_password(&password)?;
+    tracing::info!("Rotated server password");
+    Ok(())
+}
+
+async fn restart(app: &AppHandle) -> Result<(), String> {
+    restart_with(app, async { Ok(()) }).await
+}
+
+/// Stops the supervised sidecar, runs `while_stopped` and starts it again, even if that failed.
+pub async fn restart_with(
+    app: &AppHandle,
+    while_stopped: impl Future<Output = Result<(), String>>,
+) -> Result<(), String> {
+    let state = app.state::<ServerState>();
+    let previous = lifecycle::transition(app, ServerLifecycle::Stopping)?;
+    let Some(supervised) = state.supervisor.lock().unwrap().take() else {
+        lifecycle::revert(app, previous);
+        return Err("The server is not managed by the desktop app".to_string());
+    };
+    supervised.task.abort();
+    let mut spec = supervised.spec;
+
+    tracing::info!("Restarting server");
+    let _ = ServerRestartProgress::Stopping.emit(app);
+
+    let child = state.child.lock().unwrap().take();
+    if let Some(child) = child {
+        child.shutdown(cli::SHUTDOWN_GRACE).await;
+    }
+
+    let stopped = while_stopped.await;
+
+    // Surface a broken config now rather than as an opaque spawn failure.
+    if cli::get_config(app).await.is_none() {
+        tracing::warn!("Could not read CLI config before restarting");
+    }
+
+    let _ = ServerRestartProgress::Starting.emit(app);
+    let started = match lifecycle::transition(app, ServerLifecycle::Starting) {
+        Ok(_) => start(app, &mut spec).await,
+        Err(e) => Err(e),
+    };
+    let (child, exit) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            // Nothing watches the dead server, but keeping its spec lets it be restarted again.
+            state.supervisor.lock().unwrap().replace(Supervised {
+                spec,
+                task: tokio::spawn(async {}),
+            });
+            return Err(match stopped {
+                Ok(()) => e,
+                Err(stopped) => format!("{stopped}, and the server did not restart: {e}"),
+            });
+        }
+    };
+
+    state.set_child(Some(child));
+    let _ = lifecycle::transition(app, ServerLifecycle::Running);
+    health::start(app, spec.url(), Some(spec.password.clone()));
+    spawn(app.clone(), spec, exit);
+
+    tracing::info!("Server restarted");
+    telemetry::record(app, "server_restart");
+    let _ = ServerRestartProgress::Ready.emit(app);
+
+    stopped
+}
+
+/// Spawns the sidecar for a restart and waits for it to become healthy.
+async fn start(
+    app: &AppHandle,
+    spec: &mut SidecarSpec,
+) -> Result<(CommandChild, SidecarExit), String> {
+    let (child, health_check, exit) = server::spawn_local_server(app.clone(), spec)
+        .await
+        .inspect_err(|_| crashed(app))?;
+
+    if let Err(e) = wait_healthy(health_check).await {
+        let _ = child.kill();
+        crashed(app);
+        return Err(format!("Restarted server failed to become healthy: {e}"));
+    }
+    Ok((child, exit))
+}
+
+/// A sidecar `supervise` restarts, and how it reports on it.
+pub trait Supervisee: Send + 'static {
+    fn app(&self) -> &AppHandle;
+    /// The spec to restart it with, or `None` once it was stopped on purpose.
+    fn spec(&self) -> Option<SidecarSpec>;
+    /// The project it serves, for crash reports and logs.
+    fn directory(&self) -> Option<&Path> {
+        None
+    }
+    /// It exited without being asked to.
+    fn exited(&self) {}
+    /// Restarts stopped, after `attempts` of them.
+    fn gave_up(&self, attempts: u32);
+    /// Restart `attempt` runs after `delay`.
+    fn scheduled(&self, _attempt: u32, _delay: Duration, _exit: Option<&SidecarTerminated>) {}
+    /// Whether the restart may still go ahead once the delay is over.
+    fn may_restart(&self) -> bool {
+        self.spec().is_some()
+    }
+    /// The restarted sidecar failed its health check.
+    fn unhealthy(&self) {}
+    /// Takes over the healthy restarted child and the spec it was started with, or hands the
+    /// child back when it is no longer wanted.
+    fn restarted(
+        &self,
+        child: CommandChild,
+        spec: SidecarSpec,
+        attempt: u32,
+    ) -> Option<CommandChild>;
+}
+
+/// The app's shared sidecar, whose child lives in the server state.
+struct SharedServer {
+    app: AppHandle,
+    spec: SidecarSpec,
+}
+
+impl Supervisee for SharedServer {
+    fn app(&self) -> &AppHandle {
+        &self.app
+    }
+
+    // `kill_sidecar` takes the child out of the server state, so a missing child means the
+    // sidecar was stopped on purpose.
+    fn spec(&self) -> Option<SidecarSpec> {
+        is_supervised(&self.app).then(|| self.spec.clone())
+    }
+
+    fn exited(&self) {
+        crashed(&self.app);
+    }
+
+    fn gave_up(&self, attempts: u32) {
+        self.app.state::<ServerState>().set_child(None);
+        let _ = SidecarRestart::GaveUp { attempts }.emit(&self.app);
+    }
+
+    fn scheduled(&self, attempt: u32, delay: Duration, exit: Option<&SidecarTerminated>) {
+        let _ = SidecarRestart::Scheduled {
+            attempt,
+            max_attempts: MAX_RESTARTS,
+            delay_ms: delay.as_millis() as u32,
+            code: exit.and_then(|p| p.code),
+            signal: exit.and_then(|p| p.signal),
+        }
+        .emit(&self.app);
+    }
+
+    fn may_restart(&self) -> bool {
+        if !is_supervised(&self.app) {
+            return false;
+        }
+        // Someone else is already stopping or restarting it.
+        lifecycle::transition(&self.app, ServerLifecycle::Starting)
+            .inspect_err(|err| tracing::info!(%err, "Not restarting sidecar"))
+            .is_ok()
+    }
+
+    fn unhealthy(&self) {
+        crashed(&self.app);
+    }
+
+    fn restarted(
+        &self,
+        child: CommandChild,
+        spec: SidecarSpec,
+        attempt: u32,
+    ) -> Option<CommandChild> {
+        if !is_supervised(&self.app) {
+            return Some(child);
+        }
+        let state = self.app.state::<ServerState>();
+        state.set_child(Some(child));
+        // The sidecar may be reached at a new WSL address now.
+        if let Some(supervised) = state.supervisor.lock().unwrap().as_mut()
+            && supervised.spec.url() != spec.url()
+        {
+            health::start(&self.app, spec.url(), Some(spec.password.clone()));
+            supervised.spec = spec;
+        }
+        let _ = lifecycle::transition(&self.app, ServerLifecycle::Running);
+        telemetry::record(&self.app, "server_restart");
+        let _ = SidecarRestart::Restarted { attempt }.emit(&self.app);
+        None
+    }
+}
+
+/// Restarts the sidecar with exponential backoff whenever it exits, until it is stopped on
+/// purpose, keeps crashing right after starting, or used up its attempts.
+pub async fn supervise(target: impl Supervisee, mut exit: SidecarExit) {
+    let app = target.app().clone();
+    let directory = target.directory().map(Path::to_path_buf);
+    let mut attempt = 0;
+    let mut started = Instant::now();
+    let mut crash_loop = CrashLoop::default();
+
+    loop {
+        let payload = exit.await.ok();
+
+        let Some(mut spec) = target.spec() else {
+            tracing::info!(?directory, "Sidecar stopped, not restarting");
+            return;
+        };
+        target.exited();
+        crash_reports::record_exit(directory.as_deref(), payload.as_ref(), started.elapsed());
+
+        if crash_loop.exited(started.elapsed()) {
+            crash::report(&app, directory.as_deref(), payload);
+            target.gave_up(attempt);
+            return;
+        }
+
+        if started.elapsed() >= STABLE_UPTIME {
+            attempt = 0;
+        }
+        attempt += 1;
+
+        if attempt > MAX_RESTARTS {
+            tracing::error!(
+                ?directory,
+                attempts = MAX_RESTARTS,
+                "Sidecar keeps crashing, giving up"
+            );
+            target.gave_up(MAX_RESTARTS);
+            return;
+        }
+
+        let delay = backoff_delay(attempt);
+        tracing::warn!(
+            ?directory,
+            attempt,
+            ?delay,
+            code = ?payload.as_ref().and_then(|p| p.code),
+            signal = ?payload.as_ref().and_then(|p| p.signal),
+            "Sidecar terminated unexpectedly, scheduling restart"
+        );
+        target.scheduled(attempt, delay, payload.as_ref());
+
+        tokio::time::sleep(delay).await;
+
+        if !target.may_restart() {
+            tracing::info!(
+                ?directory,
+                "Sidecar stopped during restart backoff, not restarting"
+            );
+            return;
+        }
+
+        let (child, health_check, next_exit) =
+            match server::spawn_local_server(app.clone(), &mut spec).await {
+                Ok(spawned) => spawned,
+                // Spawning fails the same way on every attempt, so there is no point retrying.
+                Err(err) => {
+                    tracing::error!(?directory, attempt, %err, "Failed to restart sidecar");
+                    target.unhealthy();
+                    target.gave_up(attempt);
+                    return;
+                }
+            };
+        exit = next_exit;
+        started = Instant::now();
+
+        if let Err(err) = wait_healthy(health_check).await {
+            tracing::warn!(?directory, attempt, %err, "Restarted sidecar failed to become healthy");
+            let _ = child.kill();
+            target.unhealthy();
+            continue;
+        }
+
+        if let Some(child) = target.restarted(child, spec, attempt) {
+            child.shutdown(cli::SHUTDOWN_GRACE).await;
+            return;
+        }
+        tracing::info!(?directory, attempt, "Sidecar restarted");
+    }
+}
+
+fn crashed(app: &AppHandle) {
+    let _ = lifecycle::transition(app, ServerLifecycle::Crashed);
+}
+
+fn is_supervised(app: &AppHandle) -> bool {
+    app.try_state::<ServerState>()
+        .is_some_and(|state| state.child.lock().unwrap().is_some())
+}
+
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    BASE_DELAY.saturating_mul(factor).min(MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_each_attempt() {
+        assert_eq!(backoff_delay(1), Duration::from_millis(500));
+        assert_eq!(backoff_delay(2), Duration::from_secs(1));
+        assert_eq!(backoff_delay(3), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff_delay(10), MAX_DELAY);
+        assert_eq!(backoff_delay(u32::MAX), MAX_DELAY);
+    }
+}
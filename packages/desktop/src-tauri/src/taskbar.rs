@@ -0,0 +1,183 @@
+use std::{sync::Mutex, time::Duration};
+
+use tauri::{
+    AppHandle, Manager, State,
+    window::{ProgressBarState, ProgressBarStatus},
+};
+
+use crate::{
+    update_guard,
+    windows::{MainWindow, ProjectWindow},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskbarStatus {
+    Normal,
+    /// Working without a known end. Shown as `Normal` outside Windows.
+    Indeterminate,
+    Paused,
+    Error,
+}
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct TaskbarProgress {
+    pub status: TaskbarStatus,
+    /// From 0 to 100, or unset for indeterminate progress.
+    pub percent: Option<u32>,
+}
+
+/// What the dock or taskbar shows, and what it is derived from.
+#[derive(Clone, Copy, serde::Serialize, specta::Type, Debug, Default, PartialEq, Eq)]
+pub struct TaskbarActivity {
+    /// Sessions the app's servers are working on, as last polled.
+    pub busy_sessions: u32,
+    /// Set by the frontend in place of the busy session count.
+    pub badge: Option<u32>,
+    /// Set by the frontend in place of the busy indicator.
+    pub progress: Option<TaskbarProgress>,
+}
+
+impl TaskbarActivity {
+    /// The badge and progress to show: the frontend's when it set them, otherwise the number of
+    /// busy sessions and an indeterminate bar while there are any.
+    fn shown(&self) -> (Option<u32>, Option<TaskbarProgress>) {
+        let busy = self.busy_sessions > 0;
+        let badge = self.badge.or(busy.then_some(self.busy_sessions));
+        let progress = self.progress.or(busy.then_some(TaskbarProgress {
+            status: TaskbarStatus::Indeterminate,
+            percent: None,
+        }));
+        (badge.filter(|count| *count > 0), progress)
+    }
+}
+
+#[derive(Default)]
+pub struct Taskbar(Mutex<TaskbarActivity>);
+
+fn progress_state(progress: Option<TaskbarProgress>) -> ProgressBarState {
+    let Some(progress) = progress else {
+        return ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        };
+    };
+    let status = match progress.status {
+        TaskbarStatus::Normal => ProgressBarStatus::Normal,
+        TaskbarStatus::Indeterminate => ProgressBarStatus::Indeterminate,
+        TaskbarStatus::Paused => ProgressBarStatus::Paused,
+        TaskbarStatus::Error => ProgressBarStatus::Error,
+    };
+    ProgressBarState {
+        status: Some(status),
+        progress: progress.percent.map(|percent| percent.min(100) as u64),
+    }
+}
+
+/// Shows `activity` on the main and project windows. Windows has no badge counts, so only the
+/// progress shows there.
+fn apply(app: &AppHandle, activity: TaskbarActivity) {
+    let (badge, progress) = activity.shown();
+    for (label, window) in app.webview_windows() {
+        if label != MainWindow::LABEL && !label.starts_with(ProjectWindow::LABEL_PREFIX) {
+            continue;
+        }
+        if let Err(e) = window.set_badge_count(badge.map(i64::from)) {
+            tracing::debug!(window = %label, "Failed to set the badge: {e}");
+        }
+        if let Err(e) = window.set_progress_bar(progress_state(progress)) {
+            tracing::debug!(window = %label, "Failed to set the progress bar: {e}");
+        }
+    }
+}
+
+fn update(app: &AppHandle, f: impl FnOnce(&mut TaskbarActivity)) {
+    let taskbar = app.state::<Taskbar>();
+    let (previous, activity) = {
+        let mut activity = taskbar.0.lock().unwrap();
+        let previous = *activity;
+        f(&mut activity);
+        (previous, *activity)
+    };
+    if previous.shown() != activity.shown() {
+        apply(app, activity);
+    }
+}
+
+/// Polls the app's servers for busy sessions and reflects them in the dock or taskbar.
+pub fn spawn(app: &AppHandle) {
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            let busy = update_guard::busy_sessions(&app).await.len();
+            update(&app, |activity| {
+                activity.busy_sessions = busy.try_into().unwrap_or(u32::MAX)
+            });
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_taskbar_activity(taskbar: State<'_, Taskbar>) -> TaskbarActivity {
+    *taskbar.0.lock().unwrap()
+}
+
+/// Shows `count` on the dock or taskbar icon instead of the busy session count. `None` goes back
+/// to the busy session count, and `0` hides the badge.
+#[tauri::command]
+#[specta::specta]
+pub fn set_taskbar_badge(app: AppHandle, count: Option<u32>) {
+    update(&app, |activity| activity.badge = count);
+}
+
+/// Shows `progress` on the dock or taskbar icon, such as while a response streams. `None` goes
+/// back to showing whether sessions are busy.
+#[tauri::command]
+#[specta::specta]
+pub fn set_taskbar_progress(
+    app: AppHandle,
+    progress: Option<TaskbarProgress>,
+) -> Result<(), String> {
+    if progress
+        .and_then(|p| p.percent)
+        .is_some_and(|percent| percent > 100)
+    {
+        return Err("Progress must be between 0 and 100".to_string());
+    }
+    update(&app, |activity| activity.progress = progress);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontend_values_take_precedence_over_busy_sessions() {
+        let idle = TaskbarActivity::default();
+        assert_eq!(idle.shown(), (None, None));
+
+        let busy = TaskbarActivity {
+            busy_sessions: 2,
+            ..Default::default()
+        };
+        let (badge, progress) = busy.shown();
+        assert_eq!(badge, Some(2));
+        assert_eq!(progress.unwrap().status, TaskbarStatus::Indeterminate);
+
+        let streaming = TaskbarProgress {
+            status: TaskbarStatus::Normal,
+            percent: Some(40),
+        };
+        let overridden = TaskbarActivity {
+            busy_sessions: 2,
+            badge: Some(0),
+            progress: Some(streaming),
+        };
+        assert_eq!(overridden.shown(), (None, Some(streaming)));
+    }
+}
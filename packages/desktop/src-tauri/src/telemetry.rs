@@ -0,0 +1,277 @@
+use std::{path::PathBuf, sync::Mutex, time::Duration};
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::{constants, proxy, settings};
+
+const QUEUE_FILE: &str = "usage-metrics.json";
+/// The oldest counts are dropped beyond this many, if uploads keep failing.
+const MAX_ENTRIES: usize = 500;
+const FIRST_UPLOAD_DELAY: Duration = Duration::from_secs(60);
+const UPLOAD_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The only events that are counted. Feature names are what the frontend may record.
+const EVENTS: [&str; 2] = ["app_start", "server_restart"];
+const FEATURES: [&str; 10] = [
+    "file_drop",
+    "git_stage",
+    "global_hotkey",
+    "quick_prompt",
+    "search",
+    "session_archive",
+    "session_export",
+    "terminal",
+    "tray",
+    "workspace_editor",
+];
+
+/// How often an event happened on one day. Nothing else about it is kept.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct UsageCount {
+    pub event: String,
+    /// The UTC date, as `YYYY-MM-DD`.
+    pub day: String,
+    pub count: u32,
+}
+
+/// Exactly what an upload sends. There is no id tying it to the user or the install.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct UsageReport {
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub counts: Vec<UsageCount>,
+}
+
+/// Counts waiting to be uploaded, oldest day first.
+#[derive(Default)]
+pub struct Telemetry(Mutex<Vec<UsageCount>>);
+
+fn add(queue: &mut Vec<UsageCount>, event: &str, day: &str) {
+    match queue.iter_mut().find(|c| c.event == event && c.day == day) {
+        Some(count) => count.count = count.count.saturating_add(1),
+        None => queue.push(UsageCount {
+            event: event.to_string(),
+            day: day.to_string(),
+            count: 1,
+        }),
+    }
+    if queue.len() > MAX_ENTRIES {
+        queue.sort_by(|a, b| a.day.cmp(&b.day));
+        let excess = queue.len() - MAX_ENTRIES;
+        queue.drain(..excess);
+    }
+}
+
+/// Takes the uploaded `sent` counts out of the queue, keeping whatever was recorded meanwhile.
+fn remove_sent(queue: &mut Vec<UsageCount>, sent: &[UsageCount]) {
+    for sent in sent {
+        if let Some(count) = queue
+            .iter_mut()
+            .find(|c| c.event == sent.event && c.day == sent.day)
+        {
+            count.count = count.count.saturating_sub(sent.count);
+        }
+    }
+    queue.retain(|count| count.count > 0);
+}
+
+fn queue_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(QUEUE_FILE))
+        .map_err(|e| format!("Failed to resolve the usage metrics queue: {}", e))
+}
+
+fn persist(app: &AppHandle, queue: &[UsageCount]) {
+    let res = queue_path(app).and_then(|path| {
+        let contents = serde_json::to_string(queue)
+            .map_err(|e| format!("Failed to serialize usage metrics: {}", e))?;
+        std::fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    });
+    if let Err(e) = res {
+        tracing::debug!("Failed to save usage metrics: {e}");
+    }
+}
+
+fn known(event: &str) -> bool {
+    EVENTS.contains(&event)
+        || event
+            .strip_prefix("feature.")
+            .is_some_and(|feature| FEATURES.contains(&feature))
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    settings::load(app).is_ok_and(|s| s.usage_metrics)
+}
+
+fn report(app: &AppHandle, counts: Vec<UsageCount>) -> UsageReport {
+    UsageReport {
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        counts,
+    }
+}
+
+/// Counts `event` for today, if the user opted in to usage metrics.
+pub fn record(app: &AppHandle, event: &str) {
+    if !known(event) {
+        tracing::debug!(event, "Not counting unknown usage event");
+        return;
+    }
+    if !enabled(app) {
+        return;
+    }
+    let Some(telemetry) = app.try_state::<Telemetry>() else {
+        return;
+    };
+    let day = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let mut queue = telemetry.0.lock().unwrap();
+    add(&mut queue, event, &day);
+    persist(app, &queue);
+}
+
+async fn upload(app: &AppHandle) -> Result<(), String> {
+    let Some(url) = constants::USAGE_METRICS_URL else {
+        return Ok(());
+    };
+    let counts = app.state::<Telemetry>().0.lock().unwrap().clone();
+    if counts.is_empty() || !enabled(app) {
+        return Ok(());
+    }
+
+    let body = serde_json::to_string(&report(app, counts.clone()))
+        .map_err(|e| format!("Failed to serialize usage metrics: {}", e))?;
+    proxy::http_client(app, UPLOAD_TIMEOUT)?
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to upload usage metrics: {}", e))?;
+
+    let telemetry = app.state::<Telemetry>();
+    let mut queue = telemetry.0.lock().unwrap();
+    remove_sent(&mut queue, &counts);
+    persist(app, &queue);
+    tracing::debug!(entries = counts.len(), "Uploaded usage metrics");
+    Ok(())
+}
+
+/// Loads the queue left by the last run, counts this start and uploads on an interval while
+/// the user is opted in.
+pub fn spawn(app: &AppHandle) {
+    let queue = queue_path(app)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<Vec<UsageCount>>(&contents).ok())
+        .unwrap_or_default();
+    *app.state::<Telemetry>().0.lock().unwrap() = queue;
+    record(app, "app_start");
+
+    let app = app.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(FIRST_UPLOAD_DELAY).await;
+        loop {
+            if let Err(e) = upload(&app).await {
+                tracing::debug!("{e}");
+            }
+            tokio::time::sleep(UPLOAD_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_usage_metrics(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.usage_metrics)
+}
+
+/// Whether coarse usage counts are recorded and uploaded. Turning it off drops the queue.
+#[tauri::command]
+#[specta::specta]
+pub fn set_usage_metrics(
+    app: AppHandle,
+    telemetry: State<'_, Telemetry>,
+    enabled: bool,
+) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.usage_metrics = enabled;
+        Ok(())
+    })?;
+    if !enabled {
+        let mut queue = telemetry.0.lock().unwrap();
+        queue.clear();
+        persist(&app, &queue);
+    }
+    tracing::info!(enabled, "Changed usage metrics consent");
+    Ok(())
+}
+
+/// What the next upload would send.
+#[tauri::command]
+#[specta::specta]
+pub fn get_pending_usage_report(app: AppHandle, telemetry: State<'_, Telemetry>) -> UsageReport {
+    report(&app, telemetry.0.lock().unwrap().clone())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn purge_usage_metrics(app: AppHandle, telemetry: State<'_, Telemetry>) {
+    let mut queue = telemetry.0.lock().unwrap();
+    queue.clear();
+    persist(&app, &queue);
+}
+
+/// Counts a use of one of the features the frontend reports on.
+#[tauri::command]
+#[specta::specta]
+pub fn record_feature_usage(app: AppHandle, feature: String) -> Result<(), String> {
+    if !FEATURES.contains(&feature.as_str()) {
+        return Err(format!("Unknown feature {feature:?}"));
+    }
+    record(&app, &format!("feature.{feature}"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_per_day_and_keeps_what_was_not_sent() {
+        let mut queue = Vec::new();
+        add(&mut queue, EVENTS[0], "2026-01-01");
+        add(&mut queue, EVENTS[0], "2026-01-01");
+        add(&mut queue, EVENTS[1], "2026-01-02");
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue[0].count, 2);
+
+        let sent = queue.clone();
+        // Recorded while the upload was in flight.
+        add(&mut queue, EVENTS[0], "2026-01-01");
+        remove_sent(&mut queue, &sent);
+        assert_eq!(
+            queue,
+            [UsageCount {
+                event: "app_start".to_string(),
+                day: "2026-01-01".to_string(),
+                count: 1,
+            }]
+        );
+
+        for day in 0..MAX_ENTRIES + 5 {
+            add(&mut queue, "feature.search", &format!("day-{day:04}"));
+        }
+        assert_eq!(queue.len(), MAX_ENTRIES);
+        assert!(queue.iter().all(|c| c.day != "2026-01-01"));
+
+        assert!(known("feature.search"));
+        assert!(!known("feature.unknown"));
+        assert!(!known("search"));
+    }
+}
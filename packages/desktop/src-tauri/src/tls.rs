@@ -0,0 +1,210 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::{server, server_socket, settings, supervisor};
+
+const CERT_FILE: &str = "cert.pem";
+const KEY_FILE: &str = "key.pem";
+
+// The certificate the local sidecar serves, trusted when the app checks its health.
+static TRUSTED: Mutex<Option<reqwest::Certificate>> = Mutex::new(None);
+
+/// Certificate and private key the sidecar serves https with.
+#[derive(Clone, Debug)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+fn tls_files(app: &AppHandle) -> Result<TlsFiles, String> {
+    let dir = app
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("tls");
+
+    Ok(TlsFiles {
+        cert: dir.join(CERT_FILE),
+        key: dir.join(KEY_FILE),
+    })
+}
+
+/// Returns the certificate files, generating a self-signed certificate on first use.
+pub fn ensure_certificate(app: &AppHandle) -> Result<TlsFiles, String> {
+    let files = tls_files(app)?;
+    if !files.cert.exists() || !files.key.exists() {
+        generate(&files)?;
+    }
+
+    let pem =
+        fs::read(&files.cert).map_err(|e| format!("Failed to read TLS certificate: {}", e))?;
+    let cert = reqwest::Certificate::from_pem(&pem)
+        .map_err(|e| format!("Failed to parse TLS certificate: {}", e))?;
+    *TRUSTED.lock().unwrap() = Some(cert);
+
+    Ok(files)
+}
+
+fn generate(files: &TlsFiles) -> Result<(), String> {
+    let hostname = tauri_plugin_os::hostname();
+    let names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+        "opencode.local".to_string(),
+        format!("{}.local", hostname.trim_end_matches(".local")),
+    ];
+
+    let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("Failed to generate TLS certificate: {}", e))?;
+
+    if let Some(dir) = files.cert.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create TLS directory: {}", e))?;
+    }
+    write_private(&files.key, key_pair.serialize_pem().as_bytes())
+        .map_err(|e| format!("Failed to write TLS key: {}", e))?;
+    fs::write(&files.cert, cert.pem())
+        .map_err(|e| format!("Failed to write TLS certificate: {}", e))?;
+
+    tracing::info!(path = %files.cert.display(), "Generated TLS certificate");
+    Ok(())
+}
+
+/// Writes a file only the current user can read.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// SHA-256 fingerprint of a PEM certificate, as colon separated hex.
+fn fingerprint(pem: &str) -> Result<String, String> {
+    let cert = pem::parse(pem).map_err(|e| format!("Failed to parse TLS certificate: {}", e))?;
+
+    Ok(Sha256::digest(cert.contents())
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":"))
+}
+
+/// The certificate to serve when the sidecar binds to `hostname`. Loopback servers stay on plain
+/// http, and a certificate that can't be created falls back to it with a warning.
+pub fn for_hostname(app: &AppHandle, hostname: &str) -> Option<TlsFiles> {
+    if server::is_loopback_hostname(hostname) || !get_local_server_tls(app.clone()).unwrap_or(false)
+    {
+        return None;
+    }
+
+    ensure_certificate(app)
+        .inspect_err(|e| tracing::warn!("{e}, serving plain http"))
+        .ok()
+}
+
+pub fn trusted_certificate() -> Option<reqwest::Certificate> {
+    TRUSTED.lock().unwrap().clone()
+}
+
+/// Serves the https sidecar as plain http on a loopback port, for the webview and its fetch,
+/// which don't trust the self-signed certificate. The bridge checks the sidecar against that
+/// certificate alone, and always connects to the sidecar of the current spec.
+pub async fn bridge(app: AppHandle) -> Result<u32, String> {
+    let pem = fs::read_to_string(tls_files(&app)?.cert)
+        .map_err(|e| format!("Failed to read TLS certificate: {}", e))?;
+    let cert = pem::parse(pem).map_err(|e| format!("Failed to parse TLS certificate: {}", e))?;
+    let mut roots = rustls::RootCertStore::empty();
+    roots
+        .add(cert.into_contents().into())
+        .map_err(|e| format!("Failed to trust TLS certificate: {}", e))?;
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| format!("Failed to configure TLS: {}", e))?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+
+    server_socket::forward(move || {
+        let app = app.clone();
+        let connector = connector.clone();
+        async move {
+            let url = supervisor::current_spec(&app)
+                .and_then(|spec| reqwest::Url::parse(&spec.url()).ok())
+                .ok_or_else(|| std::io::Error::other("No https server is running"))?;
+            let host = url.host_str().unwrap_or("127.0.0.1").to_string();
+            let port = url.port().unwrap_or(443);
+            let stream =
+                tokio::net::TcpStream::connect((host.trim_matches(['[', ']']), port)).await?;
+            // The address may be a WSL one the certificate doesn't name, but it names localhost.
+            let name = rustls::pki_types::ServerName::try_from("localhost")
+                .map_err(std::io::Error::other)?;
+            connector.connect(name, stream).await
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_local_server_tls(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.local_server_tls)
+}
+
+/// Takes effect the next time the local server starts listening on non-loopback addresses.
+#[tauri::command]
+#[specta::specta]
+pub fn set_local_server_tls(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.local_server_tls = enabled;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Fingerprint of the local server's certificate, for clients on other machines to pin.
+#[tauri::command]
+#[specta::specta]
+pub fn get_tls_fingerprint(app: AppHandle) -> Result<String, String> {
+    let files = ensure_certificate(&app)?;
+    let pem = fs::read_to_string(&files.cert)
+        .map_err(|e| format!("Failed to read TLS certificate: {}", e))?;
+
+    fingerprint(&pem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_matches_der_digest() {
+        let rcgen::CertifiedKey { cert, .. } =
+            rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let expected = Sha256::digest(cert.der());
+
+        let fingerprint = fingerprint(&cert.pem()).unwrap();
+
+        assert_eq!(fingerprint.split(':').count(), 32);
+        assert_eq!(
+            fingerprint.replace(':', ""),
+            expected
+                .iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<String>()
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use tauri::{
+    AppHandle, Manager, Wry,
+    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    tray::TrayIconBuilder,
+};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+use tauri_specta::Event;
+
+use crate::{
+    ServerState, backup,
+    crash::ServerCrash,
+    health::{HealthMonitor, HealthStatus, ServerHealthChanged},
+    supervisor::{self, ServerRestartProgress, SidecarRestart},
+    windows::{MainWindow, QuickPromptWindow},
+};
+
+const TRAY_ID: &str = "main";
+const OPEN_WINDOW: &str = "open-window";
+const QUICK_PROMPT: &str = "quick-prompt";
+const RESTART: &str = "restart-server";
+const OPEN_LOGS: &str = "open-logs";
+const COPY_URL: &str = "copy-server-url";
+const BACKUP_NOW: &str = "backup-now";
+const QUIT: &str = "quit";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServerStatus {
+    Starting,
+    Running,
+    Unhealthy,
+    Stopped,
+}
+
+impl ServerStatus {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Starting => "Server: starting",
+            Self::Running => "Server: running",
+            Self::Unhealthy => "Server: not responding",
+            Self::Stopped => "Server: stopped",
+        }
+    }
+}
+
+/// Menu items whose text or state follows the server.
+struct TrayItems {
+    status: MenuItem<Wry>,
+    copy_url: MenuItem<Wry>,
+    backup: MenuItem<Wry>,
+}
+
+pub fn server_status(app: &AppHandle) -> ServerStatus {
+    let health = app
+        .try_state::<HealthMonitor>()
+        .map(|monitor| monitor.snapshot());
+    // A server the app manages but has no process for was stopped or gave up restarting.
+    let stopped = supervisor::current_spec(app).is_some()
+        && app
+            .try_state::<ServerState>()
+            .is_some_and(|state| state.child.lock().unwrap().is_none());
+
+    match health.map(|h| h.status) {
+        _ if stopped => ServerStatus::Stopped,
+        Some(HealthStatus::Healthy) => ServerStatus::Running,
+        Some(HealthStatus::Unhealthy) => ServerStatus::Unhealthy,
+        Some(HealthStatus::Unknown) | None => ServerStatus::Starting,
+    }
+}
+
+fn backup_label(app: &AppHandle) -> String {
+    let last = backup::list_backups(app.clone())
+        .ok()
+        .and_then(|backups| backups.into_iter().next())
+        .and_then(|last| chrono::DateTime::parse_from_rfc3339(&last.created_at).ok());
+    match last {
+        Some(at) => format!(
+            "Last backup: {}",
+            at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+        ),
+        None => "Last backup: never".to_string(),
+    }
+}
+
+fn server_url(app: &AppHandle) -> Option<String> {
+    app.try_state::<HealthMonitor>()
+        .and_then(|monitor| monitor.snapshot().url)
+}
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let status = MenuItem::with_id(
+        app,
+        "server-status",
+        ServerStatus::Starting.label(),
+        false,
+        None::<&str>,
+    )?;
+    let copy_url = MenuItem::with_id(app, COPY_URL, "Copy Server URL", false, None::<&str>)?;
+    let backup = MenuItem::with_id(app, "backup-status", backup_label(app), false, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, OPEN_WINDOW, "Open OpenCode", true, None::<&str>)?,
+            &MenuItem::with_id(app, QUICK_PROMPT, "Quick Prompt", true, None::<&str>)?,
+            &MenuItem::with_id(app, RESTART, "Restart Server", true, None::<&str>)?,
+            &copy_url,
+            &MenuItem::with_id(app, OPEN_LOGS, "Open Logs", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &backup,
+            &MenuItem::with_id(app, BACKUP_NOW, "Back Up Now", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, QUIT, "Quit OpenCode", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("OpenCode")
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+
+    app.manage(TrayItems {
+        status,
+        copy_url,
+        backup,
+    });
+
+    ServerHealthChanged::listen(app, {
+        let app = app.clone();
+        move |_| refresh(&app)
+    });
+    SidecarRestart::listen(app, {
+        let app = app.clone();
+        move |_| refresh(&app)
+    });
+    ServerRestartProgress::listen(app, {
+        let app = app.clone();
+        move |_| refresh(&app)
+    });
+    ServerCrash::listen(app, {
+        let app = app.clone();
+        move |_| refresh(&app)
+    });
+
+    Ok(())
+}
+
+/// Updates the tray to the current server status.
+pub fn refresh(app: &AppHandle) {
+    let Some(items) = app.try_state::<TrayItems>() else {
+        return;
+    };
+
+    let status = server_status(app);
+    let _ = items.status.set_text(status.label());
+    let _ = items.copy_url.set_enabled(server_url(app).is_some());
+    let _ = items.backup.set_text(backup_label(app));
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("OpenCode - {}", status.label())));
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        OPEN_WINDOW => {
+            // Building a window from a menu handler can deadlock on Windows.
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = MainWindow::create(&app) {
+                    tracing::warn!("Failed to open window: {e}");
+                }
+            });
+        }
+        QUICK_PROMPT => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = QuickPromptWindow::toggle(&app) {
+                    tracing::warn!("Failed to open quick prompt: {e}");
+                }
+            });
+        }
+        RESTART => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = supervisor::restart_server(app.clone()).await;
+                refresh(&app);
+            });
+        }
+        BACKUP_NOW => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = backup::create_backup(app, None).await {
+                    tracing::warn!("Failed to back up state: {e}");
+                }
+            });
+        }
+        COPY_URL => {
+            if let Some(url) = server_url(app)
+                && let Err(e) = app.clipboard().write_text(url)
+            {
+                tracing::warn!("Failed to copy server URL: {e}");
+            }
+        }
+        OPEN_LOGS => {
+            let opened = app
+                .path()
+                .app_log_dir()
+                .map_err(|e| e.to_string())
+                .and_then(|dir| {
+                    app.opener()
+                        .open_path(dir.display().to_string(), None::<&str>)
+                        .map_err(|e| e.to_string())
+                });
+            if let Err(e) = opened {
+                tracing::warn!("Failed to open logs: {e}");
+            }
+        }
+        QUIT => app.exit(0),
+        _ => {}
+    }
+}
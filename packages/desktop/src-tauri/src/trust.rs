@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+
+use crate::{file_dialogs, instances, settings};
+
+/// A directory the user allowed the server to run in. Trust covers its subdirectories too.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq)]
+pub struct TrustedPath {
+    pub path: String,
+    pub trusted_at: String,
+}
+
+pub fn is_trusted(trusted: &[TrustedPath], directory: &Path) -> bool {
+    trusted
+        .iter()
+        .any(|entry| directory.starts_with(&entry.path))
+}
+
+/// Whether the user opened `path` in the app, by picking it in a dialog or trusting it, so its
+/// contents may be read on the frontend's behalf.
+pub fn is_opened(app: &AppHandle, path: &Path) -> Result<bool, String> {
+    Ok(
+        file_dialogs::is_granted(app, path)
+            || is_trusted(&settings::load(app)?.trusted_paths, path),
+    )
+}
+
+fn trust(app: &AppHandle, directory: &Path) -> Result<(), String> {
+    settings::update(app, |s| {
+        if !is_trusted(&s.trusted_paths, directory) {
+            s.trusted_paths.push(TrustedPath {
+                path: directory.to_string_lossy().to_string(),
+                trusted_at: chrono::Utc::now().to_rfc3339(),
+            });
+        }
+        Ok(())
+    })?;
+
+    tracing::info!(directory = %directory.display(), "Trusted project directory");
+    Ok(())
+}
+
+/// Asks the user to trust `directory` the first time a server is started in it, since the server
+/// loads the project's config, `.env` file and pinned CLI version. Fails when they decline.
+pub async fn ensure(app: &AppHandle, directory: &Path) -> Result<(), String> {
+    if is_trusted(&settings::load(app)?.trusted_paths, directory) {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Do you trust the files in {}?\n\nOpenCode will run in this folder and use its configuration, environment files and pinned CLI version.",
+        directory.display()
+    );
+    let confirmed = tokio::task::spawn_blocking({
+        let app = app.clone();
+        move || {
+            app.dialog()
+                .message(message)
+                .title("Trust this folder?")
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "Trust".to_string(),
+                    "Cancel".to_string(),
+                ))
+                .blocking_show()
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to ask for confirmation: {}", e))?;
+    if !confirmed {
+        return Err(format!("{} is not trusted", directory.display()));
+    }
+
+    trust(app, directory)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_trusted_paths(app: AppHandle) -> Result<Vec<TrustedPath>, String> {
+    Ok(settings::load(&app)?.trusted_paths)
+}
+
+/// Trusts `path` once the user confirms it in the same native dialog servers ask with, so the
+/// webview can't trust a directory on its own.
+#[tauri::command]
+#[specta::specta]
+pub async fn trust_path(app: AppHandle, path: String) -> Result<(), String> {
+    ensure(&app, &instances::canonical_directory(&path)?).await
+}
+
+/// Forgets that `path` was trusted, so opening it asks again. Servers already running in it keep
+/// running.
+#[tauri::command]
+#[specta::specta]
+pub fn revoke_trust(app: AppHandle, path: String) -> Result<(), String> {
+    let path = dunce::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(&path));
+    settings::update(&app, |s| {
+        s.trusted_paths
+            .retain(|entry| Path::new(&entry.path) != path);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trust_covers_subdirectories() {
+        let trusted = [TrustedPath {
+            path: "/home/me/work".to_string(),
+            trusted_at: String::new(),
+        }];
+
+        assert!(is_trusted(&trusted, Path::new("/home/me/work")));
+        assert!(is_trusted(&trusted, Path::new("/home/me/work/app")));
+        assert!(!is_trusted(&trusted, Path::new("/home/me/workshop")));
+        assert!(!is_trusted(&trusted, Path::new("/home/me")));
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager};
+use tauri_specta::Event;
+use tokio::{net::TcpStream, process::Command, task::JoinHandle};
+
+use crate::{port, remote::RemoteProfile, supervisor::backoff_delay};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+// A tunnel that stays up this long gets a fresh reconnect backoff.
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+// Reconnects in a row before the tunnel is given up on.
+const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+
+/// Reach the profile's server through `ssh -L` instead of connecting to it directly.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct SshTunnel {
+    /// Destination passed to ssh, e.g. `user@devbox` or a `Host` alias from `~/.ssh/config`.
+    pub destination: String,
+    pub port: Option<u32>,
+    pub identity_file: Option<String>,
+}
+
+impl SshTunnel {
+    /// Rejects destinations ssh would read as an option, like `-oProxyCommand=...`.
+    pub fn validate(&self) -> Result<(), String> {
+        let destination = self.destination.trim();
+        if destination.is_empty() {
+            return Err("SSH destination cannot be empty".to_string());
+        }
+        if destination
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control())
+        {
+            return Err(format!("Invalid SSH destination '{destination}'"));
+        }
+
+        let (user, host) = match destination.rsplit_once('@') {
+            Some((user, host)) => (Some(user), host),
+            None => (None, destination),
+        };
+        if user.is_some_and(|user| user.is_empty() || user.starts_with('-')) {
+            return Err(format!("Invalid SSH user in '{destination}'"));
+        }
+        if host.is_empty() || host.starts_with('-') {
+            return Err(format!("Invalid SSH host in '{destination}'"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SshTunnelStatus {
+    Connecting {
+        profile: String,
+    },
+    Connected {
+        profile: String,
+        local_port: u32,
+    },
+    Reconnecting {
+        profile: String,
+        attempt: u32,
+        delay_ms: u32,
+    },
+    Stopped {
+        profile: String,
+    },
+}
+
+struct ActiveTunnel {
+    profile: String,
+    task: JoinHandle<()>,
+}
+
+#[derive(Clone, Default)]
+pub struct TunnelManager(Arc<Mutex<Option<ActiveTunnel>>>);
+
+/// Opens a tunnel for `profile`, replacing any open one, and returns the local port forwarding
+/// to the remote server once ssh is listening on it.
+pub async fn open(
+    app: &AppHandle,
+    profile: &RemoteProfile,
+    ssh: &SshTunnel,
+) -> Result<u32, String> {
+    close(app);
+    ssh.validate()?;
+
+    let local_port = port::ephemeral_port("127.0.0.1")?;
+    let mut child = spawn_ssh(profile, ssh, local_port)?;
+    let _ = SshTunnelStatus::Connecting {
+        profile: profile.name.clone(),
+    }
+    .emit(app);
+
+    let ready = tokio::select! {
+        ready = wait_listening(local_port) => ready,
+        status = child.wait() => Err(format!(
+            "ssh exited before the tunnel was up ({})",
+            status.map_or_else(|e| e.to_string(), |s| s.to_string())
+        )),
+    };
+    if let Err(e) = ready {
+        let _ = child.start_kill();
+        return Err(format!(
+            "Failed to open SSH tunnel to {}: {e}",
+            ssh.destination
+        ));
+    }
+
+    tracing::info!(profile = %profile.name, local_port, "SSH tunnel open");
+    let _ = SshTunnelStatus::Connected {
+        profile: profile.name.clone(),
+        local_port,
+    }
+    .emit(app);
+
+    let task = tokio::spawn(supervise(
+        app.clone(),
+        profile.clone(),
+        ssh.clone(),
+        local_port,
+        child,
+    ));
+    if let Some(manager) = app.try_state::<TunnelManager>() {
+        *manager.0.lock().unwrap() = Some(ActiveTunnel {
+            profile: profile.name.clone(),
+            task,
+        });
+    }
+
+    Ok(local_port)
+}
+
+/// Closes the open tunnel, if any.
+pub fn close(app: &AppHandle) {
+    let Some(manager) = app.try_state::<TunnelManager>() else {
+        return;
+    };
+    let Some(tunnel) = manager.0.lock().unwrap().take() else {
+        return;
+    };
+
+    // Aborting drops the ssh child, which is spawned with `kill_on_drop`.
+    tunnel.task.abort();
+    tracing::info!(profile = %tunnel.profile, "SSH tunnel closed");
+    let _ = SshTunnelStatus::Stopped {
+        profile: tunnel.profile,
+    }
+    .emit(app);
+}
+
+async fn supervise(
+    app: AppHandle,
+    profile: RemoteProfile,
+    ssh: SshTunnel,
+    local_port: u32,
+    mut child: tokio::process::Child,
+) {
+    let mut attempt = 0;
+    let mut started = Instant::now();
+
+    loop {
+        let status = child.wait().await;
+        tracing::warn!(profile = %profile.name, ?status, "SSH tunnel dropped");
+
+        if started.elapsed() >= STABLE_UPTIME {
+            attempt = 0;
+        }
+        attempt += 1;
+        if attempt > MAX_RECONNECT_ATTEMPTS {
+            tracing::error!(profile = %profile.name, "Giving up on the SSH tunnel");
+            let _ = SshTunnelStatus::Stopped {
+                profile: profile.name.clone(),
+            }
+            .emit(&app);
+            return;
+        }
+
+        let delay = backoff_delay(attempt);
+        let _ = SshTunnelStatus::Reconnecting {
+            profile: profile.name.clone(),
+            attempt,
+            delay_ms: delay.as_millis() as u32,
+        }
+        .emit(&app);
+        tokio::time::sleep(delay).await;
+
+        child = match spawn_ssh(&profile, &ssh, local_port) {
+            Ok(child) => child,
+            Err(e) => {
+                tracing::error!(profile = %profile.name, %e, "Failed to respawn ssh");
+                continue;
+            }
+        };
+        started = Instant::now();
+
+        if wait_listening(local_port).await.is_ok() {
+            tracing::info!(profile = %profile.name, attempt, "SSH tunnel reconnected");
+            let _ = SshTunnelStatus::Connected {
+                profile: profile.name.clone(),
+                local_port,
+            }
+            .emit(&app);
+        }
+    }
+}
+
+fn spawn_ssh(
+    profile: &RemoteProfile,
+    ssh: &SshTunnel,
+    local_port: u32,
+) -> Result<tokio::process::Child, String> {
+    let mut cmd = Command::new("ssh");
+    cmd.args(ssh_args(profile, ssh, local_port))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(windows)]
+    cmd.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+
+    cmd.spawn().map_err(|e| format!("Failed to run ssh: {}", e))
+}
+
+fn ssh_args(profile: &RemoteProfile, ssh: &SshTunnel, local_port: u32) -> Vec<String> {
+    let mut args = vec![
+        "-N".to_string(),
+        "-L".to_string(),
+        format!(
+            "127.0.0.1:{local_port}:{}:{}",
+            profile.hostname, profile.port
+        ),
+        // Fail instead of prompting, there is no terminal to answer on.
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ExitOnForwardFailure=yes".to_string(),
+        "-o".to_string(),
+        "ServerAliveInterval=15".to_string(),
+        "-o".to_string(),
+        "ServerAliveCountMax=3".to_string(),
+    ];
+
+    if let Some(port) = ssh.port {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+    if let Some(identity) = &ssh.identity_file {
+        args.extend(["-i".to_string(), identity.clone()]);
+    }
+    args.extend(["--".to_string(), ssh.destination.trim().to_string()]);
+
+    args
+}
+
+async fn wait_listening(local_port: u32) -> Result<(), String> {
+    let deadline = Instant::now() + CONNECT_TIMEOUT;
+    while Instant::now() < deadline {
+        if TcpStream::connect(("127.0.0.1", local_port as u16))
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    Err("timed out waiting for ssh to listen".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwards_local_port_to_remote_server() {
+        let profile = RemoteProfile {
+            name: "devbox".to_string(),
+            hostname: "localhost".to_string(),
+            port: 4096,
+            secure: false,
+            ssh: None,
+        };
+        let ssh = SshTunnel {
+            destination: "me@devbox".to_string(),
+            port: Some(2222),
+            identity_file: None,
+        };
+
+        let args = ssh_args(&profile, &ssh, 50000);
+        assert_eq!(args[..3], ["-N", "-L", "127.0.0.1:50000:localhost:4096"]);
+        assert_eq!(args[args.len() - 4..], ["-p", "2222", "--", "me@devbox"]);
+    }
+
+    #[test]
+    fn rejects_destinations_that_look_like_options() {
+        let tunnel = |destination: &str| SshTunnel {
+            destination: destination.to_string(),
+            port: None,
+            identity_file: None,
+        };
+
+        assert!(tunnel("me@devbox").validate().is_ok());
+        assert!(tunnel("devbox").validate().is_ok());
+        assert!(tunnel("-oProxyCommand=touch /tmp/x").validate().is_err());
+        assert!(tunnel("-x@devbox").validate().is_err());
+        assert!(tunnel("me@-devbox").validate().is_err());
+        assert!(tunnel("me@").validate().is_err());
+        assert!(tunnel("me devbox").validate().is_err());
+        assert!(tunnel("").validate().is_err());
+    }
+}
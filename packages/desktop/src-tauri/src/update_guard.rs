@@ -0,0 +1,170 @@
+use std::{sync::Mutex, time::Duration};
+
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{cli_channel, instances::Instances, server, supervisor};
+
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Updates held back because the app's servers were working on sessions.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug, Default, PartialEq)]
+pub struct PendingUpdate {
+    /// The CLI version to install, applied on its own once the sessions finish.
+    pub cli_version: Option<String>,
+    /// The app version that was downloaded, installed once the user agrees.
+    pub app_version: Option<String>,
+    /// The sessions that were busy when last checked.
+    pub sessions: Vec<String>,
+}
+
+impl PendingUpdate {
+    fn is_empty(&self) -> bool {
+        self.cli_version.is_none() && self.app_version.is_none()
+    }
+}
+
+/// Sent when an update is held back for busy sessions.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct UpdateDeferred {
+    pub pending: PendingUpdate,
+}
+
+/// Sent once no session is busy anymore, so the UI can offer the pending app update.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct PendingUpdateReady {
+    pub pending: PendingUpdate,
+}
+
+#[derive(Default)]
+struct Inner {
+    pending: PendingUpdate,
+    waiting: bool,
+}
+
+#[derive(Default)]
+pub struct UpdateGuard(Mutex<Inner>);
+
+/// Ids of the sessions the app's servers are still working on. Servers that don't answer are
+/// skipped.
+pub async fn busy_sessions(app: &AppHandle) -> Vec<String> {
+    let mut specs = app
+        .try_state::<Instances>()
+        .map(|instances| instances.specs())
+        .unwrap_or_default();
+    specs.extend(supervisor::current_spec(app));
+
+    let mut sessions = Vec::new();
+    for spec in specs {
+        match server::busy_sessions(&spec.url(), Some(&spec.password)).await {
+            Ok(busy) => sessions.extend(busy),
+            Err(e) => tracing::warn!(url = %spec.url(), "Failed to check for busy sessions: {e}"),
+        }
+    }
+    sessions
+}
+
+fn update(app: &AppHandle, f: impl FnOnce(&mut PendingUpdate)) -> PendingUpdate {
+    let guard = app.state::<UpdateGuard>();
+    let mut inner = guard.0.lock().unwrap();
+    f(&mut inner.pending);
+    inner.pending.clone()
+}
+
+fn defer(app: &AppHandle, sessions: Vec<String>, f: impl FnOnce(&mut PendingUpdate)) {
+    let pending = update(app, |pending| {
+        f(pending);
+        pending.sessions = sessions;
+    });
+    tracing::info!(
+        cli = ?pending.cli_version,
+        app = ?pending.app_version,
+        sessions = pending.sessions.len(),
+        "Deferring update while sessions are busy"
+    );
+    let _ = UpdateDeferred {
+        pending: pending.clone(),
+    }
+    .emit(app);
+    wait_for_idle(app);
+}
+
+/// Holds back replacing the CLI with `version` while sessions are busy. Returns whether it was
+/// deferred, in which case it is installed once they finish.
+pub async fn defer_cli(app: &AppHandle, version: &str) -> bool {
+    let sessions = busy_sessions(app).await;
+    if sessions.is_empty() {
+        update(app, |pending| pending.cli_version = None);
+        return false;
+    }
+
+    defer(app, sessions, |pending| {
+        pending.cli_version = Some(version.to_string())
+    });
+    true
+}
+
+/// Records a downloaded app update that was not installed because of `sessions`.
+pub fn defer_app(app: &AppHandle, version: &str, sessions: Vec<String>) {
+    defer(app, sessions, |pending| {
+        pending.app_version = Some(version.to_string())
+    });
+}
+
+/// Forgets the pending app update, once it is installed or no longer applies.
+pub fn clear_app(app: &AppHandle) {
+    if let Some(guard) = app.try_state::<UpdateGuard>() {
+        guard.0.lock().unwrap().pending.app_version = None;
+    }
+}
+
+/// Polls the sessions until none is busy, then installs the pending CLI and tells the UI it can
+/// offer the pending app update.
+fn wait_for_idle(app: &AppHandle) {
+    {
+        let guard = app.state::<UpdateGuard>();
+        let mut inner = guard.0.lock().unwrap();
+        if inner.waiting {
+            return;
+        }
+        inner.waiting = true;
+    }
+
+    let app = app.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+            let sessions = busy_sessions(&app).await;
+            let idle = sessions.is_empty();
+            let pending = update(&app, |pending| pending.sessions = sessions);
+            if pending.is_empty() || !idle {
+                if pending.is_empty() {
+                    break;
+                }
+                continue;
+            }
+
+            tracing::info!("Sessions finished, applying pending update");
+            if pending.cli_version.is_some() {
+                update(&app, |pending| pending.cli_version = None);
+                if let Err(e) = cli_channel::sync(app.clone()).await {
+                    tracing::error!("Failed to apply pending CLI update: {e}");
+                }
+            }
+            if pending.app_version.is_some() {
+                let _ = PendingUpdateReady { pending }.emit(&app);
+            }
+            break;
+        }
+
+        app.state::<UpdateGuard>().0.lock().unwrap().waiting = false;
+    });
+}
+
+/// Updates waiting for busy sessions to finish, if any.
+#[tauri::command]
+#[specta::specta]
+pub fn get_pending_update(guard: State<'_, UpdateGuard>) -> Option<PendingUpdate> {
+    let inner = guard.0.lock().unwrap();
+    (!inner.pending.is_empty()).then(|| inner.pending.clone())
+}
@@ -0,0 +1,176 @@
+use std::{sync::Mutex, time::Duration};
+
+use tauri::{AppHandle, Manager, State};
+use tauri_specta::Event;
+
+use crate::{
+    ServerState,
+    lifecycle::{self, ServerLifecycle},
+    resources::{ResourceMonitor, ServerStats},
+    server, settings,
+    sidecar_logs::{SidecarLog, SidecarLogs},
+    supervisor,
+};
+
+const DEBUG_TIMEOUT: Duration = Duration::from_secs(3);
+const SNAPSHOT_LOG_LINES: usize = 200;
+/// Keeps a hung debug endpoint from filling the snapshot.
+const MAX_DEBUG_BYTES: usize = 256 * 1024;
+
+/// What the sidecar was doing when it stopped answering health checks.
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct StallSnapshot {
+    pub captured_at: String,
+    pub url: String,
+    pub pid: Option<u32>,
+    pub consecutive_failures: u32,
+    pub stats: Option<ServerStats>,
+    /// Output of the server's `/debug` endpoint, when it has one and still answers it.
+    pub debug: Option<String>,
+    /// The last lines of sidecar output, oldest first.
+    pub logs: Vec<SidecarLog>,
+}
+
+/// Sent when the sidecar is still running but no longer answers health checks.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct ServerStalled {
+    pub url: String,
+    pub consecutive_failures: u32,
+    /// Whether the server is being restarted, otherwise the UI can offer to.
+    pub restarting: bool,
+}
+
+#[derive(Default)]
+pub struct Watchdog(Mutex<Option<StallSnapshot>>);
+
+/// The supervised sidecar serving `url`, if its process is still running.
+fn running_pid(app: &AppHandle, url: &str) -> Option<Option<u32>> {
+    let spec = supervisor::current_spec(app)?;
+    if spec.url() != url {
+        return None;
+    }
+    let state = app.state::<ServerState>();
+    let child = state.child.lock().unwrap();
+    child
+        .as_ref()
+        .filter(|child| !child.has_exited())
+        .map(|child| child.pid())
+}
+
+async fn fetch_debug(url: &str, password: Option<&str>) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?.join("/debug").ok()?;
+    let client = server::client_for(&url, DEBUG_TIMEOUT).ok()?;
+    let mut req = client.get(url);
+    if let Some(password) = password {
+        req = req.basic_auth("opencode", Some(password));
+    }
+
+    // Read in chunks and stopped at the cap, so a huge or endless response from a stuck server
+    // isn't buffered whole.
+    let mut response = req.send().await.ok()?.error_for_status().ok()?;
+    let mut bytes = Vec::new();
+    while bytes.len() < MAX_DEBUG_BYTES
+        && let Ok(Some(chunk)) = response.chunk().await
+    {
+        bytes.extend_from_slice(&chunk);
+    }
+    let mut body = String::from_utf8_lossy(&bytes).into_owned();
+    if body.len() > MAX_DEBUG_BYTES {
+        let end = body.floor_char_boundary(MAX_DEBUG_BYTES);
+        body.truncate(end);
+    }
+    Some(body)
+}
+
+async fn capture(
+    app: &AppHandle,
+    url: &str,
+    password: Option<&str>,
+    pid: Option<u32>,
+    consecutive_failures: u32,
+) -> StallSnapshot {
+    StallSnapshot {
+        captured_at: chrono::Utc::now().to_rfc3339(),
+        url: url.to_string(),
+        pid,
+        consecutive_failures,
+        stats: app
+            .try_state::<ResourceMonitor>()
+            .and_then(|monitor| monitor.latest()),
+        debug: fetch_debug(url, password).await,
+        logs: app
+            .try_state::<SidecarLogs>()
+            .map(|logs| logs.tail(SNAPSHOT_LOG_LINES))
+            .unwrap_or_default(),
+    }
+}
+
+/// Called when the server at `url` turned unhealthy. Only a sidecar whose process is still alive
+/// counts as stalled, since the supervisor already handles ones that exited.
+pub fn unhealthy(app: &AppHandle, url: &str, password: Option<String>, consecutive_failures: u32) {
+    let Some(pid) = running_pid(app, url) else {
+        return;
+    };
+
+    let app = app.clone();
+    let url = url.to_string();
+    tokio::spawn(async move {
+        let snapshot = capture(&app, &url, password.as_deref(), pid, consecutive_failures).await;
+        tracing::error!(
+            %url,
+            ?pid,
+            consecutive_failures,
+            debug = snapshot.debug.is_some(),
+            "Sidecar is running but stopped answering health checks"
+        );
+        if let Some(watchdog) = app.try_state::<Watchdog>() {
+            *watchdog.0.lock().unwrap() = Some(snapshot);
+        }
+
+        // A sidecar that is already being restarted or stopped is left alone.
+        let restarting =
+            restart_on_stall(&app) && lifecycle::current(&app) == ServerLifecycle::Running;
+        let _ = ServerStalled {
+            url,
+            consecutive_failures,
+            restarting,
+        }
+        .emit(&app);
+
+        if restarting {
+            tracing::info!("Restarting stalled sidecar");
+            let _ = supervisor::restart_server(app).await;
+        }
+    });
+}
+
+fn restart_on_stall(app: &AppHandle) -> bool {
+    settings::load(app)
+        .map(|s| s.restart_on_stall)
+        .unwrap_or(false)
+}
+
+/// The snapshot taken when the sidecar last stalled.
+#[tauri::command]
+#[specta::specta]
+pub fn get_stall_snapshot(watchdog: State<'_, Watchdog>) -> Option<StallSnapshot> {
+    watchdog.0.lock().unwrap().clone()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_restart_on_stall(app: AppHandle) -> Result<bool, String> {
+    Ok(settings::load(&app)?.restart_on_stall)
+}
+
+/// Whether a sidecar that stops answering health checks is restarted without asking.
+#[tauri::command]
+#[specta::specta]
+pub fn set_restart_on_stall(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |s| {
+        s.restart_on_stall = enabled;
+        Ok(())
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, State};
+use tauri_specta::Event;
+use tokio::sync::mpsc;
+
+use crate::{git, trust};
+
+/// Collects the burst of events a save or checkout causes into one `FsChanged`.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+const MAX_PATHS: usize = 1000;
+
+/// Sent when files under a watched directory change. Paths ignored by git are left out.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct FsChanged {
+    pub root: String,
+    /// Files and folders that were created, changed or removed, sorted.
+    pub paths: Vec<String>,
+    /// More paths changed than are listed, so the whole tree should be reloaded.
+    pub truncated: bool,
+    /// Something in the repository's `.git` directory changed, such as the branch or the index.
+    pub git: bool,
+}
+
+/// The directories being watched, each with the watcher that stops when it is dropped.
+#[derive(Default)]
+pub struct FsWatchers(Mutex<HashMap<PathBuf, RecommendedWatcher>>);
+
+/// The `.gitignore` rules of a tree, loaded as paths in each directory come up.
+struct Ignores {
+    root: PathBuf,
+    matchers: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl Ignores {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            matchers: HashMap::new(),
+        }
+    }
+
+    fn load(&self, dir: &Path) -> Option<Gitignore> {
+        let mut files = vec![dir.join(".gitignore")];
+        if dir == self.root {
+            files.push(dir.join(".git/info/exclude"));
+        }
+        let files = files
+            .into_iter()
+            .filter(|f| f.is_file())
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            return None;
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        for file in files {
+            if let Some(e) = builder.add(&file) {
+                tracing::debug!(file = %file.display(), "Failed to read ignore rules: {e}");
+            }
+        }
+        builder.build().ok()
+    }
+
+    /// Whether git ignores `path`. Rules in deeper directories take precedence.
+    fn is_ignored(&mut self, path: &Path) -> bool {
+        let is_dir = path.is_dir();
+        let dirs = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root))
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>();
+
+        for dir in dirs {
+            if !self.matchers.contains_key(&dir) {
+                let matcher = self.load(&dir);
+                self.matchers.insert(dir.clone(), matcher);
+            }
+            let Some(Some(matcher)) = self.matchers.get(&dir) else {
+                continue;
+            };
+            let matched = matcher.matched_path_or_any_parents(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Sorts changed paths into the ones to report and whether git state changed, picking up
+    /// edits to ignore files on the way.
+    fn classify(&mut self, changed: BTreeSet<PathBuf>) -> (Vec<PathBuf>, bool) {
+        let git_dir = self.root.join(".git");
+        let mut git = false;
+        let mut visible = Vec::new();
+
+        for path in changed {
+            if path.starts_with(&git_dir) {
+                git = true;
+                if path.ends_with("info/exclude") {
+                    self.matchers.remove(&self.root);
+                }
+                continue;
+            }
+            if path.file_name().is_some_and(|name| name == ".gitignore")
+                && let Some(dir) = path.parent()
+            {
+                self.matchers.remove(dir);
+            }
+            if !self.is_ignored(&path) {
+                visible.push(path);
+            }
+        }
+        (visible, git)
+    }
+}
+
+fn forward(app: AppHandle, root: PathBuf, mut rx: mpsc::UnboundedReceiver<Vec<PathBuf>>) {
+    tauri::async_runtime::spawn(async move {
+        let mut ignores = Ignores::new(root.clone());
+
+        while let Some(first) = rx.recv().await {
+            tokio::time::sleep(DEBOUNCE).await;
+            let mut changed = first.into_iter().collect::<BTreeSet<_>>();
+            while let Ok(paths) = rx.try_recv() {
+                changed.extend(paths);
+            }
+
+            let (mut paths, git) = ignores.classify(changed);
+            if paths.is_empty() && !git {
+                continue;
+            }
+            let truncated = paths.len() > MAX_PATHS;
+            paths.truncate(MAX_PATHS);
+            let _ = FsChanged {
+                root: root.to_string_lossy().to_string(),
+                paths: paths
+                    .iter()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect(),
+                truncated,
+                git,
+            }
+            .emit(&app);
+            git::refresh(&app, root.clone());
+        }
+    });
+}
+
+/// Reports changes under `path` as `FsChanged` until it is unwatched. The folder must have been
+/// picked by the user or trusted.
+#[tauri::command]
+#[specta::specta]
+pub fn watch_directory(
+    app: AppHandle,
+    watchers: State<'_, FsWatchers>,
+    path: String,
+) -> Result<String, String> {
+    let root =
+        dunce::canonicalize(&path).map_err(|e| format!("Failed to resolve {}: {}", path, e))?;
+    if !root.is_dir() {
+        return Err(format!("{} is not a folder", root.display()));
+    }
+    if !trust::is_opened(&app, &root)? {
+        return Err(format!("{} was not opened in the app", root.display()));
+    }
+    let shown = root.to_string_lossy().to_string();
+
+    let mut watchers = watchers.0.lock().unwrap();
+    if watchers.contains_key(&root) {
+        return Ok(shown);
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                let _ = tx.send(event.paths);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("File watcher error: {e}"),
+        })
+        .map_err(|e| format!("Failed to create the file watcher: {}", e))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+    forward(app.clone(), root.clone(), rx);
+    tracing::info!(root = %root.display(), "Watching directory");
+    watchers.insert(root, watcher);
+    Ok(shown)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn unwatch_directory(app: AppHandle, watchers: State<'_, FsWatchers>, path: String) {
+    let root = dunce::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(&path));
+    git::forget(&app, &root);
+    if watchers.0.lock().unwrap().remove(&root).is_some() {
+        tracing::info!(root = %root.display(), "Stopped watching directory");
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_watched_directories(watchers: State<'_, FsWatchers>) -> Vec<String> {
+    let mut roots = watchers
+        .0
+        .lock()
+        .unwrap()
+        .keys()
+        .map(|root| root.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    roots.sort();
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn honours_nested_gitignores() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+        std::fs::write(root.join("sub/.gitignore"), "local.txt\n!debug.log\n").unwrap();
+
+        let mut ignores = Ignores::new(root.to_path_buf());
+        assert!(ignores.is_ignored(&root.join("target/debug/app")));
+        assert!(ignores.is_ignored(&root.join("server.log")));
+        assert!(!ignores.is_ignored(&root.join("keep.log")));
+        assert!(ignores.is_ignored(&root.join("sub/local.txt")));
+        assert!(!ignores.is_ignored(&root.join("sub/debug.log")));
+        assert!(!ignores.is_ignored(&root.join("src/main.rs")));
+
+        let changed = [".git/HEAD", "src/main.rs", "server.log"]
+            .into_iter()
+            .map(|path| root.join(path))
+            .collect();
+        assert_eq!(
+            ignores.classify(changed),
+            (vec![root.join("src/main.rs")], true)
+        );
+
+        // Editing an ignore file applies its new rules.
+        std::fs::write(root.join("sub/.gitignore"), "").unwrap();
+        ignores.classify(BTreeSet::from([root.join("sub/.gitignore")]));
+        assert!(!ignores.is_ignored(&root.join("sub/local.txt")));
+    }
+}
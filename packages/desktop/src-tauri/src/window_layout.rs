@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Monitor, PhysicalPosition, PhysicalSize, WebviewWindow, WindowEvent};
+use tokio::sync::mpsc;
+
+use crate::{
+    settings,
+    windows::{self, WindowGeometry},
+};
+
+/// How much of a window's top edge must be on a monitor to restore it there, so its title bar
+/// can still be grabbed.
+const MIN_VISIBLE_WIDTH: i64 = 100;
+const TITLE_BAR_HEIGHT: i64 = 40;
+const MIN_WIDTH: u32 = 400;
+const MIN_HEIGHT: u32 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Identifies a set of monitors by where they are, their resolution and scaling, in any order.
+fn layout_key(monitors: &[(Rect, f64)]) -> String {
+    let mut monitors = monitors
+        .iter()
+        .map(|(rect, scale)| {
+            format!(
+                "{},{},{}x{}@{scale}",
+                rect.x, rect.y, rect.width, rect.height
+            )
+        })
+        .collect::<Vec<_>>();
+    monitors.sort();
+    let digest = Sha256::digest(monitors.join(";").as_bytes());
+    digest[..8].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Whether enough of the window's title bar is inside one of `areas` to move it from there.
+fn reachable(geometry: &WindowGeometry, areas: &[Rect]) -> bool {
+    let left = geometry.x as i64;
+    let right = left + geometry.width as i64;
+    let top = geometry.y as i64;
+    let bottom = top + TITLE_BAR_HEIGHT.min(geometry.height as i64);
+
+    areas.iter().any(|area| {
+        let width = right.min(area.x as i64 + area.width as i64) - left.max(area.x as i64);
+        let height = bottom.min(area.y as i64 + area.height as i64) - top.max(area.y as i64);
+        width >= MIN_VISIBLE_WIDTH && height > 0
+    })
+}
+
+fn monitors(app: &AppHandle) -> Vec<Monitor> {
+    app.available_monitors().unwrap_or_default()
+}
+
+fn current_key(monitors: &[Monitor]) -> Option<String> {
+    if monitors.is_empty() {
+        return None;
+    }
+    let rects = monitors
+        .iter()
+        .map(|monitor| {
+            let rect = Rect {
+                x: monitor.position().x,
+                y: monitor.position().y,
+                width: monitor.size().width,
+                height: monitor.size().height,
+            };
+            (rect, monitor.scale_factor())
+        })
+        .collect::<Vec<_>>();
+    Some(layout_key(&rects))
+}
+
+/// Whether a window at `geometry` could be grabbed on one of the connected monitors.
+pub fn on_screen(app: &AppHandle, geometry: &WindowGeometry) -> bool {
+    let areas = monitors(app)
+        .iter()
+        .map(|monitor| {
+            let area = monitor.work_area();
+            Rect {
+                x: area.position.x,
+                y: area.position.y,
+                width: area.size.width,
+                height: area.size.height,
+            }
+        })
+        .collect::<Vec<_>>();
+    reachable(geometry, &areas)
+}
+
+/// The main window geometry saved for the current monitors, if it would still be reachable.
+pub fn saved(app: &AppHandle) -> Option<WindowGeometry> {
+    let key = current_key(&monitors(app))?;
+    let geometry = *settings::load(app).ok()?.window_layouts.get(&key)?;
+    if !on_screen(app, &geometry) {
+        tracing::info!(layout = %key, "Saved window position is off-screen, not restoring it");
+        return None;
+    }
+    Some(geometry)
+}
+
+/// Applies `geometry` to a window that is not shown yet.
+pub fn restore(window: &WebviewWindow, geometry: &WindowGeometry) {
+    let _ = window.set_size(PhysicalSize::new(
+        geometry.width.max(MIN_WIDTH),
+        geometry.height.max(MIN_HEIGHT),
+    ));
+    let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    if geometry.maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// Saves the window's geometry for the current monitors whenever it moves or is resized.
+pub fn track(app: &AppHandle, window: &WebviewWindow) {
+    let (tx, mut rx) = mpsc::channel::<()>(1);
+
+    window.on_window_event(move |event| {
+        if !matches!(event, WindowEvent::Moved(_) | WindowEvent::Resized(_)) {
+            return;
+        }
+        let _ = tx.try_send(());
+    });
+
+    tokio::spawn({
+        let app = app.clone();
+        let window = window.clone();
+
+        async move {
+            while rx.recv().await.is_some() {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+
+                // Minimized windows report a meaningless position on some platforms.
+                if window.is_minimized().unwrap_or(false) {
+                    continue;
+                }
+                let Some(key) = current_key(&monitors(&app)) else {
+                    continue;
+                };
+                let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size())
+                else {
+                    continue;
+                };
+                let maximized = window.is_maximized().unwrap_or(false);
+                let saved = settings::update(&app, |s| {
+                    let previous = s.window_layouts.get(&key).copied();
+                    s.window_layouts.insert(
+                        key.clone(),
+                        windows::next_geometry(previous, position, size, maximized),
+                    );
+                    Ok(())
+                });
+                if let Err(e) = saved {
+                    tracing::debug!("Failed to save window geometry: {e}");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn geometry(x: i32, y: i32) -> WindowGeometry {
+        WindowGeometry {
+            x,
+            y,
+            width: 1200,
+            height: 800,
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn layout_key_ignores_monitor_order() {
+        let laptop = (rect(0, 0, 2880, 1800), 2.0);
+        let external = (rect(2880, 0, 2560, 1440), 1.0);
+
+        assert_eq!(
+            layout_key(&[laptop, external]),
+            layout_key(&[external, laptop])
+        );
+        assert_ne!(layout_key(&[laptop]), layout_key(&[laptop, external]));
+        assert_ne!(
+            layout_key(&[laptop]),
+            layout_key(&[(rect(0, 0, 2880, 1800), 1.0)])
+        );
+    }
+
+    #[test]
+    fn off_screen_windows_are_not_restored() {
+        let areas = [rect(0, 0, 1920, 1040), rect(1920, 0, 1920, 1040)];
+
+        assert!(reachable(&geometry(100, 100), &areas));
+        assert!(reachable(&geometry(3000, 500), &areas));
+        // Only a sliver of the title bar is left on the right monitor.
+        assert!(!reachable(&geometry(3800, 100), &areas));
+        assert!(!reachable(&geometry(100, 1100), &areas));
+        assert!(!reachable(&geometry(-1500, 100), &areas));
+        assert!(reachable(&geometry(-1000, 100), &areas));
+    }
+}
@@ -1,10 +1,25 @@
 use crate::{
-    constants::{UPDATER_ENABLED, window_state_flags},
+    constants::UPDATER_ENABLED,
+    file_drop,
+    instances::{InstanceInfo, Instances},
+    projects,
     server::get_wsl_config,
+    settings, window_layout,
+};
+use std::{
+    collections::BTreeMap,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, PhysicalSize, Runtime, State, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, WindowEvent,
 };
-use std::{ops::Deref, time::Duration};
-use tauri::{AppHandle, Manager, Runtime, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
-use tauri_plugin_window_state::AppHandleExt;
 use tokio::sync::mpsc;
 
 #[cfg(target_os = "linux")]
@@ -48,16 +63,17 @@ impl MainWindow {
             .map(|v| v.enabled)
             .unwrap_or(false);
         let decorations = use_decorations();
+        // Restored here rather than by the frontend so the window never flashes at the default.
+        let geometry = window_layout::saved(app);
         let window_builder = base_window_config(
             WebviewWindowBuilder::new(app, Self::LABEL, WebviewUrl::App("/".into())),
             app,
             decorations,
         )
         .title("OpenCode")
-        .disable_drag_drop_handler()
         .zoom_hotkeys_enabled(false)
-        .visible(true)
-        .maximized(true)
+        .visible(false)
+        .maximized(geometry.is_none())
         .initialization_script(format!(
             r#"
             window.__OPENCODE__ ??= {{}};
@@ -66,12 +82,17 @@ impl MainWindow {
           "#
         ));
 
-        let window = window_builder.build()?;
+        let window = with_drop_handler(window_builder, app).build()?;
+        if let Some(geometry) = &geometry {
+            window_layout::restore(&window, geometry);
+        }
+        window.show()?;
 
         // Ensure window is focused after creation (e.g., after update/relaunch)
         let _ = window.set_focus();
 
-        setup_window_state_listener(app, &window);
+        window_layout::track(app, &window);
+        watch_drops(app, &window);
 
         #[cfg(windows)]
         {
@@ -83,38 +104,240 @@ impl MainWindow {
     }
 }
 
-fn setup_window_state_listener(app: &AppHandle, window: &WebviewWindow) {
+/// Where a project window was and how big, in physical pixels, so it reopens the same way.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+/// The geometry to save for a window. A maximized window keeps the bounds it had before, so
+/// unmaximizing it after a restart puts it back there.
+pub fn next_geometry(
+    previous: Option<WindowGeometry>,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    maximized: bool,
+) -> WindowGeometry {
+    match previous {
+        Some(previous) if maximized => WindowGeometry {
+            maximized,
+            ..previous
+        },
+        _ => WindowGeometry {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            maximized,
+        },
+    }
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct WindowInfo {
+    pub label: String,
+    /// The project the window is bound to, unset for windows using the shared server.
+    pub directory: Option<String>,
+    pub focused: bool,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct ProjectWindowInfo {
+    pub label: String,
+    pub server: InstanceInfo,
+}
+
+/// Project windows by label, each bound to the project server it talks to.
+#[derive(Default)]
+pub struct ProjectWindows {
+    windows: Mutex<BTreeMap<String, PathBuf>>,
+    next_id: AtomicU32,
+}
+
+impl ProjectWindows {
+    fn label_for(&self, directory: &Path) -> Option<String> {
+        self.windows
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, bound)| bound.as_path() == directory)
+            .map(|(label, _)| label.clone())
+    }
+}
+
+pub struct ProjectWindow;
+
+impl ProjectWindow {
+    pub const LABEL_PREFIX: &str = "project-";
+
+    fn create(
+        app: &AppHandle,
+        label: &str,
+        server: &InstanceInfo,
+    ) -> Result<WebviewWindow, tauri::Error> {
+        let geometry = settings::load(app)
+            .ok()
+            .and_then(|s| s.project_windows.get(&server.directory).copied())
+            .filter(|geometry| window_layout::on_screen(app, geometry));
+        // Requests are proxied with the password, so the window only learns where the server is.
+        let project = serde_json::json!({ "directory": server.directory, "url": server.url });
+
+        let window = base_window_config(
+            WebviewWindowBuilder::new(app, label, WebviewUrl::App("/".into())),
+            app,
+            use_decorations(),
+        )
+        .title(format!("OpenCode - {}", server.directory))
+        .zoom_hotkeys_enabled(false)
+        .visible(false)
+        .initialization_script(format!(
+            r#"
+            window.__OPENCODE__ ??= {{}};
+            window.__OPENCODE__.updaterEnabled = {UPDATER_ENABLED};
+            window.__OPENCODE__.project = {project};
+          "#
+        ));
+        let window = with_drop_handler(window, app).build()?;
+
+        match geometry {
+            Some(geometry) => window_layout::restore(&window, &geometry),
+            None => {
+                let _ = window.center();
+            }
+        }
+        window.show()?;
+        let _ = window.set_focus();
+        watch_drops(app, &window);
+
+        #[cfg(windows)]
+        {
+            use tauri_plugin_decorum::WebviewWindowExt;
+            let _ = window.create_overlay_titlebar();
+        }
+
+        Ok(window)
+    }
+}
+
+/// Saves the window's geometry for its project as it moves, and forgets the window once closed.
+fn track_project_window(app: &AppHandle, window: &WebviewWindow, directory: String) {
     let (tx, mut rx) = mpsc::channel::<()>(1);
 
-    window.on_window_event(move |event| {
-        use tauri::WindowEvent;
-        if !matches!(event, WindowEvent::Moved(_) | WindowEvent::Resized(_)) {
-            return;
+    window.on_window_event({
+        let app = app.clone();
+        let label = window.label().to_string();
+        move |event| match event {
+            WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+                let _ = tx.try_send(());
+            }
+            WindowEvent::Destroyed => {
+                app.state::<ProjectWindows>()
+                    .windows
+                    .lock()
+                    .unwrap()
+                    .remove(&label);
+            }
+            _ => {}
         }
-        let _ = tx.try_send(());
     });
 
     tokio::spawn({
+        let window = window.clone();
         let app = app.clone();
-
         async move {
-            let save = || {
-                let handle = app.clone();
-                let app = app.clone();
-                let _ = handle.run_on_main_thread(move || {
-                    let _ = app.save_window_state(window_state_flags());
-                });
-            };
-
             while rx.recv().await.is_some() {
                 tokio::time::sleep(Duration::from_millis(200)).await;
 
-                save();
+                let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size())
+                else {
+                    continue;
+                };
+                let maximized = window.is_maximized().unwrap_or(false);
+                let saved = settings::update(&app, |s| {
+                    let previous = s.project_windows.get(&directory).copied();
+                    s.project_windows.insert(
+                        directory.clone(),
+                        next_geometry(previous, position, size, maximized),
+                    );
+                    Ok(())
+                });
+                if let Err(e) = saved {
+                    tracing::debug!("Failed to save project window geometry: {e}");
+                }
             }
         }
     });
 }
 
+/// Opens a window for the project at `path`, running its own server, or focuses the one already
+/// open for it. The server keeps running once the window closes, until `stop_instance`.
+#[tauri::command]
+#[specta::specta]
+pub async fn open_project_window(
+    app: AppHandle,
+    instances: State<'_, Instances>,
+    path: String,
+) -> Result<ProjectWindowInfo, String> {
+    let server = projects::open_project(app.clone(), instances, path).await?;
+    let directory = PathBuf::from(&server.directory);
+    let windows = app.state::<ProjectWindows>();
+
+    if let Some(label) = windows.label_for(&directory)
+        && let Some(window) = app.get_webview_window(&label)
+    {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(ProjectWindowInfo { label, server });
+    }
+
+    let id = windows.next_id.fetch_add(1, Ordering::Relaxed);
+    let label = format!("{}{id}", ProjectWindow::LABEL_PREFIX);
+    let window = ProjectWindow::create(&app, &label, &server)
+        .map_err(|e| format!("Failed to open project window: {}", e))?;
+    windows
+        .windows
+        .lock()
+        .unwrap()
+        .insert(label.clone(), directory);
+    track_project_window(&app, &window, server.directory.clone());
+
+    tracing::info!(%label, directory = %server.directory, "Opened project window");
+    Ok(ProjectWindowInfo { label, server })
+}
+
+/// Every open window, with the project each one is bound to.
+#[tauri::command]
+#[specta::specta]
+pub fn list_windows(app: AppHandle, windows: State<'_, ProjectWindows>) -> Vec<WindowInfo> {
+    let bound = windows.windows.lock().unwrap().clone();
+    app.webview_windows()
+        .into_iter()
+        .map(|(label, window)| WindowInfo {
+            directory: bound
+                .get(&label)
+                .map(|directory| directory.to_string_lossy().to_string()),
+            focused: window.is_focused().unwrap_or(false),
+            label,
+        })
+        .collect()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn focus_window(app: AppHandle, label: String) -> Result<(), String> {
+    let window = app
+        .get_webview_window(&label)
+        .ok_or_else(|| format!("No window {label}"))?;
+    let _ = window.unminimize();
+    window
+        .set_focus()
+        .map_err(|e| format!("Failed to focus window {label}: {}", e))
+}
+
 pub struct LoadingWindow(WebviewWindow);
 
 impl Deref for LoadingWindow {
@@ -145,6 +368,106 @@ impl LoadingWindow {
     }
 }
 
+/// A small palette for sending a prompt without switching to the main window.
+pub struct QuickPromptWindow;
+
+impl QuickPromptWindow {
+    pub const LABEL: &str = "quick-prompt";
+    const WIDTH: f64 = 640.0;
+    const HEIGHT: f64 = 120.0;
+
+    /// Opens the palette, or closes it when it is already focused.
+    pub fn toggle(app: &AppHandle) -> Result<(), tauri::Error> {
+        if let Some(window) = app.get_webview_window(Self::LABEL) {
+            if window.is_focused()? {
+                return window.close();
+            }
+            return window.set_focus();
+        }
+
+        let window = base_window_config(
+            WebviewWindowBuilder::new(app, Self::LABEL, WebviewUrl::App("/quick-prompt".into())),
+            app,
+            false,
+        )
+        .title("Quick Prompt")
+        .resizable(false)
+        .inner_size(Self::WIDTH, Self::HEIGHT)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()?;
+        Self::place(app, &window);
+        window.show()?;
+        let _ = window.set_focus();
+
+        // Like other palettes it goes away once the user clicks elsewhere.
+        let handle = window.clone();
+        window.on_window_event(move |event| {
+            if matches!(event, WindowEvent::Focused(false)) {
+                let _ = handle.close();
+            }
+        });
+        Ok(())
+    }
+
+    pub fn close(app: &AppHandle) {
+        if let Some(window) = app.get_webview_window(Self::LABEL) {
+            let _ = window.close();
+        }
+    }
+
+    /// Moves the palette to the monitor under the cursor, where the user is looking.
+    fn place(app: &AppHandle, window: &WebviewWindow) {
+        let monitor = app
+            .cursor_position()
+            .ok()
+            .and_then(|cursor| app.monitor_from_point(cursor.x, cursor.y).ok().flatten())
+            .or_else(|| app.primary_monitor().ok().flatten());
+        let Some(monitor) = monitor else {
+            let _ = window.center();
+            return;
+        };
+
+        let area = monitor.work_area();
+        let scale = monitor.scale_factor();
+        let (x, y) = palette_position(
+            (area.position.x, area.position.y),
+            (area.size.width, area.size.height),
+            ((Self::WIDTH * scale) as u32, (Self::HEIGHT * scale) as u32),
+        );
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+}
+
+/// Top-left corner that centers a window of `size` horizontally in the work area, a fifth of the
+/// way down like launcher palettes. Windows larger than the area stick to its top-left corner.
+fn palette_position(origin: (i32, i32), area: (u32, u32), size: (u32, u32)) -> (i32, i32) {
+    let offset = |area: u32, size: u32, divisor: u32| (area.saturating_sub(size) / divisor) as i32;
+    (
+        origin.0 + offset(area.0, size.0, 2),
+        origin.1 + offset(area.1, size.1, 5),
+    )
+}
+
+/// Leaves drops to the web view unless they are handled natively.
+fn with_drop_handler<'a, R: Runtime, M: Manager<R>>(
+    window_builder: WebviewWindowBuilder<'a, R, M>,
+    app: &AppHandle,
+) -> WebviewWindowBuilder<'a, R, M> {
+    if file_drop::native_enabled(app) {
+        window_builder
+    } else {
+        window_builder.disable_drag_drop_handler()
+    }
+}
+
+fn watch_drops(app: &AppHandle, window: &WebviewWindow) {
+    if file_drop::native_enabled(app) {
+        file_drop::watch(app, window);
+    }
+}
+
 fn base_window_config<'a, R: Runtime, M: Manager<R>>(
     window_builder: WebviewWindowBuilder<'a, R, M>,
     _app: &AppHandle,
@@ -172,3 +495,56 @@ fn base_window_config<'a, R: Runtime, M: Manager<R>>(
 
     window_builder
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_sits_near_the_top_of_the_monitor() {
+        assert_eq!(
+            palette_position((0, 0), (1920, 1080), (640, 120)),
+            (640, 192)
+        );
+        // A second monitor to the left of the primary one.
+        assert_eq!(
+            palette_position((-1280, 40), (1280, 1000), (1280, 240)),
+            (-1280, 192)
+        );
+        assert_eq!(palette_position((10, 10), (300, 100), (640, 120)), (10, 10));
+    }
+
+    #[test]
+    fn maximized_windows_keep_their_normal_bounds() {
+        let normal = next_geometry(
+            None,
+            PhysicalPosition::new(100, 50),
+            PhysicalSize::new(1200, 800),
+            false,
+        );
+        assert_eq!(
+            normal,
+            WindowGeometry {
+                x: 100,
+                y: 50,
+                width: 1200,
+                height: 800,
+                maximized: false,
+            }
+        );
+
+        let maximized = next_geometry(
+            Some(normal),
+            PhysicalPosition::new(0, 0),
+            PhysicalSize::new(2560, 1440),
+            true,
+        );
+        assert_eq!(
+            maximized,
+            WindowGeometry {
+                maximized: true,
+                ..normal
+            }
+        );
+    }
+}
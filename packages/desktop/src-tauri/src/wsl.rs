@@ -0,0 +1,385 @@
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::{
+    cli,
+    server::{get_wsl_distro, wsl_distro_args},
+    settings,
+};
+
+const REQUIRED_TOOLS: [&str; 2] = ["bash", "curl"];
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum WslStatus {
+    Ready {
+        distro: String,
+    },
+    Unsupported,
+    NotInstalled,
+    NoDistro,
+    DistroNotFound {
+        distro: String,
+    },
+    #[serde(rename = "not_wsl2")]
+    NotWsl2 {
+        distro: String,
+        version: u32,
+    },
+    MissingTools {
+        distro: String,
+        tools: Vec<String>,
+    },
+}
+
+/// How WSL 2 connects the distro to Windows, the `networkingMode` of `.wslconfig`.
+#[derive(Clone, Copy, serde::Serialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WslNetworking {
+    /// The distro is behind a virtual NAT, with its own address.
+    Nat,
+    /// The distro shares the Windows network interfaces, so localhost always works.
+    Mirrored,
+    VirtioProxy,
+    /// The distro has no network.
+    None,
+}
+
+impl WslNetworking {
+    fn parse(mode: &str) -> Option<Self> {
+        match mode.trim().to_ascii_lowercase().as_str() {
+            "nat" => Some(Self::Nat),
+            "mirrored" => Some(Self::Mirrored),
+            "virtioproxy" => Some(Self::VirtioProxy),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug, PartialEq, Eq)]
+pub struct WslNetworkInfo {
+    pub mode: WslNetworking,
+    /// Whether Windows reaches servers listening on localhost in the distro.
+    pub localhost_forwarding: bool,
+    /// The distro's own address, which the server is reached at when localhost isn't forwarded.
+    pub address: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Distro {
+    name: String,
+    version: u32,
+    is_default: bool,
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_wsl_distros() -> Result<Vec<String>, String> {
+    if !cfg!(windows) {
+        return Ok(vec![]);
+    }
+
+    let mut command = std::process::Command::new("wsl");
+    command.args(["-l", "-q"]);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(cli::CREATE_NO_WINDOW.0);
+    }
+
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to list WSL distros: {}", e))?;
+
+    if !output.status.success() {
+        return Err("Failed to list WSL distros".to_string());
+    }
+
+    Ok(decode_output(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// `wsl`, without a console window flashing up for it.
+#[cfg(windows)]
+fn wsl() -> Command {
+    let mut command = Command::new("wsl");
+    command.creation_flags(cli::CREATE_NO_WINDOW.0);
+    command
+}
+
+#[cfg(not(windows))]
+fn wsl() -> Command {
+    Command::new("wsl")
+}
+
+/// Checks everything the WSL server needs, so the UI can explain what is missing before the
+/// user enables WSL instead of the spawn failing later.
+#[tauri::command]
+#[specta::specta]
+pub async fn check_wsl_status(app: AppHandle) -> Result<WslStatus, String> {
+    if !cfg!(windows) {
+        return Ok(WslStatus::Unsupported);
+    }
+
+    let output = match wsl().args(["-l", "-v"]).output().await {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(WslStatus::NotInstalled);
+        }
+        Err(e) => return Err(format!("Failed to run wsl: {}", e)),
+    };
+
+    // `wsl -l -v` exits with an error when no distro is registered.
+    let distros = if output.status.success() {
+        parse_distros(&decode_output(&output.stdout))
+    } else {
+        vec![]
+    };
+
+    // Offline installs copy the CLI in, so only the download needs curl.
+    let offline = settings::load(&app).is_ok_and(|s| s.wsl_offline_install);
+    let configured = get_wsl_distro(app).ok().flatten();
+    let distro = match &configured {
+        Some(name) => distros
+            .into_iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name)),
+        None => distros.into_iter().find(|d| d.is_default),
+    };
+
+    let Some(distro) = distro else {
+        return Ok(match configured {
+            Some(distro) => WslStatus::DistroNotFound { distro },
+            None => WslStatus::NoDistro,
+        });
+    };
+
+    if distro.version != 2 {
+        return Ok(WslStatus::NotWsl2 {
+            distro: distro.name,
+            version: distro.version,
+        });
+    }
+
+    let script = REQUIRED_TOOLS
+        .iter()
+        .filter(|tool| !(offline && **tool == "curl"))
+        .map(|tool| format!("command -v {tool} >/dev/null 2>&1 || echo {tool}"))
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    let output = wsl()
+        .args(["-d", &distro.name, "-e", "sh", "-c", &script])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run wsl: {}", e))?;
+
+    let tools = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    if !tools.is_empty() {
+        return Ok(WslStatus::MissingTools {
+            distro: distro.name,
+            tools,
+        });
+    }
+
+    Ok(WslStatus::Ready {
+        distro: distro.name,
+    })
+}
+
+/// `networkingMode` and `localhostForwarding` from the `[wsl2]` section of `.wslconfig`.
+fn parse_wslconfig(contents: &str) -> (Option<WslNetworking>, Option<bool>) {
+    let mut section = String::new();
+    let (mut mode, mut forwarding) = (None, None);
+    for line in contents.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim().to_ascii_lowercase();
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if section != "wsl2" {
+            continue;
+        }
+        // Values may carry a trailing comment.
+        let value = value.split('#').next().unwrap_or("").trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "networkingmode" => mode = WslNetworking::parse(value),
+            "localhostforwarding" => forwarding = value.parse().ok(),
+            _ => {}
+        }
+    }
+    (mode, forwarding)
+}
+
+async fn run_in_distro(app: &AppHandle, args: &[&str]) -> Option<String> {
+    let output = wsl()
+        .args(wsl_distro_args(app))
+        .arg("-e")
+        .args(args)
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// How the configured distro is networked. `wslinfo` knows best, but only recent WSL releases
+/// ship it, so `.wslconfig` is read as well.
+async fn network_info(app: &AppHandle) -> WslNetworkInfo {
+    let (configured_mode, forwarding) = dirs::home_dir()
+        .and_then(|home| std::fs::read_to_string(home.join(".wslconfig")).ok())
+        .map(|contents| parse_wslconfig(&contents))
+        .unwrap_or_default();
+    let mode = match run_in_distro(app, &["wslinfo", "--networking-mode"]).await {
+        Some(mode) => WslNetworking::parse(&mode),
+        None => configured_mode,
+    }
+    .unwrap_or(WslNetworking::Nat);
+
+    let localhost_forwarding = mode != WslNetworking::Nat || forwarding.unwrap_or(true);
+    let address = if localhost_forwarding {
+        None
+    } else {
+        run_in_distro(app, &["hostname", "-I"])
+            .await
+            .and_then(|addresses| {
+                addresses
+                    .split_whitespace()
+                    .find(|address| address.parse::<std::net::Ipv4Addr>().is_ok())
+                    .map(String::from)
+            })
+    };
+
+    WslNetworkInfo {
+        mode,
+        localhost_forwarding,
+        address,
+    }
+}
+
+/// The distro address a WSL sidecar has to listen on and be reached at, when it runs behind a
+/// NAT that doesn't forward localhost to it.
+pub async fn nat_address(app: &AppHandle) -> Option<String> {
+    if !cfg!(windows) || !cli::is_wsl_enabled(app) {
+        return None;
+    }
+    let info = network_info(app).await;
+    if let Some(address) = &info.address {
+        tracing::info!(
+            %address,
+            "WSL doesn't forward localhost, reaching the server at the distro's address"
+        );
+    }
+    info.address
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_wsl_networking(app: AppHandle) -> Result<Option<WslNetworkInfo>, String> {
+    if !cfg!(windows) {
+        return Ok(None);
+    }
+    Ok(Some(network_info(&app).await))
+}
+
+// `wsl.exe` writes UTF-16LE when its output is not a console.
+fn decode_output(stdout: &[u8]) -> String {
+    let text = if stdout.contains(&0) {
+        let units = stdout
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(stdout).into_owned()
+    };
+
+    text.replace('\u{feff}', "")
+}
+
+/// Parses the `NAME STATE VERSION` table printed by `wsl -l -v`.
+fn parse_distros(output: &str) -> Vec<Distro> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let line = line.trim();
+            let (is_default, line) = match line.strip_prefix('*') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+
+            let mut columns = line.split_whitespace();
+            let name = columns.next()?.to_string();
+            let version = columns.last()?.parse().ok()?;
+
+            Some(Distro {
+                name,
+                version,
+                is_default,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_utf16_output() {
+        let stdout = "\u{feff}Ubuntu\r\nDebian\r\n"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        assert_eq!(decode_output(&stdout), "Ubuntu\r\nDebian\r\n");
+        assert_eq!(decode_output(b"Ubuntu\n"), "Ubuntu\n");
+    }
+
+    #[test]
+    fn reads_networking_from_wslconfig() {
+        let contents = "[wsl2]\nmemory=8GB\nnetworkingMode = Mirrored # since 2.0\n\
+                        localhostForwarding=false\n[experimental]\nnetworkingMode=nat\n";
+        assert_eq!(
+            parse_wslconfig(contents),
+            (Some(WslNetworking::Mirrored), Some(false))
+        );
+        assert_eq!(parse_wslconfig("[boot]\nsystemd=true\n"), (None, None));
+    }
+
+    #[test]
+    fn parses_verbose_distro_list() {
+        let output = "  NAME            STATE           VERSION\r\n\
+                      * Ubuntu-22.04    Running         2\r\n  \
+                      Debian          Stopped         1\r\n";
+
+        assert_eq!(
+            parse_distros(output),
+            vec![
+                Distro {
+                    name: "Ubuntu-22.04".to_string(),
+                    version: 2,
+                    is_default: true,
+                },
+                Distro {
+                    name: "Debian".to_string(),
+                    version: 1,
+                    is_default: false,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use std::process::Stdio;
+
+use tauri::AppHandle;
+use tauri_specta::Event;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+};
+
+use crate::{
+    cli::{self, CLI_BINARY_NAME, CLI_INSTALL_DIR, CliInstallProgress},
+    cli_channel,
+    server::wsl_distro_args,
+};
+
+/// Printed to stderr by the install script as it moves from one step to the next.
+const MARKER: &str = "__OPENCODE_BOOTSTRAP__";
+const STDERR_TAIL: usize = 20;
+
+/// Prints `installed` when the CLI is there already.
+const INSTALLED_SCRIPT: &str = r#"
+if [ -x "$HOME/$CLI_INSTALL_DIR/$CLI_BINARY_NAME" ]; then echo installed; exit 0; fi
+"#;
+
+/// Prints the release target for the distro, named like the install script does.
+const TARGET_SCRIPT: &str = r#"
+case "$(uname -m)" in
+  x86_64|amd64) arch=x64 ;;
+  aarch64|arm64) arch=arm64 ;;
+  *) echo "No CLI releases for $(uname -m)" >&2; exit 1 ;;
+esac
+target="linux-$arch"
+if [ "$arch" = x64 ] && ! grep -qwi avx2 /proc/cpuinfo; then target="$target-baseline"; fi
+if [ -f /etc/alpine-release ] || ldd --version 2>&1 | grep -qi musl; then target="$target-musl"; fi
+echo "$target"
+"#;
+
+/// What the WSL bootstrap was doing when it failed.
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapStep {
+    /// Checking for an installed CLI and which release fits the distro.
+    Detecting,
+    /// Looking up the release and its checksum.
+    Resolving,
+    Downloading,
+    Verifying,
+    Extracting,
+    Installing,
+}
+
+impl BootstrapStep {
+    fn parse(line: &str) -> Option<Self> {
+        match line.strip_prefix(MARKER)?.trim() {
+            "downloading" => Some(Self::Downloading),
+            "verifying" => Some(Self::Verifying),
+            "extracting" => Some(Self::Extracting),
+            "installing" => Some(Self::Installing),
+            _ => None,
+        }
+    }
+
+    fn progress(self) -> Option<CliInstallProgress> {
+        match self {
+            Self::Detecting | Self::Resolving => None,
+            Self::Downloading => Some(CliInstallProgress::Downloading { percent: None }),
+            Self::Verifying => Some(CliInstallProgress::Verifying),
+            Self::Extracting => Some(CliInstallProgress::Extracting),
+            Self::Installing => Some(CliInstallProgress::Installing),
+        }
+    }
+}
+
+/// Sent when the CLI could not be installed inside WSL, so the server can't start there.
+#[derive(tauri_specta::Event, serde::Serialize, serde::Deserialize, Clone, Debug, specta::Type)]
+pub struct WslBootstrapFailed {
+    pub step: BootstrapStep,
+    pub message: String,
+}
+
+/// Where the CLI installed into WSL comes from.
+enum Source {
+    /// A release archive, checked against the checksum published for it.
+    Release { url: String, sha256: String },
+    /// The Linux CLI bundled with the app, for offline installs.
+    Bundled(cli::WslBinary),
+}
+
+/// Installs the CLI from `source` into a temporary directory next to its final place, then moves
+/// it there, so an interrupted install never leaves a broken binary behind.
+fn install_script(source: &Source) -> String {
+    let fetch = match source {
+        Source::Release { url, sha256 } => format!(
+            "echo '{MARKER} downloading' >&2\n\
+             curl -fsSL --retry 3 -o \"$TMP/cli.tar.gz\" {url}\n\
+             echo '{MARKER} verifying' >&2\n\
+             echo \"{sha256}  $TMP/cli.tar.gz\" | sha256sum -c --status\n\
+             echo '{MARKER} extracting' >&2\n\
+             tar -xzf \"$TMP/cli.tar.gz\" -C \"$TMP\"",
+            url = cli::shell_escape(url),
+        ),
+        Source::Bundled(binary) => format!(
+            "BIN=\"$TMP/{CLI_BINARY_NAME}\"\n{}",
+            cli::wsl_copy_command(binary)
+        ),
+    };
+    format!(
+        "set -e\n\
+         DIR=\"$HOME/{CLI_INSTALL_DIR}\"\n\
+         mkdir -p \"$DIR\"\n\
+         TMP=$(mktemp -d \"$DIR/.bootstrap.XXXXXX\")\n\
+         trap 'rm -rf \"$TMP\"' EXIT\n\
+         {fetch}\n\
+         echo '{MARKER} installing' >&2\n\
+         chmod 755 \"$TMP/{CLI_BINARY_NAME}\"\n\
+         mv -f \"$TMP/{CLI_BINARY_NAME}\" \"$DIR/{CLI_BINARY_NAME}\"\n"
+    )
+}
+
+fn wsl(app: &AppHandle, script: &str) -> Command {
+    let mut cmd = Command::new("wsl");
+    cmd.args(wsl_distro_args(app))
+        .args(["-e", "bash", "-c", script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    cmd.creation_flags(cli::CREATE_NO_WINDOW.0);
+    cmd
+}
+
+fn failed(step: BootstrapStep, message: impl Into<String>) -> WslBootstrapFailed {
+    WslBootstrapFailed {
+        step,
+        message: message.into(),
+    }
+}
+
+/// The release target to install, or `None` when the CLI is already there.
+async fn detect(app: &AppHandle) -> Result<Option<String>, WslBootstrapFailed> {
+    let script = format!(
+        "CLI_INSTALL_DIR={CLI_INSTALL_DIR}\nCLI_BINARY_NAME={CLI_BINARY_NAME}\n{INSTALLED_SCRIPT}{TARGET_SCRIPT}"
+    );
+    let output = wsl(app, &script)
+        .output()
+        .await
+        .map_err(|e| failed(BootstrapStep::Detecting, format!("Failed to run wsl: {e}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(failed(BootstrapStep::Detecting, stderr.trim()));
+    }
+
+    let target = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((target != "installed").then_some(target))
+}
+
+/// The release target for the WSL distro, whether or not the CLI is installed there.
+#[cfg(windows)]
+pub async fn target(app: &AppHandle) -> Result<String, String> {
+    let output = wsl(app, TARGET_SCRIPT)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run wsl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Failed to detect the WSL distro: {}",
+            stderr.trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Runs the install script, forwarding its steps as install progress.
+async fn install(app: &AppHandle, source: &Source) -> Result<(), WslBootstrapFailed> {
+    let mut step = BootstrapStep::Installing;
+    let mut child = wsl(app, &install_script(source))
+        .spawn()
+        .map_err(|e| failed(step, format!("Failed to run wsl: {e}")))?;
+
+    let mut tail = Vec::new();
+    if let Some(stderr) = child.stderr.take() {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            match BootstrapStep::parse(&line) {
+                Some(next) => {
+                    step = next;
+                    if let Some(progress) = step.progress() {
+                        progress.send(app);
+                    }
+                }
+                None if !line.trim().is_empty() => {
+                    if tail.len() == STDERR_TAIL {
+                        tail.remove(0);
+                    }
+                    tail.push(line);
+                }
+                None => {}
+            }
+        }
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| failed(step, format!("Failed to run wsl: {e}")))?;
+    if status.success() {
+        return Ok(());
+    }
+
+    let reason = match step {
+        BootstrapStep::Downloading => "Failed to download the CLI",
+        BootstrapStep::Verifying => "The downloaded CLI does not match its published checksum",
+        BootstrapStep::Extracting => "Failed to extract the CLI",
+        _ => "Failed to install the CLI",
+    };
+    if tail.is_empty() {
+        Err(failed(step, reason))
+    } else {
+        Err(failed(step, format!("{reason}: {}", tail.join("\n"))))
+    }
+}
+
+async fn bootstrap(app: &AppHandle) -> Result<(), WslBootstrapFailed> {
+    let Some(target) = detect(app).await? else {
+        return Ok(());
+    };
+
+    let source = match cli::offline_wsl_binary(app, &target) {
+        Some(binary) => Source::Bundled(binary),
+        None => {
+            let version = cli_channel::parse_version(&app.package_info().version.to_string())
+                .map_err(|e| failed(BootstrapStep::Resolving, e.message))?;
+            let name = format!("opencode-{target}.tar.gz");
+            let (url, sha256) = cli_channel::release_asset(app, &version, &name)
+                .await
+                .map_err(|e| failed(BootstrapStep::Resolving, e))?;
+            Source::Release { url, sha256 }
+        }
+    };
+
+    tracing::info!(%target, "Installing the CLI in WSL");
+    install(app, &source).await?;
+    let _ = CliInstallProgress::Done {
+        path: format!("~/{CLI_INSTALL_DIR}/{CLI_BINARY_NAME}"),
+    }
+    .emit(app);
+    Ok(())
+}
+
+/// Installs the CLI inside WSL when the server runs there and it is missing, reporting why when
+/// it can't be.
+pub async fn ensure(app: &AppHandle) -> Result<(), String> {
+    if !cfg!(windows) || !cli::is_wsl_enabled(app) {
+        return Ok(());
+    }
+
+    match bootstrap(app).await {
+        Ok(()) => Ok(()),
+        Err(failed) => {
+            tracing::error!(step = ?failed.step, message = %failed.message, "Failed to install the CLI in WSL");
+            let _ = CliInstallProgress::Failed {
+                message: failed.message.clone(),
+            }
+            .emit(app);
+            let message = failed.message.clone();
+            let _ = failed.emit(app);
+            Err(message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_step_markers() {
+        assert_eq!(
+            BootstrapStep::parse("__OPENCODE_BOOTSTRAP__ verifying"),
+            Some(BootstrapStep::Verifying)
+        );
+        assert_eq!(BootstrapStep::parse("verifying"), None);
+        assert_eq!(BootstrapStep::parse("__OPENCODE_BOOTSTRAP__ done"), None);
+    }
+
+    #[test]
+    fn verifies_downloads_before_moving_them_into_place() {
+        let script = install_script(&Source::Release {
+            url: "https://example.com/opencode-linux-x64.tar.gz".to_string(),
+            sha256: "ab".repeat(32),
+        });
+        let verify = script.find("sha256sum -c").unwrap();
+        let extract = script.find("tar -xzf").unwrap();
+        let install = script.find("mv -f").unwrap();
+        assert!(verify < extract && extract < install);
+        assert!(script.contains(&format!("echo \"{}  $TMP/cli.tar.gz\"", "ab".repeat(32))));
+        assert!(script.contains("'https://example.com/opencode-linux-x64.tar.gz'"));
+        assert!(script.starts_with("set -e\n"));
+    }
+}
@@ -0,0 +1,205 @@
+use std::process::Command;
+
+use tauri::AppHandle;
+
+use crate::server;
+
+/// Where Windows drives are mounted inside WSL, unless `/etc/wsl.conf` moves them.
+const MOUNT_ROOT: &str = "/mnt/";
+const UNC_HOSTS: [&str; 2] = ["wsl.localhost", "wsl$"];
+
+#[derive(Clone, Copy, serde::Deserialize, specta::Type, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WslPathMode {
+    /// A path inside WSL as Windows sees it.
+    Windows,
+    /// A Windows path as WSL sees it.
+    Linux,
+}
+
+/// `path` as WSL sees it, like `wslpath -u`: drives are under `/mnt` and `\\wsl.localhost` shares
+/// are the distro's own files. Paths already inside WSL are kept, others can't be translated.
+pub fn to_linux(path: &str) -> Option<String> {
+    if path.starts_with('/') {
+        return Some(path.to_string());
+    }
+
+    let normalized = path.replace('\\', "/");
+    if let Some(unc) = normalized.strip_prefix("//") {
+        let (host, rest) = unc.split_once('/')?;
+        if !UNC_HOSTS.iter().any(|h| host.eq_ignore_ascii_case(h)) {
+            return None;
+        }
+        // The distro name is the first segment, the rest is a path inside it.
+        let rest = rest.split_once('/').map_or("", |(_, rest)| rest);
+        return Some(format!("/{rest}"));
+    }
+
+    let mut chars = normalized.chars();
+    let (Some(drive), Some(':')) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    if !drive.is_ascii_alphabetic() {
+        return None;
+    }
+    let rest = chars.as_str();
+    if !rest.is_empty() && !rest.starts_with('/') {
+        // `C:foo` is relative to the drive's current directory.
+        return None;
+    }
+    Some(format!("{MOUNT_ROOT}{}{rest}", drive.to_ascii_lowercase()))
+}
+
+/// `path` as Windows sees it, like `wslpath -w`. Files outside the mounted drives are reached
+/// through the `\\wsl.localhost` share, which needs the `distro`'s name.
+pub fn to_windows(path: &str, distro: Option<&str>) -> Option<String> {
+    if !path.starts_with('/') {
+        return to_linux(path).map(|_| path.to_string());
+    }
+
+    if let Some(mounted) = path.strip_prefix(MOUNT_ROOT) {
+        let (drive, rest) = mounted.split_once('/').unwrap_or((mounted, ""));
+        let mut chars = drive.chars();
+        if let (Some(letter), None) = (chars.next(), chars.next())
+            && letter.is_ascii_alphabetic()
+        {
+            return Some(format!(
+                "{}:\\{}",
+                letter.to_ascii_uppercase(),
+                rest.replace('/', "\\")
+            ));
+        }
+    }
+
+    let distro = distro?;
+    Some(format!(
+        "\\\\{}\\{distro}{}",
+        UNC_HOSTS[0],
+        path.replace('/', "\\")
+    ))
+}
+
+/// Asks `wslpath` inside the distro, for what [`to_linux`] and [`to_windows`] can't work out
+/// themselves, like `~` or drives mounted elsewhere.
+fn run_wslpath(app: &AppHandle, path: &str, mode: WslPathMode) -> Result<String, String> {
+    let flag = match mode {
+        WslPathMode::Windows => "-w",
+        WslPathMode::Linux => "-u",
+    };
+
+    let mut command = Command::new("wsl");
+    command.args(server::wsl_distro_args(app));
+    if let Some(suffix) = path.strip_prefix('~') {
+        // Other users' homes (`~name`) would need the shell to expand them.
+        if !(suffix.is_empty() || suffix.starts_with('/')) {
+            return Err(format!("Unsupported home path {path}"));
+        }
+        // The suffix is passed as an argument, so the shell never reads it as code.
+        command.args(["-e", "sh", "-c", r#"wslpath "$0" "$HOME$1""#, flag, suffix]);
+    } else {
+        command.args(["-e", "wslpath", flag, path]);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(crate::cli::CREATE_NO_WINDOW.0);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run wslpath: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if stderr.is_empty() {
+            return Err("wslpath failed".to_string());
+        }
+        return Err(stderr);
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Translates `path` between Windows and the WSL distro the server runs in. Paths are kept as
+/// they are off Windows.
+pub fn translate(app: &AppHandle, path: &str, mode: WslPathMode) -> Result<String, String> {
+    if !cfg!(windows) {
+        return Ok(path.to_string());
+    }
+
+    let translated = match mode {
+        WslPathMode::Linux => to_linux(path),
+        WslPathMode::Windows => {
+            let distro = server::get_wsl_distro(app.clone()).ok().flatten();
+            to_windows(path, distro.as_deref())
+        }
+    };
+    match translated {
+        Some(translated) => Ok(translated),
+        None => run_wslpath(app, path, mode),
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn wsl_path(app: AppHandle, path: String, mode: Option<WslPathMode>) -> Result<String, String> {
+    translate(&app, &path, mode.unwrap_or(WslPathMode::Linux))
+}
+
+/// [`wsl_path`] for several paths at once, in order.
+#[tauri::command]
+#[specta::specta]
+pub fn wsl_paths(
+    app: AppHandle,
+    paths: Vec<String>,
+    mode: Option<WslPathMode>,
+) -> Result<Vec<String>, String> {
+    let mode = mode.unwrap_or(WslPathMode::Linux);
+    paths
+        .iter()
+        .map(|path| translate(&app, path, mode))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_windows_paths_for_wsl() {
+        assert_eq!(
+            to_linux(r"C:\Users\me\project").as_deref(),
+            Some("/mnt/c/Users/me/project")
+        );
+        assert_eq!(to_linux("D:/src").as_deref(), Some("/mnt/d/src"));
+        assert_eq!(to_linux("C:").as_deref(), Some("/mnt/c"));
+        assert_eq!(
+            to_linux(r"\\wsl.localhost\Ubuntu\home\me").as_deref(),
+            Some("/home/me")
+        );
+        assert_eq!(to_linux(r"\\wsl$\Ubuntu").as_deref(), Some("/"));
+        assert_eq!(to_linux("/home/me").as_deref(), Some("/home/me"));
+
+        assert_eq!(to_linux(r"\\server\share\file"), None);
+        assert_eq!(to_linux("C:relative"), None);
+        assert_eq!(to_linux("~/project"), None);
+        assert_eq!(to_linux("project"), None);
+    }
+
+    #[test]
+    fn translates_wsl_paths_for_windows() {
+        assert_eq!(
+            to_windows("/mnt/c/Users/me", None).as_deref(),
+            Some(r"C:\Users\me")
+        );
+        assert_eq!(to_windows("/mnt/d", None).as_deref(), Some(r"D:\"));
+        assert_eq!(
+            to_windows("/home/me/project", Some("Ubuntu")).as_deref(),
+            Some(r"\\wsl.localhost\Ubuntu\home\me\project")
+        );
+        assert_eq!(to_windows(r"C:\Users", None).as_deref(), Some(r"C:\Users"));
+
+        assert_eq!(to_windows("/home/me", None), None);
+        assert_eq!(to_windows("/mnt/wsl/x", None), None);
+        assert_eq!(to_windows("~", Some("Ubuntu")), None);
+    }
+}
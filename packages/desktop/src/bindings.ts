@@ -6,48 +6,1076 @@ import * as __TAURI_EVENT from "@tauri-apps/api/event";
 /** Commands */
 export const commands = {
 	killSidecar: () => __TAURI_INVOKE<void>("kill_sidecar"),
-	installCli: () => __TAURI_INVOKE<string>("install_cli"),
+	stopServer: () => __TAURI_INVOKE<void>("stop_server"),
+	restartServer: () => __TAURI_INVOKE<null>("restart_server"),
+	installCli: (operationId: string | null) => __TAURI_INVOKE<string>("install_cli", { operationId }),
+	uninstallCli: () => __TAURI_INVOKE<UninstallReport>("uninstall_cli"),
+	getCliChannel: () => __TAURI_INVOKE<CliChannel>("get_cli_channel"),
+	switchCliChannel: (channel: CliChannel, operationId: string | null) => __TAURI_INVOKE<string>("switch_cli_channel", { channel, operationId }),
+	getFullConfig: () => __TAURI_INVOKE<JsonValue>("get_full_config"),
+	getConfigError: () => __TAURI_INVOKE<ConfigInvalid | null>("get_config_error"),
+	setConfigValue: (path: string[], value: JsonValue) => __TAURI_INVOKE<null>("set_config_value", { path, value }),
+	patchConfig: (patch: JsonValue, directory: string | null) => __TAURI_INVOKE<null>("patch_config", { patch, directory }),
+	openConfigInEditor: () => __TAURI_INVOKE<string>("open_config_in_editor"),
+	getConfigPath: () => __TAURI_INVOKE<string | null>("get_config_path"),
+	setConfigPath: (path: string | null) => __TAURI_INVOKE<ConfigPath>("set_config_path", { path }),
+	resolveConfigPath: () => __TAURI_INVOKE<ConfigPath>("resolve_config_path"),
+	revealConfigPath: () => __TAURI_INVOKE<null>("reveal_config_path"),
+	listCliVersions: () => __TAURI_INVOKE<CliVersionInfo[]>("list_cli_versions"),
+	installCliVersion: (version: string, operationId: string | null) => __TAURI_INVOKE<string>("install_cli_version", { version, operationId }),
+	activateCliVersion: (version: string) => __TAURI_INVOKE<string>("activate_cli_version", { version }),
+	pruneCliVersions: (keep: number) => __TAURI_INVOKE<string[]>("prune_cli_versions", { keep }),
 	awaitInitialization: (events: Channel) => __TAURI_INVOKE<ServerReadyData>("await_initialization", { events }),
 	getDefaultServerUrl: () => __TAURI_INVOKE<string | null>("get_default_server_url"),
 	setDefaultServerUrl: (url: string | null) => __TAURI_INVOKE<null>("set_default_server_url", { url }),
 	getWslConfig: () => __TAURI_INVOKE<WslConfig>("get_wsl_config"),
+	getWslOfflineInstall: () => __TAURI_INVOKE<boolean>("get_wsl_offline_install"),
+	setWslOfflineInstall: (enabled: boolean) => __TAURI_INVOKE<null>("set_wsl_offline_install", { enabled }),
+	getWslSharedState: () => __TAURI_INVOKE<boolean>("get_wsl_shared_state"),
+	setWslSharedState: (enabled: boolean) => __TAURI_INVOKE<null>("set_wsl_shared_state", { enabled }),
 	setWslConfig: (config: WslConfig) => __TAURI_INVOKE<null>("set_wsl_config", { config }),
+	getWslDistro: () => __TAURI_INVOKE<string | null>("get_wsl_distro"),
+	setWslDistro: (distro: string | null) => __TAURI_INVOKE<null>("set_wsl_distro", { distro }),
+	listWslDistros: () => __TAURI_INVOKE<string[]>("list_wsl_distros"),
+	checkWslStatus: () => __TAURI_INVOKE<WslStatus>("check_wsl_status"),
+	getWslNetworking: () => __TAURI_INVOKE<WslNetworkInfo | null>("get_wsl_networking"),
+	getExternalServerConfig: () => __TAURI_INVOKE<ExternalServerConfig>("get_external_server_config"),
+	setExternalServerConfig: (config: ExternalServerConfig) => __TAURI_INVOKE<null>("set_external_server_config", { config }),
+	setExternalServerPassword: (password: string | null) => __TAURI_INVOKE<null>("set_external_server_password", { password }),
+	detectExternalServer: () => __TAURI_INVOKE<ExternalServerStatus>("detect_external_server"),
+	listRemoteProfiles: () => __TAURI_INVOKE<RemoteProfile[]>("list_remote_profiles"),
+	saveRemoteProfile: (profile: RemoteProfile, password: string | null) => __TAURI_INVOKE<null>("save_remote_profile", { profile, password }),
+	deleteRemoteProfile: (name: string) => __TAURI_INVOKE<null>("delete_remote_profile", { name }),
+	getActiveRemoteProfile: () => __TAURI_INVOKE<string | null>("get_active_remote_profile"),
+	connectRemote: (profile: string) => __TAURI_INVOKE<ServerReadyData>("connect_remote", { profile }),
+	disconnectRemote: () => __TAURI_INVOKE<null>("disconnect_remote"),
+	getLanDiscovery: () => __TAURI_INVOKE<boolean>("get_lan_discovery"),
+	setLanDiscovery: (enabled: boolean) => __TAURI_INVOKE<null>("set_lan_discovery", { enabled }),
+	getAllowLan: () => __TAURI_INVOKE<boolean>("get_allow_lan"),
+	getStallSnapshot: () => __TAURI_INVOKE<StallSnapshot | null>("get_stall_snapshot"),
+	getRestartOnStall: () => __TAURI_INVOKE<boolean>("get_restart_on_stall"),
+	setRestartOnStall: (enabled: boolean) => __TAURI_INVOKE<null>("set_restart_on_stall", { enabled }),
+	setAllowLan: (allowed: boolean) => __TAURI_INVOKE<null>("set_allow_lan", { allowed }),
+	discoverServers: (timeoutMs: number | null) => __TAURI_INVOKE<DiscoveredServer[]>("discover_servers", { timeoutMs }),
+	getLocalServerTls: () => __TAURI_INVOKE<boolean>("get_local_server_tls"),
+	setLocalServerTls: (enabled: boolean) => __TAURI_INVOKE<null>("set_local_server_tls", { enabled }),
+	getTlsFingerprint: () => __TAURI_INVOKE<string>("get_tls_fingerprint"),
+	getServerSocket: () => __TAURI_INVOKE<boolean>("get_server_socket"),
+	setServerSocket: (enabled: boolean) => __TAURI_INVOKE<null>("set_server_socket", { enabled }),
+	getPairingInfo: (readOnly: boolean) => __TAURI_INVOKE<PairingInfo>("get_pairing_info", { readOnly }),
+	startInstance: (directory: string) => __TAURI_INVOKE<InstanceInfo>("start_instance", { directory }),
+	listTrustedPaths: () => __TAURI_INVOKE<TrustedPath[]>("list_trusted_paths"),
+	trustPath: (path: string) => __TAURI_INVOKE<null>("trust_path", { path }),
+	revokeTrust: (path: string) => __TAURI_INVOKE<null>("revoke_trust", { path }),
+	listInstances: () => __TAURI_INVOKE<InstanceInfo[]>("list_instances"),
+	stopInstance: (directory: string) => __TAURI_INVOKE<null>("stop_instance", { directory }),
+	listRecentProjects: () => __TAURI_INVOKE<RecentProject[]>("list_recent_projects"),
+	addRecentProject: (path: string) => __TAURI_INVOKE<RecentProject[]>("add_recent_project", { path }),
+	removeRecentProject: (path: string) => __TAURI_INVOKE<RecentProject[]>("remove_recent_project", { path }),
+	pinRecentProject: (path: string, pinned: boolean) => __TAURI_INVOKE<RecentProject[]>("pin_recent_project", { path, pinned }),
+	openProject: (path: string) => __TAURI_INVOKE<InstanceInfo>("open_project", { path }),
+	takePendingDeepLinks: () => __TAURI_INVOKE<DeepLink[]>("take_pending_deep_links"),
+	getSettings: () => __TAURI_INVOKE<Settings>("get_settings"),
+	updateSettings: (settings: Settings) => __TAURI_INVOKE<Settings>("update_settings", { settings }),
+	exportSettings: (path: string, includeCliConfig: boolean) => __TAURI_INVOKE<null>("export_settings", { path, includeCliConfig }),
+	importSettings: (path: string, includeCliConfig: boolean) => __TAURI_INVOKE<Settings>("import_settings", { path, includeCliConfig }),
+	getExtraEnv: () => __TAURI_INVOKE<Partial<{ [key in string]: string }>>("get_extra_env"),
+	setExtraEnv: (env: Partial<{ [key in string]: string }>) => __TAURI_INVOKE<null>("set_extra_env", { env }),
+	getDotenvConfig: () => __TAURI_INVOKE<DotenvConfig>("get_dotenv_config"),
+	setDotenvConfig: (config: DotenvConfig) => __TAURI_INVOKE<null>("set_dotenv_config", { config }),
+	previewDotenv: (directory: string | null) => __TAURI_INVOKE<DotenvPreview>("preview_dotenv", { directory }),
+	getAutostart: () => __TAURI_INVOKE<boolean>("get_autostart"),
+	setAutostart: (enabled: boolean) => __TAURI_INVOKE<null>("set_autostart", { enabled }),
+	getBackgroundMode: () => __TAURI_INVOKE<boolean>("get_background_mode"),
+	setBackgroundMode: (enabled: boolean) => __TAURI_INVOKE<null>("set_background_mode", { enabled }),
+	getShellConfig: () => __TAURI_INVOKE<UserShell>("get_shell_config"),
+	setShellConfig: (path: string | null, args: string[] | null) => __TAURI_INVOKE<UserShell>("set_shell_config", { path, args }),
+	getWindowsShellEnv: () => __TAURI_INVOKE<boolean>("get_windows_shell_env"),
+	setWindowsShellEnv: (enabled: boolean) => __TAURI_INVOKE<null>("set_windows_shell_env", { enabled }),
+	getProxyConfig: () => __TAURI_INVOKE<ProxyConfig>("get_proxy_config"),
+	setProxyConfig: (config: ProxyConfig, password: string | null) => __TAURI_INVOKE<null>("set_proxy_config", { config, password }),
+	testProxy: (url: string | null) => __TAURI_INVOKE<number>("test_proxy", { url }),
+	getServerHealth: () => __TAURI_INVOKE<ServerHealth>("get_server_health"),
+	runDoctor: () => __TAURI_INVOKE<DoctorReport>("run_doctor"),
+	getOnboardingStep: () => __TAURI_INVOKE<OnboardingStep>("get_onboarding_step"),
+	runOnboardingStep: (step: OnboardingStep) => __TAURI_INVOKE<StepOutcome>("run_onboarding_step", { step }),
+	completeOnboardingStep: (step: OnboardingStep) => __TAURI_INVOKE<OnboardingStep>("complete_onboarding_step", { step }),
+	setDefaultProjectFolder: (path: string) => __TAURI_INVOKE<OnboardingStep>("set_default_project_folder", { path }),
+	resetOnboarding: () => __TAURI_INVOKE<null>("reset_onboarding"),
+	getServerStats: () => __TAURI_INVOKE<ServerStats | null>("get_server_stats"),
+	getMemoryWarningThreshold: () => __TAURI_INVOKE<number>("get_memory_warning_threshold"),
+	setMemoryWarningThreshold: (thresholdMb: number) => __TAURI_INVOKE<null>("set_memory_warning_threshold", { thresholdMb }),
+	issueServerToken: (scope: TokenScope, ttlMinutes: number) => __TAURI_INVOKE<IssuedToken>("issue_server_token", { scope, ttlMinutes }),
+	listServerTokens: () => __TAURI_INVOKE<ServerToken[]>("list_server_tokens"),
+	revokeServerToken: (id: string) => __TAURI_INVOKE<null>("revoke_server_token", { id }),
+	rotateServerCredentials: () => __TAURI_INVOKE<null>("rotate_server_credentials"),
+	getPasswordRotationDays: () => __TAURI_INVOKE<number | null>("get_password_rotation_days"),
+	setPasswordRotationDays: (days: number | null) => __TAURI_INVOKE<null>("set_password_rotation_days", { days }),
+	getSidecarPort: () => __TAURI_INVOKE<number | null>("get_sidecar_port"),
+	getSidecarPortRange: () => __TAURI_INVOKE<PortRange | null>("get_sidecar_port_range"),
+	setSidecarPortRange: (range: PortRange | null) => __TAURI_INVOKE<null>("set_sidecar_port_range", { range }),
+	inspectPort: (port: number) => __TAURI_INVOKE<PortInspection>("inspect_port", { port }),
+	freePort: (port: number) => __TAURI_INVOKE<boolean>("free_port", { port }),
+	getServerServiceDefinition: () => __TAURI_INVOKE<string>("get_server_service_definition"),
+	installServerService: (port: number | null) => __TAURI_INVOKE<ServiceStatus>("install_server_service", { port }),
+	uninstallServerService: () => __TAURI_INVOKE<null>("uninstall_server_service"),
+	installBackgroundServer: () => __TAURI_INVOKE<ServiceStatus>("install_background_server"),
+	removeBackgroundServer: () => __TAURI_INVOKE<null>("remove_background_server"),
+	startServerService: () => __TAURI_INVOKE<null>("start_server_service"),
+	stopServerService: () => __TAURI_INVOKE<null>("stop_server_service"),
+	getServerServiceStatus: () => __TAURI_INVOKE<ServiceStatus>("get_server_service_status"),
+	getUpdateChannel: () => __TAURI_INVOKE<UpdateChannel>("get_update_channel"),
+	setUpdateChannel: (channel: UpdateChannel) => __TAURI_INVOKE<null>("set_update_channel", { channel }),
+	checkForUpdate: () => __TAURI_INVOKE<AppUpdateInfo | null>("check_for_update"),
+	downloadUpdate: () => __TAURI_INVOKE<AppUpdateInfo>("download_update"),
+	installUpdate: (force: boolean) => __TAURI_INVOKE<string[]>("install_update", { force }),
+	getPendingUpdate: () => __TAURI_INVOKE<PendingUpdate | null>("get_pending_update"),
+	createBackup: (operationId: string | null) => __TAURI_INVOKE<BackupInfo>("create_backup", { operationId }),
+	listBackups: () => __TAURI_INVOKE<BackupInfo[]>("list_backups"),
+	restoreBackup: (name: string) => __TAURI_INVOKE<null>("restore_backup", { name }),
+	getBackupStatus: () => __TAURI_INVOKE<BackupStatus>("get_backup_status"),
+	setBackupSchedule: (schedule: BackupSchedule | null) => __TAURI_INVOKE<null>("set_backup_schedule", { schedule }),
+	getStorageUsage: () => __TAURI_INVOKE<StorageUsage>("get_storage_usage"),
+	pruneStorage: (categories: StorageCategory[]) => __TAURI_INVOKE<number>("prune_storage", { categories }),
+	spawnTerminal: (kind: TerminalKind, directory: string | null, cols: number, rows: number) => __TAURI_INVOKE<TerminalInfo>("spawn_terminal", { kind, directory, cols, rows }),
+	writeTerminal: (id: number, data: string) => __TAURI_INVOKE<null>("write_terminal", { id, data }),
+	resizeTerminal: (id: number, cols: number, rows: number) => __TAURI_INVOKE<null>("resize_terminal", { id, cols, rows }),
+	closeTerminal: (id: number) => __TAURI_INVOKE<null>("close_terminal", { id }),
+	listTerminals: () => __TAURI_INVOKE<TerminalInfo[]>("list_terminals"),
+	runCli: (subcommand: string, args: string[], output: Channel) => __TAURI_INVOKE<number | null>("run_cli", { subcommand, args, output }),
+	listProviderAuth: () => __TAURI_INVOKE<ProviderAuth[]>("list_provider_auth"),
+	setProviderApiKey: (providerId: string, key: string) => __TAURI_INVOKE<null>("set_provider_api_key", { providerId, key }),
+	logoutProvider: (providerId: string) => __TAURI_INVOKE<null>("logout_provider", { providerId }),
+	startProviderOauth: (providerId: string, method: number) => __TAURI_INVOKE<OauthAuthorization | null>("start_provider_oauth", { providerId, method }),
+	finishProviderOauth: (providerId: string, method: number, code: string | null) => __TAURI_INVOKE<null>("finish_provider_oauth", { providerId, method, code }),
+	listModels: (refresh: boolean) => __TAURI_INVOKE<ModelList>("list_models", { refresh }),
+	getGlobalHotkey: () => __TAURI_INVOKE<GlobalHotkey | null>("get_global_hotkey"),
+	setGlobalHotkey: (shortcut: string, action: HotkeyAction) => __TAURI_INVOKE<GlobalHotkey>("set_global_hotkey", { shortcut, action }),
+	disableGlobalHotkey: () => __TAURI_INVOKE<null>("disable_global_hotkey"),
+	submitQuickPrompt: (text: string, directory: string | null) => __TAURI_INVOKE<QuickPromptSubmitted>("submit_quick_prompt", { text, directory }),
+	toggleQuickPrompt: () => __TAURI_INVOKE<null>("toggle_quick_prompt"),
+	openProjectWindow: (path: string) => __TAURI_INVOKE<ProjectWindowInfo>("open_project_window", { path }),
+	listWindows: () => __TAURI_INVOKE<WindowInfo[]>("list_windows"),
+	focusWindow: (label: string) => __TAURI_INVOKE<null>("focus_window", { label }),
+	pickProjectFolder: (title: string | null) => __TAURI_INVOKE<string | null>("pick_project_folder", { title }),
+	pickAttachments: (title: string | null, multiple: boolean) => __TAURI_INVOKE<string[]>("pick_attachments", { title, multiple }),
+	pickExportPath: (title: string | null, fileName: string | null, extensions: string[]) => __TAURI_INVOKE<string | null>("pick_export_path", { title, fileName, extensions }),
+	isPathGranted: (path: string) => __TAURI_INVOKE<boolean>("is_path_granted", { path }),
+	listGrantedPaths: () => __TAURI_INVOKE<GrantedPath[]>("list_granted_paths"),
+	getFileDropConfig: () => __TAURI_INVOKE<FileDropConfig>("get_file_drop_config"),
+	setFileDropConfig: (config: FileDropConfig) => __TAURI_INVOKE<null>("set_file_drop_config", { config }),
+	ingestPaths: (paths: string[]) => __TAURI_INVOKE<FilesDropped>("ingest_paths", { paths }),
+	pasteClipboardImage: () => __TAURI_INVOKE<ClipboardImage | null>("paste_clipboard_image"),
+	getTaskbarActivity: () => __TAURI_INVOKE<TaskbarActivity>("get_taskbar_activity"),
+	setTaskbarBadge: (count: number | null) => __TAURI_INVOKE<void>("set_taskbar_badge", { count }),
+	setTaskbarProgress: (progress: TaskbarProgress | null) => __TAURI_INVOKE<null>("set_taskbar_progress", { progress }),
+	getEventStreamStatus: () => __TAURI_INVOKE<EventStreamStatus>("get_event_stream_status"),
+	serverRequest: (method: string, path: string, body: JsonValue | null, directory: string | null, server: string | null) => __TAURI_INVOKE<JsonValue>("server_request", { method, path, body, directory, server }),
+	openServerStream: (path: string, directory: string | null, server: string | null) => __TAURI_INVOKE<string>("open_server_stream", { path, directory, server }),
+	watchDirectory: (path: string) => __TAURI_INVOKE<string>("watch_directory", { path }),
+	unwatchDirectory: (path: string) => __TAURI_INVOKE<void>("unwatch_directory", { path }),
+	listWatchedDirectories: () => __TAURI_INVOKE<string[]>("list_watched_directories"),
+	getGitStatus: (directory: string) => __TAURI_INVOKE<GitStatus | null>("get_git_status", { directory }),
+	getGitDiffStats: (directory: string) => __TAURI_INVOKE<DiffStats | null>("get_git_diff_stats", { directory }),
+	listRecentCommits: (directory: string, limit: number | null) => __TAURI_INVOKE<CommitInfo[]>("list_recent_commits", { directory, limit }),
+	getFileDiffs: (directory: string, paths: string[] | null, staged: boolean) => __TAURI_INVOKE<FileDiff[]>("get_file_diffs", { directory, paths, staged }),
+	stageHunks: (directory: string, path: string, hunks: number[] | null) => __TAURI_INVOKE<null>("stage_hunks", { directory, path, hunks }),
+	unstageHunks: (directory: string, path: string, hunks: number[] | null) => __TAURI_INVOKE<null>("unstage_hunks", { directory, path, hunks }),
+	revertFile: (directory: string, path: string) => __TAURI_INVOKE<null>("revert_file", { directory, path }),
+	startSearch: (directory: string, query: SearchQuery) => __TAURI_INVOKE<string>("start_search", { directory, query }),
+	cancelSearch: (searchId: string) => __TAURI_INVOKE<boolean>("cancel_search", { searchId }),
+	cancelOperation: (operationId: string) => __TAURI_INVOKE<boolean>("cancel_operation", { operationId }),
+	getServerLifecycle: () => __TAURI_INVOKE<ServerLifecycle>("get_server_lifecycle"),
+	listOperations: () => __TAURI_INVOKE<OperationInfo[]>("list_operations"),
+	readProjectFile: (path: string, range: LineRange | null) => __TAURI_INVOKE<ProjectFile>("read_project_file", { path, range }),
+	writeProjectFile: (path: string, contents: string) => __TAURI_INVOKE<null>("write_project_file", { path, contents }),
+	exportSession: (sessionId: string, format: ExportFormat, path: string | null, directory: string | null, attachments: AttachmentMode | null, operationId: string | null) => __TAURI_INVOKE<string | null>("export_session", { sessionId, format, path, directory, attachments, operationId }),
+	exportSessions: (sessionIds: string[], directory: string | null, path: string | null, operationId: string | null) => __TAURI_INVOKE<ArchivedSession[] | null>("export_sessions", { sessionIds, directory, path, operationId }),
+	importSessions: (path: string) => __TAURI_INVOKE<ArchivedSession[]>("import_sessions", { path }),
+	getCrashReporting: () => __TAURI_INVOKE<boolean>("get_crash_reporting"),
+	setCrashReporting: (enabled: boolean) => __TAURI_INVOKE<null>("set_crash_reporting", { enabled }),
+	listCrashReports: () => __TAURI_INVOKE<CrashReport[]>("list_crash_reports"),
+	deleteCrashReport: (id: string) => __TAURI_INVOKE<null>("delete_crash_report", { id }),
+	submitCrashReport: (id: string) => __TAURI_INVOKE<CrashReport>("submit_crash_report", { id }),
+	getLocale: () => __TAURI_INVOKE<string>("get_locale"),
+	setLocale: (locale: string | null) => __TAURI_INVOKE<null>("set_locale", { locale }),
+	getUsageMetrics: () => __TAURI_INVOKE<boolean>("get_usage_metrics"),
+	setUsageMetrics: (enabled: boolean) => __TAURI_INVOKE<null>("set_usage_metrics", { enabled }),
+	getPendingUsageReport: () => __TAURI_INVOKE<UsageReport>("get_pending_usage_report"),
+	purgeUsageMetrics: () => __TAURI_INVOKE<void>("purge_usage_metrics"),
+	recordFeatureUsage: (feature: string) => __TAURI_INVOKE<null>("record_feature_usage", { feature }),
+	getSidecarLogs: (offset: number | null, limit: number | null) => __TAURI_INVOKE<SidecarLog[]>("get_sidecar_logs", { offset, limit }),
+	getLogFilePath: () => __TAURI_INVOKE<string | null>("get_log_file_path"),
+	getLogRetentionDays: () => __TAURI_INVOKE<number>("get_log_retention_days"),
+	setLogRetentionDays: (days: number) => __TAURI_INVOKE<null>("set_log_retention_days", { days }),
+	getLogLevel: () => __TAURI_INVOKE<LogLevel | null>("get_log_level"),
+	setLogLevel: (level: LogLevel) => __TAURI_INVOKE<null>("set_log_level", { level }),
+	exportDiagnostics: () => __TAURI_INVOKE<string>("export_diagnostics"),
+	getStartupTimings: () => __TAURI_INVOKE<StartupPhase[]>("get_startup_timings"),
 	getDisplayBackend: () => __TAURI_INVOKE<"wayland" | "auto" | null>("get_display_backend"),
 	setDisplayBackend: (backend: LinuxDisplayBackend) => __TAURI_INVOKE<null>("set_display_backend", { backend }),
 	parseMarkdownCommand: (markdown: string) => __TAURI_INVOKE<string>("parse_markdown_command", { markdown }),
 	checkAppExists: (appName: string) => __TAURI_INVOKE<boolean>("check_app_exists", { appName }),
-	wslPath: (path: string, mode: "windows" | "linux" | null) => __TAURI_INVOKE<string>("wsl_path", { path, mode }),
+	wslPath: (path: string, mode: WslPathMode | null) => __TAURI_INVOKE<string>("wsl_path", { path, mode }),
+	wslPaths: (paths: string[], mode: WslPathMode | null) => __TAURI_INVOKE<string[]>("wsl_paths", { paths, mode }),
 	resolveAppPath: (appName: string) => __TAURI_INVOKE<string | null>("resolve_app_path", { appName }),
 };
 
 /** Events */
 export const events = {
+	appUpdateProgress: makeEvent<AppUpdateProgress>("app-update-progress"),
+	cliInstallProgress: makeEvent<CliInstallProgress>("cli-install-progress"),
+	configChanged: makeEvent<ConfigChanged>("config-changed"),
+	configInvalid: makeEvent<ConfigInvalid>("config-invalid"),
+	deepLink: makeEvent<DeepLink>("deep-link"),
+	eventStreamChanged: makeEvent<EventStreamChanged>("event-stream-changed"),
+	filesDropped: makeEvent<FilesDropped>("files-dropped"),
+	fsChanged: makeEvent<FsChanged>("fs-changed"),
+	gitStatusChanged: makeEvent<GitStatusChanged>("git-status-changed"),
 	loadingWindowComplete: makeEvent<LoadingWindowComplete>("loading-window-complete"),
+	onboardingChanged: makeEvent<OnboardingChanged>("onboarding-changed"),
+	operationProgress: makeEvent<OperationProgress>("operation-progress"),
+	pendingUpdateReady: makeEvent<PendingUpdateReady>("pending-update-ready"),
+	projectOpened: makeEvent<ProjectOpened>("project-opened"),
+	quickPromptSubmitted: makeEvent<QuickPromptSubmitted>("quick-prompt-submitted"),
+	searchFinished: makeEvent<SearchFinished>("search-finished"),
+	searchResults: makeEvent<SearchResults>("search-results"),
+	secondInstance: makeEvent<SecondInstance>("second-instance"),
+	serverBindRejected: makeEvent<ServerBindRejected>("server-bind-rejected"),
+	serverCrash: makeEvent<ServerCrash>("server-crash"),
+	serverCredentialsChanged: makeEvent<ServerCredentialsChanged>("server-credentials-changed"),
+	serverEvent: makeEvent<ServerEvent>("server-event"),
+	serverHealthChanged: makeEvent<ServerHealthChanged>("server-health-changed"),
+	serverLifecycleChanged: makeEvent<ServerLifecycleChanged>("server-lifecycle-changed"),
+	serverMemoryWarning: makeEvent<ServerMemoryWarning>("server-memory-warning"),
+	serverRestartProgress: makeEvent<ServerRestartProgress>("server-restart-progress"),
+	serverStalled: makeEvent<ServerStalled>("server-stalled"),
+	sidecarAlert: makeEvent<SidecarAlert>("sidecar-alert"),
+	sidecarLog: makeEvent<SidecarLog>("sidecar-log"),
+	sidecarPortSelected: makeEvent<SidecarPortSelected>("sidecar-port-selected"),
+	sidecarRestart: makeEvent<SidecarRestart>("sidecar-restart"),
 	sqliteMigrationProgress: makeEvent<SqliteMigrationProgress>("sqlite-migration-progress"),
+	sshTunnelStatus: makeEvent<SshTunnelStatus>("ssh-tunnel-status"),
+	terminalExited: makeEvent<TerminalExited>("terminal-exited"),
+	terminalOutput: makeEvent<TerminalOutput>("terminal-output"),
+	updateDeferred: makeEvent<UpdateDeferred>("update-deferred"),
+	wslBootstrapFailed: makeEvent<WslBootstrapFailed>("wsl-bootstrap-failed"),
 };
 
 /* Types */
+export type AlertKind = "crash" | "port_in_use" | "auth_failure";
+
+export type AppUpdateInfo = {
+		version: string,
+		current_version: string,
+		notes: string | null,
+		date: string | null,
+		downloaded: boolean,
+	};
+
+export type AppUpdateProgress = { type: "downloading"; percent: number | null } | { type: "downloaded"; version: string } | { type: "installing" } | { type: "failed"; message: string };
+
+export type ArchivedSession = {
+		id: string,
+		title: string,
+		directory: string | null,
+	};
+
+export type Attachment = {
+		path: string,
+		source: string,
+		name: string,
+		mime: string,
+		size_kb: number,
+		directory: boolean,
+		staged: boolean,
+	};
+
+export type AttachmentMode = "embed" | "link";
+
+export type AuthMethod = {
+		type: AuthMethodKind,
+		label: string,
+	};
+
+export type AuthMethodKind = "oauth" | "api";
+
+export type BackupInfo = {
+		name: string,
+		path: string,
+		created_at: string,
+		size_kb: number,
+		automatic: boolean,
+	};
+
+export type BackupSchedule = {
+		interval_hours: number,
+		keep: number,
+	};
+
+export type BackupStatus = {
+		schedule: BackupSchedule | null,
+		last: BackupInfo | null,
+		next_at: string | null,
+		last_error: string | null,
+	};
+
+export type BootstrapStep = "detecting" | "resolving" | "downloading" | "verifying" | "extracting" | "installing";
+
+export type CategoryUsage = {
+		category: StorageCategory,
+		size_kb: number,
+		files: number,
+		paths: string[],
+		prunable: boolean,
+	};
+
+export type ChangeKind = "added" | "modified" | "deleted" | "renamed" | "untracked" | "conflicted";
+
+export type CheckStatus = "pass" | "warn" | "fail";
+
+export type CliChannel = { type: "bundled" } | { type: "stable" } | { type: "nightly" } | { type: "pinned"; version: string };
+
+export type CliInstallProgress = { type: "downloading"; percent: number | null } | { type: "verifying" } | { type: "extracting" } | { type: "installing" } | { type: "updating_path" } | { type: "done"; path: string } | { type: "failed"; message: string };
+
+export type CliOutput = {
+		stream: LogStream,
+		line: string,
+	};
+
+export type CliVersionInfo = {
+		version: string,
+		path: string,
+		active: boolean,
+	};
+
+export type ClipboardImage = {
+		path: string,
+		width: number,
+		height: number,
+		size_kb: number,
+	};
+
+export type CommitInfo = {
+		id: string,
+		summary: string,
+		author: string,
+		time: string,
+	};
+
+export type ConfigChanged = {
+		keys: string[],
+		restart_required: boolean,
+	};
+
+export type ConfigInvalid = {
+		path: string | null,
+		line: number | null,
+		column: number | null,
+		message: string,
+	};
+
+export type ConfigPath = {
+		path: string,
+		source: ConfigPathSource,
+		exists: boolean,
+	};
+
+export type ConfigPathSource = "setting" | "environment" | "global";
+
+export type CrashKind = "port_in_use" | "missing_binary" | "bad_config" | "unknown";
+
+export type CrashReport = {
+		id: string,
+		kind: CrashReportKind,
+		created_at: string,
+		app_version: string,
+		os: string,
+		arch: string,
+		message: string,
+		location: string | null,
+		thread: string | null,
+		backtrace: string[],
+		directory: string | null,
+		code: number | null,
+		signal: number | null,
+		uptime_secs: number | null,
+		stderr: string[],
+		submitted_at?: string | null,
+	};
+
+export type CrashReportKind = "panic" | "sidecar_exit";
+
+export type DeepLink = { type: "open_project"; directory: string } | { type: "session"; id: string; directory: string | null } | { type: "logs"; seq: number | null };
+
+export type DesktopError = {
+		code: ErrorCode,
+		message: string,
+		hint: string | null,
+		params: Partial<{ [key in string]: string }>,
+	};
+
+export type DiffHunk = {
+		header: string,
+		old_start: number,
+		old_lines: number,
+		new_start: number,
+		new_lines: number,
+	};
+
+export type DiffStats = {
+		files_changed: number,
+		insertions: number,
+		deletions: number,
+	};
+
+export type DiscoveredServer = {
+		name: string,
+		host: string,
+		port: number,
+		addresses: string[],
+		version: string | null,
+	};
+
+export type DoctorCheck = {
+		name: string,
+		status: CheckStatus,
+		message: string,
+	};
+
+export type DoctorReport = {
+		status: CheckStatus,
+		checks: DoctorCheck[],
+	};
+
+export type DotenvConfig = {
+		enabled: boolean,
+		path: string | null,
+	};
+
+export type DotenvPreview = {
+		path: string | null,
+		variables: string[],
+	};
+
+export type ErrorCode = "unknown" | "sidecar_not_found" | "cli_install_path_unknown" | "cli_install_script_failed" | "cli_version_not_installed" | "cli_versions_unavailable_in_wsl" | "invalid_cli_version" | "cli_command_not_allowed" | "invalid_cli_argument" | "cli_config_unreadable" | "invalid_cli_config" | "cancelled";
+
+export type EventStreamChanged = {
+		status: EventStreamStatus,
+	};
+
+export type EventStreamStatus = {
+		connected: boolean,
+		url: string | null,
+		reconnects: number,
+		last_error: string | null,
+	};
+
+export type ExportFormat = "markdown" | "html" | "json";
+
+export type ExternalServerConfig = {
+		enabled: boolean,
+		hostname: string | null,
+		port: number | null,
+	};
+
+export type ExternalServerStatus = {
+		url: string,
+		probe: ServerProbe,
+	};
+
+export type FileDiff = {
+		path: string,
+		old_path: string | null,
+		change: ChangeKind,
+		binary: boolean,
+		hunks: DiffHunk[],
+		patch: string,
+		truncated: boolean,
+	};
+
+export type FileDropConfig = {
+		native: boolean,
+		stage_above_mb: number | null,
+	};
+
+export type FilesDropped = {
+		window: string,
+		attachments: Attachment[],
+		rejected: RejectedFile[],
+	};
+
+export type FsChanged = {
+		root: string,
+		paths: string[],
+		truncated: boolean,
+		git: boolean,
+	};
+
+export type GitFile = {
+		path: string,
+		staged: ChangeKind | null,
+		unstaged: ChangeKind | null,
+	};
+
+export type GitStatus = {
+		root: string,
+		branch: string | null,
+		head: string | null,
+		upstream: string | null,
+		ahead: number,
+		behind: number,
+		files: GitFile[],
+		truncated: boolean,
+	};
+
+export type GitStatusChanged = {
+		directory: string,
+		status: GitStatus,
+	};
+
+export type GlobalHotkey = {
+		shortcut: string,
+		action: HotkeyAction,
+	};
+
+export type GrantKind = "folder" | "file" | "save_target";
+
+export type GrantedPath = {
+		path: string,
+		kind: GrantKind,
+	};
+
+export type HealthStatus = "unknown" | "healthy" | "unhealthy";
+
+export type HotkeyAction = "show_window" | "quick_prompt";
+
 export type InitStep = { phase: "server_waiting" } | { phase: "sqlite_waiting" } | { phase: "done" };
 
+export type InstanceInfo = {
+		directory: string,
+		url: string,
+		running: boolean,
+	};
+
+export type IssuedToken = {
+		id: string,
+		token: string,
+		scope: TokenScope,
+		expires_at: string,
+	};
+
+export type JsonValue = null | boolean | number | string | JsonValue[] | Partial<{ [key in string]: JsonValue }>;
+
+export type LineRange = {
+		start: number,
+		end: number | null,
+	};
+
 export type LinuxDisplayBackend = "wayland" | "auto";
 
 export type LoadingWindowComplete = null;
 
+export type LogLevel = "debug" | "info" | "warn" | "error";
+
+export type LogStream = "stdout" | "stderr";
+
+export type MatchRange = {
+		start: number,
+		end: number,
+	};
+
+export type ModelInfo = {
+		id: string,
+		name: string,
+		provider_id: string,
+		provider_name: string,
+		reasoning: boolean | null,
+		attachment: boolean | null,
+		context_limit: number | null,
+		output_limit: number | null,
+		status: string | null,
+	};
+
+export type ModelList = {
+		models: ModelInfo[],
+		defaults: Partial<{ [key in string]: string }>,
+		source: ModelSource,
+		fetched_at: string,
+	};
+
+export type ModelSource = "server" | "cli";
+
+export type OauthAuthorization = {
+		url: string,
+		method: string,
+		instructions: string,
+	};
+
+export type OnboardingChanged = {
+		step: OnboardingStep,
+	};
+
+export type OnboardingState = {
+		completed: OnboardingStep[],
+		defaultProjectFolder: string | null,
+	};
+
+export type OnboardingStep = "verify_sidecar" | "install_cli" | "detect_wsl" | "project_folder" | "done";
+
+export type OperationInfo = {
+		id: string,
+		kind: OperationKind,
+	};
+
+export type OperationKind = "cli_install" | "backup" | "search" | "session_export";
+
+export type OperationProgress = {
+		operation_id: string,
+		kind: OperationKind,
+		status: OperationStatus,
+	};
+
+export type OperationStatus = { type: "started" } | { type: "progress"; percent: number | null; step: string | null } | { type: "finished" } | { type: "cancelled" } | { type: "failed"; message: string };
+
+export type PairingInfo = {
+		hosts: string[],
+		port: number,
+		secure: boolean,
+		secret: string,
+		scope: TokenScope,
+		expires_at: string,
+		fingerprint: string | null,
+		link: string,
+	};
+
+export type PendingUpdate = {
+		cli_version: string | null,
+		app_version: string | null,
+		sessions: string[],
+	};
+
+export type PendingUpdateReady = {
+		pending: PendingUpdate,
+	};
+
+export type PortInspection = {
+		port: number,
+		free: boolean,
+		owners: PortOwner[],
+	};
+
+export type PortOwner = {
+		pid: number,
+		name: string,
+		path: string,
+		is_opencode: boolean,
+		stale: boolean,
+	};
+
+export type PortRange = {
+		start: number,
+		end: number,
+	};
+
+export type ProjectFile = {
+		path: string,
+		contents: string | null,
+		binary: boolean,
+		size_kb: number,
+		truncated: boolean,
+	};
+
+export type ProjectOpened = {
+		directory: string,
+		instance: InstanceInfo,
+	};
+
+export type ProjectWindowInfo = {
+		label: string,
+		server: InstanceInfo,
+	};
+
+export type ProviderAuth = {
+		id: string,
+		name: string,
+		connected: boolean,
+		methods: AuthMethod[],
+	};
+
+export type ProxyConfig = {
+		enabled: boolean,
+		host: string,
+		port: number,
+		username: string | null,
+		no_proxy: string | null,
+	};
+
+export type QuickPromptSubmitted = {
+		session_id: string,
+		directory: string | null,
+	};
+
+export type RecentProject = {
+		path: string,
+		pinned?: boolean,
+		last_opened: string,
+	};
+
+export type RejectedFile = {
+		path: string,
+		reason: string,
+	};
+
+export type RemoteProfile = {
+		name: string,
+		hostname: string,
+		port: number,
+		secure?: boolean,
+		ssh?: SshTunnel | null,
+	};
+
+export type SearchFinished = {
+		search_id: string,
+		files: number,
+		matches: number,
+		truncated: boolean,
+		cancelled: boolean,
+	};
+
+export type SearchLine = {
+		line: number,
+		text: string,
+	};
+
+export type SearchMatch = {
+		line: number,
+		text: string,
+		ranges: MatchRange[],
+		before: SearchLine[],
+		after: SearchLine[],
+	};
+
+export type SearchQuery = {
+		pattern: string,
+		regex?: boolean,
+		case_sensitive?: boolean,
+		whole_word?: boolean,
+		globs?: string[],
+		context_lines?: number,
+		max_results: number | null,
+	};
+
+export type SearchResults = {
+		search_id: string,
+		path: string,
+		matches: SearchMatch[],
+	};
+
+export type SecondInstance = {
+		args: string[],
+		cwd: string,
+		directories: string[],
+	};
+
+export type ServerBindRejected = { type: "lan_not_allowed"; hostname: string } | { type: "weak_password"; hostname: string; min_length: number };
+
+export type ServerCrash = {
+		directory: string | null,
+		kind: CrashKind,
+		suggestion: string,
+		exits: number,
+		code: number | null,
+		signal: number | null,
+		stderr: string[],
+	};
+
+export type ServerCredentialsChanged = null;
+
+export type ServerEvent = {
+		directory: string,
+		type: string,
+		properties: JsonValue,
+	};
+
+export type ServerHealth = {
+		url: string | null,
+		status: HealthStatus,
+		latency_ms: number | null,
+		uptime_secs: number | null,
+		consecutive_failures: number,
+	};
+
+export type ServerHealthChanged = {
+		status: HealthStatus,
+		previous: HealthStatus,
+	};
+
+export type ServerLifecycle = "stopped" | "starting" | "running" | "stopping" | "crashed";
+
+export type ServerLifecycleChanged = {
+		state: ServerLifecycle,
+	};
+
+export type ServerMemoryWarning = {
+		pid: number,
+		memory_mb: number,
+		threshold_mb: number,
+	};
+
+export type ServerProbe = "healthy" | "unauthorized" | "unreachable";
+
 export type ServerReadyData = {
 		url: string,
 		username: string | null,
-		password: string | null,
 		is_sidecar: boolean,
 	};
 
+export type ServerRequestError = { kind: "not_allowed"; path: string } | { kind: "no_server" } | { kind: "unreachable"; message: string; timed_out: boolean } | { kind: "status"; status: number; body: string };
+
+export type ServerRestartProgress = { type: "stopping" } | { type: "starting" } | { type: "ready" } | { type: "failed"; message: string };
+
+export type ServerStalled = {
+		url: string,
+		consecutive_failures: number,
+		restarting: boolean,
+	};
+
+export type ServerStats = {
+		pid: number,
+		cpu_percent: number,
+		memory_mb: number,
+		processes: number,
+	};
+
+export type ServerToken = {
+		id: string,
+		scope: TokenScope,
+		expires_at: string,
+	};
+
+export type ServiceStatus = {
+		supported: boolean,
+		installed: boolean,
+		running: boolean,
+		url: string,
+		definition_path: string | null,
+	};
+
+export type Settings = {
+		defaultServerUrl?: string | null,
+		wslEnabled?: boolean,
+		wslDistro?: string | null,
+		wslOfflineInstall?: boolean,
+		wslSharedState?: boolean,
+		sidecarPortRange?: PortRange | null,
+		logRetentionDays?: number | null,
+		logLevel?: LogLevel | null,
+		externalServer?: ExternalServerConfig,
+		remoteProfiles?: RemoteProfile[],
+		activeRemoteProfile?: string | null,
+		lanDiscovery?: boolean,
+		allowLan?: boolean,
+		restartOnStall?: boolean,
+		serverServicePort?: number | null,
+		localServerTls?: boolean,
+		serverSocket?: boolean,
+		recentProjects?: RecentProject[],
+		extraEnv?: Partial<{ [key in string]: string }>,
+		dotenv?: DotenvConfig,
+		proxy?: ProxyConfig,
+		shellPath?: string | null,
+		shellArgs?: string[] | null,
+		windowsShellEnv?: boolean,
+		backgroundMode?: boolean,
+		memoryWarningMb?: number | null,
+		cliChannel?: CliChannel,
+		onboarding?: OnboardingState,
+		updateChannel?: UpdateChannel | null,
+		installId?: string | null,
+		passwordRotationDays?: number | null,
+		serverPasswordRotatedAt?: string | null,
+		backupSchedule?: BackupSchedule | null,
+		trustedPaths?: TrustedPath[],
+		globalHotkey?: GlobalHotkey | null,
+		projectWindows?: Partial<{ [key in string]: WindowGeometry }>,
+		windowLayouts?: Partial<{ [key in string]: WindowGeometry }>,
+		fileDrop?: FileDropConfig,
+		crashReporting?: boolean,
+		usageMetrics?: boolean,
+		locale?: string | null,
+		configPath?: string | null,
+	};
+
+export type SidecarAlert = {
+		kind: AlertKind,
+		line: string,
+		seq: number,
+		link: string,
+	};
+
+export type SidecarLog = {
+		seq: number,
+		stream: LogStream,
+		line: string,
+		timestamp: string,
+		level: LogLevel | null,
+		message: string | null,
+		request_id: string | null,
+		session_id: string | null,
+	};
+
+export type SidecarPortSelected = {
+		port: number,
+		conflict: number | null,
+	};
+
+export type SidecarRestart = { type: "scheduled"; attempt: number; max_attempts: number; delay_ms: number; code: number | null; signal: number | null } | { type: "restarted"; attempt: number } | { type: "gave_up"; attempts: number };
+
 export type SqliteMigrationProgress = { type: "InProgress"; value: number } | { type: "Done" };
 
+export type SshTunnel = {
+		destination: string,
+		port: number | null,
+		identity_file: string | null,
+	};
+
+export type SshTunnelStatus = { state: "connecting"; profile: string } | { state: "connected"; profile: string; local_port: number } | { state: "reconnecting"; profile: string; attempt: number; delay_ms: number } | { state: "stopped"; profile: string };
+
+export type StallSnapshot = {
+		captured_at: string,
+		url: string,
+		pid: number | null,
+		consecutive_failures: number,
+		stats: ServerStats | null,
+		debug: string | null,
+		logs: SidecarLog[],
+	};
+
+export type StartupPhase = {
+		name: string,
+		start_ms: number,
+		duration_ms: number,
+	};
+
+export type StepOutcome = {
+		step: OnboardingStep,
+		status: CheckStatus,
+		message: string,
+		completed: boolean,
+	};
+
+export type StorageCategory = "logs" | "sessions" | "caches" | "binaries" | "backups";
+
+export type StorageUsage = {
+		total_kb: number,
+		categories: CategoryUsage[],
+	};
+
+export type TaskbarActivity = {
+		busy_sessions: number,
+		badge: number | null,
+		progress: TaskbarProgress | null,
+	};
+
+export type TaskbarProgress = {
+		status: TaskbarStatus,
+		percent: number | null,
+	};
+
+export type TaskbarStatus = "normal" | "indeterminate" | "paused" | "error";
+
+export type TerminalExited = {
+		id: number,
+		code: number | null,
+	};
+
+export type TerminalInfo = {
+		id: number,
+		kind: TerminalKind,
+		directory: string | null,
+		cols: number,
+		rows: number,
+	};
+
+export type TerminalKind = "shell" | "opencode";
+
+export type TerminalOutput = {
+		id: number,
+		data: string,
+	};
+
+export type TokenScope = "read" | "full" | "pair";
+
+export type TrustedPath = {
+		path: string,
+		trusted_at: string,
+	};
+
+export type UninstallReport = {
+		removed_binary: string | null,
+		cleaned_files: string[],
+	};
+
+export type UpdateChannel = "stable" | "beta";
+
+export type UpdateDeferred = {
+		pending: PendingUpdate,
+	};
+
+export type UsageCount = {
+		event: string,
+		day: string,
+		count: number,
+	};
+
+export type UsageReport = {
+		app_version: string,
+		os: string,
+		arch: string,
+		counts: UsageCount[],
+	};
+
+export type UserShell = {
+		path: string,
+		args: string[],
+	};
+
+export type WindowGeometry = {
+		x: number,
+		y: number,
+		width: number,
+		height: number,
+		maximized: boolean,
+	};
+
+export type WindowInfo = {
+		label: string,
+		directory: string | null,
+		focused: boolean,
+	};
+
+export type WslBootstrapFailed = {
+		step: BootstrapStep,
+		message: string,
+	};
+
 export type WslConfig = {
 		enabled: boolean,
 	};
 
+export type WslNetworkInfo = {
+		mode: WslNetworking,
+		localhost_forwarding: boolean,
+		address: string | null,
+	};
+
+export type WslNetworking = "nat" | "mirrored" | "virtio_proxy" | "none";
+
 export type WslPathMode = "windows" | "linux";
 
+export type WslStatus = { status: "ready"; distro: string } | { status: "unsupported" } | { status: "not_installed" } | { status: "no_distro" } | { status: "distro_not_found"; distro: string } | { status: "not_wsl2"; distro: string; version: number } | { status: "missing_tools"; distro: string; tools: string[] };
+
 /* Tauri Specta runtime */
 function makeEvent<T>(name: string) {
     const base = {
@@ -1,15 +1,15 @@
 import { message } from "@tauri-apps/plugin-dialog"
 
-import { initI18n, t } from "./i18n"
+import { errorMessage, initI18n, t } from "./i18n"
 import { commands } from "./bindings"
 
 export async function installCli(): Promise<void> {
   await initI18n()
 
   try {
-    const path = await commands.installCli()
+    const path = await commands.installCli(null)
     await message(t("desktop.cli.installed.message", { path }), { title: t("desktop.cli.installed.title") })
   } catch (e) {
-    await message(t("desktop.cli.failed.message", { error: String(e) }), { title: t("desktop.cli.failed.title") })
+    await message(t("desktop.cli.failed.message", { error: errorMessage(e) }), { title: t("desktop.cli.failed.title") })
   }
 }
@@ -17,6 +17,8 @@ export const dict = {
   "desktop.updater.downloaded.title": "Update Downloaded",
   "desktop.updater.downloaded.prompt":
     "Version {{version}} of OpenCode has been downloaded, would you like to install it and relaunch?",
+  "desktop.updater.busy.prompt":
+    "{{count}} session(s) are still running and will be stopped by installing the update. Install it anyway?",
   "desktop.updater.installFailed.title": "Update Failed",
   "desktop.updater.installFailed.message": "Failed to install update",
 
@@ -24,4 +26,25 @@ export const dict = {
   "desktop.cli.installed.message": "CLI installed to {{path}}\n\nRestart your terminal to use the 'opencode' command.",
   "desktop.cli.failed.title": "Installation Failed",
   "desktop.cli.failed.message": "Failed to install CLI: {{error}}",
+
+  "desktop.error.sidecar_not_found": "The server binary bundled with the app is missing",
+  "desktop.error.cli_install_path_unknown": "Could not determine where to install the CLI",
+  "desktop.error.cli_install_script_failed": "The install script failed: {{output}}",
+  "desktop.error.cli_version_not_installed": "CLI {{version}} is not installed",
+  "desktop.error.cli_versions_unavailable_in_wsl": "CLI versions can't be switched while the server runs in WSL",
+  "desktop.error.invalid_cli_version": "{{version}} is not a valid CLI version",
+  "desktop.error.cli_command_not_allowed": "opencode {{subcommand}} can't be run from the app",
+  "desktop.error.invalid_cli_argument": "Invalid argument {{argument}}",
+  "desktop.error.cli_config_unreadable": "Failed to read the CLI config",
+  "desktop.error.invalid_cli_config": "Invalid config: {{errors}}",
+  "desktop.error.cancelled": "Cancelled",
+
+  "desktop.error.sidecar_not_found.hint": "Reinstall the app to restore the bundled server.",
+  "desktop.error.cli_install_path_unknown.hint": "Make sure your home directory is set.",
+  "desktop.error.cli_install_script_failed.hint": "Check that the install directory is writable, then try again.",
+  "desktop.error.cli_version_not_installed.hint": "Install that version first.",
+  "desktop.error.cli_versions_unavailable_in_wsl.hint": "Turn off WSL to switch CLI versions.",
+  "desktop.error.invalid_cli_version.hint": "Use a version like 1.2.3.",
+  "desktop.error.cli_config_unreadable.hint": "Run `opencode debug config` in a terminal to see what is wrong.",
+  "desktop.error.invalid_cli_config.hint": "Check the value against the config schema.",
 }
@@ -1,6 +1,8 @@
 import * as i18n from "@solid-primitives/i18n"
 import { Store } from "@tauri-apps/plugin-store"
 
+import { commands } from "../bindings"
+
 import { dict as desktopEn } from "./en"
 import { dict as desktopZh } from "./zh"
 import { dict as desktopZht } from "./zht"
@@ -71,33 +73,39 @@ const LOCALES: readonly Locale[] = [
   "br",
 ]
 
+function matchLanguage(language: string): Locale | null {
+  if (language.toLowerCase().startsWith("zh")) {
+    if (language.toLowerCase().includes("hant")) return "zht"
+    return "zh"
+  }
+  if (language.toLowerCase().startsWith("ko")) return "ko"
+  if (language.toLowerCase().startsWith("de")) return "de"
+  if (language.toLowerCase().startsWith("es")) return "es"
+  if (language.toLowerCase().startsWith("fr")) return "fr"
+  if (language.toLowerCase().startsWith("da")) return "da"
+  if (language.toLowerCase().startsWith("ja")) return "ja"
+  if (language.toLowerCase().startsWith("pl")) return "pl"
+  if (language.toLowerCase().startsWith("ru")) return "ru"
+  if (language.toLowerCase().startsWith("ar")) return "ar"
+  if (
+    language.toLowerCase().startsWith("no") ||
+    language.toLowerCase().startsWith("nb") ||
+    language.toLowerCase().startsWith("nn")
+  )
+    return "no"
+  if (language.toLowerCase().startsWith("pt")) return "br"
+  if (language.toLowerCase().startsWith("bs")) return "bs"
+  return null
+}
+
 function detectLocale(): Locale {
   if (typeof navigator !== "object") return "en"
 
   const languages = navigator.languages?.length ? navigator.languages : [navigator.language]
   for (const language of languages) {
     if (!language) continue
-    if (language.toLowerCase().startsWith("zh")) {
-      if (language.toLowerCase().includes("hant")) return "zht"
-      return "zh"
-    }
-    if (language.toLowerCase().startsWith("ko")) return "ko"
-    if (language.toLowerCase().startsWith("de")) return "de"
-    if (language.toLowerCase().startsWith("es")) return "es"
-    if (language.toLowerCase().startsWith("fr")) return "fr"
-    if (language.toLowerCase().startsWith("da")) return "da"
-    if (language.toLowerCase().startsWith("ja")) return "ja"
-    if (language.toLowerCase().startsWith("pl")) return "pl"
-    if (language.toLowerCase().startsWith("ru")) return "ru"
-    if (language.toLowerCase().startsWith("ar")) return "ar"
-    if (
-      language.toLowerCase().startsWith("no") ||
-      language.toLowerCase().startsWith("nb") ||
-      language.toLowerCase().startsWith("nn")
-    )
-      return "no"
-    if (language.toLowerCase().startsWith("pt")) return "br"
-    if (language.toLowerCase().startsWith("bs")) return "bs"
+    const locale = matchLanguage(language)
+    if (locale) return locale
   }
 
   return "en"
@@ -169,6 +177,25 @@ export function t(key: keyof Dictionary, params?: Record<string, string | number
   return translate(key, params)
 }
 
+function translateError(key: string, fallback: unknown, params: unknown) {
+  if (!(key in state.dict)) return typeof fallback === "string" ? fallback : null
+  return t(key as keyof Dictionary, parseRecord(params) as Record<string, string> | undefined)
+}
+
+/**
+ * Translates an error returned by a backend command, followed by what the user can do about it.
+ * Falls back to the English text for codes without a translation.
+ */
+export function errorMessage(error: unknown) {
+  const record = parseRecord(error)
+  if (!record || typeof record.code !== "string" || typeof record.message !== "string") return String(error)
+
+  const key = `desktop.error.${record.code}`
+  const message = translateError(key, record.message, record.params) ?? record.message
+  const hint = translateError(`${key}.hint`, record.hint, record.params)
+  return hint ? `${message}\n\n${hint}` : message
+}
+
 export function initI18n(): Promise<Locale> {
   const cached = state.init
   if (cached) return cached
@@ -179,7 +206,8 @@ export function initI18n(): Promise<Locale> {
 
     const raw = await store.get("language").catch(() => null)
     const value = parseStored(raw)
-    const next = pickLocale(value) ?? state.locale
+    const backend = await commands.getLocale().catch(() => null)
+    const next = pickLocale(value) ?? (backend ? matchLanguage(backend) : null) ?? state.locale
 
     state.locale = next
     state.dict = build(next)
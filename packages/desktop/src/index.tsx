@@ -13,9 +13,7 @@ import { Splash } from "@opencode-ai/ui/logo"
 import type { AsyncStorage } from "@solid-primitives/storage"
 import { getCurrentWindow } from "@tauri-apps/api/window"
 import { readImage } from "@tauri-apps/plugin-clipboard-manager"
-import { getCurrent, onOpenUrl } from "@tauri-apps/plugin-deep-link"
 import { open, save } from "@tauri-apps/plugin-dialog"
-import { fetch as tauriFetch } from "@tauri-apps/plugin-http"
 import { isPermissionGranted, requestPermission } from "@tauri-apps/plugin-notification"
 import { openPath as openerOpenPath } from "@tauri-apps/plugin-opener"
 import { type as ostype } from "@tauri-apps/plugin-os"
@@ -31,8 +29,9 @@ import { UPDATER_ENABLED } from "./updater"
 import { webviewZoom } from "./webview-zoom"
 import "./styles.css"
 import { Channel } from "@tauri-apps/api/core"
-import { commands, ServerReadyData, type InitStep } from "./bindings"
+import { commands, events, ServerReadyData, type DeepLink, type InitStep } from "./bindings"
 import { createMenu } from "./menu"
+import { connectServer, serverFetch, serverSocketUrl } from "./server-proxy"
 
 const root = document.getElementById("root")
 if (import.meta.env.DEV && !(root instanceof HTMLElement)) {
@@ -53,10 +52,19 @@ const emitDeepLinks = (urls: string[]) => {
   window.dispatchEvent(new CustomEvent(deepLinkEvent, { detail: { urls } }))
 }
 
+// The app reads links as urls, so the ones the backend validated are turned back into them.
+const deepLinkUrl = (link: DeepLink) => {
+  if (link.type === "open_project") return `opencode://open-project?directory=${encodeURIComponent(link.directory)}`
+  if (link.type === "logs") return link.seq === null ? "opencode://logs" : `opencode://logs?seq=${link.seq}`
+  const directory = link.directory === null ? "" : `?directory=${encodeURIComponent(link.directory)}`
+  return `opencode://session/${link.id}${directory}`
+}
+
+// The backend holds links back until this listens, so none arrive before the window is ready.
 const listenForDeepLinks = async () => {
-  const startUrls = await getCurrent().catch(() => null)
-  if (startUrls?.length) emitDeepLinks(startUrls)
-  await onOpenUrl((urls) => emitDeepLinks(urls)).catch(() => undefined)
+  await events.deepLink.listen((event) => emitDeepLinks([deepLinkUrl(event.payload)])).catch(() => undefined)
+  const pending = await commands.takePendingDeepLinks().catch(() => [])
+  emitDeepLinks(pending.map(deepLinkUrl))
 }
 
 const createPlatform = (): Platform => {
@@ -74,7 +82,7 @@ const createPlatform = (): Platform => {
   const handleWslPicker = async <T extends string | string[]>(result: T | null): Promise<T | null> => {
     if (!result || !window.__OPENCODE__?.wsl) return result
     if (Array.isArray(result)) {
-      return Promise.all(result.map((path) => commands.wslPath(path, "linux").catch(() => path))) as any
+      return commands.wslPaths(result, "linux").catch(() => result) as any
     }
     return commands.wslPath(result, "linux").catch(() => result) as any
   }
@@ -344,12 +352,10 @@ const createPlatform = (): Platform => {
         .catch(() => undefined)
     },
 
-    fetch: (input, init) => {
-      if (input instanceof Request) {
-        return tauriFetch(input)
-      } else {
-        return tauriFetch(input, init)
-      }
+    fetch: serverFetch,
+
+    serverProxy: {
+      socketUrl: serverSocketUrl,
     },
 
     getWslEnabled: async () => {
@@ -421,15 +427,21 @@ let menuTrigger = null as null | ((id: string) => void)
 createMenu((id) => {
   menuTrigger?.(id)
 })
-void listenForDeepLinks()
+
+// Project windows talk to their own server and open their project, leaving links to the main window.
+const project = window.__OPENCODE__?.project
+if (project) emitDeepLinks([deepLinkUrl({ type: "open_project", directory: project.directory })])
+else void listenForDeepLinks()
 
 render(() => {
   const platform = createPlatform()
 
   const [defaultServer] = createResource(() =>
-    platform.getDefaultServerUrl?.().then((url) => {
-      if (url) return ServerConnection.key({ type: "http", http: { url } })
-    }),
+    project
+      ? undefined
+      : platform.getDefaultServerUrl?.().then((url) => {
+          if (url) return ServerConnection.key({ type: "http", http: { url } })
+        }),
   )
 
   function handleClick(e: MouseEvent) {
@@ -452,10 +464,10 @@ render(() => {
       <AppBaseProviders>
         <ServerGate>
           {(data) => {
+            connectServer(data.url, !!project)
             const http = {
               url: data.url,
               username: data.username ?? undefined,
-              password: data.password ?? undefined,
             }
             const server: ServerConnection.Any = data.is_sidecar
               ? {
@@ -490,7 +502,11 @@ render(() => {
 
 // Gate component that waits for the server to be ready
 function ServerGate(props: { children: (data: ServerReadyData) => JSX.Element }) {
-  const [serverData] = createResource(() => commands.awaitInitialization(new Channel<InitStep>() as any))
+  const [serverData] = createResource(() =>
+    project
+      ? Promise.resolve<ServerReadyData>({ url: project.url, username: null, is_sidecar: false })
+      : commands.awaitInitialization(new Channel<InitStep>() as any),
+  )
   if (serverData.state === "errored") throw serverData.error
 
   return (
@@ -0,0 +1,94 @@
+import { fetch as tauriFetch } from "@tauri-apps/plugin-http"
+import { commands, type JsonValue, type ServerRequestError } from "./bindings"
+
+// Requests to the connected server go through Rust, which adds its credentials, so the webview
+// never holds them. Project windows name their server, the main window uses the app's.
+let connected: { origin: string; server: string | null } | undefined
+
+export const connectServer = (url: string, project: boolean) => {
+  const origin = new URL(url).origin
+  connected = { origin, server: project ? origin : null }
+}
+
+const streams = [/^\/event$/, /^\/global\/event$/, /^\/pty\/[^/]+\/connect$/]
+const isStream = (pathname: string) => streams.some((stream) => stream.test(pathname))
+
+const isRequestError = (error: unknown): error is ServerRequestError =>
+  typeof error === "object" && error !== null && "kind" in error
+
+// Mirrors fetch: refused and failed requests are answered with their status, unreachable servers throw.
+const respond = (request: Promise<unknown>) =>
+  request.then(
+    (value) => new Response(JSON.stringify(value ?? null), { headers: { "Content-Type": "application/json" } }),
+    (error: unknown) => {
+      if (typeof error === "string") return new Response(error, { status: 500 })
+      if (!isRequestError(error)) throw error
+      if (error.kind === "status") return new Response(error.body, { status: error.status })
+      if (error.kind === "not_allowed") return new Response(`${error.path} is not allowed`, { status: 403 })
+      if (error.kind === "no_server") throw new TypeError("No server is connected")
+      throw new TypeError(error.message)
+    },
+  )
+
+// Provider credentials and config changes have commands of their own, which check them first.
+const route = async (method: string, pathname: string, path: string, body: JsonValue, directory: string | null) => {
+  const server = connected?.server ?? null
+  const provider = pathname.match(/^\/auth\/([^/]+)$/)?.[1]
+  if (provider && method === "PUT") {
+    const auth = body as { type?: string; key?: string } | null
+    if (auth?.type !== "api" || !auth.key) throw { kind: "not_allowed", path: pathname } satisfies ServerRequestError
+    await commands.setProviderApiKey(decodeURIComponent(provider), auth.key)
+    return true
+  }
+  if (provider && method === "DELETE") {
+    await commands.logoutProvider(decodeURIComponent(provider))
+    return true
+  }
+  if (pathname === "/global/config" && method === "PATCH") {
+    await commands.patchConfig(body, null)
+    await commands.serverRequest("POST", "/global/dispose", null, null, server)
+    return commands.serverRequest("GET", "/global/config", null, null, server)
+  }
+  if (pathname === "/config" && method === "PATCH") {
+    if (!directory) throw { kind: "not_allowed", path: pathname } satisfies ServerRequestError
+    await commands.patchConfig(body, directory)
+    await commands.serverRequest("POST", "/instance/dispose", null, directory, server)
+    return commands.serverRequest("GET", "/config", null, directory, server)
+  }
+  return commands.serverRequest(method, path, body, directory, server)
+}
+
+const directoryOf = (request: Request) => {
+  const header = request.headers.get("x-opencode-directory")
+  if (!header) return null
+  try {
+    return decodeURIComponent(header)
+  } catch {
+    return header
+  }
+}
+
+/** Where to open a websocket to the connected server at `url`, without its credentials in it. */
+export const serverSocketUrl = async (url: URL) => {
+  if (!connected || url.origin.replace(/^ws/, "http") !== connected.origin) return url
+  const stream = new URL(await commands.openServerStream(url.pathname + url.search, null, connected.server))
+  stream.protocol = "ws:"
+  return stream
+}
+
+export const serverFetch = async (input: RequestInfo | URL, init?: RequestInit): Promise<Response> => {
+  const request = new Request(input, init)
+  const url = new URL(request.url)
+  if (!connected || url.origin !== connected.origin) return tauriFetch(request)
+
+  const path = url.pathname + url.search
+  const directory = directoryOf(request)
+  if (isStream(url.pathname)) {
+    const stream = await commands.openServerStream(path, directory, connected.server)
+    return tauriFetch(stream, { headers: { Accept: "text/event-stream" }, signal: request.signal })
+  }
+
+  const text = request.method === "GET" || request.method === "HEAD" ? "" : await request.text()
+  const body = text ? (JSON.parse(text) as JsonValue) : null
+  return respond(route(request.method, url.pathname, path, body, directory))
+}
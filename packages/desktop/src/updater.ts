@@ -1,7 +1,4 @@
-import { check } from "@tauri-apps/plugin-updater"
-import { relaunch } from "@tauri-apps/plugin-process"
 import { ask, message } from "@tauri-apps/plugin-dialog"
-import { type as ostype } from "@tauri-apps/plugin-os"
 
 import { initI18n, t } from "./i18n"
 import { commands } from "./bindings"
@@ -13,7 +10,7 @@ export async function runUpdater({ alertOnFail }: { alertOnFail: boolean }) {
 
   let update
   try {
-    update = await check()
+    update = await commands.checkForUpdate()
   } catch {
     if (alertOnFail)
       await message(t("desktop.updater.checkFailed.message"), { title: t("desktop.updater.checkFailed.title") })
@@ -26,7 +23,7 @@ export async function runUpdater({ alertOnFail }: { alertOnFail: boolean }) {
   }
 
   try {
-    await update.download()
+    await commands.downloadUpdate()
   } catch {
     if (alertOnFail)
       await message(t("desktop.updater.downloadFailed.message"), { title: t("desktop.updater.downloadFailed.title") })
@@ -39,13 +36,15 @@ export async function runUpdater({ alertOnFail }: { alertOnFail: boolean }) {
   if (!shouldUpdate) return
 
   try {
-    if (ostype() === "windows") await commands.killSidecar()
-    await update.install()
+    // Installing stops the servers and relaunches the app, unless sessions are still running.
+    const busy = await commands.installUpdate(false)
+    if (busy.length === 0) return
+
+    const force = await ask(t("desktop.updater.busy.prompt", { count: busy.length }), {
+      title: t("desktop.updater.downloaded.title"),
+    })
+    if (force) await commands.installUpdate(true)
   } catch {
     await message(t("desktop.updater.installFailed.message"), { title: t("desktop.updater.installFailed.title") })
-    return
   }
-
-  await commands.killSidecar()
-  await relaunch()
 }
@@ -13,7 +13,11 @@ export const ServeCommand = cmd({
     }
     const opts = await resolveNetworkOptions(args)
     const server = Server.listen(opts)
-    console.log(`opencode server listening on http://${server.hostname}:${server.port}`)
+    if (Flag.OPENCODE_SERVER_SOCKET) {
+      console.log(`opencode server listening on ${Flag.OPENCODE_SERVER_SOCKET}`)
+    } else {
+      console.log(`opencode server listening on ${server.url.protocol}//${server.hostname}:${server.port}`)
+    }
     await new Promise(() => {})
     await server.stop()
   },
@@ -30,6 +30,11 @@ export namespace Flag {
   export declare const OPENCODE_CLIENT: string
   export const OPENCODE_SERVER_PASSWORD = process.env["OPENCODE_SERVER_PASSWORD"]
   export const OPENCODE_SERVER_USERNAME = process.env["OPENCODE_SERVER_USERNAME"]
+  export const OPENCODE_SERVER_TOKENS_FILE = process.env["OPENCODE_SERVER_TOKENS_FILE"]
+  export const OPENCODE_TLS_CERT = process.env["OPENCODE_TLS_CERT"]
+  export const OPENCODE_TLS_KEY = process.env["OPENCODE_TLS_KEY"]
+  export const OPENCODE_SERVER_SOCKET = process.env["OPENCODE_SERVER_SOCKET"]
+  export const OPENCODE_DETACHED_LOG_DIR = process.env["OPENCODE_DETACHED_LOG_DIR"]
   export const OPENCODE_ENABLE_QUESTION_TOOL = truthy("OPENCODE_ENABLE_QUESTION_TOOL")
 
   // Experimental
@@ -7,6 +7,7 @@ import { cors } from "hono/cors"
 import { streamSSE } from "hono/streaming"
 import { proxy } from "hono/proxy"
 import { basicAuth } from "hono/basic-auth"
+import { ServerToken } from "./token"
 import z from "zod"
 import { Provider } from "../provider/provider"
 import { NamedError } from "@opencode-ai/util/error"
@@ -77,12 +78,24 @@ export namespace Server {
             status: 500,
           })
         })
-        .use((c, next) => {
+        .use(async (c, next) => {
           // Allow CORS preflight requests to succeed without auth.
           // Browser clients sending Authorization headers will preflight with OPTIONS.
           if (c.req.method === "OPTIONS") return next()
+          // Paired devices trade their one-time secret for a token before they can authenticate.
+          if (c.req.method === "POST" && c.req.path === "/pair") {
+            const token = await ServerToken.redeem(c.req.header("Authorization"))
+            if (!token) return c.json({ error: "The pairing secret is invalid or has expired" }, 401)
+            return c.json(token)
+          }
           const password = Flag.OPENCODE_SERVER_PASSWORD
           if (!password) return next()
+          const scope = await ServerToken.scope(c.req.header("Authorization"))
+          if (scope === "full") return next()
+          if (scope === "read") {
+            if (ServerToken.readable(c.req.method, c.req.path)) return next()
+            return c.json({ error: "This token is read-only" }, 403)
+          }
           const username = Flag.OPENCODE_SERVER_USERNAME ?? "opencode"
           return basicAuth({ username, password })(c, next)
         })
@@ -587,7 +600,25 @@ export namespace Server {
       idleTimeout: 0,
       fetch: App().fetch,
       websocket: websocket,
+      tls:
+        Flag.OPENCODE_TLS_CERT && Flag.OPENCODE_TLS_KEY
+          ? { cert: Bun.file(Flag.OPENCODE_TLS_CERT), key: Bun.file(Flag.OPENCODE_TLS_KEY) }
+          : undefined,
     } as const
+
+    // A unix socket (or named pipe on Windows) replaces the TCP listener entirely.
+    if (Flag.OPENCODE_SERVER_SOCKET) {
+      const server = Bun.serve({
+        unix: Flag.OPENCODE_SERVER_SOCKET,
+        idleTimeout: args.idleTimeout,
+        fetch: args.fetch,
+        websocket: args.websocket,
+        tls: args.tls,
+      })
+      _url = server.url
+      return server
+    }
+
     const tryServe = (port: number) => {
       try {
         return Bun.serve({ ...args, port })
@@ -0,0 +1,97 @@
+import { createHash, randomUUID } from "crypto"
+import { rename, writeFile } from "fs/promises"
+import { Flag } from "../flag/flag"
+
+// Scoped bearer tokens issued by the desktop app. The file only holds sha256 hashes of the tokens.
+export namespace ServerToken {
+  export type Scope = "read" | "full" | "pair"
+
+  // Pairing secrets carry the scope and lifetime (in ms) of the token they are exchanged for.
+  type Entry = { id: string; hash: string; scope: Scope; expiresAt: number; grants?: { scope: Scope; ttl: number } }
+
+  let cache: { modified: number; tokens: Entry[] } | undefined
+  // Redemptions run one at a time so a secret can't be exchanged twice.
+  let redeeming = Promise.resolve()
+
+  function hash(header: string | undefined) {
+    if (!header?.startsWith("Bearer ")) return
+    return createHash("sha256").update(header.slice("Bearer ".length).trim()).digest("hex")
+  }
+
+  async function load(): Promise<Entry[]> {
+    const path = Flag.OPENCODE_SERVER_TOKENS_FILE
+    if (!path) return []
+    const file = Bun.file(path)
+    if (!(await file.exists())) return []
+    if (cache?.modified === file.lastModified) return cache.tokens
+
+    const tokens = await file
+      .json()
+      .then((data) => (Array.isArray(data?.tokens) ? (data.tokens as Entry[]) : []))
+      .catch(() => [])
+    cache = { modified: file.lastModified, tokens }
+    return tokens
+  }
+
+  // What read-only tokens may reach: following sessions and the project, but not files, config,
+  // providers, terminals or anything that changes state.
+  const READ_ONLY_ROUTES = [
+    /^\/global\/(health|event)$/,
+    /^\/(event|path|vcs|agent|command|permission|question)$/,
+    /^\/project(\/current)?$/,
+    /^\/session(\/status)?$/,
+    /^\/session\/[^/]+(\/(children|todo|diff|message(\/[^/]+)?))?$/,
+  ]
+
+  /** Whether a read-only token may make this request. */
+  export function readable(method: string, path: string) {
+    if (method !== "GET" && method !== "HEAD") return false
+    return READ_ONLY_ROUTES.some((route) => route.test(path))
+  }
+
+  /** The scope of the unexpired bearer token in an `Authorization` header, if it carries one. */
+  export async function scope(header: string | undefined): Promise<Scope | undefined> {
+    const digest = hash(header)
+    if (!digest) return
+    const entry = (await load()).find((token) => token.hash === digest)
+    if (!entry || entry.expiresAt <= Date.now() || entry.scope === "pair") return
+    return entry.scope
+  }
+
+  /**
+   * Exchanges the pairing secret in an `Authorization` header for a new token, removing the
+   * secret so it can't be used again.
+   */
+  export function redeem(header: string | undefined) {
+    const result = redeeming.then(async () => {
+      const path = Flag.OPENCODE_SERVER_TOKENS_FILE
+      const digest = hash(header)
+      if (!path || !digest) return
+      const tokens = await load()
+      const secret = tokens.find((token) => token.hash === digest && token.scope === "pair")
+      if (!secret?.grants || secret.expiresAt <= Date.now()) return
+
+      const token = `oc_${randomUUID().replaceAll("-", "")}`
+      const expiresAt = Date.now() + secret.grants.ttl
+      const next = tokens
+        .filter((entry) => entry !== secret)
+        .concat({
+          id: randomUUID(),
+          hash: createHash("sha256").update(token).digest("hex"),
+          scope: secret.grants.scope,
+          expiresAt,
+        })
+      // Written next to the file and renamed over it, so the desktop app never reads half of it.
+      const temp = `${path}.${process.pid}.tmp`
+      await writeFile(temp, JSON.stringify({ tokens: next }), { mode: 0o600 })
+      await rename(temp, path)
+      cache = undefined
+      return { token, scope: secret.grants.scope, expiresAt: new Date(expiresAt).toISOString() }
+    })
+    redeeming = result.then(
+      () => {},
+      () => {},
+    )
+    return result
+  }
+}
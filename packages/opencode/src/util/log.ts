@@ -2,6 +2,7 @@ import path from "path"
 import fs from "fs/promises"
 import { createWriteStream } from "fs"
 import { Global } from "../global"
+import { Flag } from "../flag/flag"
 import z from "zod"
 import { Glob } from "./glob"
 
@@ -60,7 +61,7 @@ export namespace Log {
   export async function init(options: Options) {
     if (options.level) level = options.level
     cleanup(Global.Path.log)
-    if (options.print) return
+    if (options.print) return detachOnClosedPipe()
     logpath = path.join(
       Global.Path.log,
       options.dev ? "dev.log" : new Date().toISOString().split(".")[0].replace(/:/g, "") + ".log",
@@ -77,6 +78,29 @@ export namespace Log {
     }
   }
 
+  // A server outliving the app that started it can't print to its pipes anymore. Its logs move to
+  // `<pid>.log` in OPENCODE_DETACHED_LOG_DIR, where the next app session picks them up.
+  function detachOnClosedPipe() {
+    let detached = false
+    const detach = (error: NodeJS.ErrnoException) => {
+      if (error.code !== "EPIPE" || detached) return
+      detached = true
+      const dir = Flag.OPENCODE_DETACHED_LOG_DIR
+      if (!dir) {
+        write = (msg: any) => msg.length
+        return
+      }
+      const stream = createWriteStream(path.join(dir, `${process.pid}.log`), { flags: "a" })
+      stream.on("error", () => {})
+      write = (msg: any) => {
+        stream.write(msg)
+        return msg.length
+      }
+    }
+    process.stderr.on("error", detach)
+    process.stdout.on("error", detach)
+  }
+
   async function cleanup(dir: string) {
     const files = await Glob.scan("????-??-??T??????.log", {
       cwd: dir,
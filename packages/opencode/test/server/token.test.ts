@@ -0,0 +1,20 @@
+import { describe, expect, test } from "bun:test"
+import { ServerToken } from "../../src/server/token"
+
+describe("ServerToken.readable", () => {
+  test("allows following sessions", () => {
+    expect(ServerToken.readable("GET", "/session")).toBe(true)
+    expect(ServerToken.readable("GET", "/session/ses_1/message")).toBe(true)
+    expect(ServerToken.readable("HEAD", "/session/ses_1/message/msg_1")).toBe(true)
+    expect(ServerToken.readable("GET", "/global/event")).toBe(true)
+  })
+
+  test("rejects other routes and methods", () => {
+    expect(ServerToken.readable("POST", "/session/ses_1/message")).toBe(false)
+    expect(ServerToken.readable("GET", "/file/content")).toBe(false)
+    expect(ServerToken.readable("GET", "/config")).toBe(false)
+    expect(ServerToken.readable("GET", "/global/config")).toBe(false)
+    expect(ServerToken.readable("GET", "/pty/pty_1/connect")).toBe(false)
+    expect(ServerToken.readable("GET", "/provider/auth")).toBe(false)
+  })
+})